@@ -0,0 +1,318 @@
+// Procedural macros for the rounding_mode crate, which re-exports them. Expansions refer to the
+// runtime through `::rounding_mode`, so they only work from crates depending on it.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::visit_mut::{self, VisitMut};
+use syn::{parse_macro_input, parse_quote, BinOp, Expr, ExprLit, Ident, Item, ItemFn, Lit, Path, UnOp};
+
+/// Variants of `rounding_mode::modes::Round`
+const MODES: [&str; 7] = ["TiesToEven", "TowardZero", "TowardPInf", "TowardNInf", "Faithful", "TiesAway", "TiesToOdd"];
+
+/// Rewrites every `+`, `-`, `*` and `/` in the annotated function, along with their compound
+/// assignments, into the corresponding `rounding_mode::ops::RoundedArith` method under the given
+/// rounding mode, e.g., `#[rounding_mode(TowardNInf)]`. Floats then round as requested, through
+/// `rounding_mode::ops`, while integers keep their usual arithmetic.
+///
+/// Operands of other types don't implement `RoundedArith` and fail to compile. Neither nested
+/// items nor the contents of macro invocations are rewritten.
+#[proc_macro_attribute]
+pub fn rounding_mode(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mode = parse_macro_input!(attr as Path);
+    let mut function = parse_macro_input!(item as ItemFn);
+
+    match rewrite(&mode, &mut function) {
+        Ok(()) => quote!(#function).into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Evaluates an arithmetic expression with every `+`, `-`, `*` and `/` rounded toward negative
+/// infinity, as `#[rounding_mode(TowardNInf)]` would, e.g., `round_down!{ a * b + c }`.
+///
+/// The result is only a lower bound on the exact value if every operation is monotonically
+/// increasing in the rounded-down subterms it takes: negating or subtracting one, or multiplying
+/// or dividing by a negative value, turns its rounding the wrong way, so that `round_down!{ c - a
+/// * b }` can exceed `c - ab`. Use `enclose!` for bounds in those cases.
+#[proc_macro]
+pub fn round_down(input: TokenStream) -> TokenStream {
+    let mut expr = parse_macro_input!(input as Expr);
+    Rewriter { mode: parse_quote!(::rounding_mode::modes::Round::TowardNInf) }.visit_expr_mut(&mut expr);
+
+    quote!(#expr).into()
+}
+
+/// Evaluates an arithmetic expression with every `+`, `-`, `*` and `/` rounded toward positive
+/// infinity, as `#[rounding_mode(TowardPInf)]` would.
+///
+/// As for `round_down!`, the result is only an upper bound on the exact value if no rounded-up
+/// subterm is negated, subtracted, or multiplied or divided by a negative value.
+#[proc_macro]
+pub fn round_up(input: TokenStream) -> TokenStream {
+    let mut expr = parse_macro_input!(input as Expr);
+    Rewriter { mode: parse_quote!(::rounding_mode::modes::Round::TowardPInf) }.visit_expr_mut(&mut expr);
+
+    quote!(#expr).into()
+}
+
+/// Evaluates a float expression into an `(lo, hi)` enclosure of its exact value, e.g.,
+/// `enclose!{ x * x - 2.0 * x }`, using the interval arithmetic of `rounding_mode::ops::Enclose`.
+/// Each bound takes the direction its operation needs, so that the lower bound of `a - b`
+/// subtracts the upper bound of `b`, products and quotients take the extremes over their
+/// operands' bounds, and negation swaps the bounds.
+///
+/// Only `+`, `-`, `*`, `/`, negation and parentheses are followed. Variables, constants, fields,
+/// indexing and dereferences read a value that is already a float, so they are taken as exact,
+/// and float literals that aren't exactly representable widen to their neighbors. Anything else,
+/// such as a call or a cast, may round in ways the macro can't see, and fails to compile; bind
+/// its value to a variable first if it is exact, or enclose it by hand.
+#[proc_macro]
+pub fn enclose(input: TokenStream) -> TokenStream {
+    let expr = parse_macro_input!(input as Expr);
+
+    match enclosure(&expr) {
+        Ok(enclosure) => quote!(#enclosure).into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Expression evaluating to an enclosure of `expr`, or an error if `expr` contains a
+/// subexpression that it can't bound.
+fn enclosure(expr: &Expr) -> syn::Result<Expr> {
+    let enclose = quote!(::rounding_mode::ops::Enclose);
+
+    Ok(match expr {
+        Expr::Paren(paren) => enclosure(&paren.expr)?,
+        Expr::Group(group) => enclosure(&group.expr)?,
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => {
+            let operand = enclosure(&unary.expr)?;
+            parse_quote!(#enclose::neg_enclosure(#operand))
+        }
+        Expr::Binary(binary) => {
+            let method = match binary.op {
+                BinOp::Add(_) => "add_enclosure",
+                BinOp::Sub(_) => "sub_enclosure",
+                BinOp::Mul(_) => "mul_enclosure",
+                BinOp::Div(_) => "div_enclosure",
+                _ => return Err(unsupported(expr)),
+            };
+
+            let method = Ident::new(method, Span::call_site());
+            let (left, right) = (enclosure(&binary.left)?, enclosure(&binary.right)?);
+            parse_quote!(#enclose::#method(#left, #right))
+        }
+        Expr::Lit(ExprLit { lit: Lit::Float(lit), .. }) => {
+            let bits = match significant_bits(lit.base10_digits()) {
+                Some(bits) => quote!(::core::option::Option::Some(#bits)),
+                None => quote!(::core::option::Option::None),
+            };
+
+            parse_quote!(#enclose::literal(#lit, #bits))
+        }
+        Expr::Path(_) | Expr::Field(_) | Expr::Index(_) => parse_quote!(#enclose::point(#expr)),
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Deref(_)) => parse_quote!(#enclose::point(#expr)),
+        _ => return Err(unsupported(expr)),
+    })
+}
+
+fn unsupported(expr: &Expr) -> syn::Error {
+    syn::Error::new_spanned(
+        expr,
+        "enclose! can't bound this expression's rounding; bind it to a variable if it is exact",
+    )
+}
+
+/// Number of significant bits in the exact value of a decimal literal, from its leading one to
+/// its trailing one, or None if the value isn't a dyadic rational or is too long to tell.
+fn significant_bits(digits: &str) -> Option<u32> {
+    let (mantissa, exp) = match digits.split_once(['e', 'E']) {
+        Some((mantissa, exp)) => (mantissa, exp.parse::<i32>().ok()?),
+        None => (digits, 0),
+    };
+
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let mut m: u128 = format!("{}{}", int, frac).parse().ok()?;
+    let mut exp = exp - frac.len() as i32;
+
+    if m == 0 {
+        return Some(0);
+    }
+
+    while m.is_multiple_of(10) {
+        m /= 10;
+        exp += 1;
+    }
+
+    // m * 10^exp is m * 5^exp * 2^exp, which is dyadic if 5^-exp divides m
+    let pow5 = 5u128.checked_pow(exp.unsigned_abs())?;
+    let odd = if exp >= 0 {
+        m.checked_mul(pow5)?
+    } else if m.is_multiple_of(pow5) {
+        m / pow5
+    } else {
+        return None;
+    };
+
+    Some(128 - odd.leading_zeros() - odd.trailing_zeros())
+}
+
+/// Rewrites the operators in `function` to round in `mode`, a variant of `Round` named either on
+/// its own or through a path.
+fn rewrite(mode: &Path, function: &mut ItemFn) -> syn::Result<()> {
+    let variant = &mode.segments.last().unwrap().ident;
+    if !MODES.iter().any(|m| variant == m) {
+        let msg = format!("unknown rounding mode `{}`, expected one of {}", variant, MODES.join(", "));
+        return Err(syn::Error::new_spanned(mode, msg));
+    }
+
+    let mut rewriter = Rewriter { mode: parse_quote!(::rounding_mode::modes::Round::#variant) };
+    rewriter.visit_block_mut(&mut function.block);
+
+    Ok(())
+}
+
+struct Rewriter {
+    /// Expression naming the rounding mode
+    mode: Expr,
+}
+
+/// Strips redundant parentheses, which would otherwise end up around call arguments
+fn unparen(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Paren(paren) => unparen(&paren.expr),
+        _ => expr,
+    }
+}
+
+impl VisitMut for Rewriter {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        // Operands first, so that nested operators are rewritten too
+        visit_mut::visit_expr_mut(self, expr);
+
+        let Expr::Binary(binary) = expr else {
+            return;
+        };
+
+        let (method, assign) = match binary.op {
+            BinOp::Add(_) => ("add_rounded", false),
+            BinOp::Sub(_) => ("sub_rounded", false),
+            BinOp::Mul(_) => ("mul_rounded", false),
+            BinOp::Div(_) => ("div_rounded", false),
+            BinOp::AddAssign(_) => ("add_rounded", true),
+            BinOp::SubAssign(_) => ("sub_rounded", true),
+            BinOp::MulAssign(_) => ("mul_rounded", true),
+            BinOp::DivAssign(_) => ("div_rounded", true),
+            _ => return,
+        };
+
+        let method = Ident::new(method, Span::call_site());
+        let (left, right, mode) = (unparen(&binary.left), unparen(&binary.right), &self.mode);
+
+        *expr = if assign {
+            // As with the primitive compound assignments, the right-hand side is evaluated before
+            // the place it is assigned to. Mixed-site spans keep these bindings out of the way of
+            // the user's own variables.
+            let rhs = Ident::new("rhs", Span::mixed_site());
+            let place = Ident::new("place", Span::mixed_site());
+
+            parse_quote!({
+                let #rhs = #right;
+                let #place = &mut #left;
+                *#place = ::rounding_mode::ops::RoundedArith::#method(*#place, #rhs, #mode);
+            })
+        } else {
+            parse_quote!(::rounding_mode::ops::RoundedArith::#method(#left, #right, #mode))
+        };
+    }
+
+    fn visit_item_mut(&mut self, _: &mut Item) {
+        // Nested items are functions of their own, rewritten only if annotated themselves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(mode: Path, mut function: ItemFn) -> String {
+        rewrite(&mode, &mut function).unwrap();
+        quote!(#function).to_string()
+    }
+
+    #[test]
+    fn test_operators() {
+        let actual = expand(parse_quote!(TowardNInf), parse_quote!(fn f(x: f64) -> f64 { (x * x) - 2.0 * x }));
+        let expected = quote!(fn f(x: f64) -> f64 {
+            ::rounding_mode::ops::RoundedArith::sub_rounded(
+                ::rounding_mode::ops::RoundedArith::mul_rounded(x, x, ::rounding_mode::modes::Round::TowardNInf),
+                ::rounding_mode::ops::RoundedArith::mul_rounded(2.0, x, ::rounding_mode::modes::Round::TowardNInf),
+                ::rounding_mode::modes::Round::TowardNInf
+            )
+        });
+
+        assert_eq!(actual, expected.to_string());
+    }
+
+    #[test]
+    fn test_untouched() {
+        // Comparisons, remainders, nested items and macro arguments keep their operators
+        let function: ItemFn = parse_quote!(fn f(x: f64) -> bool {
+            fn g(y: f64) -> f64 { y + 1.0 }
+            assert!(x * 2.0 > 0.0);
+            x % 2.0 < g(x)
+        });
+
+        assert_eq!(expand(parse_quote!(Round::TowardZero), function.clone()), quote!(#function).to_string());
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let actual = expand(parse_quote!(TowardPInf), parse_quote!(fn f(s: &mut f64, x: f64) { *s += x; }));
+        assert!(actual.contains("add_rounded (* place , rhs , :: rounding_mode :: modes :: Round :: TowardPInf)"), "{}", actual);
+        assert!(actual.contains("let place = & mut * s"), "{}", actual);
+    }
+
+    #[test]
+    fn test_enclosure() {
+        let actual = enclosure(&parse_quote!(-(a - v[i]) * 0.5)).unwrap_or_else(|err| panic!("{}", err));
+        let enclose = quote!(::rounding_mode::ops::Enclose);
+        let expected = quote!(#enclose::mul_enclosure(
+            #enclose::neg_enclosure(#enclose::sub_enclosure(#enclose::point(a), #enclose::point(v[i]))),
+            #enclose::literal(0.5, ::core::option::Option::Some(1u32))
+        ));
+
+        assert_eq!(quote!(#actual).to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_unsupported() {
+        // Calls, casts and other operators may round or not even give floats
+        for expr in [parse_quote!(f(b)), parse_quote!(x.sqrt() + 1.0), parse_quote!(2.0 * (n as f64)), parse_quote!(a % b)] {
+            let err = enclosure(&expr).err().unwrap();
+            assert!(err.to_string().starts_with("enclose! can't bound"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_significant_bits() {
+        assert_eq!(significant_bits("0"), Some(0));
+        assert_eq!(significant_bits("0.5"), Some(1));
+        assert_eq!(significant_bits("2.75"), Some(4));
+        assert_eq!(significant_bits("1e3"), Some(7));
+        assert_eq!(significant_bits("6.25e-2"), Some(1));
+        assert_eq!(significant_bits("3.4375"), Some(6));
+        assert_eq!(significant_bits("9007199254740993"), Some(54));
+        assert_eq!(significant_bits("0.1"), None);
+        assert_eq!(significant_bits("1e-60"), None);
+        assert_eq!(significant_bits("1e300"), None);
+    }
+
+    #[test]
+    fn test_unknown_mode() {
+        let mut function: ItemFn = parse_quote!(fn f(x: f64) -> f64 { x + x });
+        let err = rewrite(&parse_quote!(Downward), &mut function).unwrap_err();
+
+        assert!(err.to_string().starts_with("unknown rounding mode `Downward`"));
+    }
+}
@@ -0,0 +1,391 @@
+// Arbitrary-precision unsigned integers, for the few places that need exact arithmetic beyond
+// 128 bits: decimal conversions, the slow paths of the elementary functions and the exact oracle
+// used by the tests.
+
+use std::cmp::Ordering;
+
+/// Arbitrary-precision unsigned integer, stored as little-endian 32-bit limbs without leading
+/// zero limbs
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint { limbs: vec![] }
+    }
+
+    pub fn one() -> BigUint {
+        BigUint::from_u128(1)
+    }
+
+    pub fn from_u128(mut n: u128) -> BigUint {
+        let mut limbs = vec![];
+        while n != 0 {
+            limbs.push(n as u32);
+            n >>= 32;
+        }
+
+        BigUint { limbs }
+    }
+
+    fn trim(mut self) -> BigUint {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+
+        self
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Number of significant bits, i.e., floor(log2(self)) + 1, or 0 for zero
+    pub fn bits(&self) -> u32 {
+        match self.limbs.last() {
+            None => 0,
+            Some(top) => 32 * self.limbs.len() as u32 - top.leading_zeros()
+        }
+    }
+
+    /// Lowest 128 bits
+    pub fn low_u128(&self) -> u128 {
+        self.limbs.iter().take(4).rev().fold(0, |acc, &l| (acc << 32) | l as u128)
+    }
+
+    pub fn shl(&self, n: u32) -> BigUint {
+        if self.is_zero() {
+            return BigUint::zero();
+        }
+
+        let (words, bits) = ((n / 32) as usize, n % 32);
+        let mut limbs = vec![0; words];
+        let mut carry = 0u32;
+
+        for &l in &self.limbs {
+            if bits == 0 {
+                limbs.push(l);
+            } else {
+                limbs.push((l << bits) | carry);
+                carry = l >> (32 - bits);
+            }
+        }
+        limbs.push(carry);
+
+        BigUint { limbs }.trim()
+    }
+
+    pub fn shr(&self, n: u32) -> BigUint {
+        let (words, bits) = ((n / 32) as usize, n % 32);
+        if words >= self.limbs.len() {
+            return BigUint::zero();
+        }
+
+        let limbs = (words..self.limbs.len()).map(|i| {
+            let hi = if bits == 0 { 0 } else { *self.limbs.get(i + 1).unwrap_or(&0) << (32 - bits) };
+            (self.limbs[i] >> bits) | hi
+        }).collect();
+
+        BigUint { limbs }.trim()
+    }
+
+    pub fn add(&self, other: &BigUint) -> BigUint {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry = 0u64;
+
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64 + *other.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+
+        BigUint { limbs }.trim()
+    }
+
+    /// Computes `self - other`, which must not be negative
+    pub fn sub(&self, other: &BigUint) -> BigUint {
+        assert!(*self >= *other, "BigUint subtraction underflowed");
+
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0i64;
+
+        for i in 0..self.limbs.len() {
+            let diff = self.limbs[i] as i64 - *other.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            limbs.push(diff as u32);
+            borrow = (diff < 0) as i64;
+        }
+
+        BigUint { limbs }.trim()
+    }
+
+    pub fn mul(&self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let t = a as u64 * b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+
+        BigUint { limbs }.trim()
+    }
+
+    /// Computes `base^n` by repeated squaring.
+    pub fn pow(base: u32, mut n: u32) -> BigUint {
+        let mut result = BigUint::one();
+        let mut square = BigUint::from_u128(base as u128);
+
+        while n != 0 {
+            if n & 1 == 1 {
+                result = result.mul(&square);
+            }
+            square = square.mul(&square);
+            n >>= 1;
+        }
+
+        result
+    }
+
+    /// Quotient and remainder of `self / other`, by schoolbook long division on the limbs, Knuth's
+    /// Algorithm D; `other` must be nonzero.
+    pub fn div_rem(&self, other: &BigUint) -> (BigUint, BigUint) {
+        assert!(!other.is_zero(), "BigUint division by zero");
+
+        if *self < *other {
+            return (BigUint::zero(), self.clone());
+        } else if other.limbs.len() == 1 {
+            let quot = self.div_u32(other.limbs[0]);
+            let rem = self.sub(&quot.mul(other));
+            return (quot, rem);
+        }
+
+        // Shifting both until the divisor's top bit is set makes each estimate of a quotient limb
+        // from the top limbs at most two too large, and the check against the next limb down
+        // leaves it at most one too large
+        let shift = other.limbs[other.limbs.len() - 1].leading_zeros();
+        let v = other.shl(shift).limbs;
+        let mut u = self.shl(shift).limbs;
+        u.push(0);
+
+        let n = v.len();
+        let m = u.len() - n;
+        let (v1, v2) = (v[n - 1] as u64, v[n - 2] as u64);
+        let mut quot = vec![0u32; m];
+
+        for j in (0..m).rev() {
+            let top = ((u[j + n] as u64) << 32) | u[j + n - 1] as u64;
+            let (mut qhat, mut rhat) = (top / v1, top % v1);
+            while qhat >> 32 != 0 || qhat * v2 > ((rhat << 32) | u[j + n - 2] as u64) {
+                qhat -= 1;
+                rhat += v1;
+                if rhat >> 32 != 0 {
+                    break;
+                }
+            }
+
+            // Subtract qhat times the divisor from the running remainder
+            let (mut carry, mut borrow) = (0u64, 0i64);
+            for i in 0..n {
+                let product = qhat * v[i] as u64 + carry;
+                carry = product >> 32;
+                let diff = u[i + j] as i64 - (product as u32) as i64 - borrow;
+                u[i + j] = diff as u32;
+                borrow = (diff < 0) as i64;
+            }
+            let diff = u[j + n] as i64 - carry as i64 - borrow;
+            u[j + n] = diff as u32;
+
+            // If that went negative, qhat was one too large, so add the divisor back
+            if diff < 0 {
+                qhat -= 1;
+                let mut carry = 0u64;
+                for i in 0..n {
+                    let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                    u[i + j] = sum as u32;
+                    carry = sum >> 32;
+                }
+                u[j + n] = u[j + n].wrapping_add(carry as u32);
+            }
+
+            quot[j] = qhat as u32;
+        }
+
+        u.truncate(n);
+        (BigUint { limbs: quot }.trim(), BigUint { limbs: u }.trim().shr(shift))
+    }
+
+    /// Quotient of `self / d`, rounded down, by schoolbook division on the limbs; `d` must be
+    /// nonzero.
+    pub fn div_u32(&self, d: u32) -> BigUint {
+        assert!(d != 0, "BigUint division by zero");
+
+        let mut limbs = vec![0; self.limbs.len()];
+        let mut rem = 0u64;
+
+        for (i, &l) in self.limbs.iter().enumerate().rev() {
+            let cur = (rem << 32) | l as u64;
+            limbs[i] = (cur / d as u64) as u32;
+            rem = cur % d as u64;
+        }
+
+        BigUint { limbs }.trim()
+    }
+
+    /// An upper bound on the `n`th root, good to about 40 bits, from the
+    /// leading bits in floating point.
+    fn root_seed(&self, n: u32) -> BigUint {
+        let shift = self.bits().saturating_sub(64).next_multiple_of(n);
+        let top = self.shr(shift).low_u128() as f64;
+        let root = if n == 2 { top.sqrt() } else { top.cbrt() };
+
+        // The margin covers the rounding of `top` and the discarded low bits
+        BigUint::from_u128((root * (1.0 + 1e-12)) as u128 + 2).shl(shift / n)
+    }
+
+    /// Square root, rounded down, by Newton's iteration from above.
+    pub fn isqrt(&self) -> BigUint {
+        if self.is_zero() {
+            return BigUint::zero();
+        }
+
+        // The iterates decrease monotonically to the root once they start above it
+        let mut x = self.root_seed(2);
+        loop {
+            let y = x.add(&self.div_rem(&x).0).shr(1);
+            if y >= x {
+                return x;
+            }
+            x = y;
+        }
+    }
+
+    /// Cube root, rounded down, by Newton's iteration from above.
+    pub fn icbrt(&self) -> BigUint {
+        if self.is_zero() {
+            return BigUint::zero();
+        }
+
+        let mut x = self.root_seed(3);
+        loop {
+            let y = x.add(&x).add(&self.div_rem(&x.mul(&x)).0).div_u32(3);
+            if y >= x {
+                return x;
+            }
+            x = y;
+        }
+    }
+}
+
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_cases::*;
+
+    #[test]
+    fn test_big_uint() {
+        let a = BigUint::from_u128(u128::MAX);
+        let b = BigUint::from_u128(0xdead_beef);
+
+        assert_eq!(a.bits(), 128);
+        assert_eq!(a.add(&BigUint::one()), BigUint::one().shl(128));
+        assert_eq!(a.add(&BigUint::one()).sub(&BigUint::one()), a);
+        assert_eq!(a.mul(&b).sub(&a.mul(&b.sub(&BigUint::one()))), a);
+        assert_eq!(b.shl(100).bits(), 132);
+        assert_eq!(b.shl(64).low_u128(), 0xdead_beef << 64);
+        assert_eq!(b.shl(75).shr(75), b);
+        assert_eq!(b.shl(40).shr(44).low_u128(), 0xdead_beef >> 4);
+        assert_eq!(b.shr(32), BigUint::zero());
+        assert!(a < a.add(&b) && BigUint::zero() < b);
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let ten_30 = BigUint::pow(10, 30);
+        assert_eq!(ten_30, BigUint::from_u128(10u128.pow(30)));
+
+        let (q, r) = ten_30.add(&BigUint::from_u128(7)).div_rem(&BigUint::from_u128(10u128.pow(15)));
+        assert_eq!(q, BigUint::from_u128(10u128.pow(15)));
+        assert_eq!(r, BigUint::from_u128(7));
+
+        let (q, r) = BigUint::from_u128(5).div_rem(&BigUint::from_u128(9));
+        assert_eq!((q, r), (BigUint::zero(), BigUint::from_u128(5)));
+
+        let a = BigUint::pow(3, 200);
+        let b = BigUint::pow(7, 50);
+        let (q, r) = a.div_rem(&b);
+        assert!(r < b);
+        assert_eq!(q.mul(&b).add(&r), a);
+
+        assert_eq!(a.div_u32(7), a.div_rem(&BigUint::from_u128(7)).0);
+
+        // Divisors with few and many limbs, top limbs just past a power of two and all ones, where
+        // the estimates of the quotient limbs need correcting
+        let random = |rng: &mut SplitMix64, limbs: i32| {
+            (0..limbs).fold(BigUint::zero(), |acc, _| {
+                let limb = match rng.range(0, 3) {
+                    0 => 0,
+                    1 => u32::MAX,
+                    2 => 0x8000_0000 | rng.range(0, 1) as u32,
+                    _ => rng.next_u64() as u32,
+                };
+                acc.shl(32).add(&BigUint::from_u128(limb as u128))
+            })
+        };
+
+        let mut rng = SplitMix64::new(SEED);
+        for _ in 0..2000 {
+            let (a_limbs, b_limbs) = (rng.range(1, 12), rng.range(1, 6));
+            let (a, b) = (random(&mut rng, a_limbs), random(&mut rng, b_limbs));
+            if !b.is_zero() {
+                let (q, r) = a.div_rem(&b);
+                assert!(r < b && q.mul(&b).add(&r) == a, "{:?} / {:?}", a, b);
+            }
+        }
+        assert_eq!(ten_30.div_u32(1_000_000_000), BigUint::pow(10, 21));
+        assert_eq!(BigUint::from_u128(8).div_u32(9), BigUint::zero());
+    }
+
+    #[test]
+    fn test_roots() {
+        assert_eq!(BigUint::zero().isqrt(), BigUint::zero());
+        assert_eq!(BigUint::from_u128(15).isqrt(), BigUint::from_u128(3));
+        assert_eq!(BigUint::from_u128(16).isqrt(), BigUint::from_u128(4));
+        assert_eq!(BigUint::pow(10, 60).isqrt(), BigUint::pow(10, 30));
+
+        let a = BigUint::pow(3, 301);
+        let s = a.isqrt();
+        assert!(s.mul(&s) <= a && a < s.add(&BigUint::one()).mul(&s.add(&BigUint::one())));
+
+        assert_eq!(BigUint::from_u128(26).icbrt(), BigUint::from_u128(2));
+        assert_eq!(BigUint::from_u128(27).icbrt(), BigUint::from_u128(3));
+        assert_eq!(BigUint::pow(10, 90).icbrt(), BigUint::pow(10, 30));
+
+        let c = a.icbrt();
+        let next = c.add(&BigUint::one());
+        assert!(c.mul(&c).mul(&c) <= a && a < next.mul(&next).mul(&next));
+    }
+}
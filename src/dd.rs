@@ -0,0 +1,367 @@
+// Double-double arithmetic: a value carried as the unevaluated sum of two f64s, good for about 106
+// bits of precision at a fraction of the cost of the software F128, since every operation is a
+// handful of hardware ones. The algorithms are those of Joldes, Muller and Popescu, "Tight and
+// rigorous error bounds for basic building blocks of double-word arithmetic" (2017), built on the
+// error-free transformations, and round to nearest. Conversions back to f64, on the other hand,
+// honor any rounding mode exactly.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use std::str::FromStr;
+use crate::eft::*;
+use crate::f128::{F128, ParseF128Error};
+use crate::modes::*;
+use crate::ops;
+use crate::successor::{predecessor_f64, successor_f64};
+
+/// A double-double number `hi + lo`, normalized so that `hi` is `hi + lo` rounded to nearest.
+/// Comparisons follow IEEE 754 semantics, so NaNs are unordered and the two zeros are equal.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DD {
+    hi: f64,
+    lo: f64,
+}
+
+/// `a + b` for `|a| >= |b|`, normalized
+fn fast(a: f64, b: f64) -> DD {
+    let (hi, lo) = fast_two_sum_f64(a, b);
+    DD { hi, lo }
+}
+
+impl DD {
+    pub const ZERO: DD = DD { hi: 0.0, lo: 0.0 };
+    pub const ONE: DD = DD { hi: 1.0, lo: 0.0 };
+    pub const NAN: DD = DD { hi: f64::NAN, lo: f64::NAN };
+    pub const INFINITY: DD = DD { hi: f64::INFINITY, lo: 0.0 };
+    pub const NEG_INFINITY: DD = DD { hi: f64::NEG_INFINITY, lo: 0.0 };
+    /// Relative precision of double-double arithmetic, 2^-104. Since the low word can be
+    /// arbitrarily small, there is no next value after 1 to take the difference with.
+    pub const EPSILON: DD = DD { hi: 4.930380657631324e-32, lo: 0.0 };
+
+    /// The exact sum `hi + lo`, normalized. Returns an infinity if the sum overflows.
+    pub fn new(hi: f64, lo: f64) -> DD {
+        let (s, e) = two_sum_f64(hi, lo);
+        if !s.is_finite() {
+            return DD { hi: s, lo: 0.0 };
+        }
+
+        DD { hi: s, lo: e }
+    }
+
+    /// High word, the value rounded to nearest
+    pub fn hi(self) -> f64 {
+        self.hi
+    }
+
+    /// Low word, the error of the high word
+    pub fn lo(self) -> f64 {
+        self.lo
+    }
+
+    pub fn is_nan(self) -> bool {
+        self.hi.is_nan()
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.hi.is_finite()
+    }
+
+    pub fn abs(self) -> DD {
+        if self.hi.is_sign_negative() { -self } else { self }
+    }
+
+    /// Square root, with a relative error of at most about 2^-104.
+    pub fn sqrt(self) -> DD {
+        if self.hi <= 0.0 || !self.hi.is_finite() {
+            // Zeros, infinities, NaNs and negative values, all decided by the high word
+            return DD { hi: self.hi.sqrt(), lo: 0.0 };
+        }
+
+        let s = self.hi.sqrt();
+        let (p, e) = two_prod_f64(s, s);
+        let t = ((self.hi - p) - e + self.lo) / (2.0 * s);
+
+        fast(s, t)
+    }
+
+    /// Rounds to an f64 under the given rounding mode, exactly: the low word decides the direction
+    /// and, when it is exactly half an ulp, the tie.
+    pub fn to_f64(self, mode: Round) -> f64 {
+        let (hi, lo) = (self.hi, self.lo);
+        if lo == 0.0 || !hi.is_finite() {
+            return hi;
+        }
+
+        // hi is the nearest f64 already, so the exact value lies between hi and this neighbor
+        let neighbor = if lo > 0.0 { successor_f64(hi) } else { predecessor_f64(hi) };
+        let tie = lo == (neighbor - hi) / 2.0;
+        let away = neighbor.abs() > hi.abs();
+
+        let round_to_neighbor = match mode {
+            Round::TiesToEven | Round::Faithful => false,
+            Round::TowardPInf => lo > 0.0,
+            Round::TowardNInf => lo < 0.0,
+            Round::TowardZero => !away,
+            Round::TiesAway => tie && away,
+            Round::TiesToOdd => tie && hi.to_bits() & 1 == 0,
+        };
+
+        if round_to_neighbor { neighbor } else { hi }
+    }
+
+    /// Converts to an F128, rounding to nearest when the words span more than its precision.
+    pub fn to_f128(self) -> F128 {
+        F128::from_f64(self.hi).add(F128::from_f64(self.lo), Round::TiesToEven)
+    }
+
+    /// Converts from an F128, keeping about 106 of its 113 bits. Values outside the range of f64
+    /// overflow or underflow as they would there.
+    pub fn from_f128(f: F128) -> DD {
+        let hi = f.to_f64(Round::TiesToEven);
+        if !hi.is_finite() {
+            return DD { hi, lo: 0.0 };
+        }
+
+        DD::new(hi, f.sub(F128::from_f64(hi), Round::TiesToEven).to_f64(Round::TiesToEven))
+    }
+}
+
+impl From<f64> for DD {
+    fn from(f: f64) -> DD {
+        DD { hi: f, lo: 0.0 }
+    }
+}
+
+impl Neg for DD {
+    type Output = DD;
+
+    fn neg(self) -> DD {
+        DD { hi: -self.hi, lo: -self.lo }
+    }
+}
+
+impl Add for DD {
+    type Output = DD;
+
+    /// Relative error at most 3 * 2^-106 (AccurateDWPlusDW).
+    fn add(self, other: DD) -> DD {
+        let (sh, sl) = two_sum_f64(self.hi, other.hi);
+        let (th, tl) = two_sum_f64(self.lo, other.lo);
+        if !sh.is_finite() {
+            return DD { hi: sh, lo: 0.0 };
+        }
+
+        let v = fast(sh, sl + th);
+        fast(v.hi, tl + v.lo)
+    }
+}
+
+impl Sub for DD {
+    type Output = DD;
+
+    fn sub(self, other: DD) -> DD {
+        self + -other
+    }
+}
+
+impl Mul for DD {
+    type Output = DD;
+
+    /// Relative error at most 4 * 2^-106 (DWTimesDW3).
+    fn mul(self, other: DD) -> DD {
+        let (ch, cl1) = two_prod_f64(self.hi, other.hi);
+        if !ch.is_finite() || ch == 0.0 {
+            return DD { hi: ch, lo: 0.0 };
+        }
+
+        let tl = ops::fma_f64(self.hi, other.lo, self.lo * other.lo, Round::TiesToEven);
+        let cl2 = ops::fma_f64(self.lo, other.hi, tl, Round::TiesToEven);
+
+        fast(ch, cl1 + cl2)
+    }
+}
+
+impl Div for DD {
+    type Output = DD;
+
+    /// Relative error at most 15 * 2^-106 (DWDivDW2).
+    fn div(self, other: DD) -> DD {
+        let th = self.hi / other.hi;
+        if !th.is_finite() || th == 0.0 {
+            return DD { hi: th, lo: 0.0 };
+        }
+
+        // r = other * th, as a double-double
+        let (rh, rl1) = two_prod_f64(other.hi, th);
+        let r = fast(rh, ops::fma_f64(other.lo, th, rl1, Round::TiesToEven));
+
+        let pi = self.hi - r.hi;
+        let delta = pi + (self.lo - r.lo);
+
+        fast(th, delta / other.hi)
+    }
+}
+
+impl PartialEq for DD {
+    fn eq(&self, other: &DD) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for DD {
+    fn partial_cmp(&self, other: &DD) -> Option<Ordering> {
+        // Normalized values order by their high words first
+        match self.hi.partial_cmp(&other.hi)? {
+            Ordering::Equal => self.lo.partial_cmp(&other.lo),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl FromStr for DD {
+    type Err = ParseF128Error;
+
+    /// Parses a decimal string through an F128, so within about 2^-106 of the value it denotes.
+    fn from_str(s: &str) -> Result<DD, ParseF128Error> {
+        F128::from_str(s).map(DD::from_f128)
+    }
+}
+
+impl fmt::Display for DD {
+    /// Formats with 32 significant digits, enough to tell apart values differing in the last bit
+    /// of the low word in most cases, without trailing zeros, e.g., "1.5e0".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = self.to_f128().to_decimal(32, Round::TiesToEven);
+
+        match s.split_once('e') {
+            Some((mantissa, exp)) if mantissa.contains('.') => {
+                write!(f, "{}e{}", mantissa.trim_end_matches('0').trim_end_matches('.'), exp)
+            }
+            _ => write!(f, "{}", s)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fallback::Format;
+    use crate::oracle::{Rational, Value};
+    use crate::test_cases::*;
+
+    /// Random normalized double-double around 1, scaled by up to 2^±100, with a low word of full
+    /// width or a few bits, and every so often exactly half an ulp
+    fn random_dd(rng: &mut SplitMix64) -> DD {
+        let scale = 2f64.powi(rng.range(-100, 100));
+        let hi = rng.significand_f64() * scale * if rng.coin() { -1.0 } else { 1.0 };
+        let ulp = successor_f64(hi.abs()) - hi.abs();
+
+        let lo = match rng.range(0, 3) {
+            0 => ulp * (rng.next_u64() as f64 / u64::MAX as f64 - 0.5),
+            1 => ulp * (rng.range(-8, 8) as f64 / 16.0),
+            2 => ulp * 0.5 * if rng.coin() { -1.0 } else { 1.0 },
+            _ => ulp * 2f64.powi(-rng.range(1, 60)),
+        };
+
+        DD::new(hi, lo)
+    }
+
+    fn exact(d: DD) -> Rational {
+        let value = |f: f64| match Value::decode(Format::BINARY64, f.to_bits() as u128) {
+            Value::Finite(r) => r,
+            _ => panic!("{} is not finite", f),
+        };
+
+        value(d.hi).add(&value(d.lo))
+    }
+
+    /// Asserts that `actual` is within `bound` times 2^-106 of `expected`, relatively
+    fn assert_close(actual: DD, expected: F128, bound: f64, context: &str) {
+        let error = actual.to_f128().sub(expected, Round::TiesToEven).to_f64(Round::TiesToEven).abs();
+        let limit = expected.to_f64(Round::TiesToEven).abs() * bound * 2f64.powi(-106);
+
+        assert!(error <= limit, "{}: actual = {}, expected = {}, error = {:e}", context, actual, expected, error);
+    }
+
+    #[test]
+    fn test_to_f64() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..20000 {
+            let d = random_dd(&mut rng);
+            for mode in Round::ALL {
+                let expected = f64::from_bits(exact(d).round(Format::BINARY64, mode) as u64);
+                assert_eq!(d.to_f64(mode), expected, "{:?}, mode = {:?}", d, mode);
+            }
+        }
+
+        // 1 + 2^-53 is a tie between 1 and its successor, 1 - 2^-54 one between 1 and its
+        // predecessor
+        let tie = DD::new(1.0, 2f64.powi(-53));
+        assert_eq!(tie.to_f64(Round::TiesToEven), 1.0);
+        assert_eq!(tie.to_f64(Round::TiesAway), 1.0 + f64::EPSILON);
+        assert_eq!(tie.to_f64(Round::TiesToOdd), 1.0 + f64::EPSILON);
+        assert_eq!(DD::new(1.0, -2f64.powi(-54)).to_f64(Round::TiesToOdd), 1.0 - f64::EPSILON / 2.0);
+        assert_eq!(DD::new(-1.0, -2f64.powi(-80)).to_f64(Round::TowardZero), -1.0);
+        assert_eq!(DD::new(-1.0, -2f64.powi(-80)).to_f64(Round::TowardNInf), -1.0 - f64::EPSILON);
+        assert_eq!(DD::new(f64::MAX, 2f64.powi(960)).to_f64(Round::TowardPInf), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..5000 {
+            let (a, b) = (random_dd(&mut rng), random_dd(&mut rng));
+            let (x, y) = (a.to_f128(), b.to_f128());
+            let context = format!("a = {:?}, b = {:?}", a, b);
+
+            // Sums of values of opposite signs can cancel down to the error of the F128 inputs,
+            // so only compare them when the signs agree
+            if a.hi.is_sign_negative() == b.hi.is_sign_negative() {
+                assert_close(a + b, x.add(y, Round::TiesToEven), 3.5, &context);
+                assert_close(a - -b, x.add(y, Round::TiesToEven), 3.5, &context);
+            }
+
+            assert_close(a * b, x.mul(y, Round::TiesToEven), 4.5, &context);
+            assert_close(a / b, x.div(y, Round::TiesToEven), 15.5, &context);
+            assert_close(a.abs().sqrt(), x.abs().sqrt(Round::TiesToEven), 4.0, &context);
+        }
+    }
+
+    #[test]
+    fn test_special_values() {
+        let max = DD::from(f64::MAX);
+
+        assert!((max + max).hi().is_infinite() && (max * max).hi().is_infinite());
+        assert!((DD::ONE / DD::ZERO).hi() == f64::INFINITY);
+        assert!((DD::ZERO / DD::ZERO).is_nan() && DD::from(-1.0).sqrt().is_nan());
+        assert_eq!(DD::ZERO.sqrt(), DD::ZERO);
+        assert_eq!(DD::INFINITY.sqrt(), DD::INFINITY);
+        assert_eq!(DD::ONE - DD::ONE, DD::ZERO);
+        assert!(DD::NAN != DD::NAN && DD::ZERO == -DD::ZERO);
+        assert!(DD::new(1.0, -1e-30) < DD::ONE && DD::ONE < DD::new(1.0, 1e-30));
+        assert!(DD::new(1.0, 1e-30) < DD::from(1.0000000000000002) && DD::NEG_INFINITY < DD::ZERO);
+    }
+
+    #[test]
+    fn test_decimal() {
+        let third = DD::ONE / DD::from(3.0);
+        assert_eq!(third.to_string(), "3.3333333333333333333333333333333e-1");
+        assert_eq!("0.1".parse::<DD>().unwrap().to_string(), "1e-1");
+        assert_eq!(DD::from(1.5).to_string(), "1.5e0");
+        assert_eq!(DD::NEG_INFINITY.to_string(), "-inf");
+
+        // 0.1 needs the low word, which holds the error of the f64 nearest to it
+        let tenth: DD = "0.1".parse().unwrap();
+        assert_eq!(tenth.hi(), 0.1);
+        assert_eq!(tenth.lo(), -5.551115123125783e-18);
+        assert!("1.0.0".parse::<DD>().is_err());
+
+        let pi: DD = "3.14159265358979323846264338327950288".parse().unwrap();
+        assert_eq!(pi.to_string(), "3.1415926535897932384626433832795e0");
+        assert_eq!(pi.to_f64(Round::TowardNInf), std::f64::consts::PI);
+        assert_eq!(pi.to_f64(Round::TowardPInf), successor_f64(std::f64::consts::PI));
+    }
+}
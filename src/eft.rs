@@ -0,0 +1,227 @@
+// Error-free transformations: operations returning their rounded result together with the exact
+// error of that rounding, so that result + error equals the exact value. They are the building
+// blocks of compensated summation and double-word arithmetic, and all round to nearest.
+//
+// The error terms are exact as long as nothing overflows and, for the products, quotients and
+// square roots, the error doesn't fall below the subnormal range; each function states the bound.
+
+use crate::modes::*;
+use crate::ops;
+
+/// Defines the error-free transformations for one float type. `$split_factor` is the Veltkamp
+/// splitting constant 2^s + 1 for s = ceil(p / 2), p being the precision.
+macro_rules! eft {
+    ($ty:ident, $two_sum:ident, $fast_two_sum:ident, $two_prod:ident, $two_prod_dekker:ident, $split:ident,
+     $div_rem:ident, $sqrt_rem:ident, $fma:path, $split_factor:expr) => {
+        /// Rounded sum `s` of `a` and `b` and its error `e`, such that `s + e = a + b` exactly,
+        /// unless the sum overflows. Knuth's branch-free algorithm, six operations.
+        pub fn $two_sum(a: $ty, b: $ty) -> ($ty, $ty) {
+            let s = a + b;
+            let a_approx = s - b;
+            let b_approx = s - a_approx;
+
+            (s, (a - a_approx) + (b - b_approx))
+        }
+
+        /// Like the two-sum above, in three operations, but requires the exponent of `a` to be at
+        /// least that of `b`, e.g., `|a| >= |b|`, or either to be zero.
+        pub fn $fast_two_sum(a: $ty, b: $ty) -> ($ty, $ty) {
+            let s = a + b;
+
+            (s, b - (s - a))
+        }
+
+        /// Rounded product `p` of `a` and `b` and its error `e`, such that `p + e = a * b`
+        /// exactly, unless the product overflows or the error underflows, which it can't while
+        /// the exponents of `a` and `b` sum to at least the minimum exponent plus the precision.
+        /// Uses a fused multiply-add, through `ops`.
+        pub fn $two_prod(a: $ty, b: $ty) -> ($ty, $ty) {
+            let p = a * b;
+
+            (p, $fma(a, b, -p, Round::TiesToEven))
+        }
+
+        /// Splits `a` into a high part of half the precision and a low part with the rest, both
+        /// exact, such that `hi + lo = a`. Overflows for `|a|` within a factor of 2^(p / 2) of
+        /// the largest finite value.
+        pub fn $split(a: $ty) -> ($ty, $ty) {
+            let c = $split_factor * a;
+            let hi = c - (c - a);
+
+            (hi, a - hi)
+        }
+
+        /// Like the FMA-based two-product above, but with Dekker's algorithm, through splitting
+        /// both operands, for when fused multiply-add is slow. Also requires both operands to
+        /// split without overflow and the product to stay a little clear of the largest finite
+        /// value.
+        pub fn $two_prod_dekker(a: $ty, b: $ty) -> ($ty, $ty) {
+            let p = a * b;
+            let (a_hi, a_lo) = $split(a);
+            let (b_hi, b_lo) = $split(b);
+
+            let e = ((a_hi * b_hi - p) + a_hi * b_lo + a_lo * b_hi) + a_lo * b_lo;
+            (p, e)
+        }
+
+        /// Rounded quotient `q` of `a` and `b` and the exact remainder `r = a - q * b`, unless
+        /// the remainder underflows.
+        pub fn $div_rem(a: $ty, b: $ty) -> ($ty, $ty) {
+            let q = a / b;
+
+            (q, -$fma(q, b, -a, Round::TiesToEven))
+        }
+
+        /// Rounded square root `s` of `a` and the exact remainder `r = a - s * s`, unless the
+        /// remainder underflows.
+        pub fn $sqrt_rem(a: $ty) -> ($ty, $ty) {
+            let s = a.sqrt();
+
+            (s, $fma(-s, s, a, Round::TiesToEven))
+        }
+    };
+}
+
+eft!(f64, two_sum_f64, fast_two_sum_f64, two_prod_f64, two_prod_dekker_f64, split_f64,
+     div_rem_f64, sqrt_rem_f64, ops::fma_f64, 134217729.0);
+eft!(f32, two_sum_f32, fast_two_sum_f32, two_prod_f32, two_prod_dekker_f32, split_f32,
+     div_rem_f32, sqrt_rem_f32, ops::fma_f32, 4097.0);
+
+#[cfg(test)]
+mod tests {
+    use crate::fallback::Format;
+    use crate::oracle::{Rational, Value};
+    use crate::test_cases::*;
+    use super::*;
+
+    fn exact_f64(f: f64) -> Rational {
+        match Value::decode(Format::BINARY64, f.to_bits() as u128) {
+            Value::Finite(r) => r,
+            _ => panic!("{} is not finite", f),
+        }
+    }
+
+    fn exact_f32(f: f32) -> Rational {
+        match Value::decode(Format::BINARY32, f.to_bits() as u128) {
+            Value::Finite(r) => r,
+            _ => panic!("{} is not finite", f),
+        }
+    }
+
+    fn same(a: &Rational, b: &Rational) -> bool {
+        a.add(&b.neg()).is_zero()
+    }
+
+    /// Whether the error of an operation on values with these exponents can't underflow
+    fn no_underflow_f64(exp_sum: i32) -> bool {
+        exp_sum > f64::MIN_EXP + f64::MANTISSA_DIGITS as i32
+    }
+
+    fn exp_f64(f: f64) -> i32 {
+        ((f.to_bits() >> 52) & 0x7ff) as i32 - 1023
+    }
+
+    fn exp_f32(f: f32) -> i32 {
+        ((f.to_bits() >> 23) & 0xff) as i32 - 127
+    }
+
+    #[test]
+    fn test_f64() {
+        for (a, b) in hard_f64_pairs(SEED, 20000) {
+            if !a.is_finite() || !b.is_finite() {
+                continue;
+            }
+
+            let (x, y) = (exact_f64(a), exact_f64(b));
+
+            let (s, e) = two_sum_f64(a, b);
+            if s.is_finite() {
+                assert_eq!(s, a + b);
+                assert!(same(&exact_f64(s).add(&exact_f64(e)), &x.add(&y)), "two_sum({:e}, {:e})", a, b);
+
+                let (big, small) = if a.abs() >= b.abs() { (a, b) } else { (b, a) };
+                assert_eq!(fast_two_sum_f64(big, small), (s, e));
+            }
+
+            let (p, e) = two_prod_f64(a, b);
+            if p.is_finite() && no_underflow_f64(exp_f64(a) + exp_f64(b)) {
+                assert_eq!(p, a * b);
+                assert!(same(&exact_f64(p).add(&exact_f64(e)), &x.mul(&y)), "two_prod({:e}, {:e})", a, b);
+
+                if a.abs() < 1e290 && b.abs() < 1e290 && p.abs() < 1e300 && !p.is_subnormal() {
+                    assert_eq!(two_prod_dekker_f64(a, b), (p, e), "two_prod_dekker({:e}, {:e})", a, b);
+                }
+            }
+
+            let (q, r) = div_rem_f64(a, b);
+            if b != 0. && q.is_finite() && q != 0. && no_underflow_f64(exp_f64(q) + exp_f64(b)) {
+                assert!(same(&exact_f64(q).mul(&y).add(&exact_f64(r)), &x), "div_rem({:e}, {:e})", a, b);
+            }
+
+            let a = a.abs();
+            let (s, r) = sqrt_rem_f64(a);
+            if no_underflow_f64(2 * exp_f64(s)) {
+                assert!(same(&exact_f64(s).mul(&exact_f64(s)).add(&exact_f64(r)), &exact_f64(a)), "sqrt_rem({:e})", a);
+            }
+        }
+    }
+
+    #[test]
+    fn test_f32() {
+        let no_underflow = |exp_sum: i32| exp_sum > f32::MIN_EXP + f32::MANTISSA_DIGITS as i32;
+
+        for (a, b) in hard_f32_pairs(SEED, 20000) {
+            if !a.is_finite() || !b.is_finite() {
+                continue;
+            }
+
+            let (x, y) = (exact_f32(a), exact_f32(b));
+
+            let (s, e) = two_sum_f32(a, b);
+            if s.is_finite() {
+                assert!(same(&exact_f32(s).add(&exact_f32(e)), &x.add(&y)), "two_sum({:e}, {:e})", a, b);
+
+                let (big, small) = if a.abs() >= b.abs() { (a, b) } else { (b, a) };
+                assert_eq!(fast_two_sum_f32(big, small), (s, e));
+            }
+
+            let (p, e) = two_prod_f32(a, b);
+            if p.is_finite() && no_underflow(exp_f32(a) + exp_f32(b)) {
+                assert!(same(&exact_f32(p).add(&exact_f32(e)), &x.mul(&y)), "two_prod({:e}, {:e})", a, b);
+
+                if a.abs() < 1e34 && b.abs() < 1e34 && p.abs() < 1e37 && !p.is_subnormal() {
+                    assert_eq!(two_prod_dekker_f32(a, b), (p, e), "two_prod_dekker({:e}, {:e})", a, b);
+                }
+            }
+
+            let (q, r) = div_rem_f32(a, b);
+            if b != 0. && q.is_finite() && q != 0. && no_underflow(exp_f32(q) + exp_f32(b)) {
+                assert!(same(&exact_f32(q).mul(&y).add(&exact_f32(r)), &x), "div_rem({:e}, {:e})", a, b);
+            }
+
+            let a = a.abs();
+            let (s, r) = sqrt_rem_f32(a);
+            if no_underflow(2 * exp_f32(s)) {
+                assert!(same(&exact_f32(s).mul(&exact_f32(s)).add(&exact_f32(r)), &exact_f32(a)), "sqrt_rem({:e})", a);
+            }
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split_f64(1.0 + f64::EPSILON), (1.0, f64::EPSILON));
+
+        let third = 1.0 / 3.0;
+        let (hi, lo) = split_f64(third);
+        assert_eq!(hi + lo, third);
+        assert!(hi.to_bits().trailing_zeros() >= 26 && lo.abs() <= hi.abs() * 2f64.powi(-27));
+
+        let (hi, lo) = split_f32(1.0 / 3.0);
+        assert_eq!(hi + lo, 1.0 / 3.0);
+        assert!(hi.to_bits().trailing_zeros() >= 11 && lo.abs() <= hi.abs() * 2f32.powi(-12));
+
+        // The error of 0.1 + 0.2, and that of 1/3 in f64, both known exactly
+        assert_eq!(two_sum_f64(0.1, 0.2), (0.30000000000000004, -2.7755575615628914e-17));
+        assert_eq!(div_rem_f64(1.0, 3.0), (third, 5.551115123125783e-17));
+    }
+}
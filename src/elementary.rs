@@ -0,0 +1,708 @@
+// Correctly rounded exponentials and logarithms of f64s, in every rounding mode, for interval
+// arithmetic and anything else that needs rigorous bounds on them; the trigonometric, power,
+// hyperbolic and special functions live in submodules of their own. Each function first
+// evaluates in double-double arithmetic, to well within FAST_ERROR relatively, and returns if
+// every value within that error rounds to the same f64, which fails for about one argument in
+// 2^27. Those fall back to fixed-point arithmetic on big integers with a running error bound, at
+// doubling precisions until the rounding is decided: Ziv's strategy.
+//
+// That only decides if the exact result is neither a float nor a midpoint between two. By the
+// Lindemann–Weierstrass theorem, the only rational values these functions take at rational points
+// are the obvious ones, such as exp(0) and log2 of powers of two, and those are handled up front,
+// as are arguments so close to zero or so large that the first-order term decides the rounding.
+// For functions where that rests on conjecture rather than proof, the loop still gives up at
+// MAX_PREC bits, far beyond what any hard case needs, with a bound from the ball that is still
+// rigorous in the directed modes.
+
+use crate::dd::DD;
+use crate::fallback::{round_to_format, sum_exact, Exact, Format};
+use crate::fixed::Fixed;
+use crate::modes::*;
+use crate::successor::{predecessor_f64, successor_f64, FloatParts};
+
+mod hyperbolic;
+mod power;
+mod special;
+mod trig;
+pub use self::hyperbolic::*;
+pub use self::power::*;
+pub use self::special::*;
+pub use self::trig::*;
+
+/// Bound on the relative error of the double-double evaluations below, 2^-80, with a margin of
+/// more than 2^10 over their worst case
+const FAST_ERROR: f64 = 1.0 / (1u128 << 80) as f64;
+
+/// Arguments below 2^-60 in magnitude are close enough to zero for the first-order term of each
+/// function to decide the rounding.
+const TINY: f64 = 1.0 / (1u64 << 60) as f64;
+
+/// ln 2 as a double-double, `(hi, lo)`
+const LN_2: (f64, f64) = (std::f64::consts::LN_2, 2.3190468138462996e-17);
+
+/// log2(e) as a double-double, `(hi, lo)`
+const LOG2_E: (f64, f64) = (std::f64::consts::LOG2_E, 2.0355273740931033e-17);
+
+fn dd((hi, lo): (f64, f64)) -> DD {
+    DD::new(hi, lo)
+}
+
+/// 2^n for n within the range of f64, subnormals included
+fn pow2(n: i32) -> f64 {
+    debug_assert!((-1074..=1023).contains(&n));
+
+    if n >= -1022 {
+        f64::from_bits(((n + 1023) as u64) << 52)
+    } else {
+        f64::from_bits(1 << (n + 1074))
+    }
+}
+
+/// Exponent of the leading bit of a finite nonzero `f`
+fn exponent(f: f64) -> i32 {
+    let parts = FloatParts::of_f64(f);
+    parts.exp + 63 - parts.mant.leading_zeros() as i32
+}
+
+/// `f * 2^n` in two steps, so that it is exact unless the result is subnormal
+fn ldexp(f: f64, n: i32) -> f64 {
+    f * pow2(n / 2) * pow2(n - n / 2)
+}
+
+fn ldexp_dd(d: DD, n: i32) -> DD {
+    DD::new(ldexp(d.hi(), n), ldexp(d.lo(), n))
+}
+
+/// Rounds `(-1)^negative * mant * 2^exp`, with `sticky` as in `round_to_format`.
+fn round_f64(negative: bool, mant: u128, exp: i32, sticky: bool, mode: Round) -> f64 {
+    f64::from_bits(round_to_format(Format::BINARY64, negative, mant, exp, sticky, mode) as u64)
+}
+
+/// Rounds 2^n, for any n, under the given rounding mode. Also rounds like any other positive
+/// value far enough out of the range of f64 on the same side.
+fn pow2_rounded(n: i32, mode: Round) -> f64 {
+    round_f64(false, 1, n, false, mode)
+}
+
+/// Rounds a value lying just above `x` if `above`, or just below otherwise, closer to it than any
+/// midpoint between floats; `x` must be finite and nonzero.
+fn nudge(x: f64, above: bool, mode: Round) -> f64 {
+    let step = match mode {
+        Round::TowardPInf => above,
+        Round::TowardNInf => !above,
+        Round::TowardZero => above == (x < 0.0),
+        _ => false,
+    };
+
+    match (step, above) {
+        (false, _) => x,
+        (true, true) => successor_f64(x),
+        (true, false) => predecessor_f64(x),
+    }
+}
+
+/// Rounds `d * 2^scale` exactly, through the fallback's exact sums.
+fn round_scaled(d: DD, scale: i32, mode: Round) -> f64 {
+    let exact = |f: f64| {
+        let parts = FloatParts::of_f64(f);
+        Exact { negative: parts.negative, mant: parts.mant as u128, exp: parts.exp + scale, sticky: false }
+    };
+
+    let sum = sum_exact(exact(d.hi()), exact(d.lo()), mode);
+    f64::from_bits(sum.round(Format::BINARY64, mode) as u64)
+}
+
+/// Rounds `d * 2^scale`, for `d` within a relative FAST_ERROR of the exact result, if every value
+/// that close rounds to the same f64.
+fn round_fast(d: DD, scale: i32, mode: Round) -> Option<f64> {
+    // Doubling the error leaves room for the rounding errors of the bounds themselves
+    let err = DD::from(2.0 * FAST_ERROR * d.hi().abs());
+    let lower = round_scaled(d - err, scale, mode);
+    let upper = round_scaled(d + err, scale, mode);
+
+    if lower.to_bits() == upper.to_bits() { Some(lower) } else { None }
+}
+
+/// Precision at which Ziv's loop stops doubling. The hardest arguments of these functions are
+/// known or expected to need about 2^-120 relatively, so reaching it means the exact result sits
+/// at a float or midpoint, or as good as.
+const MAX_PREC: u32 = 1 << 13;
+
+/// Ziv's loop: runs `eval`, which returns a value to some precision, its error in units in its
+/// last place and a power of two to scale both by, at doubling precisions until the rounding of
+/// the exact result is decided, or until MAX_PREC, where it settles for the bound of the last
+/// ball in the direction of `mode`.
+fn ziv(mode: Round, eval: impl Fn(u32) -> (Fixed, u64, i32)) -> f64 {
+    let mut prec = 128;
+
+    loop {
+        let (value, err, scale) = eval(prec);
+        if let Some(result) = value.round_ball(err, scale, mode) {
+            return result;
+        } else if prec >= MAX_PREC {
+            return value.bound_ball(err, scale, mode);
+        }
+
+        prec *= 2;
+    }
+}
+
+/// Splits `x` into `k ln 2 + r` for an integer k and `|r| <= ln 2 / 2`, give or take an ulp,
+/// returning k and r to within about 2^-94.
+fn reduce_exp(x: f64) -> (i32, DD) {
+    let k = (x * LOG2_E.0).round();
+    (k as i32, DD::from(x) - DD::from(k) * dd(LN_2))
+}
+
+/// `e^r - 1` for `|r| <= ln 2 / 2`, or a little more, to within about 2^-97 relatively.
+fn expm1_reduced(r: DD) -> DD {
+    // Taylor series on r / 2^8, which has converged after 11 terms, then back up through
+    // e^2y - 1 = (e^y - 1)(e^y + 1), which doesn't lose relative accuracy for small results
+    let q = ldexp_dd(r, -8);
+
+    let mut s = DD::ONE;
+    for n in (2..=11).rev() {
+        s = DD::ONE + q * s / DD::from(n as f64);
+    }
+
+    let mut t = q * s;
+    for _ in 0..8 {
+        t = t * (t + DD::from(2.0));
+    }
+
+    t
+}
+
+/// Exponent e such that `hi * 2^-e` lies within [sqrt(1/2), sqrt(2)), roughly, for a positive
+/// finite `hi`.
+fn log_exponent(hi: f64) -> i32 {
+    let e = exponent(hi);
+
+    if ldexp(hi, -e) >= std::f64::consts::SQRT_2 { e + 1 } else { e }
+}
+
+/// atanh(t) for `|t| < 0.172`, to within about 2^-100 relatively.
+fn atanh_fast(t: DD) -> DD {
+    // The series in t^2 has converged after 23 terms
+    let t2 = t * t;
+
+    let mut s = DD::ZERO;
+    for n in (0..23).rev() {
+        s = DD::ONE / DD::from((2 * n + 1) as f64) + t2 * s;
+    }
+
+    t * s
+}
+
+/// Splits the natural logarithm of a positive finite `u` into `e ln 2 + log(m)`, returning e and
+/// log(m) to within about 2^-97 relatively.
+fn log_fast(u: DD) -> (i32, DD) {
+    let e = log_exponent(u.hi());
+    let m = ldexp_dd(u, -e);
+
+    // log(m) = 2 atanh(t) for t = (m - 1) / (m + 1), so that |t| < 0.172
+    let t = (m - DD::ONE) / (m + DD::ONE);
+    (e, DD::from(2.0) * atanh_fast(t))
+}
+
+/// ln 2 to `prec` bits, with its error in units in the last place
+fn ln2_slow(prec: u32) -> (Fixed, u64) {
+    // ln 2 = 2 atanh(1/3), the sum of 2 / ((2n + 1) 3^(2n + 1)). Each power is off by less than
+    // 9/8 units, each term by less than 3, and the truncated tail adds up to less than 3.
+    let mut power = Fixed::from_int(2, prec).div_int(3);
+    let mut sum = power.clone();
+    let mut terms = 1;
+
+    loop {
+        power = power.div_int(9);
+        if power.is_zero() {
+            break;
+        }
+
+        sum = sum.add(&power.div_int(2 * terms + 1));
+        terms += 1;
+    }
+
+    (sum, 3 * terms as u64 + 3)
+}
+
+/// `e^r` for `|r| <= 1/2`, given `r` to within `r_err` units, with the error of the result.
+fn exp_taylor(r: &Fixed, r_err: u64) -> (Fixed, u64) {
+    // With r exact, each term is off by less than 4 units and the truncated tail adds up to less
+    // than 8. The error of r moves the result by less than e^(1/2) < 2 times as much.
+    let mut term = Fixed::from_int(1, r.prec);
+    let mut sum = term.clone();
+    let mut n = 1;
+
+    loop {
+        term = term.mul(r).div_int(n);
+        if term.is_zero() {
+            break;
+        }
+
+        sum = sum.add(&term);
+        n += 1;
+    }
+
+    (sum, 4 * n as u64 + 8 + 2 * r_err)
+}
+
+/// `log(m)` for `m` within [sqrt(1/2), sqrt(2)), or a little more, given to within `m_err` units,
+/// with the error of the result.
+fn log_atanh(m: &Fixed, m_err: u64) -> (Fixed, u64) {
+    // As in log_fast. With t exact, each term is off by less than 3 units and the truncated tail
+    // adds up to less than 3, before doubling. The error of t, less than m_err + 1 units, moves
+    // the result by less than 2 / (1 - t^2) < 3 times as much.
+    let one = Fixed::from_int(1, m.prec);
+    let t = m.sub(&one).div(&m.add(&one));
+    let t2 = t.mul(&t);
+
+    let mut power = t.clone();
+    let mut sum = t;
+    let mut n = 1;
+
+    loop {
+        power = power.mul(&t2);
+        if power.is_zero() {
+            break;
+        }
+
+        sum = sum.add(&power.div_int(2 * n + 1));
+        n += 1;
+    }
+
+    (sum.scale(1), 2 * (3 * n as u64 + 3) + 3 * (m_err + 1))
+}
+
+/// Slow path of the logarithms: e and log(m) for `hi + lo = m * 2^e`, as in log_fast, with the
+/// error of log(m)
+fn log_parts_slow(hi: f64, lo: f64, prec: u32) -> (i32, Fixed, u64) {
+    let e = log_exponent(hi);
+    let m = Fixed::from_f64(hi, -e, prec).add(&Fixed::from_f64(lo, -e, prec));
+    let (log_m, err) = log_atanh(&m, 2);
+
+    (e, log_m, err)
+}
+
+/// Natural logarithm of `v >= 1`, given to within `v_err` units times v, with the error of the
+/// result
+fn log_fixed(v: &Fixed, v_err: u64) -> (Fixed, u64) {
+    // log(v) = j ln 2 + log(v / 2^j), for the last within the range of log_atanh and so off by
+    // less than 2 v_err + 1 units. The leading bits of v place j, however large v is.
+    let b = v.mag.bits() as i32 - v.prec as i32 - 1;
+    let j = b + log_exponent(v.scale(-b).to_f64(0, Round::TiesToEven));
+    let (log_m, err) = log_atanh(&v.scale(-j), 2 * v_err + 1);
+    let (ln2, ln2_err) = ln2_slow(v.prec);
+
+    (log_m.add(&ln2.mul_int(j)), err + j as u64 * ln2_err)
+}
+
+// Slow paths, each returning a value, its error and the power of two to scale both by, as `ziv`
+// expects
+
+fn exp_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    let k = (x * LOG2_E.0).round() as i32;
+    let (ln2, ln2_err) = ln2_slow(prec);
+    let r = Fixed::from_f64(x, 0, prec).sub(&ln2.mul_int(k));
+    let (value, err) = exp_taylor(&r, k.unsigned_abs() as u64 * ln2_err + 1);
+
+    (value, err, k)
+}
+
+fn exp2_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    let k = x.round();
+    let (ln2, ln2_err) = ln2_slow(prec);
+    let r = Fixed::from_f64(x - k, 0, prec).mul(&ln2);
+    let (value, err) = exp_taylor(&r, ln2_err + 2);
+
+    (value, err, k as i32)
+}
+
+fn expm1_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    // e^x - 1 = (e^r - 2^-k) 2^k
+    let (value, err, k) = exp_slow(x, prec);
+    (value.sub(&Fixed::from_int(1, prec).scale(-k)), err + 1, k)
+}
+
+fn log_slow(hi: f64, lo: f64, prec: u32) -> (Fixed, u64, i32) {
+    let (e, log_m, err) = log_parts_slow(hi, lo, prec);
+    let (ln2, ln2_err) = ln2_slow(prec);
+
+    (ln2.mul_int(e).add(&log_m), err + e.unsigned_abs() as u64 * ln2_err, 0)
+}
+
+fn log2_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    // e + log(m) / ln 2, where |log(m)| < 0.35 and 1 / ln 2 < 1.45
+    let (e, log_m, err) = log_parts_slow(x, 0.0, prec);
+    let (ln2, ln2_err) = ln2_slow(prec);
+
+    (log_m.div(&ln2).add(&Fixed::from_int(e as i64, prec)), 2 * err + ln2_err + 1, 0)
+}
+
+/// Natural logarithm of the positive finite `u`, to within about 2^-97 relatively unless it is
+/// close to zero
+fn log_dd(u: DD) -> DD {
+    let (e, log_m) = log_fast(u);
+    DD::from(e as f64) * dd(LN_2) + log_m
+}
+
+/// Natural logarithm of the positive finite `u`, which must not be 1
+fn log_round_dd(u: DD, mode: Round) -> f64 {
+    round_fast(log_dd(u), 0, mode).unwrap_or_else(|| ziv(mode, |prec| log_slow(u.hi(), u.lo(), prec)))
+}
+
+/// Computes e^x, correctly rounded under the given rounding mode.
+pub fn exp_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x.is_infinite() {
+        return if x > 0.0 { x } else { 0.0 };
+    } else if x == 0.0 {
+        return 1.0;
+    } else if x.abs() < TINY {
+        // 1 + x rounds like e^x, since the only float or midpoint near either is 1 itself
+        return DD::new(1.0, x).to_f64(mode);
+    } else if x.abs() > 1000.0 {
+        return pow2_rounded(if x > 0.0 { 2000 } else { -2000 }, mode);
+    }
+
+    let (k, r) = reduce_exp(x);
+    round_fast(DD::ONE + expm1_reduced(r), k, mode).unwrap_or_else(|| ziv(mode, |prec| exp_slow(x, prec)))
+}
+
+/// Computes 2^x, correctly rounded under the given rounding mode.
+pub fn exp2_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x.is_infinite() {
+        return if x > 0.0 { x } else { 0.0 };
+    } else if x == x.round() {
+        return pow2_rounded(x.clamp(-2000.0, 2000.0) as i32, mode);
+    } else if x.abs() < TINY {
+        // As for exp
+        return DD::new(1.0, x).to_f64(mode);
+    } else if x.abs() > 1100.0 {
+        return pow2_rounded(if x > 0.0 { 2000 } else { -2000 }, mode);
+    }
+
+    let k = x.round();
+    let r = DD::from(x - k) * dd(LN_2);
+    round_fast(DD::ONE + expm1_reduced(r), k as i32, mode).unwrap_or_else(|| ziv(mode, |prec| exp2_slow(x, prec)))
+}
+
+/// Computes e^x - 1, correctly rounded under the given rounding mode.
+pub fn expm1_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x == 0.0 || x == f64::INFINITY {
+        return x;
+    } else if x == f64::NEG_INFINITY {
+        return -1.0;
+    } else if x.abs() < TINY {
+        // e^x - 1 = x + x^2 / 2 + ..., just above x
+        return nudge(x, true, mode);
+    } else if x < -40.0 {
+        // Within e^-40 < 2^-57 above -1
+        return nudge(-1.0, true, mode);
+    } else if x > 710.0 {
+        return pow2_rounded(2000, mode);
+    }
+
+    let (k, r) = reduce_exp(x);
+    let t = expm1_reduced(r);
+    let d = if k == 0 { t } else { (DD::ONE + t) - DD::from(pow2(-k)) };
+
+    round_fast(d, k, mode).unwrap_or_else(|| ziv(mode, |prec| expm1_slow(x, prec)))
+}
+
+/// Computes the natural logarithm of x, correctly rounded under the given rounding mode.
+pub fn log_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x < 0.0 {
+        return f64::NAN;
+    } else if x == 0.0 {
+        return f64::NEG_INFINITY;
+    } else if x == f64::INFINITY {
+        return x;
+    } else if x == 1.0 {
+        return 0.0;
+    }
+
+    log_round_dd(DD::from(x), mode)
+}
+
+/// Computes the base 2 logarithm of x, correctly rounded under the given rounding mode.
+pub fn log2_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x < 0.0 {
+        return f64::NAN;
+    } else if x == 0.0 {
+        return f64::NEG_INFINITY;
+    } else if x == f64::INFINITY {
+        return x;
+    }
+
+    let parts = FloatParts::of_f64(x);
+    if parts.mant.is_power_of_two() {
+        return (parts.exp + parts.mant.trailing_zeros() as i32) as f64;
+    }
+
+    let (e, log_m) = log_fast(DD::from(x));
+    let d = DD::from(e as f64) + log_m * dd(LOG2_E);
+
+    round_fast(d, 0, mode).unwrap_or_else(|| ziv(mode, |prec| log2_slow(x, prec)))
+}
+
+/// Computes log(1 + x), correctly rounded under the given rounding mode.
+pub fn log1p_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x < -1.0 {
+        return f64::NAN;
+    } else if x == -1.0 {
+        return f64::NEG_INFINITY;
+    } else if x == 0.0 || x == f64::INFINITY {
+        return x;
+    } else if x.abs() < TINY {
+        // log(1 + x) = x - x^2 / 2 + ..., just below x
+        return nudge(x, false, mode);
+    }
+
+    // 1 + x, exactly
+    log_round_dd(DD::new(1.0, x), mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::successor::{identical_f64, ulp_distance_f64};
+    use crate::test_cases::*;
+    use std::f64::consts;
+
+    pub(super) type Function = fn(f64, Round) -> f64;
+
+    /// Random argument generator for the named function, e.g., each module's `random_arg`
+    pub(super) type ArgFn = fn(&str, &mut SplitMix64) -> f64;
+
+    const FUNCTIONS: [(&str, Function); 6] = [
+        ("exp", exp_round),
+        ("exp2", exp2_round),
+        ("expm1", expm1_round),
+        ("log", log_round),
+        ("log2", log2_round),
+        ("log1p", log1p_round),
+    ];
+
+    /// Random sign, -1 or 1
+    pub(super) fn random_sign(rng: &mut SplitMix64) -> f64 {
+        if rng.coin() { -1.0 } else { 1.0 }
+    }
+
+    /// Random positive value with an exponent in `[lo, hi]`
+    pub(super) fn magnitude(rng: &mut SplitMix64, lo: i32, hi: i32) -> f64 {
+        rng.significand_f64() * 2f64.powi(rng.range(lo, hi))
+    }
+
+    /// Whether a result is within an ulp of the standard library's, which are nearly correctly
+    /// rounded
+    pub(super) fn near_std(actual: f64, std: f64) -> bool {
+        ulp_distance_f64(actual, std) <= 1
+    }
+
+    /// Checks each function against the reference table in every rounding mode.
+    pub(super) fn check_references(functions: &[(&str, Function)]) {
+        for &(name, f) in functions {
+            check_reference(name, |args, mode| f(args[0], mode));
+        }
+    }
+
+    /// Checks each function at an argument against its value correctly rounded to nearest, e.g.,
+    /// a standard library constant or a multiple-precision library's value, and that rounding
+    /// down and up gives the floats either side of the exact value.
+    pub(super) fn check_known_values(cases: &[(Function, f64, f64)]) {
+        for &(f, x, expected) in cases {
+            assert_eq!(f(x, Round::TiesToEven), expected, "{}", x);
+
+            let (lower, upper) = (f(x, Round::TowardNInf), f(x, Round::TowardPInf));
+            assert!(lower <= expected && expected <= upper && upper == successor_f64(lower), "{}", x);
+        }
+    }
+
+    /// Checks that every rounding mode picks the right one of the two floats around each
+    /// function's result, on arguments from `random_arg` that must not give exact results.
+    pub(super) fn check_directions(functions: &[(&str, Function)], random_arg: ArgFn) {
+        let mut rng = SplitMix64::new(SEED ^ 1);
+
+        for &(name, f) in functions {
+            for _ in 0..2000 {
+                let x = random_arg(name, &mut rng);
+                let (lower, upper, nearest) = (f(x, Round::TowardNInf), f(x, Round::TowardPInf), f(x, Round::TiesToEven));
+
+                let context = format!("{}({:e})", name, x);
+                assert_eq!(upper, successor_f64(lower), "{}", context);
+                assert!(nearest == lower || nearest == upper, "{}", context);
+                assert_eq!(f(x, Round::TowardZero), if lower >= 0.0 { lower } else { upper }, "{}", context);
+                assert_eq!(f(x, Round::TiesAway), nearest, "{}", context);
+                assert_eq!(f(x, Round::TiesToOdd), nearest, "{}", context);
+            }
+        }
+    }
+
+    /// Random argument for the named function, mostly within its interesting range, where
+    /// neither shortcuts nor huge intermediate precisions apply
+    fn random_arg(name: &str, rng: &mut SplitMix64) -> f64 {
+        let sign = random_sign(rng);
+
+        match name {
+            "exp" => sign * magnitude(rng, -60, 9).min(745.0),
+            "exp2" => sign * magnitude(rng, -60, 10).min(1074.5),
+            "expm1" => (sign * magnitude(rng, -60, 9)).clamp(-40.0, 709.0),
+            "log" | "log2" => f64::from_bits(rng.next_u64() >> 1).min(f64::MAX),
+            _ => match rng.range(0, 2) {
+                0 => sign * magnitude(rng, -60, -1),
+                1 => magnitude(rng, 0, 1023),
+                _ => -1.0 + magnitude(rng, -52, -1),
+            },
+        }
+    }
+
+    #[test]
+    fn test_ziv_cap() {
+        // An exact midpoint between 1 and the float above never rounds, but gives up with bounds
+        let midpoint = |prec: u32| (Fixed::from_int(1, prec).add(&Fixed::ulps(1, prec).scale(prec as i32 - 53)), 1, 0);
+        assert_eq!(ziv(Round::TowardNInf, midpoint), 1.0);
+        assert_eq!(ziv(Round::TowardPInf, midpoint), 1.0 + f64::EPSILON);
+        assert_eq!(ziv(Round::TowardZero, midpoint), 1.0);
+        assert_eq!(ziv(Round::TiesToEven, midpoint), 1.0);
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(dd(LN_2), "0.693147180559945309417232121458176568".parse::<DD>().unwrap());
+        assert_eq!(dd(LOG2_E), "1.44269504088896340735992468100189214".parse::<DD>().unwrap());
+
+        // The slow path's ln 2 against the double-double one, which is within 2^-107
+        let (ln2, err) = ln2_slow(160);
+        let ln2_dd = Fixed::from_f64(LN_2.0, 0, 160).add(&Fixed::from_f64(LN_2.1, 0, 160));
+        assert!(ln2.sub(&ln2_dd).mag <= Fixed::ulps(err + (1 << 53), 160).mag);
+        assert_eq!(ln2.round_ball(err, 0, Round::TiesToEven), Some(consts::LN_2));
+    }
+
+    #[test]
+    fn test_special_values() {
+        for mode in Round::ALL {
+            assert_eq!(exp_round(0.0, mode), 1.0);
+            assert_eq!(exp_round(f64::NEG_INFINITY, mode), 0.0);
+            assert_eq!(exp_round(f64::INFINITY, mode), f64::INFINITY);
+            assert_eq!(exp2_round(-3.0, mode), 0.125);
+            assert_eq!(exp2_round(-1074.0, mode), 5e-324);
+            assert_eq!(exp2_round(1023.0, mode), 2f64.powi(1023));
+            assert!(identical_f64(expm1_round(-0.0, mode), -0.0));
+            assert_eq!(expm1_round(f64::NEG_INFINITY, mode), -1.0);
+
+            assert!(identical_f64(log_round(1.0, mode), 0.0));
+            assert_eq!(log_round(0.0, mode), f64::NEG_INFINITY);
+            assert_eq!(log_round(f64::INFINITY, mode), f64::INFINITY);
+            assert_eq!(log2_round(1024.0, mode), 10.0);
+            assert_eq!(log2_round(0.25, mode), -2.0);
+            assert_eq!(log2_round(5e-324, mode), -1074.0);
+            assert!(identical_f64(log2_round(1.0, mode), 0.0));
+            assert_eq!(log1p_round(-1.0, mode), f64::NEG_INFINITY);
+            assert!(identical_f64(log1p_round(-0.0, mode), -0.0));
+
+            for (_, f) in FUNCTIONS {
+                assert!(f(f64::NAN, mode).is_nan());
+            }
+            assert!(log_round(-1.0, mode).is_nan() && log2_round(-0.5, mode).is_nan());
+            assert!(log1p_round(-1.5, mode).is_nan() && log_round(f64::NEG_INFINITY, mode).is_nan());
+        }
+
+        // 2^-1075 is a tie between zero and the smallest subnormal, 2^1024 overflows
+        assert_eq!(exp2_round(-1075.0, Round::TiesToEven), 0.0);
+        assert_eq!(exp2_round(-1075.0, Round::TiesAway), 5e-324);
+        assert_eq!(exp2_round(-1075.0, Round::TowardPInf), 5e-324);
+        assert_eq!(exp2_round(1024.0, Round::TowardZero), f64::MAX);
+        assert_eq!(exp2_round(1024.0, Round::TiesToEven), f64::INFINITY);
+
+        // Overflow and underflow of the others
+        assert_eq!(exp_round(709.8, Round::TowardNInf), f64::MAX);
+        assert_eq!(exp_round(709.8, Round::TowardPInf), f64::INFINITY);
+        assert_eq!(exp_round(1e10, Round::TowardZero), f64::MAX);
+        assert_eq!(exp_round(-745.2, Round::TiesToEven), 0.0);
+        assert_eq!(exp_round(-745.1, Round::TiesToEven), 5e-324);
+        assert_eq!(exp_round(-1e10, Round::TowardPInf), 5e-324);
+        assert_eq!(expm1_round(-1e10, Round::TowardNInf), -1.0);
+        assert_eq!(expm1_round(-1e10, Round::TowardPInf), -1.0 + f64::EPSILON / 2.0);
+        assert_eq!(expm1_round(1e3, Round::TowardNInf), f64::MAX);
+    }
+
+    #[test]
+    fn test_known_values() {
+        check_known_values(&[
+            (exp_round, 1.0, consts::E),
+            (exp2_round, 0.5, consts::SQRT_2),
+            (exp2_round, -0.5, consts::FRAC_1_SQRT_2),
+            (log_round, 2.0, consts::LN_2),
+            (log_round, 10.0, consts::LN_10),
+            (log2_round, 10.0, consts::LOG2_10),
+            (log2_round, consts::E, consts::LOG2_E),
+            (log1p_round, 1.0, consts::LN_2),
+        ]);
+
+        assert_eq!(exp_round(1.0, Round::TowardNInf), consts::E);
+        assert_eq!(log2_round(consts::E, Round::TowardPInf), consts::LOG2_E);
+    }
+
+    #[test]
+    fn test_reference() {
+        check_references(&FUNCTIONS);
+    }
+
+    #[test]
+    fn test_hard_cases() {
+        // Near powers of two, each function's series puts a float or a midpoint within far less
+        // than 2^-80 of the result, e.g., e^x - 1 = 2^-50 + 2^-101 + 2^-152 / 3 + ... for x = 2^-50,
+        // so these go through the slow path; the reference table has many more
+        let x = 2f64.powi(-50);
+        let (_, r) = reduce_exp(x);
+        assert_eq!(round_fast(expm1_reduced(r), 0, Round::TowardNInf), None);
+        assert_eq!(expm1_round(x, Round::TowardNInf), x + 2f64.powi(-101));
+        assert_eq!(expm1_round(x, Round::TowardPInf), successor_f64(x + 2f64.powi(-101)));
+        assert_eq!(log1p_round(x, Round::TowardNInf), x - 2f64.powi(-101));
+        assert_eq!(exp_round(-x, Round::TowardNInf), 1.0 - x);
+        assert_eq!(log_round(1.0 + x, Round::TowardNInf), x - 2f64.powi(-101));
+    }
+
+    #[test]
+    fn test_rounding_directions() {
+        check_directions(&FUNCTIONS, random_arg);
+    }
+
+    #[test]
+    fn test_inverses() {
+        let mut rng = SplitMix64::new(SEED ^ 2);
+
+        for _ in 0..2000 {
+            let x = random_arg("exp", &mut rng);
+            let (lower, upper) = (exp_round(x, Round::TowardNInf), exp_round(x, Round::TowardPInf));
+            if lower > 0.0 && upper.is_finite() {
+                assert!(log_round(lower, Round::TowardNInf) <= x && x <= log_round(upper, Round::TowardPInf), "{:e}", x);
+            }
+
+            let x = random_arg("expm1", &mut rng);
+            let (lower, upper) = (expm1_round(x, Round::TowardNInf), expm1_round(x, Round::TowardPInf));
+            if upper.is_finite() {
+                assert!(log1p_round(lower, Round::TowardNInf) <= x && x <= log1p_round(upper, Round::TowardPInf), "{:e}", x);
+            }
+
+            let x = random_arg("log", &mut rng);
+            let (lower, upper) = (log2_round(x, Round::TowardNInf), log2_round(x, Round::TowardPInf));
+            assert!(exp2_round(lower, Round::TowardNInf) <= x && x <= exp2_round(upper, Round::TowardPInf), "{:e}", x);
+
+            assert!(near_std(log_round(x, Round::TiesToEven), x.ln()), "{:e}", x);
+            assert!(near_std(log2_round(x, Round::TiesToEven), x.log2()), "{:e}", x);
+        }
+    }
+}
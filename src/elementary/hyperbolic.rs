@@ -0,0 +1,430 @@
+// Correctly rounded hyperbolic functions and their inverses, on the same two speeds as the rest of
+// the module. sinh, cosh and tanh come down to e^|x| = e^r 2^k, computed once, with the sign and
+// the scaling applied last; below 1/2 or so, sinh and tanh go through e^x - 1 instead, which keeps
+// their relative accuracy. The inverses come down to log(1 + w) for an exactly computed w, through
+// the series of atanh when w is small and through log otherwise.
+//
+// As for the exponentials and logarithms, none of these functions takes a rational value at a
+// nonzero rational point, so Ziv's loop terminates everywhere but at zero, and cosh(0) = 1, which
+// are handled up front.
+
+use crate::dd::DD;
+use crate::fixed::Fixed;
+use crate::modes::*;
+use super::{atanh_fast, dd, exp_taylor, expm1_reduced, exponent, ldexp_dd, ln2_slow, log_dd, log_fixed, nudge,
+    pow2, pow2_rounded, reduce_exp, round_f64, round_fast, ziv, LN_2, LOG2_E, TINY};
+
+/// |x| above this makes sinh and cosh overflow
+const OVERFLOW: f64 = 711.0;
+
+/// |x| above this leaves tanh closer to ±1 than any float or midpoint
+const SATURATED: f64 = 20.0;
+
+/// 2^500: from here on, asinh(x) and acosh(x) are log(2|x|) give or take less than 2^-1000
+const HUGE: f64 = 3.273390607896142e150;
+
+/// log(1 + w) for `w > -1`, to within about 2^-97 relatively.
+fn log1p_fast(w: DD) -> DD {
+    if w.hi().abs() < 0.25 {
+        // 2 atanh(w / (2 + w)), for which |w / (2 + w)| < 0.143
+        DD::from(2.0) * atanh_fast(w / (DD::from(2.0) + w))
+    } else {
+        log_dd(DD::ONE + w)
+    }
+}
+
+/// e^|x| as `(k, u)` for u = e^r and `e^|x| = u 2^k`, for `|x| <= OVERFLOW`
+fn exp_fast(a: f64) -> (i32, DD) {
+    let (k, r) = reduce_exp(a);
+    (k, DD::ONE + expm1_reduced(r))
+}
+
+/// `e^-|x|` relative to the scale of `exp_fast`'s u, 2^-2k / u, or zero where that's negligible
+fn reciprocal_fast(k: i32, u: DD) -> DD {
+    if k <= 60 { DD::from(pow2(-2 * k)) / u } else { DD::ZERO }
+}
+
+/// e^a - 1 for `0 <= a <= 2 SATURATED`, to within about 2^-95 relatively
+fn expm1_fast(a: f64) -> DD {
+    let (k, r) = reduce_exp(a);
+    let t = expm1_reduced(r);
+
+    if k == 0 { t } else { ldexp_dd(DD::ONE + t, k) - DD::ONE }
+}
+
+/// e^r and e^-r 2^-2k for `a = k ln 2 + r`, so that e^a = e^r 2^k and e^-a = e^-r 2^-2k 2^k, for
+/// `0 <= a <= OVERFLOW`, with the error of both
+fn exp_pair_slow(a: f64, prec: u32) -> (Fixed, Fixed, u64, i32) {
+    let k = (a * LOG2_E.0).round() as i32;
+    let (ln2, ln2_err) = ln2_slow(prec);
+    let r = Fixed::from_f64(a, 0, prec).sub(&ln2.mul_int(k));
+    let r_err = k as u64 * ln2_err + 1;
+
+    let (p, p_err) = exp_taylor(&r, r_err);
+    let (q, q_err) = exp_taylor(&r.neg(), r_err);
+    (p, q.scale(-2 * k), p_err.max(q_err + 1), k)
+}
+
+fn signed(value: Fixed, negative: bool) -> Fixed {
+    if negative { value.neg() } else { value }
+}
+
+// Slow paths, as `ziv` expects
+
+fn sinh_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    let (p, q, err, k) = exp_pair_slow(x.abs(), prec);
+    (signed(p.sub(&q), x < 0.0), 2 * err, k - 1)
+}
+
+fn cosh_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    let (p, q, err, k) = exp_pair_slow(x.abs(), prec);
+    (p.add(&q), 2 * err, k - 1)
+}
+
+fn tanh_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    // The quotient of the two above, whose denominator is above e^(-ln 2 / 2) > 0.7
+    let (p, q, err, _) = exp_pair_slow(x.abs(), prec);
+    (signed(p.sub(&q).div(&p.add(&q)), x < 0.0), 6 * err + 1, 0)
+}
+
+fn asinh_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    let a = x.abs();
+    let one = Fixed::from_int(1, prec);
+
+    let (value, err) = if a < 1.0 {
+        // log(1 + w) for w = a + a^2 / (1 + sqrt(1 + a^2)), where the root is off by less than 2
+        // units and so is w
+        let t = Fixed::from_f64(a, 0, prec);
+        let t2 = t.mul(&t);
+        let root = Fixed::sqrt_product(&one.add(&t2), &one);
+        log_fixed(&one.add(&t.add(&t2.div(&one.add(&root)))), 2)
+    } else {
+        // log(t + sqrt(t^2 + 4^-e)) + e ln 2 for t = a / 2^e in [1, 2)
+        let e = exponent(a);
+        let t = Fixed::from_f64(a, -e, prec);
+        let root = Fixed::sqrt_product(&t.mul(&t).add(&one.scale(-2 * e)), &one);
+        let (log_v, err) = log_fixed(&t.add(&root), 2);
+        let (ln2, ln2_err) = ln2_slow(prec);
+        (log_v.add(&ln2.mul_int(e)), err + e as u64 * ln2_err)
+    };
+
+    (signed(value, x < 0.0), err, 0)
+}
+
+fn acosh_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    let one = Fixed::from_int(1, prec);
+
+    if x < 1.25 {
+        // log(1 + w) for w = d + sqrt(d (d + 2)) and d = x - 1, exactly
+        let d = Fixed::from_f64(x - 1.0, 0, prec);
+        let w = d.add(&Fixed::sqrt_product(&d, &d.add(&Fixed::from_int(2, prec))));
+        let (value, err) = log_fixed(&one.add(&w), 1);
+        (value, err, 0)
+    } else {
+        // log(t + sqrt(t^2 - 4^-e)) + e ln 2 for t = x / 2^e in [1, 2), where t^2 - 4^-e is above
+        // 1/2, so the root is off by less than 3 units
+        let e = exponent(x);
+        let t = Fixed::from_f64(x, -e, prec);
+        let root = Fixed::sqrt_product(&t.mul(&t).sub(&one.scale(-2 * e)), &one);
+        let (log_v, err) = log_fixed(&t.add(&root), 3);
+        let (ln2, ln2_err) = ln2_slow(prec);
+        (log_v.add(&ln2.mul_int(e)), err + e as u64 * ln2_err, 0)
+    }
+}
+
+fn atanh_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    // log((1 + a) / (1 - a)) / 2, where both 1 + a and 1 - a are exact
+    let a = Fixed::from_f64(x.abs(), 0, prec);
+    let one = Fixed::from_int(1, prec);
+    let (value, err) = log_fixed(&one.add(&a).div(&one.sub(&a)), 1);
+
+    (signed(value, x < 0.0), err, -1)
+}
+
+/// Computes the hyperbolic sine of x, correctly rounded under the given rounding mode.
+pub fn sinh_round(x: f64, mode: Round) -> f64 {
+    let a = x.abs();
+    if x.is_nan() {
+        return x + x;
+    } else if x == 0.0 || x.is_infinite() {
+        return x;
+    } else if a < TINY {
+        // x + x^3 / 6 + ..., just beyond x
+        return nudge(x, x > 0.0, mode);
+    } else if a > OVERFLOW {
+        return round_f64(x < 0.0, 1, 2000, false, mode);
+    }
+
+    let (d, scale) = if a < LN_2.0 / 2.0 {
+        // (E + E / (E + 1)) / 2 for E = e^a - 1, whose terms have the same sign
+        let e = expm1_fast(a);
+        ((e + e / (DD::ONE + e)) * DD::from(0.5), 0)
+    } else {
+        let (k, u) = exp_fast(a);
+        (u - reciprocal_fast(k, u), k - 1)
+    };
+
+    round_fast(if x < 0.0 { -d } else { d }, scale, mode).unwrap_or_else(|| ziv(mode, |prec| sinh_slow(x, prec)))
+}
+
+/// Computes the hyperbolic cosine of x, correctly rounded under the given rounding mode.
+pub fn cosh_round(x: f64, mode: Round) -> f64 {
+    let a = x.abs();
+    if x.is_nan() {
+        return x + x;
+    } else if x.is_infinite() {
+        return a;
+    } else if x == 0.0 {
+        return 1.0;
+    } else if a < TINY {
+        // 1 + x^2 / 2 + ...
+        return nudge(1.0, true, mode);
+    } else if a > OVERFLOW {
+        return pow2_rounded(2000, mode);
+    }
+
+    let (k, u) = exp_fast(a);
+    round_fast(u + reciprocal_fast(k, u), k - 1, mode).unwrap_or_else(|| ziv(mode, |prec| cosh_slow(x, prec)))
+}
+
+/// Computes the hyperbolic tangent of x, correctly rounded under the given rounding mode.
+pub fn tanh_round(x: f64, mode: Round) -> f64 {
+    let a = x.abs();
+    if x.is_nan() {
+        return x + x;
+    } else if x == 0.0 {
+        return x;
+    } else if x.is_infinite() {
+        return x.signum();
+    } else if a < TINY {
+        // x - x^3 / 3 + ..., just short of x
+        return nudge(x, x < 0.0, mode);
+    } else if a > SATURATED {
+        // Within 2 e^-40 < 2^-56 of ±1
+        return nudge(x.signum(), x < 0.0, mode);
+    }
+
+    // E / (E + 2) for E = e^2a - 1
+    let e = expm1_fast(2.0 * a);
+    let d = e / (e + DD::from(2.0));
+
+    round_fast(if x < 0.0 { -d } else { d }, 0, mode).unwrap_or_else(|| ziv(mode, |prec| tanh_slow(x, prec)))
+}
+
+/// Computes the inverse hyperbolic sine of x, correctly rounded under the given rounding mode.
+pub fn asinh_round(x: f64, mode: Round) -> f64 {
+    let a = x.abs();
+    if x.is_nan() {
+        return x + x;
+    } else if x == 0.0 || x.is_infinite() {
+        return x;
+    } else if a < TINY {
+        // x - x^3 / 6 + ..., just short of x
+        return nudge(x, x < 0.0, mode);
+    }
+
+    let t = DD::from(a);
+    let d = if a < 1.0 {
+        let t2 = t * t;
+        log1p_fast(t + t2 / (DD::ONE + (DD::ONE + t2).sqrt()))
+    } else if a < HUGE {
+        log_dd(t + (t * t + DD::ONE).sqrt())
+    } else {
+        // log(2a) + 1 / (4 a^2) - ...
+        log_dd(t) + dd(LN_2)
+    };
+
+    round_fast(if x < 0.0 { -d } else { d }, 0, mode).unwrap_or_else(|| ziv(mode, |prec| asinh_slow(x, prec)))
+}
+
+/// Computes the inverse hyperbolic cosine of x, correctly rounded under the given rounding mode.
+pub fn acosh_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x < 1.0 {
+        return f64::NAN;
+    } else if x == 1.0 {
+        return 0.0;
+    } else if x.is_infinite() {
+        return x;
+    }
+
+    let t = DD::from(x);
+    let d = if x < 1.25 {
+        let s = DD::from(x - 1.0);
+        log1p_fast(s + (s * (s + DD::from(2.0))).sqrt())
+    } else if x < HUGE {
+        log_dd(t + (t * t - DD::ONE).sqrt())
+    } else {
+        // log(2x) - 1 / (4 x^2) - ...
+        log_dd(t) + dd(LN_2)
+    };
+
+    round_fast(d, 0, mode).unwrap_or_else(|| ziv(mode, |prec| acosh_slow(x, prec)))
+}
+
+/// Computes the inverse hyperbolic tangent of x, correctly rounded under the given rounding mode.
+pub fn atanh_round(x: f64, mode: Round) -> f64 {
+    let a = x.abs();
+    if x.is_nan() || a > 1.0 {
+        return if x.is_nan() { x + x } else { f64::NAN };
+    } else if a == 1.0 {
+        return x * f64::INFINITY;
+    } else if x == 0.0 {
+        return x;
+    } else if a < TINY {
+        // x + x^3 / 3 + ..., just beyond x
+        return nudge(x, x > 0.0, mode);
+    }
+
+    // log(1 + 2a / (1 - a)) / 2
+    let t = DD::from(a);
+    let d = log1p_fast(DD::from(2.0) * t / (DD::ONE - t)) * DD::from(0.5);
+
+    round_fast(if x < 0.0 { -d } else { d }, 0, mode).unwrap_or_else(|| ziv(mode, |prec| atanh_slow(x, prec)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elementary::tests::*;
+    use crate::successor::{identical_f64, predecessor_f64, successor_f64};
+    use crate::test_cases::*;
+
+    const FUNCTIONS: [(&str, Function); 6] = [
+        ("sinh", sinh_round),
+        ("cosh", cosh_round),
+        ("tanh", tanh_round),
+        ("asinh", asinh_round),
+        ("acosh", acosh_round),
+        ("atanh", atanh_round),
+    ];
+
+    /// Random argument for the named function, away from the shortcuts
+    fn random_arg(name: &str, rng: &mut SplitMix64) -> f64 {
+        let sign = random_sign(rng);
+
+        match name {
+            "sinh" | "cosh" => sign * magnitude(rng, -60, 9).min(OVERFLOW),
+            "tanh" => sign * magnitude(rng, -60, 4),
+            "asinh" => sign * magnitude(rng, -60, 1023),
+            "acosh" => match rng.range(0, 1) {
+                0 => 1.0 + magnitude(rng, -52, -3),
+                _ => magnitude(rng, 0, 1023),
+            },
+            _ => match rng.range(0, 1) {
+                0 => sign * magnitude(rng, -60, -2),
+                _ => sign * (1.0 - magnitude(rng, -53, -2)),
+            },
+        }
+    }
+
+    #[test]
+    fn test_special_values() {
+        let inf = f64::INFINITY;
+
+        for mode in Round::ALL {
+            for (_, f) in FUNCTIONS {
+                assert!(f(f64::NAN, mode).is_nan());
+            }
+            for f in [sinh_round, tanh_round, asinh_round, atanh_round] {
+                assert!(identical_f64(f(0.0, mode), 0.0) && identical_f64(f(-0.0, mode), -0.0));
+            }
+
+            assert_eq!(sinh_round(-inf, mode), -inf);
+            assert_eq!(cosh_round(-inf, mode), inf);
+            assert_eq!(cosh_round(-0.0, mode), 1.0);
+            assert_eq!(tanh_round(inf, mode), 1.0);
+            assert_eq!(tanh_round(-inf, mode), -1.0);
+            assert_eq!(asinh_round(-inf, mode), -inf);
+            assert_eq!(acosh_round(inf, mode), inf);
+            assert!(identical_f64(acosh_round(1.0, mode), 0.0));
+            assert!(acosh_round(1.0 - f64::EPSILON / 2.0, mode).is_nan() && acosh_round(-inf, mode).is_nan());
+            assert_eq!(atanh_round(1.0, mode), inf);
+            assert_eq!(atanh_round(-1.0, mode), -inf);
+            assert!(atanh_round(1.0 + f64::EPSILON, mode).is_nan() && atanh_round(-inf, mode).is_nan());
+        }
+
+        // The shortcuts near zero and far out
+        let x = 2f64.powi(-70);
+        assert_eq!(sinh_round(x, Round::TowardPInf), successor_f64(x));
+        assert_eq!(sinh_round(-x, Round::TowardZero), -x);
+        assert_eq!(tanh_round(x, Round::TowardZero), predecessor_f64(x));
+        assert_eq!(asinh_round(-x, Round::TowardNInf), -x);
+        assert_eq!(atanh_round(-x, Round::TowardNInf), -successor_f64(x));
+        assert_eq!(cosh_round(x, Round::TowardPInf), successor_f64(1.0));
+        assert_eq!(cosh_round(x, Round::TiesToEven), 1.0);
+        assert_eq!(tanh_round(30.0, Round::TowardPInf), 1.0);
+        assert_eq!(tanh_round(30.0, Round::TowardZero), predecessor_f64(1.0));
+        assert_eq!(tanh_round(-30.0, Round::TowardNInf), -1.0);
+
+        // Overflow, just past its threshold and far beyond
+        assert_eq!(sinh_round(710.5, Round::TowardZero), f64::MAX);
+        assert_eq!(sinh_round(-710.5, Round::TiesToEven), -inf);
+        assert_eq!(sinh_round(-1e10, Round::TowardPInf), f64::MIN);
+        assert_eq!(cosh_round(-710.5, Round::TowardNInf), f64::MAX);
+        assert_eq!(cosh_round(1e10, Round::TowardPInf), inf);
+    }
+
+    #[test]
+    fn test_known_values() {
+        check_known_values(&[
+            (sinh_round, 1.0, 1.1752011936438014),
+            (sinh_round, 0.5, 0.5210953054937474),
+            (sinh_round, -3.0, -10.017874927409903),
+            (sinh_round, 700.0, 5.0711602736750225e303),
+            (cosh_round, 1.0, 1.5430806348152437),
+            (cosh_round, 0.5, 1.1276259652063807),
+            (cosh_round, -3.0, 10.067661995777765),
+            (tanh_round, 1.0, 0.7615941559557649),
+            (tanh_round, 0.5, 0.46211715726000974),
+            (tanh_round, -3.0, -0.9950547536867305),
+            (asinh_round, 1.0, 0.881373587019543),
+            (asinh_round, 0.5, 0.48121182505960347),
+            (asinh_round, -3.0, -1.8184464592320668),
+            (asinh_round, 1e300, 691.4686750787737),
+            (acosh_round, 2.0, 1.3169578969248168),
+            (acosh_round, 1.5, 0.9624236501192069),
+            (acosh_round, 1e300, 691.4686750787737),
+            (atanh_round, 0.5, 0.5493061443340549),
+            (atanh_round, -0.9, -1.4722194895832204),
+            (atanh_round, 0.25, 0.25541281188299536),
+        ]);
+    }
+
+    #[test]
+    fn test_reference() {
+        check_references(&FUNCTIONS);
+    }
+
+    #[test]
+    fn test_rounding_directions() {
+        check_directions(&FUNCTIONS, random_arg);
+    }
+
+    #[test]
+    fn test_inverses() {
+        let mut rng = SplitMix64::new(SEED ^ 2);
+
+        for _ in 0..2000 {
+            let x = random_arg("sinh", &mut rng);
+            let (lower, upper) = (sinh_round(x, Round::TowardNInf), sinh_round(x, Round::TowardPInf));
+            if lower.is_finite() && upper.is_finite() {
+                assert!(asinh_round(lower, Round::TowardNInf) <= x && x <= asinh_round(upper, Round::TowardPInf), "{:e}", x);
+            }
+
+            let x = random_arg("tanh", &mut rng);
+            let (lower, upper) = (tanh_round(x, Round::TowardNInf), tanh_round(x, Round::TowardPInf));
+            assert!(atanh_round(lower, Round::TowardNInf) <= x && x <= atanh_round(upper, Round::TowardPInf), "{:e}", x);
+
+            let x = random_arg("acosh", &mut rng);
+            let (lower, upper) = (acosh_round(x, Round::TowardNInf), acosh_round(x, Round::TowardPInf));
+            assert!(cosh_round(lower, Round::TowardNInf) <= x && x <= cosh_round(upper, Round::TowardPInf), "{:e}", x);
+
+            let x = random_arg("asinh", &mut rng);
+            assert!(near_std(asinh_round(x, Round::TiesToEven), x.asinh()), "{:e}", x);
+            let x = random_arg("cosh", &mut rng);
+            assert!(near_std(cosh_round(x, Round::TiesToEven), x.cosh()), "{:e}", x);
+        }
+    }
+}
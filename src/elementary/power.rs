@@ -0,0 +1,607 @@
+// Correctly rounded powers, roots and norms of f64s. The roots are algebraic, so when the
+// double-double evaluation can't decide the rounding, square roots of sums of squares, reciprocal
+// square roots and cube roots are computed exactly on big integers, up to a sticky bit, and rounded
+// once, with no need for Ziv's loop.
+//
+// pow goes through e^(y log|x|), in double-double arithmetic and then at increasing precisions, as
+// the exponentials do. Unlike theirs, its exact cases aren't only the obvious ones: x^y is rational
+// whenever y is an integer, and a float or a midpoint between two whenever, on top of that, the odd
+// part of x^y fits in 54 bits, or when y = n / 2^k and x is a perfect 2^k-th power. Those are
+// detected and computed exactly up front, since Ziv's loop can't round them.
+
+use crate::bigint::BigUint;
+use crate::dd::DD;
+use crate::fixed::Fixed;
+use crate::modes::*;
+use crate::ops;
+use crate::successor::FloatParts;
+use super::{dd, exp_taylor, exponent, expm1_reduced, ldexp, ln2_slow, log_fast, log_parts_slow, nudge, round_f64, round_fast, ziv, LN_2, LOG2_E};
+
+/// Rounds the n-th root, for n = 2 or 3, of `(mag + frac) * 2^(n exp)`, where `0 <= frac < 1` is
+/// nonzero if and only if `sticky`.
+fn round_root(mag: &BigUint, sticky: bool, exp: i32, n: u32, negative: bool, mode: Round) -> f64 {
+    // Rescale the radicand by 2^(n k) so that its root has 66 bits; the root of its integer part
+    // rounded down is that of the whole, and exact only if both are
+    let k = (66 * n as i32 - mag.bits() as i32).div_euclid(n as i32);
+    let shift = n as i32 * k;
+    let (scaled, sticky) = if shift >= 0 {
+        (mag.shl(shift as u32), sticky)
+    } else {
+        let scaled = mag.shr((-shift) as u32);
+        let lost = scaled.shl((-shift) as u32) != *mag;
+        (scaled, sticky || lost)
+    };
+
+    let root = if n == 2 { scaled.isqrt() } else { scaled.icbrt() };
+    let power = if n == 2 { root.mul(&root) } else { root.mul(&root).mul(&root) };
+
+    round_f64(negative, root.low_u128(), exp - k, sticky || power != scaled, mode)
+}
+
+/// Computes sqrt(x^2 + y^2) without undue overflow or underflow, correctly rounded under the
+/// given rounding mode. Infinite if either argument is, even if the other is a NaN.
+pub fn hypot_round(x: f64, y: f64, mode: Round) -> f64 {
+    if x.is_infinite() || y.is_infinite() {
+        return f64::INFINITY;
+    } else if x.is_nan() || y.is_nan() {
+        return x + y;
+    }
+
+    let (a, b) = (x.abs().max(y.abs()), x.abs().min(y.abs()));
+    if b == 0.0 {
+        return a;
+    } else if exponent(a) - exponent(b) > 60 {
+        // a sqrt(1 + (b / a)^2) lies within 2^-119 above a, relatively
+        return nudge(a, true, mode);
+    }
+
+    // With both scaled so that a lies in [1, 2), which is exact since b stays above 2^-61
+    let scale = exponent(a);
+    let (a_scaled, b_scaled) = (DD::from(ldexp(a, -scale)), DD::from(ldexp(b, -scale)));
+    if let Some(result) = round_fast((a_scaled * a_scaled + b_scaled * b_scaled).sqrt(), scale, mode) {
+        return result;
+    }
+
+    // a^2 + b^2 = (ma^2 4^(ea - e) + mb^2 4^(eb - e)) 4^e exactly, for e the smaller exponent
+    let (pa, pb) = (FloatParts::of_f64(a), FloatParts::of_f64(b));
+    let e = pa.exp.min(pb.exp);
+    let square = |mant: u64, exp: i32| {
+        let m = BigUint::from_u128(mant as u128);
+        m.mul(&m).shl(2 * (exp - e) as u32)
+    };
+
+    round_root(&square(pa.mant, pa.exp).add(&square(pb.mant, pb.exp)), false, e, 2, false, mode)
+}
+
+/// Computes 1 / sqrt(x), correctly rounded under the given rounding mode.
+pub fn rsqrt_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x == 0.0 {
+        return if x.is_sign_negative() { f64::NEG_INFINITY } else { f64::INFINITY };
+    } else if x < 0.0 {
+        return f64::NAN;
+    } else if x == f64::INFINITY {
+        return 0.0;
+    }
+
+    // With x scaled by an even power of two into [1, 4)
+    let k = exponent(x).div_euclid(2);
+    if let Some(result) = round_fast(DD::ONE / DD::from(ldexp(x, -2 * k)).sqrt(), -k, mode) {
+        return result;
+    }
+
+    // With x = m 2^e for an even e, 1 / sqrt(x) is the square root of (2^256 / m) 2^(2 (-128 - e / 2))
+    let parts = FloatParts::of_f64(x);
+    let (m, e) = if parts.exp % 2 == 0 { (parts.mant, parts.exp) } else { (parts.mant << 1, parts.exp - 1) };
+    let (quot, rem) = BigUint::one().shl(256).div_rem(&BigUint::from_u128(m as u128));
+
+    round_root(&quot, !rem.is_zero(), -128 - e / 2, 2, false, mode)
+}
+
+/// Computes the cube root of x, correctly rounded under the given rounding mode.
+pub fn cbrt_round(x: f64, mode: Round) -> f64 {
+    if !x.is_finite() || x == 0.0 {
+        return x + x;
+    }
+
+    // One Newton step from the cube root of x scaled into [1, 8) doubles its 52 bits
+    let k = exponent(x).div_euclid(3);
+    let scaled = DD::from(ldexp(x.abs(), -3 * k));
+    let c = DD::from(scaled.hi().cbrt());
+    let root = c + (scaled - c * c * c) / (DD::from(3.0) * c * c);
+    if let Some(result) = round_fast(if x < 0.0 { -root } else { root }, k, mode) {
+        return result;
+    }
+
+    // With x = m 2^e for e a multiple of 3
+    let parts = FloatParts::of_f64(x);
+    let r = parts.exp.rem_euclid(3);
+    let m = BigUint::from_u128((parts.mant as u128) << r);
+
+    round_root(&m, false, (parts.exp - r) / 3, 3, parts.negative, mode)
+}
+
+/// Whether `y` is an odd integer
+fn is_odd_integer(y: f64) -> bool {
+    y == y.trunc() && y.abs() < 2f64.powi(53) && (y as i64) % 2 != 0
+}
+
+/// `x^y` for a finite positive `x` other than 1 and a finite nonzero `y`, as `(mant, exp)` for
+/// `mant * 2^exp`, if it is a float or a midpoint between two, or a power of two out of range.
+fn pow_exact(x: f64, y: f64) -> Option<(u128, i32)> {
+    // x = odd 2^e and |y| = n 2^ye, for odd integers
+    let parts = FloatParts::of_f64(x);
+    let (odd, e) = (parts.mant >> parts.mant.trailing_zeros(), parts.exp + parts.mant.trailing_zeros() as i32);
+    let y_parts = FloatParts::of_f64(y);
+    let (n, ye) = (y_parts.mant >> y_parts.mant.trailing_zeros(), y_parts.exp + y_parts.mant.trailing_zeros() as i32);
+
+    // A power of two, 2^(e y), as long as e y is an integer; products too large for f64 to carry
+    // exactly are far out of range anyway
+    if odd == 1 {
+        let integral = ye >= 0 || e.trailing_zeros() as i32 >= -ye;
+        return integral.then(|| (1, (e as f64 * y).clamp(-3000.0, 3000.0) as i32));
+    }
+
+    // Otherwise, 1 / x^|y| is no dyadic fraction, and x^y is one only if the odd part is a perfect
+    // 2^k-th power for k = -ye, and e a multiple of 2^k, which no odd part above 1 and below 2^53
+    // is for k > 5
+    if y < 0.0 || ye < -5 || (ye < 0 && e.trailing_zeros() < ye.unsigned_abs()) {
+        return None;
+    }
+
+    let (mut root, power) = if ye >= 0 {
+        // Only odd parts of x^y below 2^54 qualify, which rules out any y beyond 54 here
+        if y > 54.0 {
+            return None;
+        }
+        (odd, y as u32)
+    } else {
+        (odd, n as u32)
+    };
+
+    for _ in 0..(-ye).max(0) {
+        let s = (root as f64).sqrt() as u64;
+        if s * s != root {
+            return None;
+        }
+        root = s;
+    }
+
+    if power > 54 {
+        return None;
+    }
+
+    let mut mant = 1u128;
+    for _ in 0..power {
+        mant *= root as u128;
+        if mant >= 1 << 54 {
+            return None;
+        }
+    }
+
+    Some((mant, (e >> (-ye).max(0)) * power as i32))
+}
+
+/// Splits `y log|x|` into `k ln 2 + r` and returns k and e^r, to within about 2^-85 relatively,
+/// for `|y log2|x|| <= 1200`.
+fn pow_fast(x: f64, y: f64) -> (i32, DD) {
+    let (e, log_m) = log_fast(DD::from(x.abs()));
+    let t = DD::from(y) * (DD::from(e as f64) * dd(LN_2) + log_m);
+
+    let k = (t.hi() * LOG2_E.0).round();
+    let r = t - DD::from(k) * dd(LN_2);
+    (k as i32, DD::ONE + expm1_reduced(r))
+}
+
+fn pow_slow(x: f64, y: f64, negative: bool, prec: u32) -> (Fixed, u64, i32) {
+    // The logarithm to as many more bits as y has integer bits, so that y log|x| is off by a few
+    // units of prec bits, plus up to 1024 from truncating y, if it is tiny
+    let extra = (exponent(y) + 1).max(0) as u32;
+    let wp = prec + extra;
+    let (e, log_m, log_m_err) = log_parts_slow(x.abs(), 0.0, wp);
+    let (ln2, ln2_err) = ln2_slow(wp);
+    let log = ln2.mul_int(e).add(&log_m);
+
+    let t = Fixed::from_f64(y, 0, wp).mul(&log);
+    let t = Fixed { mag: t.mag.shr(extra), prec, ..t };
+    let t_err = log_m_err + e.unsigned_abs() as u64 * ln2_err + 1027;
+
+    // Then e^t as in exp_slow
+    let k = (t.to_f64(0, Round::TiesToEven) * LOG2_E.0).round() as i32;
+    let (ln2, ln2_err) = ln2_slow(prec);
+    let r = t.sub(&ln2.mul_int(k));
+    let (value, err) = exp_taylor(&r, t_err + k.unsigned_abs() as u64 * ln2_err + 1);
+
+    (if negative { value.neg() } else { value }, err, k)
+}
+
+/// Computes x^y, correctly rounded under the given rounding mode. Special values follow IEEE 754
+/// pow, so that, e.g., `pow(x, 0)` and `pow(1, y)` are 1 even for NaNs, and `pow(-1, ±∞)` is 1.
+pub fn pow_round(x: f64, y: f64, mode: Round) -> f64 {
+    if y == 0.0 || x == 1.0 {
+        return 1.0;
+    } else if x.is_nan() || y.is_nan() {
+        return x + y;
+    } else if y.is_infinite() {
+        return match (x.abs().partial_cmp(&1.0).unwrap(), y > 0.0) {
+            (std::cmp::Ordering::Equal, _) => 1.0,
+            (std::cmp::Ordering::Less, true) | (std::cmp::Ordering::Greater, false) => 0.0,
+            _ => f64::INFINITY,
+        };
+    }
+
+    // Zeros and infinities keep their sign for odd integer powers only
+    let odd = is_odd_integer(y);
+    if x == 0.0 || x.is_infinite() {
+        let magnitude = if (x == 0.0) == (y < 0.0) { f64::INFINITY } else { 0.0 };
+        return if odd && x.is_sign_negative() { -magnitude } else { magnitude };
+    } else if x < 0.0 && y != y.trunc() {
+        return f64::NAN;
+    }
+
+    let negative = x < 0.0 && odd;
+    let a = x.abs();
+    if a == 1.0 {
+        return if negative { -1.0 } else { 1.0 };
+    } else if let Some((mant, exp)) = pow_exact(a, y) {
+        return round_f64(negative, mant, exp, false, mode);
+    }
+
+    // y log2|x|, within a few ulps, unless it underflows
+    let estimate = y * a.log2();
+    if estimate.abs() < 2f64.powi(-62) {
+        // e^t for |t| < 2^-62 rounds like 1 nudged in the direction of t, whose sign is exact
+        let t_positive = (y > 0.0) == (a > 1.0);
+        return nudge(if negative { -1.0 } else { 1.0 }, t_positive != negative, mode);
+    } else if estimate.abs() > 1200.0 {
+        return round_f64(negative, 1, if estimate > 0.0 { 2000 } else { -2000 }, false, mode);
+    }
+
+    let (k, d) = pow_fast(a, y);
+    round_fast(if negative { -d } else { d }, k, mode).unwrap_or_else(|| ziv(mode, |prec| pow_slow(a, y, negative, prec)))
+}
+
+/// Computes x^n by repeated multiplication, rounded in the direction of the given rounding mode
+/// though not necessarily correctly: every product rounds the same way, so the result bounds x^n
+/// from below under `TowardNInf`, from above under `TowardPInf` and from the side of zero under
+/// `TowardZero`. The other modes round correctly, through pow_round.
+pub fn powi_round(x: f64, n: i32, mode: Round) -> f64 {
+    let negative = x.is_sign_negative() && n % 2 != 0;
+    let up = match mode {
+        Round::TowardPInf => !negative,
+        Round::TowardNInf => negative,
+        Round::TowardZero => false,
+        _ => return pow_round(x, n as f64, mode),
+    };
+
+    // |x|^|n| by squaring, each product of nonnegative values rounded the same way, and its
+    // reciprocal rounded the same way from a bound on the other side for negative n
+    let magnitude = |up: bool| {
+        let mode = if up { Round::TowardPInf } else { Round::TowardNInf };
+        let (mut base, mut k, mut result) = (x.abs(), n.unsigned_abs(), 1.0);
+
+        while k != 0 {
+            if k & 1 == 1 {
+                result = ops::mul_f64(result, base, mode);
+            }
+            k >>= 1;
+            if k != 0 {
+                base = ops::mul_f64(base, base, mode);
+            }
+        }
+        result
+    };
+
+    let result = if n >= 0 {
+        magnitude(up)
+    } else {
+        ops::div_f64(1.0, magnitude(!up), if up { Round::TowardPInf } else { Round::TowardNInf })
+    };
+
+    if negative { -result } else { result }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elementary::tests::{magnitude, near_std};
+    use crate::fallback::Format;
+    use crate::oracle::{Rational, Value};
+    use crate::successor::{identical_f64, predecessor_f64, successor_f64};
+    use crate::test_cases::*;
+    use std::cmp::Ordering;
+
+    fn exact(f: f64) -> Rational {
+        match Value::decode(Format::BINARY64, f.to_bits() as u128) {
+            Value::Finite(r) => r,
+            _ => panic!("{} is not finite", f),
+        }
+    }
+
+    /// Sign of `a - b`
+    fn compare(a: &Rational, b: &Rational) -> Ordering {
+        let diff = a.add(&b.neg());
+        if diff.is_zero() { Ordering::Equal } else if diff.negative { Ordering::Less } else { Ordering::Greater }
+    }
+
+    fn power(r: &Rational, n: u32) -> Rational {
+        (1..n).fold(r.clone(), |p, _| p.mul(r))
+    }
+
+    /// Checks `f` in every mode against the n-th root of the positive `radicand`, through the
+    /// n-th powers of the results and of the midpoint between them; against the radicand itself
+    /// for n = 1
+    fn check_root(f: impl Fn(Round) -> f64, n: u32, radicand: &Rational, context: &str) {
+        let (lower, upper) = (f(Round::TowardNInf), f(Round::TowardPInf));
+        let vs_lower = compare(radicand, &power(&exact(lower), n));
+        assert_ne!(vs_lower, Ordering::Less, "{}", context);
+
+        if vs_lower == Ordering::Equal {
+            for mode in Round::ALL {
+                assert_eq!(f(mode), lower, "{}, {:?}", context, mode);
+            }
+            return;
+        }
+
+        assert_eq!(upper, successor_f64(lower), "{}", context);
+        assert_eq!(compare(radicand, &power(&exact(upper), n)), Ordering::Less, "{}", context);
+        assert_eq!(f(Round::TowardZero), lower, "{}", context);
+
+        let midpoint = exact(lower).add(&exact(upper)).mul(&Rational::from_parts(false, 1, -1));
+        let (nearest, tie) = match compare(radicand, &power(&midpoint, n)) {
+            Ordering::Less => (lower, false),
+            Ordering::Greater => (upper, false),
+            Ordering::Equal => (if lower.to_bits() & 1 == 0 { lower } else { upper }, true),
+        };
+        assert_eq!(f(Round::TiesToEven), nearest, "{}", context);
+        assert_eq!(f(Round::TiesAway), if tie { upper } else { nearest }, "{}", context);
+        assert_eq!(f(Round::TiesToOdd), if tie && nearest == lower { upper } else if tie { lower } else { nearest }, "{}", context);
+    }
+
+    /// The mode that rounds -x as this one rounds x
+    fn mirrored(mode: Round) -> Round {
+        match mode {
+            Round::TowardPInf => Round::TowardNInf,
+            Round::TowardNInf => Round::TowardPInf,
+            _ => mode,
+        }
+    }
+
+    #[test]
+    fn test_roots() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..1000 {
+            let (x, y) = (magnitude(&mut rng, -600, 600), magnitude(&mut rng, -600, 600) * 2f64.powi(rng.range(-70, 0)));
+            let radicand = exact(x).mul(&exact(x)).add(&exact(y).mul(&exact(y)));
+            check_root(|mode| hypot_round(x, -y, mode), 2, &radicand, &format!("hypot({:e}, {:e})", x, y));
+
+            let x = f64::from_bits(rng.next_u64() >> 1).min(f64::MAX);
+            let radicand = Rational::from_parts(false, 1, 0).div(&exact(x));
+            check_root(|mode| rsqrt_round(x, mode), 2, &radicand, &format!("rsqrt({:e})", x));
+            check_root(|mode| cbrt_round(x, mode), 3, &exact(x), &format!("cbrt({:e})", x));
+            check_root(|mode| -cbrt_round(-x, mirrored(mode)), 3, &exact(x), &format!("cbrt({:e})", -x));
+        }
+
+        // Exact results, midpoints and the ends of the range
+        let cases = [(3.0, 4.0), (5e-324, 5e-324), (1e300, 1e300), (1e-320, 3e-321), (134217727.0, 0.0), (1.5, 2f64.powi(-60))];
+        for (x, y) in cases {
+            let radicand = exact(x).mul(&exact(x)).add(&exact(y).mul(&exact(y)));
+            check_root(|mode| hypot_round(x, y, mode), 2, &radicand, &format!("hypot({:e}, {:e})", x, y));
+        }
+        for x in [4.0, 0.25, 2.0, 5e-324, f64::MIN_POSITIVE, f64::MAX, 27.0, 1.0 / 3.0, 1e-310] {
+            let radicand = Rational::from_parts(false, 1, 0).div(&exact(x));
+            check_root(|mode| rsqrt_round(x, mode), 2, &radicand, &format!("rsqrt({:e})", x));
+            check_root(|mode| cbrt_round(x, mode), 3, &exact(x), &format!("cbrt({:e})", x));
+        }
+
+        for mode in Round::ALL {
+            assert_eq!(hypot_round(3.0, -4.0, mode), 5.0);
+            assert_eq!(hypot_round(f64::NAN, f64::NEG_INFINITY, mode), f64::INFINITY);
+            assert!(hypot_round(f64::NAN, 1.0, mode).is_nan());
+            assert!(identical_f64(hypot_round(-0.0, 0.0, mode), 0.0));
+            assert_eq!(rsqrt_round(0.25, mode), 2.0);
+            assert_eq!(rsqrt_round(-0.0, mode), f64::NEG_INFINITY);
+            assert!(identical_f64(rsqrt_round(f64::INFINITY, mode), 0.0));
+            assert!(rsqrt_round(-1.0, mode).is_nan());
+            assert_eq!(cbrt_round(-27.0, mode), -3.0);
+            assert!(identical_f64(cbrt_round(-0.0, mode), -0.0));
+            assert_eq!(cbrt_round(f64::NEG_INFINITY, mode), f64::NEG_INFINITY);
+        }
+
+        // hypot doesn't overflow or underflow before it has to
+        assert_eq!(hypot_round(f64::MAX, f64::MAX, Round::TowardZero), f64::MAX);
+        assert_eq!(hypot_round(f64::MAX, f64::MAX, Round::TiesToEven), f64::INFINITY);
+        assert_eq!(hypot_round(3e-320, 4e-320, Round::TiesToEven), 5e-320);
+        assert_eq!(hypot_round(1.0, 1e-30, Round::TowardPInf), successor_f64(1.0));
+    }
+
+    #[test]
+    fn test_pow_rational() {
+        let mut rng = SplitMix64::new(SEED);
+
+        // Integer powers are rational, and square and fourth roots of them algebraic, so each
+        // checks against an exact radicand whether or not it's a float or a midpoint
+        for _ in 0..300 {
+            let x = magnitude(&mut rng, -12, 12);
+            let k = rng.range(1, 40) as u32;
+            check_root(|mode| pow_round(x, k as f64, mode), 1, &power(&exact(x), k), &format!("pow({:e}, {})", x, k));
+            let reciprocal = Rational::from_parts(false, 1, 0).div(&power(&exact(x), k));
+            check_root(|mode| pow_round(x, -(k as f64), mode), 1, &reciprocal, &format!("pow({:e}, -{})", x, k));
+
+            let x = magnitude(&mut rng, -600, 600);
+            check_root(|mode| pow_round(x, 0.5, mode), 2, &exact(x), &format!("pow({:e}, 0.5)", x));
+            check_root(|mode| pow_round(x, 0.25, mode), 4, &exact(x), &format!("pow({:e}, 0.25)", x));
+            check_root(|mode| pow_round(x, 1.5, mode), 2, &power(&exact(x), 3), &format!("pow({:e}, 1.5)", x));
+            let reciprocal = Rational::from_parts(false, 1, 0).div(&exact(x));
+            check_root(|mode| pow_round(x, -0.5, mode), 2, &reciprocal, &format!("pow({:e}, -0.5)", x));
+        }
+
+        // Floats and midpoints among them, which pow_exact has to catch, and the ends of the range
+        let cases = [
+            (4.0, 0.5_f64), (9.0, 1.5), (0.25, -0.5), (81.0, 0.25), (6561.0, 0.125), (134217727.0, 2.0),
+            (3.0, 33.0), (5.0, 22.0), (1.5, 54.0), (2.0, -1075.0), (0.5, 1074.0), (3.0, -3.0),
+            (2f64.powi(-1000), 1.0), (1e-300, 1.0), (17.0, 13.0), (2.25, 0.5), (1e-300, -0.5),
+        ];
+        for (x, y) in cases {
+            // y = p / n, for n = 1 or a power of two
+            let n = if y == y.trunc() { 1 } else { (1.0 / (y - y.trunc()).abs()) as u32 };
+            let p = power(&exact(x), (y.abs() * n as f64) as u32);
+            let radicand = if y < 0.0 { Rational::from_parts(false, 1, 0).div(&p) } else { p };
+            check_root(|mode| pow_round(x, y, mode), n, &radicand, &format!("pow({:e}, {:e})", x, y));
+        }
+
+        // 1024^0.1 is 2^(1 + 2^-54) or so, for the float nearest 0.1
+        assert_eq!(pow_round(1024.0, 0.1, Round::TiesToEven), 2.0);
+        assert_eq!(pow_round(1024.0, 0.1, Round::TowardPInf), successor_f64(2.0));
+
+        for mode in Round::ALL {
+            assert_eq!(pow_round(4.0, 0.5, mode), 2.0);
+            assert_eq!(pow_round(9.0, 1.5, mode), 27.0);
+            assert_eq!(pow_round(-3.0, 3.0, mode), -27.0);
+            assert_eq!(pow_round(-2.0, -1.0, mode), -0.5);
+            assert_eq!(pow_round(2.0, -1074.0, mode), 5e-324);
+        }
+
+        // 2^-1075 is the midpoint between zero and the least subnormal
+        assert!(identical_f64(pow_round(2.0, -1075.0, Round::TiesToEven), 0.0));
+        assert_eq!(pow_round(2.0, -1075.0, Round::TiesAway), 5e-324);
+        assert_eq!(pow_round(-2.0, -1075.0, Round::TiesToOdd), -5e-324);
+        assert_eq!(pow_round(0.5, 1075.0, Round::TowardPInf), 5e-324);
+    }
+
+    #[test]
+    fn test_pow_special_values() {
+        let inf = f64::INFINITY;
+
+        for mode in Round::ALL {
+            assert_eq!(pow_round(f64::NAN, 0.0, mode), 1.0);
+            assert_eq!(pow_round(1.0, f64::NAN, mode), 1.0);
+            assert!(pow_round(f64::NAN, 1.0, mode).is_nan());
+            assert!(pow_round(2.0, f64::NAN, mode).is_nan());
+            assert!(pow_round(-2.0, 0.5, mode).is_nan());
+
+            assert_eq!(pow_round(-1.0, inf, mode), 1.0);
+            assert_eq!(pow_round(-1.0, -inf, mode), 1.0);
+            assert!(identical_f64(pow_round(0.5, inf, mode), 0.0));
+            assert_eq!(pow_round(0.5, -inf, mode), inf);
+            assert_eq!(pow_round(-2.0, inf, mode), inf);
+            assert!(identical_f64(pow_round(-2.0, -inf, mode), 0.0));
+
+            assert!(identical_f64(pow_round(-0.0, 3.0, mode), -0.0));
+            assert!(identical_f64(pow_round(-0.0, 2.0, mode), 0.0));
+            assert!(identical_f64(pow_round(-0.0, 0.5, mode), 0.0));
+            assert_eq!(pow_round(-0.0, -3.0, mode), -inf);
+            assert_eq!(pow_round(-0.0, -2.5, mode), inf);
+            assert_eq!(pow_round(0.0, -inf, mode), inf);
+            assert_eq!(pow_round(-inf, 3.0, mode), -inf);
+            assert_eq!(pow_round(-inf, 2.0, mode), inf);
+            assert!(identical_f64(pow_round(-inf, -3.0, mode), -0.0));
+            assert!(identical_f64(pow_round(inf, -0.5, mode), 0.0));
+
+            assert_eq!(pow_round(-1.0, 3.0, mode), -1.0);
+            assert_eq!(pow_round(-1.0, 1e300, mode), 1.0);
+        }
+
+        // Overflow and underflow, with the sign of odd powers of negative bases
+        assert_eq!(pow_round(10.0, 400.0, Round::TiesToEven), inf);
+        assert_eq!(pow_round(10.0, 400.0, Round::TowardZero), f64::MAX);
+        assert_eq!(pow_round(-10.0, 401.0, Round::TowardNInf), -inf);
+        assert_eq!(pow_round(-10.0, 401.0, Round::TowardPInf), f64::MIN);
+        assert!(identical_f64(pow_round(10.0, -400.0, Round::TiesToEven), 0.0));
+        assert_eq!(pow_round(10.0, -400.0, Round::TowardPInf), 5e-324);
+        assert!(identical_f64(pow_round(-10.0, -401.0, Round::TowardZero), -0.0));
+        assert_eq!(pow_round(1.5, 1e300, Round::TowardNInf), f64::MAX);
+    }
+
+    #[test]
+    fn test_pow_random() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for i in 0..2000 {
+            // Bases near 1 and exponents of every size, within the range of the result
+            let x = if i % 2 == 0 {
+                magnitude(&mut rng, -30, 30)
+            } else {
+                1.0 + magnitude(&mut rng, -52, -1) * if rng.coin() { 1.0 } else { -0.5 }
+            };
+            let y = magnitude(&mut rng, -60, 40) * if rng.coin() { 1.0 } else { -1.0 };
+            if (y * x.log2()).abs() > 1000.0 || pow_exact(x, y).is_some() {
+                continue;
+            }
+
+            let nearest = pow_round(x, y, Round::TiesToEven);
+            assert!(near_std(nearest, x.powf(y)), "pow({:e}, {:e})", x, y);
+
+            let lower = pow_round(x, y, Round::TowardNInf);
+            assert_eq!(pow_round(x, y, Round::TowardPInf), successor_f64(lower), "pow({:e}, {:e})", x, y);
+
+            // Against the slow path alone, and odd powers of negative bases against their mirror
+            let odd = 2.0 * (y / 2.0).trunc() + 1.0;
+            for mode in Round::ALL {
+                if (y * x.log2()).abs() >= 2f64.powi(-62) {
+                    let slow = ziv(mode, |prec| pow_slow(x, y, false, prec));
+                    assert_eq!(pow_round(x, y, mode), slow, "pow({:e}, {:e}), {:?}", x, y, mode);
+                }
+                if (odd * x.log2()).abs() <= 1000.0 {
+                    assert_eq!(pow_round(-x, odd, mode), -pow_round(x, odd, mirrored(mode)), "pow({:e}, {:e}), {:?}", -x, odd, mode);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pow_near_one() {
+        // y log|x| below 2^-62 decides the rounding by its sign alone, and just above it doesn't
+        let x = successor_f64(1.0);
+        for (y, t_positive) in [(2f64.powi(-20), true), (-2f64.powi(-20), false), (1e-11, true), (-1e-11, false)] {
+            assert_eq!(pow_round(x, y, Round::TiesToEven), 1.0);
+            assert_eq!(pow_round(x, y, Round::TowardPInf), if t_positive { x } else { 1.0 });
+            assert_eq!(pow_round(x, y, Round::TowardNInf), if t_positive { 1.0 } else { predecessor_f64(1.0) });
+            assert_eq!(pow_round(-x, 1.0 / y.signum(), Round::TiesToEven), -x.powf(y.signum()));
+        }
+        assert_eq!(pow_round(0.5, 2f64.powi(-70), Round::TowardZero), predecessor_f64(1.0));
+        assert_eq!(pow_round(0.5, -2f64.powi(-70), Round::TowardPInf), successor_f64(1.0));
+    }
+
+    #[test]
+    fn test_powi() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..1000 {
+            let x = magnitude(&mut rng, -10, 10) * if rng.coin() { 1.0 } else { -1.0 };
+            let n = rng.range(-20, 20);
+            let p = power(&exact(x), n.unsigned_abs().max(1));
+            let value = match n {
+                0 => Rational::from_parts(false, 1, 0),
+                n if n < 0 => Rational::from_parts(false, 1, 0).div(&p),
+                _ => p,
+            };
+            let context = format!("powi({:e}, {})", x, n);
+
+            // Directed modes bound x^n, if not as tightly as pow, and the others round correctly
+            let (lower, upper) = (powi_round(x, n, Round::TowardNInf), powi_round(x, n, Round::TowardPInf));
+            assert_ne!(compare(&exact(lower), &value), Ordering::Greater, "{}", context);
+            assert_ne!(compare(&exact(upper), &value), Ordering::Less, "{}", context);
+            assert!(lower <= pow_round(x, n as f64, Round::TowardNInf), "{}", context);
+            assert!(upper >= pow_round(x, n as f64, Round::TowardPInf), "{}", context);
+
+            let toward_zero = powi_round(x, n, Round::TowardZero);
+            assert_eq!(toward_zero, if value.negative { upper } else { lower }, "{}", context);
+
+            for mode in [Round::TiesToEven, Round::Faithful, Round::TiesAway, Round::TiesToOdd] {
+                assert!(identical_f64(powi_round(x, n, mode), pow_round(x, n as f64, mode)), "{}, {:?}", context, mode);
+            }
+        }
+
+        // Few enough products to stay exact
+        for mode in Round::ALL {
+            assert_eq!(powi_round(-3.0, 3, mode), -27.0);
+            assert_eq!(powi_round(2.0, -10, mode), 1.0 / 1024.0);
+            assert_eq!(powi_round(f64::NAN, 0, mode), 1.0);
+        }
+        assert_eq!(powi_round(10.0, 400, Round::TowardZero), f64::MAX);
+        assert!(identical_f64(powi_round(10.0, -400, Round::TowardNInf), 0.0));
+    }
+}
@@ -0,0 +1,634 @@
+// Correctly rounded error function, its complement and the logarithm of the gamma function, on
+// the same two speeds as the rest of the module. erf sums the series of e^(x^2) erf(x), whose
+// terms are all positive, and erfc follows Laplace's continued fraction from ERFC_CF on, where
+// 1 - erf(x) would lose too many bits. lgamma shifts its argument up by the recurrence
+// Γ(z + 1) = z Γ(z) until Stirling's series converges, and reflects negative arguments through
+// Γ(x) Γ(1 - x) = π / sin(πx) first. Their slow paths sum the same series, or their alternating
+// counterparts, in fixed point with enough guard bits to absorb the cancellation.
+//
+// Beyond erf(0) = 0, erfc(0) = 1 and lgamma(1) = lgamma(2) = 0, none of these functions is known
+// to take a value at a float that is a float or a midpoint between two, but nor is it proven that
+// they don't. Should one turn up, Ziv's loop stops at its precision cap with a result that is
+// still a rigorous bound in the directed modes, though possibly an ulp wider than the tightest.
+
+use crate::bigint::BigUint;
+use crate::dd::DD;
+use crate::fixed::Fixed;
+use crate::modes::*;
+use super::trig::{pi_slow, PI_DD};
+use super::{dd, exp_taylor, expm1_reduced, exponent, ldexp, ldexp_dd, ln2_slow, log_atanh, log_dd, log_fixed, log_parts_slow,
+    nudge, pow2_rounded, round_fast, ziv, LN_2, LOG2_E, TINY};
+
+/// 2/√π as a double-double, `(hi, lo)`
+const FRAC_2_SQRT_PI: (f64, f64) = (std::f64::consts::FRAC_2_SQRT_PI, 1.533545961316588e-17);
+
+/// 1/√π as a double-double, `(hi, lo)`
+const FRAC_1_SQRT_PI: (f64, f64) = (0.5641895835477563, 7.66772980658294e-18);
+
+/// Euler's constant γ as a double-double, `(hi, lo)`
+const EULER_GAMMA: (f64, f64) = (0.5772156649015329, -4.942915152430645e-18);
+
+/// log(2π) / 2 as a double-double, `(hi, lo)`
+const HALF_LOG_2PI: (f64, f64) = (0.9189385332046728, -3.8782941580672414e-17);
+
+/// |x| from here on leaves erf(x) closer to ±1, and erfc(-|x|) closer to 2, than any float or
+/// midpoint, as erfc(6) < 2^-55
+const ERF_SATURATED: f64 = 6.0;
+
+/// erfc(x) goes through the continued fraction from here on, where 1 - erf(x) < 2^-4
+const ERFC_CF: f64 = 1.5;
+
+/// erfc(x) is below 2^-1076 above this and rounds like any tinier positive value
+const ERFC_UNDERFLOW: f64 = 27.25;
+
+/// lgamma(x) is x (log(x) - 1) - log(x) / 2 + log(2π) / 2 to within 2^-120 relatively from 2^60 on
+const LGAMMA_BIG: f64 = 1_152_921_504_606_846_976.0;
+
+/// lgamma(x) overflows above this, well beyond its threshold of about 2.5563e305
+const LGAMMA_OVERFLOW: f64 = 1e306;
+
+/// The Bernoulli numbers B_2, B_4, ..., B_58, as numerators and denominators. Stirling's series
+/// takes the first twelve on the fast path and all of them on the slow path, whose remainder is
+/// then below |B_60| / (60 · 59 w^59) < 2^115 / (3540 w^59).
+const BERNOULLI: [(i128, u32); 29] = [
+    (1, 6),
+    (-1, 30),
+    (1, 42),
+    (-1, 30),
+    (5, 66),
+    (-691, 2730),
+    (7, 6),
+    (-3617, 510),
+    (43867, 798),
+    (-174611, 330),
+    (854513, 138),
+    (-236364091, 2730),
+    (8553103, 6),
+    (-23749461029, 870),
+    (8615841276005, 14322),
+    (-7709321041217, 510),
+    (2577687858367, 6),
+    (-26315271553053477373, 1919190),
+    (2929993913841559, 6),
+    (-261082718496449122051, 13530),
+    (1520097643918070802691, 1806),
+    (-27833269579301024235023, 690),
+    (596451111593912163277961, 282),
+    (-5609403368997817686249127547, 46410),
+    (495057205241079648212477525, 66),
+    (-801165718135489957347924991853, 1590),
+    (29149963634884862421418123812691, 798),
+    (-2479392929313226753685415739663229, 870),
+    (84483613348880041862046775994036021, 354),
+];
+
+/// `B_2k / (2k (2k - 1))`, the numerator and the denominator of the kth term of Stirling's series
+fn stirling_coefficient(k: usize) -> (i128, u32) {
+    let (num, den) = BERNOULLI[k - 1];
+    (num, den * (2 * k * (2 * k - 1)) as u32)
+}
+
+/// e^(-t^2) as `(k, u)` for `e^(-t^2) = u 2^k`, to within about 2^-94 relatively
+fn exp_neg_square(t: DD) -> (i32, DD) {
+    let t2 = t * t;
+    let k = (-t2.hi() * LOG2_E.0).round();
+    let r = -t2 - DD::from(k) * dd(LN_2);
+
+    (k as i32, DD::ONE + expm1_reduced(r))
+}
+
+/// erf(a) for `TINY <= a < ERF_SATURATED`, to within about 2^-94 relatively
+fn erf_fast(a: f64) -> DD {
+    // 2/√π e^(-a^2) times the sum of 2^n a^(2n + 1) / (2n + 1)!!, whose terms rise up to n ≈ a^2
+    // and fall off quickly after
+    let t = DD::from(a);
+    let t2 = t * t;
+
+    let mut term = t;
+    let mut sum = t;
+    let mut n = 1;
+    while term.hi() >= sum.hi() * 1e-32 {
+        term = term * t2 / DD::from(n as f64 + 0.5);
+        sum = sum + term;
+        n += 1;
+    }
+
+    let (k, u) = exp_neg_square(t);
+    dd(FRAC_2_SQRT_PI) * ldexp_dd(u, k) * sum
+}
+
+/// erfc(x) for `ERFC_CF <= x <= ERFC_UNDERFLOW` as `(d, k)` for `erfc(x) = d 2^k`, to within
+/// about 2^-94 relatively
+fn erfc_cf(x: f64) -> (DD, i32) {
+    // e^(-x^2) / √π / (x + (1/2) / (x + 1 / (x + (3/2) / (x + ...)))), evaluated from the bottom
+    // up, which damps the rounding errors; this depth takes it to within 2^-106
+    let t = DD::from(x);
+    let depth = (900.0 / (x * x)) as usize + 30;
+
+    let mut fraction = t;
+    for n in (1..=depth).rev() {
+        fraction = t + DD::from(n as f64 / 2.0) / fraction;
+    }
+
+    let (k, u) = exp_neg_square(t);
+    (u * dd(FRAC_1_SQRT_PI) / fraction, k)
+}
+
+/// lgamma(z) for `z >= 1`, to within about 2^-96 times the second value returned, which bounds
+/// the magnitude of the terms that cancel into it
+fn lgamma_stirling_fast(z: DD) -> (DD, f64) {
+    // lgamma(w) - log(z (z + 1) ... (w - 1)) for w = z + n >= 20, where the first twelve terms of
+    // Stirling's series leave out less than 2^-97
+    let n = (20.0 - z.hi()).ceil().max(0.0);
+    let w = z + DD::from(n);
+
+    let mut product = DD::ONE;
+    for j in 0..n as usize {
+        product = product * (z + DD::from(j as f64));
+    }
+
+    let inv = DD::ONE / w;
+    let inv2 = inv * inv;
+    let mut series = DD::ZERO;
+    for k in (1..=12).rev() {
+        let (num, den) = stirling_coefficient(k);
+        series = series * inv2 + DD::from(num as f64) / DD::from(den as f64);
+    }
+
+    let main = (w - DD::from(0.5)) * log_dd(w);
+    let log_product = log_dd(product);
+    let d = main - w + dd(HALF_LOG_2PI) + series * inv - log_product;
+
+    (d, main.hi().abs() + w.hi() + log_product.hi())
+}
+
+/// sin(πa) / (πa) for `0 < a <= 1/2`, to within about 2^-100
+fn sinc_pi_fast(a: f64) -> DD {
+    // 1 - u^2 / (2 · 3) (1 - u^2 / (4 · 5) (1 - ...)) for u = πa, which has converged after
+    // twenty terms
+    let u = dd(PI_DD) * DD::from(a);
+    let u2 = u * u;
+
+    let mut s = DD::ONE;
+    for k in (1..=20).rev() {
+        s = DD::ONE - u2 * s / DD::from((2 * k * (2 * k + 1)) as f64);
+    }
+
+    s
+}
+
+/// lgamma(x) as `(d, scale)` for `lgamma(x) = d 2^scale`, for x away from the special cases, or
+/// None where cancellation leaves too few bits of it for round_fast
+fn lgamma_fast(x: f64) -> Option<(DD, i32)> {
+    let a = x.abs();
+
+    let (d, magnitude) = if a < TINY {
+        // -log|x| - γx + O(x^2), on either side of zero
+        let d = -log_dd(DD::from(a)) - dd(EULER_GAMMA) * DD::from(x);
+        (d, d.hi().abs())
+    } else if x >= LGAMMA_BIG {
+        // Scaled down by 2^12 to keep x log(x) in range
+        let log_x = log_dd(DD::from(x));
+        let d = DD::from(ldexp(x, -12)) * (log_x - DD::ONE) - ldexp_dd(log_x * DD::from(0.5) - dd(HALF_LOG_2PI), -12);
+        return Some((d, 12));
+    } else if x >= 1.0 {
+        lgamma_stirling_fast(DD::from(x))
+    } else if x > 0.0 {
+        // lgamma(1 + x) - log(x)
+        let (g, magnitude) = lgamma_stirling_fast(DD::ONE + DD::from(x));
+        let log_x = log_dd(DD::from(x));
+        (g - log_x, magnitude + log_x.hi().abs())
+    } else {
+        // -log|f| - log(sinc(π|f|)) - lgamma(1 - x) for the distance f of x to the nearest
+        // integer, by the reflection formula
+        let f = (x - x.round()).abs();
+        let (g, magnitude) = lgamma_stirling_fast(DD::ONE - DD::from(x));
+        let log_f = log_dd(DD::from(f));
+        let log_sinc = log_dd(sinc_pi_fast(f));
+        (-log_f - log_sinc - g, magnitude + log_f.hi().abs() + log_sinc.hi().abs())
+    };
+
+    // round_fast allows for 2^-80 of d, and the error is about 2^-96 of the magnitude
+    if magnitude <= 32768.0 * d.hi().abs() { Some((d, 0)) } else { None }
+}
+
+fn signed(value: Fixed, negative: bool) -> Fixed {
+    if negative { value.neg() } else { value }
+}
+
+/// `v` with its last `bits` bits dropped, truncated
+fn drop_bits(v: &Fixed, bits: u32) -> Fixed {
+    Fixed { negative: v.negative, mag: v.mag.shr(bits), prec: v.prec - bits }
+}
+
+/// 2/√π to `prec` bits, within 3 units
+fn frac_2_sqrt_pi_slow(prec: u32) -> Fixed {
+    // √π is off by less than 2 units, which moves 2/√π by less than 2 (2/π) units
+    let (pi, _) = pi_slow(prec);
+    let root = Fixed::sqrt_product(&pi, &Fixed::from_int(1, prec));
+    Fixed::from_int(2, prec).div(&root)
+}
+
+// Slow paths, as `ziv` expects
+
+fn erf_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    // 2/√π x S(x^2) for S(z), the sum of (-1)^n z^n / (n! (2n + 1)), which lies within (0, 1].
+    // Its terms reach e^z before they cancel, so it is summed with
+    // `extra` more bits: with z off by less than 2|x| + 1 units, and each term by less than 2 per
+    // step so far, the sum and its tail are off by less than 34 e^z + 6N units for N terms, which
+    // is below 3 units once the extra bits are dropped.
+    let a = x.abs();
+    let z_bound = a * a;
+    let extra = (z_bound * std::f64::consts::LOG2_E).ceil() as u32 + 20;
+    let wp = prec + extra;
+
+    let t = Fixed::from_f64(a, 0, wp);
+    let z = t.mul(&t);
+    let mut term = Fixed::from_int(1, wp);
+    let mut sum = term.clone();
+    let mut n = 1;
+
+    loop {
+        term = term.mul(&z).div_int(n);
+        if term.is_zero() && n as f64 > 2.0 * z_bound {
+            break;
+        }
+
+        let quotient = term.div_int(2 * n + 1);
+        sum = if n % 2 == 1 { sum.sub(&quotient) } else { sum.add(&quotient) };
+        n += 1;
+    }
+
+    // Times y = |x| / 2^e in [1, 2), exactly, and 2/√π < 1.13, off by less than 3 units
+    let e = exponent(a);
+    let y = Fixed::from_f64(a, -e, prec);
+    let value = frac_2_sqrt_pi_slow(prec).mul(&drop_bits(&sum, extra).mul(&y));
+
+    (signed(value, x < 0.0), 16, e)
+}
+
+/// Laplace's continued fraction for erfc at `t`, truncated at the given depth. Successive depths
+/// bracket its value, and the evaluation from the bottom up only damps its rounding errors, which
+/// leaves it off by less than `depth + 2` units.
+fn erfc_fraction_slow(t: &Fixed, depth: i64) -> Fixed {
+    let mut fraction = t.clone();
+    for n in (1..=depth).rev() {
+        fraction = t.add(&Fixed::from_int(n, t.prec).scale(-1).div(&fraction));
+    }
+
+    fraction
+}
+
+fn erfc_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    if x < 0.0 {
+        // 1 + erf(|x|), where |x| < ERF_SATURATED puts erf's scale at 2 at most
+        let (value, err, e) = erf_slow(-x, prec);
+        return (Fixed::from_int(1, prec).add(&value.scale(e)), (err << e.max(0)) + 1, 0);
+    } else if x < 8.0 {
+        // 1 - erf(x) > e^(-x^2) / (√π (x + 1)) cancels less than `guard` leading bits, which erf
+        // makes up for, and then the result is read at the original precision, scaled down. The
+        // continued fraction below converges slowly this close in.
+        let guard = (x * x * std::f64::consts::LOG2_E) as u32 + 8;
+        let (value, err, e) = erf_slow(x, prec + guard);
+        let difference = Fixed::from_int(1, prec + guard).sub(&value.scale(e));
+        return (Fixed { prec, ..difference }, (err << e.max(0)) + 1, -(guard as i32));
+    }
+
+    // e^r 2^k / (√π K) for the continued fraction K, as on the fast path, with 32 guard bits. The
+    // depth grows with the square of the precision, and doubles until two successive ones agree
+    // to within 2^16 units, which bounds how far either is from K.
+    let wp = prec + 32;
+    let t = Fixed::from_f64(x, 0, wp);
+    let mut depth = ((900.0 / (x * x) + 30.0) * (wp as f64 / 106.0).powi(2)) as i64;
+    let (fraction, fraction_err) = loop {
+        let (lower, upper) = (erfc_fraction_slow(&t, depth), erfc_fraction_slow(&t, depth + 1));
+        let gap = lower.sub(&upper).mag;
+        if gap.bits() <= 16 {
+            break (lower, gap.low_u128() as u64 + 2 * depth as u64 + 6);
+        }
+
+        depth *= 2;
+    };
+
+    // x^2 is exact, and k ln 2 off by less than k ln2_err units
+    let k = (-x * x * std::f64::consts::LOG2_E).round() as i32;
+    let (ln2, ln2_err) = ln2_slow(wp);
+    let r = t.mul(&t).neg().sub(&ln2.mul_int(k));
+    let (exp_r, exp_err) = exp_taylor(&r, k.unsigned_abs() as u64 * ln2_err);
+
+    // √π K > 2.6 is off by less than 2 K + 2 fraction_err + 1 units, with K < 29, and the
+    // quotient, below 0.55, by less than exp_err + (60 + 2 fraction_err) / 4 + 1
+    let (pi, _) = pi_slow(wp);
+    let denominator = Fixed::sqrt_product(&pi, &Fixed::from_int(1, wp)).mul(&fraction);
+    let value = exp_r.div(&denominator);
+    let err = exp_err + (60 + 2 * fraction_err) / 4 + 1;
+
+    (drop_bits(&value, 32), (err >> 32) + 1, k)
+}
+
+/// lgamma(z) for `z >= 1`, given to within `z_err <= 1` units, through w = z + n, which must be
+/// large enough for Stirling's series at z's precision. Returns the value, the error of everything
+/// but the main term, and the error of log(w), which the main term multiplies by w.
+fn lgamma_stirling_slow(z: &Fixed, z_err: u64, n: i64) -> (Fixed, u64, u64) {
+    // lgamma(w) - log(z (z + 1) ... (w - 1)) for w = z + n, whose product is off by less than
+    // n (z_err + 1) units times itself
+    let wp = z.prec;
+    let one = Fixed::from_int(1, wp);
+    let w = z.add(&Fixed::from_int(n, wp));
+
+    let mut product = one.clone();
+    for j in 0..n {
+        product = product.mul(&z.add(&Fixed::from_int(j, wp)));
+    }
+    let (log_product, product_err) = log_fixed(&product, n as u64 * (z_err + 1));
+
+    // (w - 1/2) log(w), whose logarithm is below 710 and so does the error of w little harm
+    let (log_w, log_w_err) = log_fixed(&w, z_err);
+    let main = w.sub(&one.scale(-1)).mul(&log_w);
+
+    // log(2π) / 2 = (ln 2 + log(π)) / 2
+    let (pi, pi_err) = pi_slow(wp);
+    let (log_pi, log_pi_err) = log_fixed(&pi, pi_err);
+    let (ln2, ln2_err) = ln2_slow(wp);
+    let half_log_2pi = ln2.add(&log_pi).scale(-1);
+
+    // The terms B_2k / (2k (2k - 1) w^(2k - 1)) fall off faster than 1/3 per step from w >= 15.
+    // Each coefficient and power is off by a small multiple of its own magnitude, so each
+    // quotient is off by less than 2 units, or is below 2 units where the sum stops early.
+    let w2 = w.mul(&w);
+    let mut power = w.clone();
+    let mut series = Fixed::from_int(0, wp);
+    for k in 1..=BERNOULLI.len() {
+        let (num, den) = stirling_coefficient(k);
+        let coefficient = Fixed { negative: num < 0, mag: BigUint::from_u128(num.unsigned_abs()).shl(wp), prec: wp };
+        let term = coefficient.div_int(den).div(&power);
+        if term.is_zero() {
+            break;
+        }
+
+        series = series.add(&term);
+        power = power.mul(&w2);
+    }
+
+    let value = main.sub(&w).add(&half_log_2pi).add(&series).sub(&log_product);
+    let err = 710 * z_err + 1 + z_err + (ln2_err + log_pi_err) / 2 + 1 + 2 * BERNOULLI.len() as u64 + 1 + product_err;
+
+    (value, err, log_w_err)
+}
+
+fn lgamma_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    // Everything is computed with `guard` more bits, at least 16 more than w has integer bits, so
+    // that the error of log(w), times w, becomes a fraction of a unit at the original precision.
+    // Stirling's series is within a unit from w >= min_w, where 59 log2(w) >= 104 + wp.
+    let min_w = |wp: u32| 2f64.powf((104 + wp) as f64 / 59.0).ceil();
+    let z_approx = if x >= 1.0 { x } else { 1.0 + x.abs() };
+    let guard = (exponent(z_approx.max(min_w(2 * prec))) + 18) as u32;
+    let wp = prec + guard;
+    let n = (min_w(wp) - z_approx.floor()).max(0.0) as i64;
+
+    let one = Fixed::from_int(1, wp);
+    let (z, z_err, value, err) = if x >= 1.0 {
+        (Fixed::from_f64(x, 0, wp), 0, Fixed::from_int(0, wp), 0)
+    } else if x > 0.0 {
+        // lgamma(1 + x) - log(x)
+        let (e, log_m, log_err) = log_parts_slow(x, 0.0, wp);
+        let (ln2, ln2_err) = ln2_slow(wp);
+        let log_x = log_m.add(&ln2.mul_int(e));
+        (one.add(&Fixed::from_f64(x, 0, wp)), 1, log_x.neg(), log_err + e.unsigned_abs() as u64 * ln2_err)
+    } else {
+        // -log|f| - log(sinc(π|f|)) - lgamma(1 - x), as on the fast path
+        let f = (x - x.round()).abs();
+        let (e, log_m, log_err) = log_parts_slow(f, 0.0, wp);
+        let (ln2, ln2_err) = ln2_slow(wp);
+        let log_f = log_m.add(&ln2.mul_int(e));
+
+        // u = π|f| is off by less than 6 units and u^2 < 2.5 by less than 20, so each term of
+        // the series of sinc, which fall off by 0.41 or more per step, moves it by less than 4
+        // units, and the tail by less than 16.
+        let (pi, _) = pi_slow(wp);
+        let u = pi.mul(&Fixed::from_f64(f, 0, wp));
+        let u2 = u.mul(&u);
+        let mut term = one.clone();
+        let mut sinc = one.clone();
+        let mut k = 1;
+        loop {
+            term = term.mul(&u2).div_int(2 * k * (2 * k + 1));
+            if term.is_zero() {
+                break;
+            }
+
+            sinc = if k % 2 == 1 { sinc.sub(&term) } else { sinc.add(&term) };
+            k += 1;
+        }
+        let (log_sinc, sinc_err) = log_atanh(&sinc, 4 * k as u64 + 16);
+
+        let value = log_f.add(&log_sinc).neg();
+        (one.sub(&Fixed::from_f64(x, 0, wp)), 1, value, log_err + e.unsigned_abs() as u64 * ln2_err + sinc_err)
+    };
+
+    let (g, g_err, log_w_err) = lgamma_stirling_slow(&z, z_err, n);
+    let total = if x < 0.0 { value.sub(&g) } else { value.add(&g) };
+    let err = (err + g_err).checked_shr(guard).unwrap_or(0) + (log_w_err >> 16) + 3;
+
+    (drop_bits(&total, guard), err, 0)
+}
+
+/// Computes the error function of x, correctly rounded under the given rounding mode.
+pub fn erf_round(x: f64, mode: Round) -> f64 {
+    let a = x.abs();
+    if x.is_nan() {
+        return x + x;
+    } else if x == 0.0 {
+        return x;
+    } else if x.is_infinite() {
+        return x.signum();
+    } else if a >= ERF_SATURATED {
+        return nudge(x.signum(), x < 0.0, mode);
+    }
+
+    let (d, scale) = if a < TINY {
+        // 2/√π x - ..., scaled up, which leaves out less than 2^-120 of it
+        (dd(FRAC_2_SQRT_PI) * DD::from(ldexp(a, 600)), -600)
+    } else {
+        (erf_fast(a), 0)
+    };
+
+    round_fast(if x < 0.0 { -d } else { d }, scale, mode).unwrap_or_else(|| ziv(mode, |prec| erf_slow(x, prec)))
+}
+
+/// Computes the complementary error function of x, 1 - erf(x), correctly rounded under the given
+/// rounding mode.
+pub fn erfc_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x == 0.0 {
+        return 1.0;
+    } else if x.is_infinite() {
+        return if x > 0.0 { 0.0 } else { 2.0 };
+    } else if x.abs() < TINY {
+        // 1 - 2/√π x + ...
+        return nudge(1.0, x < 0.0, mode);
+    } else if x <= -ERF_SATURATED {
+        return nudge(2.0, false, mode);
+    } else if x > ERFC_UNDERFLOW {
+        return pow2_rounded(-2000, mode);
+    }
+
+    let (d, scale) = if x < 0.0 {
+        (DD::ONE + erf_fast(-x), 0)
+    } else if x < ERFC_CF {
+        (DD::ONE - erf_fast(x), 0)
+    } else {
+        erfc_cf(x)
+    };
+
+    round_fast(d, scale, mode).unwrap_or_else(|| ziv(mode, |prec| erfc_slow(x, prec)))
+}
+
+/// Computes the natural logarithm of the absolute value of the gamma function of x, correctly
+/// rounded under the given rounding mode. Like C's lgamma, it is +inf at the poles, zero and the
+/// negative integers, and at both infinities.
+pub fn lgamma_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x.is_infinite() || (x <= 0.0 && x == x.floor()) {
+        return f64::INFINITY;
+    } else if x == 1.0 || x == 2.0 {
+        return 0.0;
+    } else if x > LGAMMA_OVERFLOW {
+        return pow2_rounded(2000, mode);
+    }
+
+    lgamma_fast(x)
+        .and_then(|(d, scale)| round_fast(d, scale, mode))
+        .unwrap_or_else(|| ziv(mode, |prec| lgamma_slow(x, prec)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elementary::tests::*;
+    use crate::successor::{identical_f64, predecessor_f64, successor_f64};
+    use crate::test_cases::*;
+
+    const FUNCTIONS: [(&str, Function); 3] = [("erf", erf_round), ("erfc", erfc_round), ("lgamma", lgamma_round)];
+
+    /// Random argument for the named function, away from the shortcuts
+    fn random_arg(name: &str, rng: &mut SplitMix64) -> f64 {
+        let sign = random_sign(rng);
+
+        match name {
+            "erf" => sign * magnitude(rng, -60, 2).min(5.9),
+            "erfc" => match rng.range(0, 2) {
+                0 => sign * magnitude(rng, -60, 0),
+                1 => -magnitude(rng, 0, 2).min(5.9),
+                _ => magnitude(rng, 0, 4).min(ERFC_UNDERFLOW),
+            },
+            _ => match rng.range(0, 2) {
+                0 => magnitude(rng, -60, 1023).min(LGAMMA_OVERFLOW),
+                1 => sign * magnitude(rng, -60, 6),
+                // Away from the negative integers, which the distance to is what matters
+                _ => -(rng.range(0, 200) as f64) - magnitude(rng, -20, -1),
+            },
+        }
+    }
+
+    #[test]
+    fn test_special_values() {
+        let inf = f64::INFINITY;
+
+        for mode in Round::ALL {
+            for (_, f) in FUNCTIONS {
+                assert!(f(f64::NAN, mode).is_nan());
+            }
+
+            assert!(identical_f64(erf_round(0.0, mode), 0.0) && identical_f64(erf_round(-0.0, mode), -0.0));
+            assert_eq!(erf_round(inf, mode), 1.0);
+            assert_eq!(erf_round(-inf, mode), -1.0);
+            assert_eq!(erfc_round(-0.0, mode), 1.0);
+            assert!(identical_f64(erfc_round(inf, mode), 0.0));
+            assert_eq!(erfc_round(-inf, mode), 2.0);
+
+            // The poles, both infinities and the two zeros
+            for x in [0.0, -0.0, -1.0, -2.0, -1e300, inf, -inf] {
+                assert_eq!(lgamma_round(x, mode), inf, "{}", x);
+            }
+            assert!(identical_f64(lgamma_round(1.0, mode), 0.0) && identical_f64(lgamma_round(2.0, mode), 0.0));
+        }
+
+        // The shortcuts near zero and far out
+        let x = 2f64.powi(-70);
+        assert_eq!(erfc_round(x, Round::TowardZero), predecessor_f64(1.0));
+        assert_eq!(erfc_round(-x, Round::TowardPInf), successor_f64(1.0));
+        assert_eq!(erfc_round(x, Round::TiesToEven), 1.0);
+        assert_eq!(erf_round(6.5, Round::TowardZero), predecessor_f64(1.0));
+        assert_eq!(erf_round(-6.5, Round::TowardNInf), -1.0);
+        assert_eq!(erfc_round(-6.5, Round::TowardPInf), 2.0);
+        assert_eq!(erfc_round(-6.5, Round::TowardNInf), predecessor_f64(2.0));
+
+        // Underflow and overflow, just past their thresholds and far beyond
+        assert!(identical_f64(erfc_round(27.5, Round::TiesToEven), 0.0));
+        assert_eq!(erfc_round(1e10, Round::TowardPInf), f64::from_bits(1));
+        assert_eq!(lgamma_round(1e307, Round::TowardZero), f64::MAX);
+        assert_eq!(lgamma_round(f64::MAX, Round::TiesToEven), inf);
+        assert_eq!(lgamma_round(2.6e305, Round::TiesToEven), inf);
+    }
+
+    #[test]
+    fn test_known_values() {
+        check_known_values(&[
+            (erf_round, 0.5, 0.5204998778130465),
+            (erf_round, 1.0, 0.8427007929497149),
+            (erf_round, -2.0, -0.9953222650189527),
+            (erf_round, 3.0, 0.9999779095030014),
+            (erf_round, 1e-10, 1.1283791670955126e-10),
+            (erfc_round, 0.5, 0.4795001221869535),
+            (erfc_round, -1.0, 1.8427007929497148),
+            (erfc_round, 1.5, 0.033894853524689274),
+            (erfc_round, 2.0, 0.004677734981047266),
+            (erfc_round, 3.0, 2.209049699858544e-05),
+            (erfc_round, 10.0, 2.088487583762545e-45),
+            (erfc_round, 27.0, 5.23705e-319),
+            (lgamma_round, 0.5, 0.5723649429247001),
+            (lgamma_round, 1.5, -0.12078223763524522),
+            (lgamma_round, 3.0, std::f64::consts::LN_2),
+            (lgamma_round, 10.0, 12.801827480081469),
+            (lgamma_round, 100.0, 359.1342053695754),
+            (lgamma_round, -0.5, 1.2655121234846454),
+            (lgamma_round, -2.5, -0.056243716497674054),
+            (lgamma_round, 1e-300, 690.7755278982137),
+            (lgamma_round, 1e300, 6.897755278982137e302),
+            (lgamma_round, 2.5e305, 1.7555118602376452e308),
+        ]);
+    }
+
+    #[test]
+    fn test_reference() {
+        check_references(&FUNCTIONS);
+    }
+
+    #[test]
+    fn test_rounding_directions() {
+        check_directions(&FUNCTIONS, random_arg);
+    }
+
+    #[test]
+    fn test_identities() {
+        let mut rng = SplitMix64::new(SEED ^ 2);
+
+        for _ in 0..2000 {
+            // erf(x) + erfc(x) = 1, up to the rounding of each
+            let x = random_arg("erf", &mut rng);
+            let erf = (erf_round(x, Round::TowardNInf), erf_round(x, Round::TowardPInf));
+            let erfc = (erfc_round(x, Round::TowardNInf), erfc_round(x, Round::TowardPInf));
+            assert!(erf.0 + erfc.0 <= 1.0 + f64::EPSILON && 1.0 - f64::EPSILON <= erf.1 + erfc.1, "{:e}", x);
+
+            // lgamma(x + 1) = lgamma(x) + log(x)
+            let x = random_arg("lgamma", &mut rng).abs().clamp(0.5, 1e300);
+            let (lower, upper) = (lgamma_round(x, Round::TowardNInf), lgamma_round(x, Round::TowardPInf));
+            let shifted = lgamma_round(x + 1.0, Round::TiesToEven) - x.ln();
+            let slack = 4.0 * f64::EPSILON * (lower.abs() + x.ln().abs() + 1.0);
+            assert!(lower - slack <= shifted && shifted <= upper + slack, "{:e}", x);
+        }
+    }
+}
@@ -0,0 +1,776 @@
+// Correctly rounded trigonometric functions and their inverses, on the same two speeds as the rest
+// of the module. Arguments of sin, cos and tan of any size are reduced modulo π/2 by Payne and
+// Hanek's method: the exact argument times enough bits of 2/π, computed once and cached, leaves
+// the quadrant in the integer part of the product and the reduced argument in its fraction. No
+// nonzero f64 lies within 2^-62 of a multiple of π/2, so keeping 64 bits beyond the precision
+// wanted keeps the reduced argument that precise relatively, too.
+//
+// The inverse functions all come down to atan(n / d) for n <= d, plus or minus a multiple of π/2.
+// By the Lindemann–Weierstrass theorem, none of these functions takes a rational value at a
+// nonzero rational point, acos(1) = 0 aside, so Ziv's loop terminates everywhere else.
+
+use lazy_static::lazy_static;
+use std::f64::consts::{FRAC_PI_4, PI};
+use crate::bigint::BigUint;
+use crate::dd::DD;
+use crate::fallback::{div_exact, Exact, Format};
+use crate::fixed::Fixed;
+use crate::modes::*;
+use crate::successor::FloatParts;
+use super::{dd, exponent, ldexp, ldexp_dd, nudge, round_fast, ziv};
+
+/// π as a double-double, `(hi, lo)`
+pub(super) const PI_DD: (f64, f64) = (PI, 1.2246467991473532e-16);
+
+/// Arguments below 2^-27 in magnitude are close enough to zero for the first two terms of each
+/// function's series to decide the rounding, the second only through its sign.
+const SMALL: f64 = 1.0 / (1u64 << 27) as f64;
+
+/// Fractional bits of the cached π and 2/π, enough to reduce any f64 at the precisions the slow
+/// paths usually reach
+const CACHED_BITS: u32 = 2048;
+
+lazy_static! {
+    /// π and 2/π times 2^CACHED_BITS, each within 2 of the exact value
+    static ref CACHED: (BigUint, BigUint) = pi_bits(CACHED_BITS);
+}
+
+/// atan(1/n) for n >= 2 to `prec` bits, with its error in units in the last place
+fn atan_inv(n: u32, prec: u32) -> (Fixed, u64) {
+    // The alternating series of 1 / ((2k + 1) n^(2k + 1)). Each power is off by less than 2
+    // units, each term by less than 3, and the truncated tail is smaller than the last power.
+    let mut power = Fixed::from_int(1, prec).div_int(n);
+    let mut sum = power.clone();
+    let mut k = 1;
+
+    loop {
+        power = power.div_int(n * n);
+        if power.is_zero() {
+            break;
+        }
+
+        let term = power.div_int(2 * k + 1);
+        sum = if k % 2 == 1 { sum.sub(&term) } else { sum.add(&term) };
+        k += 1;
+    }
+
+    (sum, 3 * k as u64 + 3)
+}
+
+/// π to `prec` bits by Machin's formula, π = 16 atan(1/5) - 4 atan(1/239), with its error
+fn pi_machin(prec: u32) -> (Fixed, u64) {
+    let (a, a_err) = atan_inv(5, prec);
+    let (b, b_err) = atan_inv(239, prec);
+
+    (a.mul_int(16).sub(&b.mul_int(4)), 16 * a_err + 4 * b_err)
+}
+
+/// π and 2/π times 2^bits, each within 2 of the exact value
+fn pi_bits(bits: u32) -> (BigUint, BigUint) {
+    // Both are off by less than 11 units per bit before dropping the guard bits
+    let guard = 8 + 32 - bits.leading_zeros();
+    let (pi, _) = pi_machin(bits + guard);
+    let two_over_pi = Fixed::from_int(2, bits + guard).div(&pi);
+
+    (pi.mag.shr(guard), two_over_pi.mag.shr(guard))
+}
+
+/// π to `prec` bits, with its error
+pub(super) fn pi_slow(prec: u32) -> (Fixed, u64) {
+    let (mag, err) = if prec <= CACHED_BITS {
+        (CACHED.0.shr(CACHED_BITS - prec), 3)
+    } else {
+        (pi_bits(prec).0, 2)
+    };
+
+    (Fixed { negative: false, mag, prec }, err)
+}
+
+/// 2/π times 2^bits, within 3
+fn two_over_pi(bits: u32) -> BigUint {
+    if bits <= CACHED_BITS {
+        CACHED.1.shr(CACHED_BITS - bits)
+    } else {
+        pi_bits(bits).1
+    }
+}
+
+/// Rounds n π/4 under the given rounding mode.
+fn pi_quarters(n: i32, mode: Round) -> f64 {
+    let d = DD::from(n as f64) * ldexp_dd(dd(PI_DD), -2);
+
+    round_fast(d, 0, mode).unwrap_or_else(|| ziv(mode, |prec| {
+        let (pi, err) = pi_slow(prec);
+        (pi.mul_int(n), n.unsigned_abs() as u64 * err, -2)
+    }))
+}
+
+/// Rounds a value lying just closer to zero than the exact `e`, closer to it than any float or
+/// midpoint, like nudge does for floats.
+fn round_inward(e: Exact, mode: Round) -> f64 {
+    // With a sticky bit, e lies strictly between two multiples of its last place already, with no
+    // float or midpoint in between. Otherwise, a quarter of that place inward does the same.
+    let e = if e.sticky { e } else { Exact { mant: 4 * e.mant - 1, exp: e.exp - 2, sticky: true, ..e } };
+    f64::from_bits(e.round(Format::BINARY64, mode) as u64)
+}
+
+/// Reduces a finite `x >= π/4` modulo π/2, returning the quadrant k mod 4 and `x - k π/2` within
+/// [-π/4, π/4], to `prec + 64` bits, with its error.
+fn reduce_trig(x: f64, prec: u32) -> (u32, Fixed, u64) {
+    let parts = FloatParts::of_f64(x);
+    let p = prec + 64;
+
+    // With x = m 2^e and 2/π within 3 units of its last bit, x 2/π is off by less than
+    // 3 m 2^(e - bits) < 2^-(p + 1), for 56 bits of the product's fraction to spare
+    let bits = (parts.exp + p as i32 + 56) as u32;
+    let product = BigUint::from_u128(parts.mant as u128).mul(&two_over_pi(bits));
+    let frac_bits = p + 56;
+
+    let int = product.shr(frac_bits);
+    let mut quadrant = (int.low_u128() & 3) as u32;
+    let mut f = Fixed { negative: false, mag: product.sub(&int.shl(frac_bits)).shr(56), prec: p };
+
+    // The nearest multiple of π/2 instead, for |f| <= 1/2
+    if f.mag.bits() == p {
+        quadrant = (quadrant + 1) % 4;
+        f = f.sub(&Fixed::from_int(1, p));
+    }
+
+    // f is off by less than 2 units, so f π/2 by less than 1 + π + pi_err / 2, before halving
+    let (pi, pi_err) = pi_slow(p);
+    (quadrant, f.mul(&pi).scale(-1), pi_err + 6)
+}
+
+/// Quadrant and reduced argument of a finite `x >= 0`, as in reduce_trig, to within about 2^-106
+/// relatively
+fn reduce_fast(x: f64) -> (u32, DD) {
+    if x <= FRAC_PI_4 {
+        return (0, DD::from(x));
+    }
+
+    let (quadrant, r, _) = reduce_trig(x, 128);
+    let hi = r.to_f64(0, Round::TiesToEven);
+    let lo = r.sub(&Fixed::from_f64(hi, 0, r.prec)).to_f64(0, Round::TiesToEven);
+
+    (quadrant, DD::new(hi, lo))
+}
+
+/// Number of times π/2 fits in a finite `x`, rounded down, mod 4. Exact, since no nonzero float
+/// is a multiple of π/2, so the sign of the reduced argument is never in doubt.
+pub(crate) fn floor_quadrant(x: f64) -> u32 {
+    let t = x.abs();
+    let quadrant = if t < FRAC_PI_4 {
+        0
+    } else {
+        let (quadrant, r, _) = reduce_trig(t, 64);
+        if r.negative { (quadrant + 3) % 4 } else { quadrant }
+    };
+
+    // floor(-t) = -floor(t) - 1 for t not a multiple
+    if x < 0.0 { 3 - quadrant } else { quadrant }
+}
+
+/// sin(r) and cos(r) for `|r| <= π/4`, or a little more, to within about 2^-100 relatively
+fn sin_cos_fast(r: DD) -> (DD, DD) {
+    // Both series in Horner form, converged after 15 terms since (π/4)^31 / 31! < 2^-115
+    let r2 = r * r;
+    let (mut s, mut c) = (DD::ONE, DD::ONE);
+
+    for n in (1..=15).rev() {
+        s = DD::ONE - r2 * s / DD::from((2 * n * (2 * n + 1)) as f64);
+        c = DD::ONE - r2 * c / DD::from((2 * n * (2 * n - 1)) as f64);
+    }
+
+    (r * s, c)
+}
+
+/// sin(r) and cos(r) for `|r| <= π/4`, or a little more, given `r` to within `r_err` units, with
+/// their common error
+fn sin_cos_taylor(r: &Fixed, r_err: u64) -> (Fixed, Fixed, u64) {
+    // The terms r^n / n! alternate between the two series. With r exact, each is off by less than
+    // 4 units and the truncated tails add up to less than 8. The error of r moves either result
+    // by at most as much.
+    let mut term = Fixed::from_int(1, r.prec);
+    let mut sin = Fixed::from_int(0, r.prec);
+    let mut cos = term.clone();
+    let mut n = 1;
+
+    loop {
+        term = term.mul(r).div_int(n);
+        if term.is_zero() {
+            break;
+        }
+
+        match n % 4 {
+            0 => cos = cos.add(&term),
+            1 => sin = sin.add(&term),
+            2 => cos = cos.sub(&term),
+            _ => sin = sin.sub(&term),
+        }
+        n += 1;
+    }
+
+    (sin, cos, 4 * n as u64 + 8 + r_err)
+}
+
+/// Slow path of the trigonometric functions: the quadrant of `|x|`, the sine and cosine of its
+/// reduced argument, and their error
+fn trig_parts_slow(x: f64, prec: u32) -> (u32, Fixed, Fixed, u64) {
+    let x = x.abs();
+    let (quadrant, r, r_err) = if x <= FRAC_PI_4 {
+        (0, Fixed::from_f64(x, 0, prec), 1)
+    } else {
+        reduce_trig(x, prec)
+    };
+
+    let (sin, cos, err) = sin_cos_taylor(&r, r_err);
+    (quadrant, sin, cos, err)
+}
+
+/// Whether sin(x) is the cosine of the reduced argument of |x| rather than its sine, and whether
+/// it is negated
+fn sin_of(x: f64, quadrant: u32) -> (bool, bool) {
+    (quadrant % 2 == 1, (quadrant >= 2) != (x < 0.0))
+}
+
+/// As sin_of, for cos(x)
+fn cos_of(quadrant: u32) -> (bool, bool) {
+    (quadrant.is_multiple_of(2), quadrant == 1 || quadrant == 2)
+}
+
+/// atan(t) for a finite `t >= 0`, to within about 2^-98 relatively
+fn atan_fast(t: DD) -> DD {
+    if t > DD::ONE {
+        return ldexp_dd(dd(PI_DD), -1) - atan_fast(DD::ONE / t);
+    }
+
+    // Four halvings through atan(t) = 2 atan(t / (1 + sqrt(1 + t^2))) leave t <= tan(π/64) < 0.05,
+    // where the series has converged after 14 terms
+    let mut t = t;
+    for _ in 0..4 {
+        t = t / (DD::ONE + (DD::ONE + t * t).sqrt());
+    }
+
+    let t2 = t * t;
+    let mut s = DD::ZERO;
+    for n in (0..14).rev() {
+        s = DD::ONE / DD::from((2 * n + 1) as f64) - t2 * s;
+    }
+
+    ldexp_dd(t * s, 4)
+}
+
+/// atan(t) for `0 <= t <= 1`, or a little more, given `t` to within `t_err` units, with the
+/// error of the result
+fn atan_euler(t: &Fixed, t_err: u64) -> (Fixed, u64) {
+    // Euler's series: atan(t) is t / (1 + t^2) times the sum of 2^2k (k!)^2 / (2k + 1)! y^k for
+    // y = t^2 / (1 + t^2) <= 1/2, whose terms at least halve each time. With t exact, each term is
+    // off by less than 4 units and the truncated tail adds up to less than 8. The error of t
+    // moves y, the factor and the result by less than 4, 3 and 13 times as much.
+    let one = Fixed::from_int(1, t.prec);
+    let one_t2 = one.add(&t.mul(t));
+    let y = t.mul(t).div(&one_t2);
+
+    let mut term = one.clone();
+    let mut sum = one;
+    let mut k = 1;
+
+    loop {
+        term = term.mul(&y).mul_int(2 * k).div_int(2 * k as u32 + 1);
+        if term.is_zero() {
+            break;
+        }
+
+        sum = sum.add(&term);
+        k += 1;
+    }
+
+    (t.div(&one_t2).mul(&sum), 4 * k as u64 + 16 + 16 * t_err)
+}
+
+/// atan(n / d) for nonnegative `n` and `d`, not both zero, given to within `n_err` and `d_err`
+/// units, with the error of the result. Inexact operands must not be far below 2^-30.
+fn atan_ratio(n: &Fixed, n_err: u64, d: &Fixed, d_err: u64) -> (Fixed, u64) {
+    let (num, den, swapped) = if n.mag <= d.mag { (n, d, false) } else { (d, n, true) };
+
+    // The quotient is off by less than 1 + (n_err + d_err) / den units
+    let inverse = 1u64 << (den.prec + 1).saturating_sub(den.mag.bits()).min(63);
+    let t = num.div(den);
+    let (atan, err) = atan_euler(&t, 1 + (n_err + d_err) * inverse);
+
+    if !swapped {
+        return (atan, err);
+    }
+
+    // π/2 - atan(d / n)
+    let (pi, pi_err) = pi_slow(t.prec);
+    (pi.scale(-1).sub(&atan), err + pi_err + 1)
+}
+
+// Slow paths, each returning a value, its error and the power of two to scale both by, as `ziv`
+// expects
+
+fn sin_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    let (quadrant, sin, cos, err) = trig_parts_slow(x, prec);
+    let (use_cos, negative) = sin_of(x, quadrant);
+    let value = if use_cos { cos } else { sin };
+
+    (if negative { value.neg() } else { value }, err, 0)
+}
+
+fn cos_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    let (quadrant, sin, cos, err) = trig_parts_slow(x, prec);
+    let (use_cos, negative) = cos_of(quadrant);
+    let value = if use_cos { cos } else { sin };
+
+    (if negative { value.neg() } else { value }, err, 0)
+}
+
+fn tan_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    let (quadrant, sin, cos, err) = trig_parts_slow(x, prec);
+    let negative = x < 0.0;
+
+    if quadrant.is_multiple_of(2) {
+        // tan(r), at most 1 in magnitude, over cos(r) >= sqrt(1/2)
+        let t = sin.div(&cos);
+        return (if negative { t.neg() } else { t }, 3 * err + 1, 0);
+    }
+
+    // -cot(r), as large as 2^62. Recompute with j more bits, where sin(r) is about 2^-j, and
+    // take sin(r) 2^j, within [1/2, 1), and cos(r) 2^-j to the original precision, leaving a
+    // quotient of at most 2 that is off by a few times the error of either.
+    let j = sin.prec.saturating_sub(sin.mag.bits());
+    let (_, sin, cos, err) = trig_parts_slow(x, prec + j);
+    let p = sin.prec - j;
+    let sin = Fixed { prec: p, ..sin };
+    let cos = Fixed { mag: cos.mag.shr(j), prec: p, ..cos };
+
+    let t = cos.div(&sin);
+    (if negative { t } else { t.neg() }, 32 * err + 8, j as i32)
+}
+
+fn atan_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    let (atan, err) = atan_ratio(&Fixed::from_f64(x.abs(), 0, prec), 0, &Fixed::from_int(1, prec), 0);
+    (if x < 0.0 { atan.neg() } else { atan }, err, 0)
+}
+
+fn asin_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    // As in asin_round, with (1 - |x|) (1 + |x|) exact
+    let one = Fixed::from_int(1, prec);
+    let a = Fixed::from_f64(x.abs(), 0, prec);
+    let s = Fixed::sqrt_product(&one.sub(&a), &one.add(&a));
+    let (asin, err) = atan_ratio(&a, 0, &s, 1);
+
+    (if x < 0.0 { asin.neg() } else { asin }, err, 0)
+}
+
+fn acos_slow(x: f64, prec: u32) -> (Fixed, u64, i32) {
+    // As in acos_round. Only an x so small that 1 - x and 1 + x are close to 1 gets truncated, so
+    // each square root is off by less than 2 units.
+    let one = Fixed::from_int(1, prec);
+    let a = Fixed::from_f64(x, 0, prec);
+    let num = Fixed::sqrt_product(&one.sub(&a), &one);
+    let den = Fixed::sqrt_product(&one.add(&a), &one);
+    let (atan, err) = atan_ratio(&num, 2, &den, 2);
+
+    (atan.scale(1), 2 * err, 0)
+}
+
+/// Slow path of atan2, for `y` and `x` scaled as in atan2_round
+fn atan2_slow(y: f64, x: f64, prec: u32) -> (Fixed, u64, i32) {
+    let (atan, err) = atan_ratio(&Fixed::from_f64(y.abs(), 0, prec), 0, &Fixed::from_f64(x.abs(), 0, prec), 0);
+    let (value, err) = if x > 0.0 {
+        (atan, err)
+    } else {
+        let (pi, pi_err) = pi_slow(prec);
+        (pi.sub(&atan), err + pi_err)
+    };
+
+    (if y < 0.0 { value.neg() } else { value }, err, 0)
+}
+
+/// Computes the sine of x, correctly rounded under the given rounding mode.
+pub fn sin_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x.is_infinite() {
+        return f64::NAN;
+    } else if x == 0.0 {
+        return x;
+    } else if x.abs() < SMALL {
+        // sin(x) = x - x^3 / 6 + ..., just closer to zero than x
+        return nudge(x, x < 0.0, mode);
+    }
+
+    let (quadrant, r) = reduce_fast(x.abs());
+    let (sin, cos) = sin_cos_fast(r);
+    let (use_cos, negative) = sin_of(x, quadrant);
+    let d = if use_cos { cos } else { sin };
+
+    round_fast(if negative { -d } else { d }, 0, mode).unwrap_or_else(|| ziv(mode, |prec| sin_slow(x, prec)))
+}
+
+/// Computes the cosine of x, correctly rounded under the given rounding mode.
+pub fn cos_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x.is_infinite() {
+        return f64::NAN;
+    } else if x.abs() < SMALL {
+        // cos(x) = 1 - x^2 / 2 + ..., just below 1 unless x is zero
+        return if x == 0.0 { 1.0 } else { nudge(1.0, false, mode) };
+    }
+
+    let (quadrant, r) = reduce_fast(x.abs());
+    let (sin, cos) = sin_cos_fast(r);
+    let (use_cos, negative) = cos_of(quadrant);
+    let d = if use_cos { cos } else { sin };
+
+    round_fast(if negative { -d } else { d }, 0, mode).unwrap_or_else(|| ziv(mode, |prec| cos_slow(x, prec)))
+}
+
+/// Computes the tangent of x, correctly rounded under the given rounding mode.
+pub fn tan_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x.is_infinite() {
+        return f64::NAN;
+    } else if x == 0.0 {
+        return x;
+    } else if x.abs() < SMALL {
+        // tan(x) = x + x^3 / 3 + ..., just farther from zero than x
+        return nudge(x, x > 0.0, mode);
+    }
+
+    let (quadrant, r) = reduce_fast(x.abs());
+    let (sin, cos) = sin_cos_fast(r);
+    let d = if quadrant.is_multiple_of(2) { sin / cos } else { -(cos / sin) };
+
+    round_fast(if x < 0.0 { -d } else { d }, 0, mode).unwrap_or_else(|| ziv(mode, |prec| tan_slow(x, prec)))
+}
+
+/// Computes the arcsine of x, correctly rounded under the given rounding mode.
+pub fn asin_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x.abs() > 1.0 {
+        return f64::NAN;
+    } else if x.abs() == 1.0 {
+        return pi_quarters(2 * x as i32, mode);
+    } else if x == 0.0 {
+        return x;
+    } else if x.abs() < SMALL {
+        // asin(x) = x + x^3 / 6 + ..., just farther from zero than x
+        return nudge(x, x > 0.0, mode);
+    }
+
+    // asin(x) = atan(x / sqrt((1 - x) (1 + x))), both factors exact
+    let a = x.abs();
+    let d = atan_fast(DD::from(a) / (DD::new(1.0, -a) * DD::new(1.0, a)).sqrt());
+
+    round_fast(if x < 0.0 { -d } else { d }, 0, mode).unwrap_or_else(|| ziv(mode, |prec| asin_slow(x, prec)))
+}
+
+/// Computes the arccosine of x, correctly rounded under the given rounding mode.
+pub fn acos_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x.abs() > 1.0 {
+        return f64::NAN;
+    } else if x == 1.0 {
+        return 0.0;
+    } else if x == -1.0 {
+        return pi_quarters(4, mode);
+    }
+
+    // acos(x) = 2 atan(sqrt((1 - x) / (1 + x))), both exact, which keeps its relative accuracy
+    // near 1, unlike π/2 - asin(x)
+    let d = ldexp_dd(atan_fast((DD::new(1.0, -x) / DD::new(1.0, x)).sqrt()), 1);
+
+    round_fast(d, 0, mode).unwrap_or_else(|| ziv(mode, |prec| acos_slow(x, prec)))
+}
+
+/// Computes the arctangent of x, correctly rounded under the given rounding mode.
+pub fn atan_round(x: f64, mode: Round) -> f64 {
+    if x.is_nan() {
+        return x + x;
+    } else if x.is_infinite() {
+        return pi_quarters(if x > 0.0 { 2 } else { -2 }, mode);
+    } else if x == 0.0 {
+        return x;
+    } else if x.abs() < SMALL {
+        // atan(x) = x - x^3 / 3 + ..., just closer to zero than x
+        return nudge(x, x < 0.0, mode);
+    }
+
+    let d = atan_fast(DD::from(x.abs()));
+    round_fast(if x < 0.0 { -d } else { d }, 0, mode).unwrap_or_else(|| ziv(mode, |prec| atan_slow(x, prec)))
+}
+
+/// Computes the angle from the positive x axis to the point (x, y), within [-π, π], correctly
+/// rounded under the given rounding mode. Zeros and infinities follow IEEE 754, so that, e.g.,
+/// `atan2(±0, -0)` is ±π and `atan2(±∞, +∞)` is ±π/4.
+pub fn atan2_round(y: f64, x: f64, mode: Round) -> f64 {
+    let sign = if y.is_sign_negative() { -1 } else { 1 };
+
+    if x.is_nan() || y.is_nan() {
+        return x + y;
+    } else if y == 0.0 {
+        return if x.is_sign_positive() { y } else { pi_quarters(4 * sign, mode) };
+    } else if y.is_infinite() {
+        let quarters = if x == f64::INFINITY { 1 } else if x == f64::NEG_INFINITY { 3 } else { 2 };
+        return pi_quarters(quarters * sign, mode);
+    } else if x.is_infinite() {
+        return if x > 0.0 { 0.0f64.copysign(y) } else { pi_quarters(4 * sign, mode) };
+    } else if x == 0.0 {
+        return pi_quarters(2 * sign, mode);
+    }
+
+    // No float or midpoint lies within 2^-62 of π/2 or π, relatively, and below 2^-62, y / x
+    // rounds like atan(y / x) = y / x - (y / x)^3 / 3 + ... once nudged toward zero
+    let (ey, ex) = (exponent(y), exponent(x));
+    if ey - ex > 62 {
+        return pi_quarters(2 * sign, mode);
+    } else if ex - ey > 62 {
+        return if x > 0.0 {
+            round_inward(div_exact(FloatParts::of_f64(y), FloatParts::of_f64(x)), mode)
+        } else {
+            pi_quarters(4 * sign, mode)
+        };
+    }
+
+    // Scale the larger of the two to around 1, which leaves the smaller far from underflowing
+    let scale = -ex.max(ey);
+    let (y, x) = (ldexp(y, scale), ldexp(x, scale));
+
+    let t = atan_fast(DD::from(y.abs()) / DD::from(x.abs()));
+    let d = if x > 0.0 { t } else { dd(PI_DD) - t };
+
+    round_fast(if y < 0.0 { -d } else { d }, 0, mode).unwrap_or_else(|| ziv(mode, |prec| atan2_slow(y, x, prec)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elementary::tests::*;
+    use crate::fallback::div_f64;
+    use crate::successor::{identical_f64, predecessor_f64, successor_f64};
+    use crate::test_cases::*;
+    use std::f64::consts;
+
+    const FUNCTIONS: [(&str, Function); 6] = [
+        ("sin", sin_round),
+        ("cos", cos_round),
+        ("tan", tan_round),
+        ("asin", asin_round),
+        ("acos", acos_round),
+        ("atan", atan_round),
+    ];
+
+    /// The f64 closest to a multiple of π/2, 6381956970095103 * 2^797, whose reduced argument is
+    /// about 2^-60.9
+    fn worst_reduction() -> f64 {
+        6381956970095103.0 * 2f64.powi(797)
+    }
+
+    /// Random argument for the named function, away from the shortcuts: anywhere in the range of
+    /// f64 for the trigonometric functions, mostly in the usual range
+    fn random_arg(name: &str, rng: &mut SplitMix64) -> f64 {
+        let sign = random_sign(rng);
+
+        match name {
+            "sin" | "cos" | "tan" => match rng.range(0, 2) {
+                0 => sign * magnitude(rng, -26, 2),
+                1 => sign * magnitude(rng, 2, 60),
+                _ => sign * magnitude(rng, 60, 1023),
+            },
+            "asin" | "acos" => match rng.range(0, 1) {
+                0 => sign * magnitude(rng, -26, -1),
+                _ => sign * (1.0 - magnitude(rng, -53, -2)),
+            },
+            _ => sign * magnitude(rng, -26, 1023),
+        }
+    }
+
+    /// Whether two big integers differ by at most `n`
+    fn within(a: &BigUint, b: &BigUint, n: u128) -> bool {
+        let diff = if a >= b { a.sub(b) } else { b.sub(a) };
+        diff <= BigUint::from_u128(n)
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(dd(PI_DD), "3.14159265358979323846264338327950288".parse::<DD>().unwrap());
+
+        // The cache against π to more bits, and against the standard library's constants
+        let (pi, err) = pi_machin(CACHED_BITS + 64);
+        assert!(err < 1 << 62 && within(&pi.mag.shr(64), &CACHED.0, 2));
+        assert!(within(&pi_slow(CACHED_BITS + 100).0.mag.shr(100), &CACHED.0, 2));
+        assert!(within(&two_over_pi(CACHED_BITS + 100).shr(100), &CACHED.1, 2));
+
+        let cached = |mag: &BigUint| Fixed { negative: false, mag: mag.clone(), prec: CACHED_BITS };
+        assert_eq!(cached(&CACHED.0).to_f64(0, Round::TiesToEven), PI);
+        assert_eq!(cached(&CACHED.1).to_f64(0, Round::TiesToEven), consts::FRAC_2_PI);
+
+        for (n, expected) in [(1, FRAC_PI_4), (2, consts::FRAC_PI_2), (3, 3.0 * FRAC_PI_4), (4, PI)] {
+            assert_eq!(pi_quarters(n, Round::TiesToEven), expected);
+            assert_eq!(pi_quarters(-n, Round::TiesToEven), -expected);
+        }
+        assert_eq!(pi_quarters(4, Round::TowardPInf), successor_f64(PI));
+        assert_eq!(pi_quarters(4, Round::TowardNInf), PI);
+    }
+
+    #[test]
+    fn test_reduction() {
+        // π itself leaves minus the error of its f64, in the third quadrant
+        let (quadrant, r) = reduce_fast(PI);
+        assert_eq!((quadrant, r.hi()), (2, -PI_DD.1));
+        assert_eq!(reduce_fast(3.0 * consts::FRAC_PI_2).0, 3);
+        assert_eq!(reduce_fast(2.0 * PI).0, 0);
+
+        let (_, r) = reduce_fast(worst_reduction());
+        assert!(r.hi().abs() > 2f64.powi(-61) && r.hi().abs() < 2f64.powi(-60), "{:e}", r.hi());
+
+        // Rounded-down quadrants, either side of multiples of π/2, whose f64s are all below them
+        assert_eq!(floor_quadrant(consts::FRAC_PI_2), 0);
+        assert_eq!(floor_quadrant(successor_f64(consts::FRAC_PI_2)), 1);
+        assert_eq!(floor_quadrant(PI), 1);
+        assert_eq!(floor_quadrant(successor_f64(PI)), 2);
+        assert_eq!(floor_quadrant(-PI), 2);
+        assert_eq!(floor_quadrant(-successor_f64(PI)), 1);
+        assert_eq!(floor_quadrant(-1e-300), 3);
+        assert_eq!(floor_quadrant(-0.0), 0);
+        assert_eq!(floor_quadrant(5.0), 3);
+        assert_eq!(floor_quadrant(6.5), 0);
+
+        // The slow reduction agrees with the fast one at every precision
+        let mut rng = SplitMix64::new(SEED);
+        for _ in 0..200 {
+            let x = random_arg("sin", &mut rng).abs().max(1.0);
+            let (quadrant, r) = reduce_fast(x);
+            for prec in [128, 256, 1024, 4096] {
+                let (q, slow, err) = reduce_trig(x, prec);
+                assert_eq!((q, slow.round_ball(err, 0, Round::TiesToEven)), (quadrant, Some(r.hi())), "{:e}", x);
+            }
+            assert_eq!(floor_quadrant(x), if r.hi() < 0.0 { (quadrant + 3) % 4 } else { quadrant });
+            assert_eq!(floor_quadrant(-x), 3 - floor_quadrant(x));
+        }
+    }
+
+    #[test]
+    fn test_special_values() {
+        for mode in Round::ALL {
+            for f in [sin_round, cos_round, tan_round, asin_round, acos_round, atan_round] {
+                assert!(f(f64::NAN, mode).is_nan());
+            }
+            for f in [sin_round, cos_round, tan_round] {
+                assert!(f(f64::INFINITY, mode).is_nan() && f(f64::NEG_INFINITY, mode).is_nan());
+            }
+            for f in [asin_round, acos_round] {
+                assert!(f(1.5, mode).is_nan() && f(-1.0 - f64::EPSILON, mode).is_nan());
+            }
+
+            for f in [sin_round, tan_round, asin_round, atan_round] {
+                assert!(identical_f64(f(0.0, mode), 0.0) && identical_f64(f(-0.0, mode), -0.0));
+            }
+            assert_eq!(cos_round(-0.0, mode), 1.0);
+            assert!(identical_f64(acos_round(1.0, mode), 0.0));
+            assert_eq!(acos_round(-1.0, mode), pi_quarters(4, mode));
+            assert_eq!(asin_round(-1.0, mode), pi_quarters(-2, mode));
+            assert_eq!(atan_round(f64::INFINITY, mode), pi_quarters(2, mode));
+            assert_eq!(atan_round(f64::NEG_INFINITY, mode), pi_quarters(-2, mode));
+
+            // atan2 at zeros and infinities
+            assert!(atan2_round(f64::NAN, 1.0, mode).is_nan() && atan2_round(1.0, f64::NAN, mode).is_nan());
+            assert!(identical_f64(atan2_round(-0.0, 0.0, mode), -0.0));
+            assert!(identical_f64(atan2_round(0.0, 5.0, mode), 0.0));
+            assert!(identical_f64(atan2_round(-3.0, f64::INFINITY, mode), -0.0));
+            assert_eq!(atan2_round(0.0, -0.0, mode), pi_quarters(4, mode));
+            assert_eq!(atan2_round(-0.0, -1.0, mode), pi_quarters(-4, mode));
+            assert_eq!(atan2_round(2.0, f64::NEG_INFINITY, mode), pi_quarters(4, mode));
+            assert_eq!(atan2_round(1.0, -0.0, mode), pi_quarters(2, mode));
+            assert_eq!(atan2_round(f64::NEG_INFINITY, 1e300, mode), pi_quarters(-2, mode));
+            assert_eq!(atan2_round(f64::INFINITY, f64::INFINITY, mode), pi_quarters(1, mode));
+            assert_eq!(atan2_round(f64::NEG_INFINITY, f64::NEG_INFINITY, mode), pi_quarters(-3, mode));
+        }
+
+        // The shortcuts near zero
+        let x = 2f64.powi(-30);
+        assert_eq!(sin_round(x, Round::TowardZero), predecessor_f64(x));
+        assert_eq!(sin_round(-x, Round::TowardNInf), -x);
+        assert_eq!(tan_round(x, Round::TowardPInf), successor_f64(x));
+        assert_eq!(asin_round(-x, Round::TowardNInf), -successor_f64(x));
+        assert_eq!(atan_round(x, Round::TowardPInf), x);
+        assert_eq!(cos_round(x, Round::TowardPInf), 1.0);
+        assert_eq!(cos_round(x, Round::TowardNInf), 1.0 - f64::EPSILON / 2.0);
+
+        // atan2 of a tiny quotient, exact or not, and of a huge one
+        assert_eq!(atan2_round(1.0, 2f64.powi(70), Round::TowardNInf), predecessor_f64(2f64.powi(-70)));
+        assert_eq!(atan2_round(1.0, 2f64.powi(70), Round::TiesToEven), 2f64.powi(-70));
+        assert_eq!(atan2_round(-1.0, 3e30, Round::TowardPInf), div_f64(-1.0, 3e30, Round::TowardZero));
+        assert_eq!(atan2_round(5e-324, 1e300, Round::TowardPInf), 5e-324);
+        assert_eq!(atan2_round(5e-324, 1e300, Round::TowardNInf), 0.0);
+        assert_eq!(atan2_round(1e300, -1.0, Round::TiesToEven), consts::FRAC_PI_2);
+        assert_eq!(atan2_round(-1e-300, -1.0, Round::TowardZero), -PI);
+    }
+
+    #[test]
+    fn test_known_values() {
+        check_known_values(&[
+            (atan_round, 1.0, FRAC_PI_4),
+            (asin_round, 0.5, consts::FRAC_PI_6),
+            (acos_round, 0.5, consts::FRAC_PI_3),
+            (acos_round, 0.0, consts::FRAC_PI_2),
+            (acos_round, -0.5, 2.0 * consts::FRAC_PI_3),
+            // sin(π) is the error of π's f64, sin(10^22) the classic test of argument reduction
+            (sin_round, PI, PI_DD.1),
+            (sin_round, 1e22, -0.8522008497671888),
+            (cos_round, 1e22, 0.523214785395139),
+        ]);
+
+        assert_eq!(cos_round(PI, Round::TowardNInf), -1.0);
+        assert_eq!(cos_round(PI, Round::TowardPInf), -1.0 + f64::EPSILON / 2.0);
+        assert_eq!(atan2_round(1.0, -1.0, Round::TiesToEven), 3.0 * FRAC_PI_4);
+        assert_eq!(atan2_round(-7.0, 7.0, Round::TiesToEven), -FRAC_PI_4);
+    }
+
+    #[test]
+    fn test_reference() {
+        check_references(&FUNCTIONS);
+        check_reference("atan2", |args, mode| atan2_round(args[0], args[1], mode));
+    }
+
+    #[test]
+    fn test_rounding_directions() {
+        check_directions(&FUNCTIONS, random_arg);
+    }
+
+    #[test]
+    fn test_inverses() {
+        let mut rng = SplitMix64::new(SEED ^ 2);
+
+        for _ in 0..2000 {
+            // Both functions increase on [-π/2, π/2], where the inverses land
+            let x = random_arg("asin", &mut rng);
+            let (lower, upper) = (asin_round(x, Round::TowardNInf), asin_round(x, Round::TowardPInf));
+            assert!(sin_round(lower, Round::TowardNInf) <= x && x <= sin_round(upper, Round::TowardPInf), "{:e}", x);
+
+            let x = random_arg("atan", &mut rng).clamp(-1e15, 1e15);
+            let (lower, upper) = (atan_round(x, Round::TowardNInf), atan_round(x, Round::TowardPInf));
+            assert!(tan_round(lower, Round::TowardNInf) <= x && x <= tan_round(upper, Round::TowardPInf), "{:e}", x);
+
+            // cos decreases on [0, π]
+            let x = random_arg("acos", &mut rng);
+            let (lower, upper) = (acos_round(x, Round::TowardNInf), acos_round(x, Round::TowardPInf));
+            assert!(cos_round(upper, Round::TowardNInf) <= x && x <= cos_round(lower, Round::TowardPInf), "{:e}", x);
+
+            let x = random_arg("sin", &mut rng);
+            assert!(near_std(sin_round(x, Round::TiesToEven), x.sin()), "{:e}", x);
+            assert!(near_std(cos_round(x, Round::TiesToEven), x.cos()), "{:e}", x);
+            assert!(near_std(tan_round(x, Round::TiesToEven), x.tan()), "{:e}", x);
+
+            let (y, x) = (random_arg("atan", &mut rng), random_arg("atan", &mut rng));
+            assert!(near_std(atan2_round(y, x, Round::TiesToEven), y.atan2(x)), "{:e}, {:e}", y, x);
+        }
+    }
+}
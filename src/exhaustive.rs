@@ -0,0 +1,181 @@
+// Exhaustive verification of the software fallbacks for unary f32 operations against the native
+// MXCSR results, for every natively supported rounding mode. All 2^32 inputs are checkable on one
+// machine, but it takes a while, so the full runs are ignored by default; run them with
+//
+//     cargo test --release -- --ignored exhaustive
+//
+// The non-ignored tests check a strided sample of the inputs instead.
+//
+// Rounding to an integral value has the standard library's functions as its reference, since
+// where the CPU lacks an instruction for it the native version is itself emulated in software.
+
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use crate::modes::*;
+use crate::successor::*;
+use crate::{fallback, native};
+
+/// Unary f32 operation under a rounding mode, e.g., square root
+type UnaryF32Fn<T> = fn(f32, Round) -> T;
+
+/// Number of inputs handed to a thread at a time
+const CHUNK: u64 = 1 << 20;
+
+/// Stride used by the sampled tests. Being odd, it visits every sign and exponent.
+const SAMPLE_STRIDE: u64 = 65521;
+
+/// An input on which the two implementations disagree.
+#[derive(Debug)]
+struct Mismatch {
+    bits: u32,
+    mode: Round,
+    expected: String,
+    actual: String,
+}
+
+/// A result that can be compared between implementations and printed in hex.
+trait Outcome: Debug + PartialEq + Send {
+    fn same(&self, other: &Self) -> bool;
+
+    fn hex(&self) -> String;
+}
+
+impl Outcome for f32 {
+    /// NaNs only need to agree in being NaN, since the default NaN differs between platforms
+    fn same(&self, other: &f32) -> bool {
+        identical_f32(*self, *other) || (self.is_nan() && other.is_nan())
+    }
+
+    fn hex(&self) -> String {
+        format!("{:#010x}", self.to_bits())
+    }
+}
+
+impl Outcome for Option<i32> {
+    fn same(&self, other: &Option<i32>) -> bool {
+        self == other
+    }
+
+    fn hex(&self) -> String {
+        match self {
+            Some(i) => format!("Some({:#010x})", i),
+            None => "None".to_string()
+        }
+    }
+}
+
+/// Compares the two implementations on every `stride`-th bit pattern, for every natively supported
+/// rounding mode, spreading the work over all available threads. Returns the mismatch with the
+/// smallest input bit pattern, if any.
+fn check_unary_f32<T: Outcome>(expected: UnaryF32Fn<T>, actual: UnaryF32Fn<T>, stride: u64) -> Option<Mismatch> {
+    let count = (1u64 << 32).div_ceil(stride);
+    let next_chunk = AtomicU64::new(0);
+    let first: Mutex<Option<Mismatch>> = Mutex::new(None);
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| loop {
+                let start = next_chunk.fetch_add(CHUNK, Ordering::Relaxed);
+                if start >= count {
+                    return;
+                }
+
+                // Chunks are handed out in increasing order, so once a mismatch is found, later
+                // chunks can't contain an earlier one
+                if let Some(m) = first.lock().unwrap().as_ref() {
+                    if m.bits as u64 <= start * stride {
+                        return;
+                    }
+                }
+
+                'inputs: for i in start..(start + CHUNK).min(count) {
+                    let bits = (i * stride) as u32;
+                    let f = f32::from_bits(bits);
+
+                    for mode in Round::ALL.into_iter().filter(Round::is_native) {
+                        let e = expected(f, mode);
+                        let a = actual(f, mode);
+
+                        if !e.same(&a) {
+                            let mut first = first.lock().unwrap();
+                            if first.as_ref().is_none_or(|m| bits < m.bits) {
+                                *first = Some(Mismatch { bits, mode, expected: e.hex(), actual: a.hex() });
+                            }
+
+                            break 'inputs;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    first.into_inner().unwrap()
+}
+
+/// Rounds an f32 to an integral value with the standard library's functions for each mode.
+fn reference_round_f32(a: f32, mode: Round) -> f32 {
+    match mode {
+        Round::TowardZero => a.trunc(),
+        Round::TowardPInf => a.ceil(),
+        Round::TowardNInf => a.floor(),
+        _ => a.round_ties_even(),
+    }
+}
+
+fn assert_agrees<T: Outcome>(name: &str, expected: UnaryF32Fn<T>, actual: UnaryF32Fn<T>, stride: u64) {
+    if let Some(m) = check_unary_f32(expected, actual, stride) {
+        panic!("{}({:#010x}, {:?}): expected = {}, actual = {}", name, m.bits, m.mode, m.expected, m.actual);
+    }
+}
+
+#[test]
+fn sampled_sqrt_f32() {
+    assert_agrees("sqrt_f32", native::sqrt_f32, fallback::sqrt_f32, SAMPLE_STRIDE);
+}
+
+#[test]
+fn sampled_round_f32() {
+    assert_agrees("round_f32", reference_round_f32, native::round_f32, SAMPLE_STRIDE);
+    assert_agrees("round_f32", reference_round_f32, fallback::round_f32, SAMPLE_STRIDE);
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn sampled_round_f32_without_sse4_1() {
+    assert_agrees("round_f32", reference_round_f32, native::round_magic, SAMPLE_STRIDE);
+}
+
+#[test]
+fn sampled_f32_to_i32() {
+    assert_agrees("f32_to_i32", native::f32_to_i32, fallback::f32_to_i32, SAMPLE_STRIDE);
+}
+
+#[test]
+#[ignore]
+fn exhaustive_sqrt_f32() {
+    assert_agrees("sqrt_f32", native::sqrt_f32, fallback::sqrt_f32, 1);
+}
+
+#[test]
+#[ignore]
+fn exhaustive_round_f32() {
+    assert_agrees("round_f32", reference_round_f32, native::round_f32, 1);
+    assert_agrees("round_f32", reference_round_f32, fallback::round_f32, 1);
+}
+
+#[test]
+#[ignore]
+fn exhaustive_f32_to_i32() {
+    assert_agrees("f32_to_i32", native::f32_to_i32, fallback::f32_to_i32, 1);
+}
+
+#[test]
+#[should_panic(expected = "sqrt_f32(0x3f800000, TiesToEven): expected = 0x3f800000, actual = 0x3f800001")]
+fn reports_first_mismatch() {
+    assert_agrees("sqrt_f32", native::sqrt_f32, |f, mode| if f >= 1.0 { successor_f32(native::sqrt_f32(f, mode)) } else { native::sqrt_f32(f, mode) }, 1 << 10);
+}
@@ -0,0 +1,1089 @@
+// Software binary128 (quadruple precision) floats, for reference computations and error bounds
+// that need more precision than f64 offers. Operations are carried out on exact integer mantissas
+// and rounded by the same core as the fallbacks, so each one takes an explicit rounding mode.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use crate::bigint::BigUint;
+use crate::fallback::{round_shift, round_to_format, rounds_away, Format};
+use crate::modes::*;
+use crate::successor::FloatParts;
+
+const FORMAT: Format = Format::BINARY128;
+const SIGN_MASK: u128 = 1 << 127;
+const FRAC_MASK: u128 = (1 << 112) - 1;
+const QUIET_BIT: u128 = 1 << 111;
+
+/// Exponent of the last place of subnormals, i.e., of the smallest positive F128
+const MIN_LAST_PLACE: i32 = -16494;
+
+/// An IEEE 754 binary128 floating-point number, stored as its bit pattern. Comparisons follow
+/// IEEE 754 semantics, so NaNs are unordered and the two zeros are equal.
+#[derive(Copy, Clone, Default)]
+pub struct F128(u128);
+
+/// Decomposition of a finite F128 into `(-1)^negative * mant * 2^exp`, as `FloatParts` does for
+/// the hardware formats
+#[derive(Copy, Clone, Debug)]
+struct Parts {
+    negative: bool,
+    exp: i32,
+    mant: u128,
+}
+
+impl Parts {
+    fn of(f: F128) -> Parts {
+        debug_assert!(f.is_finite());
+
+        let biased = ((f.0 >> 112) & 0x7fff) as i32;
+        let mut mant = f.0 & FRAC_MASK;
+
+        if biased != 0 {
+            mant += 1 << 112; // implicit upper bit
+        }
+
+        Parts { negative: f.0 & SIGN_MASK != 0, exp: biased.max(1) + MIN_LAST_PLACE - 1, mant }
+    }
+
+    /// Shifts a nonzero mantissa so that its leading bit is bit 112.
+    fn normalized(self) -> Parts {
+        debug_assert!(self.mant != 0);
+
+        let shift = self.mant.leading_zeros() as i32 - 15;
+        Parts { mant: self.mant << shift, exp: self.exp - shift, ..self }
+    }
+}
+
+/// Unsigned 256-bit integer, wide enough for exact products of two mantissas
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct U256 {
+    hi: u128,
+    lo: u128,
+}
+
+impl U256 {
+    const ZERO: U256 = U256 { hi: 0, lo: 0 };
+
+    fn from_u128(lo: u128) -> U256 {
+        U256 { hi: 0, lo }
+    }
+
+    /// Exact product of two 128-bit integers, split into 64-bit halves
+    fn mul(a: u128, b: u128) -> U256 {
+        let (a_hi, a_lo) = (a >> 64, a & u64::MAX as u128);
+        let (b_hi, b_lo) = (b >> 64, b & u64::MAX as u128);
+
+        let lo_lo = a_lo * b_lo;
+        let (mid, carry) = (a_hi * b_lo).overflowing_add(a_lo * b_hi);
+        let (lo, carry_lo) = lo_lo.overflowing_add(mid << 64);
+
+        let hi = a_hi * b_hi + (mid >> 64) + ((carry as u128) << 64) + carry_lo as u128;
+        U256 { hi, lo }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.hi == 0 && self.lo == 0
+    }
+
+    fn leading_zeros(&self) -> u32 {
+        if self.hi != 0 { self.hi.leading_zeros() } else { 128 + self.lo.leading_zeros() }
+    }
+
+    fn shl(&self, n: u32) -> U256 {
+        match n {
+            0 => *self,
+            1..=127 => U256 { hi: (self.hi << n) | (self.lo >> (128 - n)), lo: self.lo << n },
+            128..=255 => U256 { hi: self.lo << (n - 128), lo: 0 },
+            _ => U256::ZERO
+        }
+    }
+
+    fn shr(&self, n: u32) -> U256 {
+        match n {
+            0 => *self,
+            1..=127 => U256 { hi: self.hi >> n, lo: (self.lo >> n) | (self.hi << (128 - n)) },
+            128..=255 => U256 { hi: 0, lo: self.hi >> (n - 128) },
+            _ => U256::ZERO
+        }
+    }
+
+    /// Shifts right, also returning whether any nonzero bits were shifted out
+    fn shr_sticky(&self, n: u32) -> (U256, bool) {
+        let shifted = self.shr(n);
+        (shifted, shifted.shl(n) != *self)
+    }
+
+    fn add(&self, other: U256) -> U256 {
+        let (lo, carry) = self.lo.overflowing_add(other.lo);
+        U256 { hi: self.hi + other.hi + carry as u128, lo }
+    }
+
+    fn sub(&self, other: U256) -> U256 {
+        let (lo, borrow) = self.lo.overflowing_sub(other.lo);
+        U256 { hi: self.hi - other.hi - borrow as u128, lo }
+    }
+
+    /// Integer square root and whether it is exact, computed digit by digit
+    fn isqrt(&self) -> (u128, bool) {
+        let mut n = *self;
+        let mut root = U256::ZERO;
+        let mut bit = U256::from_u128(1).shl(254);
+
+        while bit > n {
+            bit = bit.shr(2);
+        }
+
+        while !bit.is_zero() {
+            let t = root.add(bit);
+            if n >= t {
+                n = n.sub(t);
+                root = root.shr(1).add(bit);
+            } else {
+                root = root.shr(1);
+            }
+            bit = bit.shr(2);
+        }
+
+        (root.lo, n.is_zero())
+    }
+
+    /// Narrows to at most 127 significant bits, returning the mantissa, the shift applied, and
+    /// whether anything nonzero was discarded.
+    fn narrow(&self) -> (u128, i32, bool) {
+        let shift = 129u32.saturating_sub(self.leading_zeros());
+        let (mant, sticky) = self.shr_sticky(shift);
+
+        (mant.lo, shift as i32, sticky)
+    }
+}
+
+/// Rounds the exact value `(-1)^negative * (mant + sticky) * 2^exp`, with `mant` as wide as 256
+/// bits, to an F128.
+fn round_wide(negative: bool, mant: U256, exp: i32, sticky: bool, mode: Round) -> F128 {
+    let (mant, shift, lost) = mant.narrow();
+    F128(round_to_format(FORMAT, negative, mant, exp + shift, sticky || lost, mode))
+}
+
+/// Sum of two finite values.
+fn add_parts(a: Parts, b: Parts, mode: Round) -> F128 {
+    // Give each 113-bit mantissa 14 guard bits, leaving room for a carry, then align the one with
+    // the smaller exponent to the other. Bits are only shifted out when the exponents differ by
+    // more than 14, in which case the larger operand is normal and remembering them as a sticky
+    // bit is enough.
+    let (a, b) = if a.exp >= b.exp { (a, b) } else { (b, a) };
+    let x = a.mant << 14;
+    let y = b.mant << 14;
+    let exp = a.exp - 14;
+
+    let shift = (a.exp - b.exp) as u32;
+    let (y, sticky) = if shift == 0 {
+        (y, false)
+    } else if shift >= 128 {
+        (0, y != 0)
+    } else {
+        (y >> shift, y & ((1 << shift) - 1) != 0)
+    };
+
+    let bits = if a.negative == b.negative {
+        round_to_format(FORMAT, a.negative, x + y, exp, sticky, mode)
+    } else if sticky {
+        // As in the fallbacks, x - (y + sticky) = (x - y - 1) + (1 - sticky)
+        round_to_format(FORMAT, a.negative, x - y - 1, exp, true, mode)
+    } else {
+        match x.cmp(&y) {
+            Ordering::Greater => round_to_format(FORMAT, a.negative, x - y, exp, false, mode),
+            Ordering::Less => round_to_format(FORMAT, b.negative, y - x, exp, false, mode),
+            Ordering::Equal => round_to_format(FORMAT, mode == Round::TowardNInf, 0, exp, false, mode)
+        }
+    };
+
+    F128(bits)
+}
+
+/// Sum of a nonzero exact product `(-1)^negative * mant * 2^exp` and a nonzero finite value.
+fn add_wide(negative: bool, mant: U256, exp: i32, c: Parts, mode: Round) -> F128 {
+    // Normalize both to a leading bit at bit 253, leaving room for a carry. The product has at
+    // most 226 significant bits and c at most 113, so, as in add_parts, bits are only shifted out
+    // of the smaller operand when the exponents are far enough apart that the larger dominates.
+    let normalize = |mant: U256, exp: i32| {
+        let shift = mant.leading_zeros() - 2;
+        (mant.shl(shift), exp - shift as i32)
+    };
+
+    let p = normalize(mant, exp);
+    let q = normalize(U256::from_u128(c.mant), c.exp);
+    let ((x, exp, x_neg), (y, y_exp, y_neg)) = if p.1 >= q.1 {
+        ((p.0, p.1, negative), (q.0, q.1, c.negative))
+    } else {
+        ((q.0, q.1, c.negative), (p.0, p.1, negative))
+    };
+
+    let (y, sticky) = y.shr_sticky((exp - y_exp).min(256) as u32);
+
+    if x_neg == y_neg {
+        return round_wide(x_neg, x.add(y), exp, sticky, mode);
+    }
+
+    if sticky {
+        return round_wide(x_neg, x.sub(y).sub(U256::from_u128(1)), exp, true, mode);
+    }
+
+    match x.cmp(&y) {
+        Ordering::Greater => round_wide(x_neg, x.sub(y), exp, false, mode),
+        Ordering::Less => round_wide(y_neg, y.sub(x), exp, false, mode),
+        Ordering::Equal => F128(round_to_format(FORMAT, mode == Round::TowardNInf, 0, exp, false, mode))
+    }
+}
+
+/// Quotient of two finite values with nonzero divisor.
+fn div_parts(a: Parts, b: Parts, mode: Round) -> F128 {
+    let (a, b) = (a.normalized(), b.normalized());
+
+    // With both mantissas in [2^112, 2^113), long division yields 126 or 127 quotient bits
+    let mut rem = a.mant;
+    let mut quot = 0u128;
+    for i in 0..=125 {
+        if i != 0 {
+            rem <<= 1;
+        }
+
+        quot <<= 1;
+        if rem >= b.mant {
+            rem -= b.mant;
+            quot |= 1;
+        }
+    }
+
+    F128(round_to_format(FORMAT, a.negative != b.negative, quot, a.exp - b.exp - 125, rem != 0, mode))
+}
+
+/// Square root of a positive finite value.
+fn sqrt_parts(a: Parts, mode: Round) -> F128 {
+    let a = a.normalized();
+
+    // Widen the mantissa to 253 or 254 bits, keeping the exponent even, so that the root carries
+    // at least 126 bits
+    let mut shift = 141;
+    if (a.exp - shift) % 2 != 0 {
+        shift += 1;
+    }
+
+    let (root, exact) = U256::from_u128(a.mant).shl(shift as u32).isqrt();
+    F128(round_to_format(FORMAT, false, root, (a.exp - shift) / 2, !exact, mode))
+}
+
+/// Rounds `(-1)^negative * num / den` to an F128.
+fn round_ratio(negative: bool, num: &BigUint, den: &BigUint, mode: Round) -> F128 {
+    // Scale so that the integer quotient has 127 or 128 bits, and round that
+    let k = 127 - (num.bits() as i32 - den.bits() as i32);
+    let (quot, rem) = if k >= 0 {
+        num.shl(k as u32).div_rem(den)
+    } else {
+        num.div_rem(&den.shl((-k) as u32))
+    };
+
+    F128(round_to_format(FORMAT, negative, quot.low_u128(), -k, !rem.is_zero(), mode))
+}
+
+/// Error returned when parsing an F128 from a malformed string
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseF128Error;
+
+impl fmt::Display for ParseF128Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid binary128 literal")
+    }
+}
+
+impl std::error::Error for ParseF128Error {}
+
+impl F128 {
+    pub const ZERO: F128 = F128(0);
+    pub const ONE: F128 = F128(0x3fff << 112);
+    pub const INFINITY: F128 = F128(0x7fff << 112);
+    pub const NEG_INFINITY: F128 = F128(0xffff << 112);
+    pub const NAN: F128 = F128((0x7fff << 112) | QUIET_BIT);
+    /// Largest finite value, (2 - 2^-112) * 2^16383
+    pub const MAX: F128 = F128((0x7fff << 112) - 1);
+    /// Smallest finite value, -MAX
+    pub const MIN: F128 = F128(((0xffff) << 112) - 1);
+    /// Smallest positive normal value, 2^-16382
+    pub const MIN_POSITIVE: F128 = F128(1 << 112);
+    /// Difference between 1 and the next larger value, 2^-112
+    pub const EPSILON: F128 = F128((0x3fff - 112) << 112);
+
+    pub const fn from_bits(bits: u128) -> F128 {
+        F128(bits)
+    }
+
+    pub const fn to_bits(self) -> u128 {
+        self.0
+    }
+
+    pub fn is_nan(self) -> bool {
+        self.0 & !SIGN_MASK > FORMAT.inf_bits()
+    }
+
+    pub fn is_infinite(self) -> bool {
+        self.0 & !SIGN_MASK == FORMAT.inf_bits()
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.0 & !SIGN_MASK < FORMAT.inf_bits()
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 & !SIGN_MASK == 0
+    }
+
+    pub fn is_sign_negative(self) -> bool {
+        self.0 & SIGN_MASK != 0
+    }
+
+    pub fn abs(self) -> F128 {
+        F128(self.0 & !SIGN_MASK)
+    }
+
+    /// Quiets a NaN, keeping its sign and payload.
+    fn quiet(self) -> F128 {
+        F128(self.0 | QUIET_BIT)
+    }
+
+    /// Computes the sum of two F128s under the given rounding mode.
+    pub fn add(self, other: F128, mode: Round) -> F128 {
+        if self.is_nan() || other.is_nan() {
+            return if self.is_nan() { self.quiet() } else { other.quiet() };
+        } else if self.is_infinite() || other.is_infinite() {
+            if self.is_infinite() && other.is_infinite() && self.0 != other.0 {
+                return F128::NAN;
+            }
+            return if self.is_infinite() { self } else { other };
+        }
+
+        add_parts(Parts::of(self), Parts::of(other), mode)
+    }
+
+    /// Computes the difference of two F128s under the given rounding mode.
+    pub fn sub(self, other: F128, mode: Round) -> F128 {
+        if other.is_nan() {
+            return self.add(other, mode);
+        }
+
+        self.add(-other, mode)
+    }
+
+    /// Computes the product of two F128s under the given rounding mode.
+    pub fn mul(self, other: F128, mode: Round) -> F128 {
+        let negative = self.is_sign_negative() != other.is_sign_negative();
+        let sign = if negative { SIGN_MASK } else { 0 };
+
+        if self.is_nan() || other.is_nan() {
+            return if self.is_nan() { self.quiet() } else { other.quiet() };
+        } else if self.is_infinite() || other.is_infinite() {
+            if self.is_zero() || other.is_zero() {
+                return F128::NAN;
+            }
+            return F128(FORMAT.inf_bits() | sign);
+        } else if self.is_zero() || other.is_zero() {
+            return F128(sign);
+        }
+
+        let (a, b) = (Parts::of(self), Parts::of(other));
+        round_wide(negative, U256::mul(a.mant, b.mant), a.exp + b.exp, false, mode)
+    }
+
+    /// Computes the quotient of two F128s under the given rounding mode.
+    pub fn div(self, other: F128, mode: Round) -> F128 {
+        let sign = if self.is_sign_negative() != other.is_sign_negative() { SIGN_MASK } else { 0 };
+
+        if self.is_nan() || other.is_nan() {
+            return if self.is_nan() { self.quiet() } else { other.quiet() };
+        } else if self.is_infinite() {
+            return if other.is_infinite() { F128::NAN } else { F128(FORMAT.inf_bits() | sign) };
+        } else if other.is_infinite() {
+            return F128(sign);
+        } else if other.is_zero() {
+            return if self.is_zero() { F128::NAN } else { F128(FORMAT.inf_bits() | sign) };
+        } else if self.is_zero() {
+            return F128(sign);
+        }
+
+        div_parts(Parts::of(self), Parts::of(other), mode)
+    }
+
+    /// Computes the square root of an F128 under the given rounding mode.
+    pub fn sqrt(self, mode: Round) -> F128 {
+        if self.is_nan() {
+            return self.quiet();
+        } else if self.is_zero() || self == F128::INFINITY {
+            return self;
+        } else if self.is_sign_negative() {
+            return F128::NAN;
+        }
+
+        sqrt_parts(Parts::of(self), mode)
+    }
+
+    /// Computes `self * a + b` with a single rounding under the given rounding mode.
+    pub fn fma(self, a: F128, b: F128, mode: Round) -> F128 {
+        if self.is_nan() || a.is_nan() || b.is_nan() {
+            let nan = [self, a, b].into_iter().find(|f| f.is_nan()).unwrap();
+            return nan.quiet();
+        }
+
+        if self.is_infinite() || a.is_infinite() {
+            // The product is exactly infinite, or invalid
+            return self.mul(a, mode).add(b, mode);
+        } else if b.is_infinite() {
+            return b;
+        } else if self.is_zero() || a.is_zero() {
+            // The product is an exact zero, whose sign matters if b is zero as well
+            return self.mul(a, mode).add(b, mode);
+        }
+
+        let (x, y) = (Parts::of(self), Parts::of(a));
+        let negative = x.negative != y.negative;
+        let mant = U256::mul(x.mant, y.mant);
+
+        if b.is_zero() {
+            return round_wide(negative, mant, x.exp + y.exp, false, mode);
+        }
+
+        add_wide(negative, mant, x.exp + y.exp, Parts::of(b), mode)
+    }
+
+    /// Converts an f64 to an F128, which is always exact.
+    pub fn from_f64(f: f64) -> F128 {
+        if f.is_nan() {
+            let bits = f.to_bits();
+            return F128(((bits as u128 >> 63) << 127) | FORMAT.inf_bits() | ((bits as u128 & 0x000f_ffff_ffff_ffff) << 60)).quiet();
+        } else if f.is_infinite() {
+            return if f > 0. { F128::INFINITY } else { F128::NEG_INFINITY };
+        }
+
+        let parts = FloatParts::of_f64(f);
+        F128(round_to_format(FORMAT, parts.negative, parts.mant as u128, parts.exp, false, Round::TiesToEven))
+    }
+
+    /// Converts an f32 to an F128, which is always exact.
+    pub fn from_f32(f: f32) -> F128 {
+        F128::from_f64(f as f64)
+    }
+
+    /// Converts to an f64 under the given rounding mode.
+    pub fn to_f64(self, mode: Round) -> f64 {
+        if self.is_nan() {
+            let payload = ((self.0 & FRAC_MASK) >> 60) as u64 | (1 << 51);
+            return f64::from_bits(((self.0 >> 127) as u64) << 63 | 0x7ff0_0000_0000_0000 | payload);
+        }
+
+        if self.is_infinite() {
+            return if self.is_sign_negative() { f64::NEG_INFINITY } else { f64::INFINITY };
+        }
+
+        let parts = Parts::of(self);
+        f64::from_bits(round_to_format(Format::BINARY64, parts.negative, parts.mant, parts.exp, false, mode) as u64)
+    }
+
+    /// Converts to an f32 under the given rounding mode.
+    pub fn to_f32(self, mode: Round) -> f32 {
+        if self.is_nan() {
+            return self.to_f64(mode) as f32;
+        }
+
+        if self.is_infinite() {
+            return if self.is_sign_negative() { f32::NEG_INFINITY } else { f32::INFINITY };
+        }
+
+        let parts = Parts::of(self);
+        f32::from_bits(round_to_format(Format::BINARY32, parts.negative, parts.mant, parts.exp, false, mode) as u32)
+    }
+
+    /// Converts a u128 to an F128 under the given rounding mode.
+    pub fn from_u128(n: u128, mode: Round) -> F128 {
+        F128(round_to_format(FORMAT, false, n, 0, false, mode))
+    }
+
+    /// Converts an i128 to an F128 under the given rounding mode.
+    pub fn from_i128(n: i128, mode: Round) -> F128 {
+        F128(round_to_format(FORMAT, n < 0, n.unsigned_abs(), 0, false, mode))
+    }
+
+    /// Converts a u64 to an F128, which is always exact.
+    pub fn from_u64(n: u64) -> F128 {
+        F128::from_u128(n as u128, Round::TiesToEven)
+    }
+
+    /// Converts an i64 to an F128, which is always exact.
+    pub fn from_i64(n: i64) -> F128 {
+        F128::from_i128(n as i128, Round::TiesToEven)
+    }
+
+    /// Converts to an i128 under the given rounding mode, returning None for NaN and for results
+    /// out of range.
+    pub fn to_i128(self, mode: Round) -> Option<i128> {
+        if !self.is_finite() {
+            return None;
+        }
+
+        let parts = Parts::of(self);
+        let magnitude = if parts.exp >= 0 {
+            if parts.mant != 0 && parts.exp as u32 > parts.mant.leading_zeros() {
+                return None;
+            }
+            parts.mant << parts.exp
+        } else {
+            round_shift(parts.negative, parts.mant, (-parts.exp) as u32, false, mode)
+        };
+
+        if parts.negative {
+            0i128.checked_sub_unsigned(magnitude)
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+
+    /// Converts to an i64 under the given rounding mode, returning None for NaN and for results
+    /// out of range.
+    pub fn to_i64(self, mode: Round) -> Option<i64> {
+        self.to_i128(mode).and_then(|n| i64::try_from(n).ok())
+    }
+
+    /// Parses a decimal string, e.g., "-1.5e-300", "inf" or "NaN", rounding the exact value it
+    /// denotes under the given rounding mode.
+    pub fn parse(s: &str, mode: Round) -> Result<F128, ParseF128Error> {
+        let (negative, body) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s)
+        };
+        let sign = if negative { SIGN_MASK } else { 0 };
+
+        match body.to_ascii_lowercase().as_str() {
+            "inf" | "infinity" => return Ok(F128(FORMAT.inf_bits() | sign)),
+            "nan" => return Ok(F128(F128::NAN.0 | sign)),
+            _ => {}
+        }
+
+        let (mantissa, exponent) = match body.find(['e', 'E']) {
+            Some(i) => (&body[..i], Some(&body[i + 1..])),
+            None => (body, None)
+        };
+
+        let (int_digits, frac_digits) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let all_digits = |t: &str| t.bytes().all(|c| c.is_ascii_digit());
+        if int_digits.len() + frac_digits.len() == 0 || !all_digits(int_digits) || !all_digits(frac_digits) {
+            return Err(ParseF128Error);
+        }
+
+        // The exponent saturates, since anything that large over- or underflows regardless
+        let mut exp = match exponent {
+            None => 0i64,
+            Some(e) => {
+                let (e_negative, e_digits) = match e.as_bytes().first() {
+                    Some(b'-') => (true, &e[1..]),
+                    Some(b'+') => (false, &e[1..]),
+                    _ => (false, e)
+                };
+
+                if e_digits.is_empty() || !all_digits(e_digits) {
+                    return Err(ParseF128Error);
+                }
+
+                let magnitude = e_digits.bytes().fold(0i64, |acc, c| (acc * 10 + (c - b'0') as i64).min(1 << 40));
+                if e_negative { -magnitude } else { magnitude }
+            }
+        };
+
+        let digits = format!("{}{}", int_digits, frac_digits);
+        let digits = digits.trim_start_matches('0');
+        exp -= frac_digits.len() as i64;
+
+        if digits.is_empty() {
+            return Ok(F128(sign));
+        }
+
+        // Values this far outside the range of finite F128s can stand in for anything larger or
+        // smaller, sparing us enormous powers of ten
+        let magnitude = exp + digits.len() as i64;
+        if magnitude > 5000 {
+            return Ok(F128(round_to_format(FORMAT, negative, 1, 20000, false, mode)));
+        } else if magnitude < -5000 {
+            return Ok(F128(round_to_format(FORMAT, negative, 1, -20000, false, mode)));
+        }
+
+        let mantissa = digits.bytes().fold(BigUint::zero(), |acc, c| {
+            acc.mul(&BigUint::from_u128(10)).add(&BigUint::from_u128((c - b'0') as u128))
+        });
+
+        Ok(if exp >= 0 {
+            round_ratio(negative, &mantissa.mul(&BigUint::pow(10, exp as u32)), &BigUint::one(), mode)
+        } else {
+            round_ratio(negative, &mantissa, &BigUint::pow(10, (-exp) as u32), mode)
+        })
+    }
+
+    /// Formats in scientific notation with `digits` significant digits, e.g., "1.250e-3" for four
+    /// digits, rounding under the given rounding mode. Panics unless `digits` is in `1..=38`.
+    pub fn to_decimal(self, digits: usize, mode: Round) -> String {
+        assert!((1..=38).contains(&digits), "digits must be in 1..=38");
+
+        let sign = if self.is_sign_negative() { "-" } else { "" };
+        if self.is_nan() {
+            return "NaN".to_string();
+        } else if self.is_infinite() {
+            return format!("{}inf", sign);
+        }
+
+        let parts = Parts::of(self);
+        let (decimal, exp10) = if parts.mant == 0 {
+            (0, 0)
+        } else {
+            let (num, den) = if parts.exp >= 0 {
+                (BigUint::from_u128(parts.mant).shl(parts.exp as u32), BigUint::one())
+            } else {
+                (BigUint::from_u128(parts.mant), BigUint::one().shl((-parts.exp) as u32))
+            };
+
+            // Estimate floor(log10(self)), then correct it exactly
+            let log2 = (parts.mant as f64).log2() + parts.exp as f64;
+            let mut exp10 = (log2 * std::f64::consts::LOG10_2).floor() as i32;
+            let scaled = |e: i32| if e >= 0 {
+                (num.clone(), den.mul(&BigUint::pow(10, e as u32)))
+            } else {
+                (num.mul(&BigUint::pow(10, (-e) as u32)), den.clone())
+            };
+
+            loop {
+                let (n, d) = scaled(exp10);
+                if n < d {
+                    exp10 -= 1;
+                } else if n >= d.mul(&BigUint::from_u128(10)) {
+                    exp10 += 1;
+                } else {
+                    break;
+                }
+            }
+
+            // Round self / 10^(exp10 - digits + 1) to an integer with the given number of digits
+            let (n, d) = scaled(exp10 - digits as i32 + 1);
+            let (quot, rem) = n.div_rem(&d);
+            let mut decimal = quot.low_u128();
+
+            let vs_half = rem.shl(1).cmp(&d);
+            if rounds_away(parts.negative, decimal & 1 == 1, vs_half, !rem.is_zero(), mode) {
+                decimal += 1;
+                if decimal == 10u128.pow(digits as u32) {
+                    decimal /= 10;
+                    exp10 += 1;
+                }
+            }
+
+            (decimal, exp10)
+        };
+
+        let decimal = format!("{:0width$}", decimal, width = digits);
+        if digits == 1 {
+            format!("{}{}e{}", sign, decimal, exp10)
+        } else {
+            format!("{}{}.{}e{}", sign, &decimal[..1], &decimal[1..], exp10)
+        }
+    }
+}
+
+impl std::ops::Neg for F128 {
+    type Output = F128;
+
+    fn neg(self) -> F128 {
+        F128(self.0 ^ SIGN_MASK)
+    }
+}
+
+impl From<f64> for F128 {
+    fn from(f: f64) -> F128 {
+        F128::from_f64(f)
+    }
+}
+
+impl From<f32> for F128 {
+    fn from(f: f32) -> F128 {
+        F128::from_f32(f)
+    }
+}
+
+impl From<i64> for F128 {
+    fn from(n: i64) -> F128 {
+        F128::from_i64(n)
+    }
+}
+
+impl From<u64> for F128 {
+    fn from(n: u64) -> F128 {
+        F128::from_u64(n)
+    }
+}
+
+impl PartialEq for F128 {
+    fn eq(&self, other: &F128) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for F128 {
+    fn partial_cmp(&self, other: &F128) -> Option<Ordering> {
+        if self.is_nan() || other.is_nan() {
+            return None;
+        } else if self.is_zero() && other.is_zero() {
+            return Some(Ordering::Equal);
+        }
+
+        // Flip negative values so that bit patterns order like the values they represent
+        let key = |f: &F128| if f.is_sign_negative() { !f.0 } else { f.0 | SIGN_MASK };
+        Some(key(self).cmp(&key(other)))
+    }
+}
+
+impl FromStr for F128 {
+    type Err = ParseF128Error;
+
+    /// Parses a decimal string, rounding to nearest with ties to even.
+    fn from_str(s: &str) -> Result<F128, ParseF128Error> {
+        F128::parse(s, Round::TiesToEven)
+    }
+}
+
+impl fmt::Display for F128 {
+    /// Formats with enough digits to round-trip, without trailing zeros, e.g., "1.5e0".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = self.to_decimal(36, Round::TiesToEven);
+
+        match s.split_once('e') {
+            Some((mantissa, exp)) if mantissa.contains('.') => {
+                write!(f, "{}e{}", mantissa.trim_end_matches('0').trim_end_matches('.'), exp)
+            }
+            _ => write!(f, "{}", s)
+        }
+    }
+}
+
+impl fmt::Debug for F128 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "F128({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracle::{self, Rational, Value};
+    use crate::test_cases::*;
+
+    /// Number of random operands thrown at each operation
+    const CASES: usize = 2000;
+
+    /// Random F128 biased toward cases that are hard to round: full-width mantissas near the
+    /// overflow and underflow thresholds, around 1, and arbitrary bit patterns.
+    fn random_f128(rng: &mut SplitMix64) -> F128 {
+        let mant = ((rng.next_u64() as u128) << 64 | rng.next_u64() as u128) & FRAC_MASK;
+        let biased: u128 = match rng.range(0, 5) {
+            0 => rng.range(0, 0x7ffe) as u128,
+            1 => rng.range(0x3ff0, 0x400f) as u128,
+            2 => rng.range(0, 120) as u128,
+            3 => rng.range(0x7f00, 0x7ffe) as u128,
+            4 => rng.range(0x1f00, 0x5fff) as u128,
+            _ => return F128(((rng.next_u64() as u128) << 64) | rng.next_u64() as u128)
+        };
+
+        let sign = if rng.coin() { SIGN_MASK } else { 0 };
+        F128(sign | (biased << 112) | mant)
+    }
+
+    /// Pairs of random operands, every other pair being close in magnitude so that sums cancel
+    /// or products land at a tie.
+    fn random_pairs(seed: u64, count: usize) -> Vec<(F128, F128)> {
+        let mut rng = SplitMix64::new(seed);
+
+        (0..count).map(|i| {
+            let a = random_f128(&mut rng);
+            let b = if i % 2 == 0 {
+                random_f128(&mut rng)
+            } else if a.is_finite() && rng.coin() {
+                F128((a.0 as i128 + rng.range(-3, 3) as i128) as u128 ^ if rng.coin() { SIGN_MASK } else { 0 })
+            } else {
+                // Short odd mantissa, so that the product's trailing bits are interesting
+                let short = (rng.next_u64() as u128 | 1) << 49 & FRAC_MASK;
+                F128((a.0 & !FRAC_MASK) ^ short)
+            };
+
+            (a, b)
+        }).collect()
+    }
+
+    fn value(f: F128) -> Value {
+        Value::decode(FORMAT, f.0)
+    }
+
+    /// Asserts that `actual` is the oracle's result under `mode`, or for faithful rounding, under
+    /// either directed mode.
+    fn assert_accepts(expected: impl Fn(Round) -> Value, actual: F128, mode: Round, context: &dyn fmt::Display) {
+        let ok = |e: Value| {
+            let bits = e.encode(FORMAT, mode);
+            bits == actual.0 || (F128(bits).is_nan() && actual.is_nan())
+        };
+
+        let accepted = match mode {
+            Round::Faithful => ok(expected(Round::TowardNInf)) || ok(expected(Round::TowardPInf)),
+            _ => ok(expected(mode))
+        };
+
+        assert!(accepted, "{}, mode = {:?}: expected = {:#x}, actual = {:#x}", context, mode, expected(mode).encode(FORMAT, mode), actual.0);
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(F128::ONE, F128::from_f64(1.));
+        assert_eq!(F128::MAX.to_f64(Round::TiesToEven), f64::INFINITY);
+        assert_eq!(F128::MAX.to_f64(Round::TowardZero), f64::MAX);
+        assert_eq!(F128::MIN, -F128::MAX);
+        assert_eq!(F128::ONE.add(F128::EPSILON, Round::TiesToEven).to_bits(), F128::ONE.to_bits() + 1);
+        assert_eq!(F128::MIN_POSITIVE.sub(F128::from_bits(1), Round::TiesToEven).to_bits(), FRAC_MASK);
+        assert!(F128::NAN.is_nan() && !F128::NAN.is_finite() && F128::NAN != F128::NAN);
+        assert!(F128::NEG_INFINITY < F128::MIN && F128::MIN < -F128::ONE && F128::ZERO == -F128::ZERO);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let three = F128::from_i64(3);
+        let third = F128::ONE.div(three, Round::TiesToEven);
+
+        assert_eq!(third.to_bits(), 0x3ffd_5555_5555_5555_5555_5555_5555_5555);
+        assert_eq!(F128::ONE.div(three, Round::TowardPInf).to_bits(), 0x3ffd_5555_5555_5555_5555_5555_5555_5556);
+        assert_eq!(F128::from_i64(2).sqrt(Round::TiesToEven).to_bits(), 0x3fff_6a09_e667_f3bc_c908_b2fb_1366_ea95);
+        assert_eq!(F128::from_i64(9).sqrt(Round::TowardNInf), three);
+        assert_eq!(third.mul(three, Round::TiesToEven), F128::ONE);
+        assert!(third.mul(three, Round::TowardZero) < F128::ONE);
+
+        // Exact zeros take the sign prescribed by IEEE 754
+        assert!(!three.sub(three, Round::TiesToEven).is_sign_negative());
+        assert!(three.sub(three, Round::TowardNInf).is_sign_negative());
+        assert!((-F128::ZERO).add(-F128::ZERO, Round::TiesToEven).is_sign_negative());
+        assert!(F128::ZERO.fma(-three, F128::ZERO, Round::TiesToEven).to_bits() == 0);
+        assert!(F128::ZERO.fma(-three, -F128::ZERO, Round::TiesToEven).is_sign_negative());
+
+        assert!(F128::INFINITY.sub(F128::INFINITY, Round::TiesToEven).is_nan());
+        assert!(F128::INFINITY.mul(F128::ZERO, Round::TiesToEven).is_nan());
+        assert!((-F128::ONE).sqrt(Round::TiesToEven).is_nan());
+        assert_eq!(F128::ONE.div(-F128::ZERO, Round::TiesToEven), F128::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_binary_against_oracle() {
+        type Op = (&'static str, fn(F128, F128, Round) -> F128, fn(&Value, &Value, Round) -> Value);
+        let ops: [Op; 4] = [
+            ("add", F128::add, oracle::add),
+            ("sub", F128::sub, |a, b, mode| oracle::add(a, &oracle::neg(b), mode)),
+            ("mul", F128::mul, |a, b, _| oracle::mul(a, b)),
+            ("div", F128::div, |a, b, _| oracle::div(a, b)),
+        ];
+
+        for (a, b) in random_pairs(SEED, CASES) {
+            for mode in Round::ALL {
+                for (name, f, exact) in ops {
+                    let context = format!("{}({:#x}, {:#x})", name, a.0, b.0);
+                    assert_accepts(|mode| exact(&value(a), &value(b), mode), f(a, b, mode), mode, &context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fma_against_oracle() {
+        let mut rng = SplitMix64::new(SEED);
+        let pairs = random_pairs(SEED ^ 1, CASES);
+
+        for (i, &(a, b)) in pairs.iter().enumerate() {
+            // Every other addend nearly cancels the product
+            let c = if i % 2 == 0 {
+                random_f128(&mut rng)
+            } else {
+                let p = a.mul(b, Round::TowardZero);
+                let magnitude = p.abs().0.saturating_add_signed(rng.range(-2, 2) as i128);
+                if p.is_finite() { -F128(magnitude | (p.0 & SIGN_MASK)) } else { p }
+            };
+
+            for mode in Round::ALL {
+                let context = format!("fma({:#x}, {:#x}, {:#x})", a.0, b.0, c.0);
+                assert_accepts(|mode| oracle::fma(&value(a), &value(b), &value(c), mode), a.fma(b, c, mode), mode, &context);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqrt_brackets() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..CASES {
+            let a = random_f128(&mut rng).abs();
+            if !a.is_finite() || a.is_zero() {
+                continue;
+            }
+
+            let exact = |f: F128| match value(f) {
+                Value::Finite(r) => r,
+                _ => unreachable!()
+            };
+            let x = exact(a);
+            let square_cmp = |r: &Rational| {
+                let diff = r.mul(r).add(&x.neg());
+                if diff.is_zero() { Ordering::Equal } else if diff.negative { Ordering::Less } else { Ordering::Greater }
+            };
+
+            // down^2 <= a < (down + ulp)^2, and up is the same or the next float
+            let down = a.sqrt(Round::TowardNInf);
+            let up = a.sqrt(Round::TowardPInf);
+            let next = F128(down.0 + 1);
+            assert_ne!(square_cmp(&exact(down)), Ordering::Greater, "sqrt({:#x})", a.0);
+            assert_eq!(square_cmp(&exact(next)), Ordering::Greater, "sqrt({:#x})", a.0);
+
+            let is_exact = square_cmp(&exact(down)) == Ordering::Equal;
+            assert_eq!(up.0, if is_exact { down.0 } else { next.0 }, "sqrt({:#x})", a.0);
+            assert_eq!(a.sqrt(Round::TowardZero).0, down.0);
+
+            // Square roots never lie exactly halfway, so every nearest mode agrees
+            let mid = exact(down).add(&exact(next)).mul(&Rational::from_parts(false, 1, -1));
+            let nearest = if is_exact || square_cmp(&mid) == Ordering::Greater { down } else { next };
+            for mode in [Round::TiesToEven, Round::TiesAway, Round::TiesToOdd, Round::Faithful] {
+                assert_eq!(a.sqrt(mode).0, nearest.0, "sqrt({:#x}), mode = {:?}", a.0, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_float_conversions() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..CASES {
+            let a = random_f128(&mut rng);
+            for mode in Round::ALL.into_iter().filter(|&m| m != Round::Faithful) {
+                let e64 = value(a).encode(Format::BINARY64, mode) as u64;
+                let e32 = value(a).encode(Format::BINARY32, mode) as u32;
+                let (a64, a32) = (a.to_f64(mode), a.to_f32(mode));
+
+                assert!(a64.to_bits() == e64 || (a64.is_nan() && f64::from_bits(e64).is_nan()), "{:?} to f64, {:?}", a, mode);
+                assert!(a32.to_bits() == e32 || (a32.is_nan() && f32::from_bits(e32).is_nan()), "{:?} to f32, {:?}", a, mode);
+            }
+        }
+
+        for &f in RANDOM_F64.iter() {
+            let wide = F128::from_f64(f);
+            for mode in Round::ALL {
+                let back = wide.to_f64(mode);
+                assert!(back.to_bits() == f.to_bits() || (f.is_nan() && back.is_nan()), "{:e}", f);
+            }
+        }
+
+        assert_eq!(F128::from_f32(f32::MIN_POSITIVE * 0.5).to_f32(Round::TiesToEven), f32::MIN_POSITIVE * 0.5);
+        assert!(F128::from_f32(f32::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_integer_conversions() {
+        let big = (1u128 << 120) + (1 << 7) + 1;
+        assert_eq!(F128::from_u128(big, Round::TowardZero).to_i128(Round::TowardZero), Some(1 << 120));
+        assert_eq!(F128::from_u128(big, Round::TowardPInf).to_i128(Round::TowardZero), Some((1 << 120) + (1 << 8)));
+        assert_eq!(F128::from_u128(u128::MAX, Round::TiesToEven).to_i128(Round::TiesToEven), None);
+        assert_eq!(F128::from_i128(i128::MIN, Round::TiesToEven).to_i128(Round::TiesToEven), Some(i128::MIN));
+        assert_eq!(F128::from_i64(i64::MIN).to_i64(Round::TiesToEven), Some(i64::MIN));
+        assert_eq!(F128::from_u64(u64::MAX).to_i64(Round::TiesToEven), None);
+        assert_eq!(F128::NAN.to_i64(Round::TiesToEven), None);
+
+        let cases: [(f64, [i64; 7]); 4] = [
+            // TiesToEven, TowardZero, TowardPInf, TowardNInf, Faithful, TiesAway, TiesToOdd
+            (2.5, [2, 2, 3, 2, 2, 3, 3]),
+            (-2.5, [-2, -2, -2, -3, -2, -3, -3]),
+            (-0.25, [0, 0, 0, -1, 0, 0, 0]),
+            (1e18 + 0.5, [1_000_000_000_000_000_000; 7]),
+        ];
+
+        for (f, expected) in cases {
+            for (mode, e) in Round::ALL.into_iter().zip(expected) {
+                assert_eq!(F128::from_f64(f).to_i64(mode), Some(e), "{} {:?}", f, mode);
+            }
+        }
+
+        let mut rng = SplitMix64::new(SEED);
+        for _ in 0..CASES {
+            let n = ((rng.next_u64() as u128) << 64 | rng.next_u64() as u128) >> rng.range(0, 100);
+            for mode in Round::ALL.into_iter().filter(|&m| m != Round::Faithful) {
+                assert_eq!(F128::from_u128(n, mode).0, Rational::from_parts(false, n, 0).round(FORMAT, mode), "{} {:?}", n, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("0.1".parse::<F128>().unwrap().to_bits(), 0x3ffb_9999_9999_9999_9999_9999_9999_999a);
+        assert_eq!(F128::parse("0.1", Round::TowardZero).unwrap().to_bits(), 0x3ffb_9999_9999_9999_9999_9999_9999_9999);
+        assert_eq!("-2.5E+0".parse::<F128>().unwrap(), F128::from_f64(-2.5));
+        assert_eq!("1e4932".parse::<F128>().unwrap().to_bits(), 0x7ffe_ae59_6552_b8fd_ed99_d037_e3d0_4b75);
+        assert_eq!("1e5000".parse::<F128>().unwrap(), F128::INFINITY);
+        assert_eq!(F128::parse("1e5000", Round::TowardZero).unwrap(), F128::MAX);
+        assert_eq!(F128::parse("1e-5000", Round::TowardPInf).unwrap().to_bits(), 1);
+        assert!("-0.000".parse::<F128>().unwrap().is_sign_negative());
+        assert!("NaN".parse::<F128>().unwrap().is_nan());
+        assert_eq!("-Infinity".parse::<F128>().unwrap(), F128::NEG_INFINITY);
+        assert_eq!(".5".parse::<F128>().unwrap(), F128::from_f64(0.5));
+
+        for bad in ["", "-", ".", "1e", "1e+", "0x10", "1.2.3", "e5", "1 "] {
+            assert_eq!(bad.parse::<F128>(), Err(ParseF128Error), "{:?}", bad);
+        }
+
+        // Random decimal strings against the oracle
+        let mut rng = SplitMix64::new(SEED);
+        for _ in 0..200 {
+            let digits: String = (0..rng.range(1, 45)).map(|_| (b'0' + rng.range(0, 9) as u8) as char).collect();
+            let exp = rng.range(-4990, 4950);
+            let s = format!("{}e{}", digits, exp);
+
+            let num = digits.bytes().fold(BigUint::zero(), |acc, c| acc.mul(&BigUint::from_u128(10)).add(&BigUint::from_u128((c - b'0') as u128)));
+            let scale = BigUint::pow(10, exp.unsigned_abs());
+            let exact = if exp >= 0 {
+                Rational { negative: false, num: num.mul(&scale), den: BigUint::one(), exp: 0 }
+            } else {
+                Rational { negative: false, num, den: scale, exp: 0 }
+            };
+
+            for mode in Round::ALL.into_iter().filter(|&m| m != Round::Faithful) {
+                assert_eq!(F128::parse(&s, mode).unwrap().0, exact.round(FORMAT, mode), "{} {:?}", s, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_decimal() {
+        assert_eq!(F128::ONE.to_decimal(4, Round::TiesToEven), "1.000e0");
+        assert_eq!(F128::from_f64(-0.125).to_decimal(2, Round::TiesToEven), "-1.2e-1");
+        assert_eq!(F128::from_f64(-0.125).to_decimal(2, Round::TiesAway), "-1.3e-1");
+        assert_eq!(F128::from_f64(-0.125).to_decimal(2, Round::TowardPInf), "-1.2e-1");
+        assert_eq!(F128::from_f64(-0.125).to_decimal(2, Round::TowardNInf), "-1.3e-1");
+        assert_eq!(F128::from_f64(9.96).to_decimal(2, Round::TowardPInf), "1.0e1");
+        assert_eq!(F128::from_f64(123.).to_decimal(1, Round::TowardZero), "1e2");
+        assert_eq!((-F128::ZERO).to_decimal(3, Round::TiesToEven), "-0.00e0");
+        assert_eq!(F128::MAX.to_decimal(5, Round::TowardZero), "1.1897e4932");
+        assert_eq!(F128::from_bits(1).to_decimal(5, Round::TowardZero), "6.4751e-4966");
+        assert_eq!(F128::NEG_INFINITY.to_string(), "-inf");
+        assert_eq!(F128::from_f64(0.1).to_string(), "1.00000000000000005551115123125782702e-1");
+        assert_eq!(F128::from_f64(1.5).to_string(), "1.5e0");
+        assert_eq!(format!("{:?}", F128::ONE), "F128(1e0)");
+
+        // Shortest strings of 36 digits round-trip
+        let mut rng = SplitMix64::new(SEED);
+        for _ in 0..200 {
+            let a = random_f128(&mut rng);
+            if a.is_finite() {
+                assert_eq!(a.to_string().parse::<F128>().unwrap().to_bits(), a.to_bits(), "{}", a);
+            }
+        }
+    }
+}
@@ -1,11 +1,151 @@
 use crate::successor::*;
+use crate::modes::*;
+#[cfg(target_arch = "x86_64")]
 use std::arch::asm;
-use crate::native;
+use std::cmp::Ordering;
 
 const MANTISSA_MASK: u64 = 0x000f_ffff_ffff_ffff;
 const EXP_MASK: u64 = 0x7ff0_0000_0000_0000;
 const SIGN_MASK: u64 = 0x8000_0000_0000_0000;
 
+/// Layout of a binary interchange format, given by the widths of its exponent and trailing
+/// mantissa fields.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Format {
+    pub exp_bits: u32,
+    pub frac_bits: u32,
+}
+
+impl Format {
+    pub const BINARY64: Format = Format { exp_bits: 11, frac_bits: 52 };
+    pub const BINARY32: Format = Format { exp_bits: 8, frac_bits: 23 };
+    pub const BINARY128: Format = Format { exp_bits: 15, frac_bits: 112 };
+    pub const BINARY16: Format = Format { exp_bits: 5, frac_bits: 10 };
+    pub const BFLOAT16: Format = Format { exp_bits: 8, frac_bits: 7 };
+
+    pub const fn bias(&self) -> i32 {
+        (1 << (self.exp_bits - 1)) - 1
+    }
+
+    /// Exponent of the smallest normal number
+    pub const fn min_exp(&self) -> i32 {
+        1 - self.bias()
+    }
+
+    /// Exponent of the largest finite number
+    pub const fn max_exp(&self) -> i32 {
+        self.bias()
+    }
+
+    pub const fn sign_bit(&self) -> u128 {
+        1 << (self.exp_bits + self.frac_bits)
+    }
+
+    pub const fn inf_bits(&self) -> u128 {
+        ((1 << self.exp_bits) - 1) << self.frac_bits
+    }
+
+    pub const fn max_bits(&self) -> u128 {
+        self.inf_bits() - 1
+    }
+}
+
+/// Whether a truncated magnitude should be incremented, given whether it is odd, how the
+/// discarded portion compares to half a unit in its last place, and whether anything was
+/// discarded at all.
+pub(crate) fn rounds_away(negative: bool, odd: bool, vs_half: Ordering, inexact: bool, mode: Round) -> bool {
+    match mode {
+        Round::TiesToEven | Round::Faithful => vs_half == Ordering::Greater || (vs_half == Ordering::Equal && odd),
+        Round::TiesAway => vs_half != Ordering::Less,
+        Round::TiesToOdd => vs_half == Ordering::Greater || (vs_half == Ordering::Equal && !odd),
+        Round::TowardZero => false,
+        Round::TowardPInf => inexact && !negative,
+        Round::TowardNInf => inexact && negative,
+    }
+}
+
+/// Rounds the magnitude `(mant + sticky) / 2^shift` to an integer, where `sticky` stands for some
+/// nonzero quantity less than one unit in the last place of `mant`. Because we can't tell where
+/// such a quantity lies relative to a half, `sticky` may only be set if `shift` is positive.
+pub(crate) fn round_shift(negative: bool, mant: u128, shift: u32, sticky: bool, mode: Round) -> u128 {
+    debug_assert!(shift > 0 || !sticky);
+
+    let (kept, vs_half, inexact) = if shift == 0 {
+        (mant, Ordering::Less, false)
+    } else if shift > 128 {
+        (0, Ordering::Less, mant != 0 || sticky)
+    } else {
+        let kept = if shift == 128 { 0 } else { mant >> shift };
+        let rem = if shift == 128 { mant } else { mant & ((1 << shift) - 1) };
+        let half = 1u128 << (shift - 1);
+
+        let vs_half = match rem.cmp(&half) {
+            Ordering::Equal if sticky => Ordering::Greater,
+            ord => ord
+        };
+
+        (kept, vs_half, rem != 0 || sticky)
+    };
+
+    if rounds_away(negative, kept & 1 == 1, vs_half, inexact, mode) {
+        kept + 1
+    } else {
+        kept
+    }
+}
+
+/// Bit pattern of the magnitude an overflowing result rounds to.
+fn overflow_bits(format: Format, negative: bool, mode: Round) -> u128 {
+    let to_inf = match mode {
+        Round::TowardZero => false,
+        Round::TowardPInf => !negative,
+        Round::TowardNInf => negative,
+        _ => true
+    };
+
+    if to_inf { format.inf_bits() } else { format.max_bits() }
+}
+
+/// Rounds the exact value `(-1)^negative * (mant + sticky) * 2^exp` to `format`, returning its
+/// bit pattern. As in `round_shift`, `sticky` stands for a nonzero quantity less than one unit in
+/// the last place of `mant`; if it is set, `mant` must carry at least two more bits than the
+/// format's precision so the rounding position lies above it.
+pub(crate) fn round_to_format(format: Format, negative: bool, mant: u128, exp: i32, sticky: bool, mode: Round) -> u128 {
+    let sign = if negative { format.sign_bit() } else { 0 };
+
+    if mant == 0 {
+        debug_assert!(!sticky);
+        return sign;
+    }
+
+    // Exponents of the leading bit of the exact value and of the last place of the result
+    let top = exp + 127 - mant.leading_zeros() as i32;
+    if top > format.max_exp() {
+        return sign | overflow_bits(format, negative, mode);
+    }
+
+    let result_exp = top.max(format.min_exp());
+    let lsb = result_exp - format.frac_bits as i32;
+
+    let kept = if lsb <= exp {
+        debug_assert!(!sticky);
+        mant << (exp - lsb)
+    } else {
+        round_shift(negative, mant, (lsb - exp) as u32, sticky, mode)
+    };
+
+    // For normal results, kept includes the implicit bit, which bumps the exponent field by one;
+    // for subnormal results, the exponent field is zero. If rounding carried into a new binade,
+    // the exponent field is correctly incremented as well.
+    let bits = (((result_exp + format.bias() - 1) as u128) << format.frac_bits) + kept;
+    if bits >= format.inf_bits() {
+        return sign | overflow_bits(format, negative, mode);
+    }
+
+    sign | bits
+}
+
+
 /// Multiply two floats, returning the new exponent (as a bit pattern) and the exact mantissa as
 /// a 128-bit integer, which will be chopped as appropriate. Assumed to be nonzero.
 fn multiply_mantissas(a: f64, b: f64) -> (u64, u64) {
@@ -24,18 +164,21 @@ fn multiply_mantissas(a: f64, b: f64) -> (u64, u64) {
         b_mant += 1 << 52;
     }
 
-    dbg!(a_mant, b_mant);
+    mul_wide(a_mant, b_mant)
+}
 
-    let mut hi: u64;
-    let mut lo: u64;
+/// 64-bit unsigned multiplication into 128 bits, as (high, low) halves.
+#[cfg(target_arch = "x86_64")]
+fn mul_wide(a: u64, b: u64) -> (u64, u64) {
+    let hi: u64;
+    let lo: u64;
 
     unsafe {
-        // 64-bit unsigned multiplication into 128 bits. If there's an intrinsic for this I'd love
-        // to know...
+        // If there's an intrinsic for this I'd love to know...
         asm!(
             "mul {}",
-            in(reg) a_mant,
-            inlateout("rax") b_mant => lo,
+            in(reg) a,
+            inlateout("rax") b => lo,
             lateout("rdx") hi,
             options(nomem, nostack, pure)
         );
@@ -44,6 +187,13 @@ fn multiply_mantissas(a: f64, b: f64) -> (u64, u64) {
     (hi, lo)
 }
 
+/// 64-bit unsigned multiplication into 128 bits, as (high, low) halves.
+#[cfg(not(target_arch = "x86_64"))]
+fn mul_wide(a: u64, b: u64) -> (u64, u64) {
+    let product = a as u128 * b as u128;
+    ((product >> 64) as u64, product as u64)
+}
+
 fn round_down_quick(f: f64) -> f64 {
     // Using an and instruction allows less transferring between xmm and general registers
     if f.to_bits() & SIGN_MASK == 0 {
@@ -77,8 +227,6 @@ pub fn multiply_round_down(a: f64, b: f64) -> f64 {
     // We now adjust the standard result based on what it "should" be based on the rounding
     
     if original.to_bits() & EXP_MASK != 0x0 {
-        let original_m = original.to_bits() & MANTISSA_MASK;
-
         // Normal results, although it is conceivable that the answer rounded down is just barely
         // subnormal
 
@@ -148,114 +296,379 @@ pub fn multiply_round_down(a: f64, b: f64) -> f64 {
     } else {
         // Subnormal results are trickier, since the place of rounding is hard to find. We defer
         // their processing to a separate function to deter inlining.
-        multiply_round_down_subnormal(original, mul_hi, mul_lo)
+        multiply_round_down_subnormal(a, b, original, mul_hi, mul_lo)
     }
 }
 
+/// Exponent of the last place of a float's mantissa, i.e., the float is an integer multiple of
+/// two to this power.
+fn last_place_exp(f: f64) -> i32 {
+    let biased = ((f.to_bits() & EXP_MASK) >> 52) as i32;
+
+    biased.max(1) - 1075
+}
+
 /// Handler for the case where the product is subnormal. Original is thus assumed to be subnormal
-fn multiply_round_down_subnormal(original: f64, mul_hi: u64, mul_lo: u64) -> f64 {
-    let original_m = original.to_bits() & MANTISSA_MASK;
+fn multiply_round_down_subnormal(a: f64, b: f64, original: f64, mul_hi: u64, mul_lo: u64) -> f64 {
+    // The exact product is (mul_hi, mul_lo) * 2^(ea + eb). Subnormals are integer multiples of the
+    // smallest subnormal, 2^-1074, so we measure the exact product in those units and truncate.
+    let shift = -(last_place_exp(a) + last_place_exp(b) + 1074);
+    if shift <= 0 {
+        // exact
+        return original;
+    }
+
+    let exact = ((mul_hi as u128) << 64) | mul_lo as u128;
+    let (mut new_mant, rem) = if shift >= 128 {
+        (0, exact)
+    } else {
+        (exact >> shift, exact & ((1u128 << shift) - 1))
+    };
+
+    if rem == 0 {
+        // exact
+        return original;
+    }
 
-    // Essentially, we calculate how many bits of accuracy the output has, then grab that many bits
-    // from the exact calculation
+    if original < 0. {
+        new_mant += 1;
+    }
 
-    // The subnormal float has this many bits of precision 
-    let end_prec = 64 - original_m.leading_zeros();
+    // With this scheme, a mantissa of 1 << 52 is implicitly taken to the maximum negative
+    // normal number :P
+    f64::from_bits(new_mant as u64).copysign(original)
+}
+
+/// Exact result of an operation, `(-1)^negative * (mant + sticky) * 2^exp`, with `sticky` as in
+/// `round_to_format`, ready to be rounded to any format
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Exact {
+    pub negative: bool,
+    pub mant: u128,
+    pub exp: i32,
+    pub sticky: bool,
+}
 
-    // Beginning in the exact result
-    let mut lz = mul_hi.leading_zeros();
-    if lz == 64 {
-        lz += mul_lo.leading_zeros();
+impl Exact {
+    pub fn round(self, format: Format, mode: Round) -> u128 {
+        round_to_format(format, self.negative, self.mant, self.exp, self.sticky, mode)
     }
+}
 
-    let mut new_mant = 0u64;
+/// Exact sum of two finite values. The sign of an exact zero sum depends on the rounding mode.
+pub(crate) fn add_exact(a: FloatParts, b: FloatParts, mode: Round) -> Exact {
+    // Give each mantissa 64 guard bits, then align the one with the smaller exponent to the other.
+    // Anything shifted out lies more than 64 bits below the larger operand's last place, so
+    // remembering it as a sticky bit is enough.
+    let (a, b) = if a.exp >= b.exp { (a, b) } else { (b, a) };
+    let x = (a.mant as u128) << 64;
+    let y = (b.mant as u128) << 64;
+    let exp = a.exp - 64;
+
+    let shift = (a.exp - b.exp) as u32;
+    let (y, sticky) = if shift == 0 {
+        (y, false)
+    } else if shift >= 128 {
+        (0, y != 0)
+    } else {
+        (y >> shift, y & ((1 << shift) - 1) != 0)
+    };
 
-    let mut end = lz + end_prec;
-    if end < 64 {
-        dbg!("Hi");
-        // end of precision occurs within the first word
-        let trunc_amt = 64 - end;
+    if a.negative == b.negative {
+        return Exact { negative: a.negative, mant: x + y, exp, sticky };
+    }
 
-        new_mant = (mul_hi >> trunc_amt);
-        let rem = mul_hi - (new_mant << trunc_amt);
+    if sticky {
+        // x is normal while the shifted y is tiny, so x - (y + sticky) = (x - y - 1) + (1 - sticky),
+        // where 1 - sticky is again some quantity strictly between 0 and 1
+        return Exact { negative: a.negative, mant: x - y - 1, exp, sticky: true };
+    }
 
-        if rem == 0 {
-            // exact
-            return original;
-        }
+    match x.cmp(&y) {
+        Ordering::Greater => Exact { negative: a.negative, mant: x - y, exp, sticky: false },
+        Ordering::Less => Exact { negative: b.negative, mant: y - x, exp, sticky: false },
+        // Exact cancellation gives +0, except when rounding downward
+        Ordering::Equal => Exact { negative: mode == Round::TowardNInf, mant: 0, exp, sticky: false }
+    }
+}
 
-        if original < 0. {
-            new_mant += 1;
-        }
-    } else if end == 64 {
-        dbg!("Hi");
-        new_mant = mul_hi;
+/// Exact product of two finite values.
+pub(crate) fn mul_exact(a: FloatParts, b: FloatParts) -> Exact {
+    Exact { negative: a.negative != b.negative, mant: a.mant as u128 * b.mant as u128, exp: a.exp + b.exp, sticky: false }
+}
 
-        if mul_lo == 0 {
-            // exact
-            return original;
-        }
+/// Quotient of two finite values with nonzero divisor, exact up to a sticky bit.
+pub(crate) fn div_exact(a: FloatParts, b: FloatParts) -> Exact {
+    // Widen the dividend to 127 bits, leaving a quotient of at least 74 bits
+    let shift = a.mant.leading_zeros() + 63;
+    let wide = (a.mant as u128) << shift;
 
-        if original < 0. {
-            new_mant += 1;
-        }
+    let quot = wide / b.mant as u128;
+    let rem = wide % b.mant as u128;
+
+    Exact { negative: a.negative != b.negative, mant: quot, exp: a.exp - shift as i32 - b.exp, sticky: rem != 0 }
+}
+
+/// Sum of two finite values, rounded to `format`.
+pub(crate) fn add_parts(format: Format, a: FloatParts, b: FloatParts, mode: Round) -> u128 {
+    add_exact(a, b, mode).round(format, mode)
+}
+
+/// Product of two finite values, rounded to `format`.
+pub(crate) fn mul_parts(format: Format, a: FloatParts, b: FloatParts, mode: Round) -> u128 {
+    mul_exact(a, b).round(format, mode)
+}
+
+/// Quotient of two finite values with nonzero divisor, rounded to `format`.
+pub(crate) fn div_parts(format: Format, a: FloatParts, b: FloatParts, mode: Round) -> u128 {
+    div_exact(a, b).round(format, mode)
+}
+
+/// Computes the sum of two f64s under the given rounding mode.
+pub fn add_f64(a: f64, b: f64, mode: Round) -> f64 {
+    if !a.is_finite() || !b.is_finite() { // Rounding mode doesn't affect
+        return a + b;
+    }
+
+    f64::from_bits(add_parts(Format::BINARY64, FloatParts::of_f64(a), FloatParts::of_f64(b), mode) as u64)
+}
+
+/// Computes the difference of two f64s under the given rounding mode.
+pub fn sub_f64(a: f64, b: f64, mode: Round) -> f64 {
+    if !a.is_finite() || !b.is_finite() {
+        return a - b;
+    }
+
+    f64::from_bits(add_parts(Format::BINARY64, FloatParts::of_f64(a), FloatParts::of_f64(-b), mode) as u64)
+}
+
+/// Computes the product of two f64s under the given rounding mode.
+pub fn mul_f64(a: f64, b: f64, mode: Round) -> f64 {
+    if !a.is_finite() || !b.is_finite() || a == 0. || b == 0. {
+        return a * b;
+    }
+
+    f64::from_bits(mul_parts(Format::BINARY64, FloatParts::of_f64(a), FloatParts::of_f64(b), mode) as u64)
+}
+
+/// Computes the quotient of two f64s under the given rounding mode.
+pub fn div_f64(a: f64, b: f64, mode: Round) -> f64 {
+    if !a.is_finite() || !b.is_finite() || a == 0. || b == 0. {
+        return a / b;
+    }
+
+    f64::from_bits(div_parts(Format::BINARY64, FloatParts::of_f64(a), FloatParts::of_f64(b), mode) as u64)
+}
+
+/// Computes the sum of two f32s under the given rounding mode.
+pub fn add_f32(a: f32, b: f32, mode: Round) -> f32 {
+    if !a.is_finite() || !b.is_finite() {
+        return a + b;
+    }
+
+    f32::from_bits(add_parts(Format::BINARY32, FloatParts::of_f32(a), FloatParts::of_f32(b), mode) as u32)
+}
+
+/// Computes the difference of two f32s under the given rounding mode.
+pub fn sub_f32(a: f32, b: f32, mode: Round) -> f32 {
+    if !a.is_finite() || !b.is_finite() {
+        return a - b;
+    }
+
+    f32::from_bits(add_parts(Format::BINARY32, FloatParts::of_f32(a), FloatParts::of_f32(-b), mode) as u32)
+}
+
+/// Computes the product of two f32s under the given rounding mode.
+pub fn mul_f32(a: f32, b: f32, mode: Round) -> f32 {
+    if !a.is_finite() || !b.is_finite() || a == 0. || b == 0. {
+        return a * b;
+    }
+
+    f32::from_bits(mul_parts(Format::BINARY32, FloatParts::of_f32(a), FloatParts::of_f32(b), mode) as u32)
+}
+
+/// Computes the quotient of two f32s under the given rounding mode.
+pub fn div_f32(a: f32, b: f32, mode: Round) -> f32 {
+    if !a.is_finite() || !b.is_finite() || a == 0. || b == 0. {
+        return a / b;
+    }
+
+    f32::from_bits(div_parts(Format::BINARY32, FloatParts::of_f32(a), FloatParts::of_f32(b), mode) as u32)
+}
+
+/// Square root of the exact value `mant * 2^exp`, exact up to a sticky bit.
+pub(crate) fn sqrt_exact(mant: u64, exp: i32) -> Exact {
+    // Widen the mantissa to 126 or 127 bits, keeping the exponent even, so that the integer square
+    // root carries at least 63 bits, plenty for the sticky bit to lie below the rounding position
+    let mut shift = 126 - (64 - mant.leading_zeros() as i32);
+    if (exp - shift) % 2 != 0 {
+        shift += 1;
+    }
+
+    let wide = (mant as u128) << shift;
+    let root = wide.isqrt();
+
+    Exact { negative: false, mant: root, exp: (exp - shift) / 2, sticky: root * root != wide }
+}
+
+/// Square root of the exact value `mant * 2^exp`, rounded to `format`.
+pub(crate) fn sqrt_parts(format: Format, mant: u64, exp: i32, mode: Round) -> u128 {
+    sqrt_exact(mant, exp).round(format, mode)
+}
+
+/// Computes the square root of an f64 under the given rounding mode.
+pub fn sqrt_f64(a: f64, mode: Round) -> f64 {
+    if a.is_nan() {
+        return a + a;
+    } else if a == 0. || a == f64::INFINITY {
+        return a;
+    } else if a < 0. {
+        return f64::NAN;
+    }
+
+    let parts = FloatParts::of_f64(a);
+    f64::from_bits(sqrt_parts(Format::BINARY64, parts.mant, parts.exp, mode) as u64)
+}
+
+/// Computes the square root of an f32 under the given rounding mode.
+pub fn sqrt_f32(a: f32, mode: Round) -> f32 {
+    if a.is_nan() {
+        return a + a;
+    } else if a == 0. || a == f32::INFINITY {
+        return a;
+    } else if a < 0. {
+        return f32::NAN;
+    }
+
+    let parts = FloatParts::of_f32(a);
+    f32::from_bits(sqrt_parts(Format::BINARY32, parts.mant, parts.exp, mode) as u32)
+}
+
+/// Exact sum of two exact values without sticky bits and with mantissas of at most 106 bits, up to
+/// a sticky bit. The sign of an exact zero sum depends on the rounding mode.
+pub(crate) fn sum_exact(x: Exact, y: Exact, mode: Round) -> Exact {
+    if y.mant == 0 {
+        let negative = if x.mant != 0 || x.negative == y.negative { x.negative } else { mode == Round::TowardNInf };
+        return Exact { negative, ..x };
+    } else if x.mant == 0 {
+        return y;
+    }
+
+    // Normalize both mantissas to bit 126, leaving room for a carry and at least 21 zero bits
+    // below each. Bits are only shifted out when the exponents differ by more than that, in which
+    // case at most one leading bit cancels and a sticky bit is again enough.
+    let normalize = |e: Exact| {
+        let shift = e.mant.leading_zeros() - 1;
+        Exact { mant: e.mant << shift, exp: e.exp - shift as i32, ..e }
+    };
+    let (x, y) = (normalize(x), normalize(y));
+    let (x, y) = if x.exp >= y.exp { (x, y) } else { (y, x) };
+
+    let shift = (x.exp - y.exp) as u32;
+    let (mant, sticky) = if shift >= 128 {
+        (0, true)
     } else {
+        (y.mant >> shift, y.mant & ((1 << shift) - 1) != 0)
+    };
 
-        dbg!("Fod");
-        // occurs within the second word
-        let lo_trunc_amt = 128 - end;
-        new_mant = mul_lo >> lo_trunc_amt;
-        let rem = mul_lo - (new_mant << lo_trunc_amt);
-        new_mant += mul_hi << (end - 64); 
+    if x.negative == y.negative {
+        return Exact { negative: x.negative, mant: x.mant + mant, exp: x.exp, sticky };
+    }
 
-        if rem == 0 {
-            // exact
-            return original;
-        }
+    if sticky {
+        return Exact { negative: x.negative, mant: x.mant - mant - 1, exp: x.exp, sticky: true };
+    }
+
+    match x.mant.cmp(&mant) {
+        Ordering::Greater => Exact { negative: x.negative, mant: x.mant - mant, exp: x.exp, sticky: false },
+        Ordering::Less => Exact { negative: y.negative, mant: mant - x.mant, exp: x.exp, sticky: false },
+        Ordering::Equal => Exact { negative: mode == Round::TowardNInf, mant: 0, exp: x.exp, sticky: false }
+    }
+}
 
-        dbg!(rem, new_mant, mul_hi, mul_lo, lo_trunc_amt, end_prec);
+/// Exact value of `a * b + c` for finite values, up to a sticky bit. The sign of an exact zero
+/// result depends on the rounding mode.
+pub(crate) fn fma_exact(a: FloatParts, b: FloatParts, c: FloatParts, mode: Round) -> Exact {
+    let addend = Exact { negative: c.negative, mant: c.mant as u128, exp: c.exp, sticky: false };
+    sum_exact(mul_exact(a, b), addend, mode)
+}
 
-        if original < 0. {
-            new_mant += 1;
-        }
+/// Computes `a * b + c` for f64s with a single rounding under the given rounding mode.
+pub fn fma_f64(a: f64, b: f64, c: f64, mode: Round) -> f64 {
+    if !a.is_finite() || !b.is_finite() || !c.is_finite() { // Rounding mode doesn't affect
+        return a.mul_add(b, c);
     }
 
-    if new_mant == 0x1 && (mul_hi.count_ones() + mul_lo.count_ones() != 1) {
-        // Annoying edge case where the precision is "fake" in the sense that the rounded down
-        // result is actually just 0. We detect this case with a popcount. The reason that the
-        // popcount works is because of the range of relevant numbers
-    
-        new_mant = 0;
+    let exact = fma_exact(FloatParts::of_f64(a), FloatParts::of_f64(b), FloatParts::of_f64(c), mode);
+    f64::from_bits(exact.round(Format::BINARY64, mode) as u64)
+}
+
+/// Computes `a * b + c` for f32s with a single rounding under the given rounding mode.
+pub fn fma_f32(a: f32, b: f32, c: f32, mode: Round) -> f32 {
+    if !a.is_finite() || !b.is_finite() || !c.is_finite() {
+        return a.mul_add(b, c);
     }
 
-    // With this scheme, a mantissa of 1 << 52 is implicitly taken to the maximum negative
-    // normal number :P
-    return f64::from_bits(new_mant).copysign(original);
+    let exact = fma_exact(FloatParts::of_f32(a), FloatParts::of_f32(b), FloatParts::of_f32(c), mode);
+    f32::from_bits(exact.round(Format::BINARY32, mode) as u32)
+}
+
+/// Rounds the magnitude of a finite float's parts to an integer under the given rounding mode.
+fn round_parts_to_integer(parts: FloatParts, mode: Round) -> u128 {
+    if parts.exp >= 0 {
+        (parts.mant as u128) << parts.exp
+    } else {
+        round_shift(parts.negative, parts.mant as u128, (-parts.exp) as u32, false, mode)
+    }
+}
+
+/// Rounds an f32 to an integral value under the given rounding mode, preserving the sign of zero.
+pub fn round_f32(a: f32, mode: Round) -> f32 {
+    if a.is_nan() {
+        return a + a;
+    } else if a.abs() >= 8388608.0 {
+        // Already integral
+        return a;
+    }
+
+    let int = round_parts_to_integer(FloatParts::of_f32(a), mode);
+    (int as f32).copysign(a)
+}
+
+/// Converts an f32 to an i32 under the given rounding mode, returning None for NaN and for
+/// results out of range.
+pub fn f32_to_i32(a: f32, mode: Round) -> Option<i32> {
+    if !a.is_finite() || a.abs() >= 4294967296.0 {
+        return None;
+    }
+
+    let parts = FloatParts::of_f32(a);
+    let int = round_parts_to_integer(parts, mode) as i64;
+
+    i32::try_from(if parts.negative { -int } else { int }).ok()
 }
 
 #[cfg(test)]
 mod tests {
     use crate::test_cases::*;
     use super::*;
-    use crate::native;
-    use crate::successor::*;
+    use crate::{native, oracle};
 
     /// Binary function between two f64s, e.g., multiplication rounding down
     type Binary64Fn<'a> = &'a dyn Fn(f64, f64) -> f64;
 
     /// Compare the behavior of two functions, throwing a bunch of random cases at them, ensuring
-    /// they behave identically.
-    fn compare_binary_f64_impl(expected: Binary64Fn, actual: Binary64Fn) {
+    /// they behave identically. Every `strides.0`-th operand of `RANDOM_F64` is paired with every
+    /// `strides.1`-th; strides coprime to each other and to the six neighbors of each power of two
+    /// still pair every kind of operand with every other.
+    fn compare_binary_f64_impl(expected: Binary64Fn, actual: Binary64Fn, strides: (usize, usize)) {
         let mut cases = 0u64;
-        for i in 0..RANDOM_F64.len() {
-            for j in 0..RANDOM_F64.len() {
-                let op1 = RANDOM_F64[i];
-                let op2 = RANDOM_F64[j];
-
+        for &op1 in RANDOM_F64.iter().step_by(strides.0) {
+            for &op2 in RANDOM_F64.iter().step_by(strides.1) {
                 let e = expected(op1, op2);
                 let a = actual(op1, op2);
 
-                assert!(identical_f64(e, a), "a = {:.18e}, b = {:.18e}, expected = {:.18e}, actual = {:.18e}", op1, op2, e, a);
+                assert!(identical_f64(e, a) || (e.is_nan() && a.is_nan()), "a = {:.18e}, b = {:.18e}, expected = {:.18e}, actual = {:.18e}", op1, op2, e, a);
                 cases += 1;
             }
         }
@@ -263,8 +676,180 @@ mod tests {
         println!("Tested {} cases", cases);
     }
 
+    /// Binary function between two f64s under a rounding mode, e.g., addition
+    type RoundedBinary64Fn<'a> = &'a dyn Fn(f64, f64, Round) -> f64;
+
+    /// Binary function between two f32s under a rounding mode
+    type RoundedBinary32Fn<'a> = &'a dyn Fn(f32, f32, Round) -> f32;
+
+    /// Number of hard cases thrown at each binary operation
+    const HARD_CASES: usize = 20000;
+
+    /// Compare a function against the exact oracle on generated hard cases, under every rounding
+    /// mode.
+    fn compare_rounded_binary_f64_impl(expected: RoundedBinary64Fn, actual: RoundedBinary64Fn) {
+        for (op1, op2) in hard_f64_pairs(SEED, HARD_CASES) {
+            for mode in Round::ALL {
+                let a = actual(op1, op2, mode);
+
+                assert!(oracle::accepts_f64(|mode| expected(op1, op2, mode), a, mode), "a = {:.18e}, b = {:.18e}, mode = {:?}, expected = {:.18e}, actual = {:.18e}", op1, op2, mode, expected(op1, op2, mode), a);
+            }
+        }
+    }
+
+    fn compare_rounded_binary_f32_impl(expected: RoundedBinary32Fn, actual: RoundedBinary32Fn) {
+        for (op1, op2) in hard_f32_pairs(SEED, HARD_CASES) {
+            for mode in Round::ALL {
+                let a = actual(op1, op2, mode);
+
+                assert!(oracle::accepts_f32(|mode| expected(op1, op2, mode), a, mode), "a = {:.9e}, b = {:.9e}, mode = {:?}, expected = {:.9e}, actual = {:.9e}", op1, op2, mode, expected(op1, op2, mode), a);
+            }
+        }
+    }
+
+    #[test]
+    fn test_add() {
+        compare_rounded_binary_f64_impl(&oracle::add_f64, &add_f64);
+        compare_rounded_binary_f32_impl(&oracle::add_f32, &add_f32);
+    }
+
+    #[test]
+    fn test_sub() {
+        compare_rounded_binary_f64_impl(&oracle::sub_f64, &sub_f64);
+        compare_rounded_binary_f32_impl(&oracle::sub_f32, &sub_f32);
+    }
+
+    #[test]
+    fn test_mul() {
+        compare_rounded_binary_f64_impl(&oracle::mul_f64, &mul_f64);
+        compare_rounded_binary_f32_impl(&oracle::mul_f32, &mul_f32);
+    }
+
+    #[test]
+    fn test_div() {
+        compare_rounded_binary_f64_impl(&oracle::div_f64, &div_f64);
+        compare_rounded_binary_f32_impl(&oracle::div_f32, &div_f32);
+    }
+
+    #[test]
+    fn test_fma() {
+        let mut rng = SplitMix64::new(SEED);
+        for (op1, op2) in hard_f64_pairs(SEED, HARD_CASES) {
+            // Addends near the product's magnitude exercise cancellation, tiny and huge ones the
+            // sticky path
+            let scale = |exp: i32, rng: &mut SplitMix64| rng.significand_f64() * 2f64.powi(exp);
+            let c = match rng.range(0, 2) {
+                0 => -(op1 * op2) * scale(0, &mut rng),
+                1 => f64::from_bits(rng.next_u64()),
+                _ => (op1 * op2) * scale(rng.range(-120, 120), &mut rng),
+            };
+
+            for mode in Round::ALL {
+                let a = fma_f64(op1, op2, c, mode);
+                assert!(oracle::accepts_f64(|mode| oracle::fma_f64(op1, op2, c, mode), a, mode), "a = {:.18e}, b = {:.18e}, c = {:.18e}, mode = {:?}, actual = {:.18e}", op1, op2, c, mode, a);
+            }
+        }
+
+        for (op1, op2) in hard_f32_pairs(SEED, HARD_CASES) {
+            let c = match rng.range(0, 1) {
+                0 => -(op1 * op2) * rng.significand_f32(),
+                _ => f32::from_bits(rng.next_u64() as u32),
+            };
+
+            for mode in Round::ALL {
+                let a = fma_f32(op1, op2, c, mode);
+                assert!(oracle::accepts_f32(|mode| oracle::fma_f32(op1, op2, c, mode), a, mode), "a = {:.9e}, b = {:.9e}, c = {:.9e}, mode = {:?}, actual = {:.9e}", op1, op2, c, mode, a);
+            }
+        }
+
+        // Exact cancellation and zero products
+        for mode in Round::ALL {
+            let zero = if mode == Round::TowardNInf { -0. } else { 0. };
+            assert!(identical_f64(fma_f64(3., 5., -15., mode), zero));
+            assert!(identical_f64(fma_f64(-0., 5., 0., mode), zero));
+            assert!(identical_f64(fma_f64(-0., 5., -0., mode), -0.));
+            assert!(identical_f64(fma_f64(0., 5., -7., mode), -7.));
+        }
+    }
+
+    /// Compare a unary function against the exact oracle under every rounding mode, on the
+    /// special values and on random bit patterns.
+    fn compare_unary_f64_impl(expected: &dyn Fn(f64, Round) -> f64, actual: &dyn Fn(f64, Round) -> f64) {
+        let mut rng = SplitMix64::new(SEED);
+        let random = (0..HARD_CASES).map(|_| f64::from_bits(rng.next_u64()));
+        for op in RANDOM_F64.iter().copied().chain(random) {
+            for mode in Round::ALL {
+                let a = actual(op, mode);
+
+                assert!(oracle::accepts_f64(|mode| expected(op, mode), a, mode), "a = {:.18e}, mode = {:?}, expected = {:.18e}, actual = {:.18e}", op, mode, expected(op, mode), a);
+            }
+        }
+    }
+
+    fn compare_unary_f32_impl(expected: &dyn Fn(f32, Round) -> f32, actual: &dyn Fn(f32, Round) -> f32) {
+        let mut rng = SplitMix64::new(SEED);
+        for op in (0..HARD_CASES).map(|_| f32::from_bits(rng.next_u64() as u32)) {
+            for mode in Round::ALL {
+                let a = actual(op, mode);
+
+                assert!(oracle::accepts_f32(|mode| expected(op, mode), a, mode), "a = {:.9e}, mode = {:?}, expected = {:.9e}, actual = {:.9e}", op, mode, expected(op, mode), a);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqrt() {
+        compare_unary_f64_impl(&oracle::sqrt_f64, &sqrt_f64);
+        compare_unary_f32_impl(&oracle::sqrt_f32, &sqrt_f32);
+    }
+
+    #[test]
+    fn test_non_native_modes() {
+        let cases: [(f32, [f32; 7]); 6] = [
+            // TiesToEven, TowardZero, TowardPInf, TowardNInf, Faithful, TiesAway, TiesToOdd
+            (2.5, [2., 2., 3., 2., 2., 3., 3.]),
+            (-2.5, [-2., -2., -2., -3., -2., -3., -3.]),
+            (3.5, [4., 3., 4., 3., 4., 4., 3.]),
+            (-0.5, [-0., -0., -0., -1., -0., -1., -1.]),
+            (0.25, [0., 0., 1., 0., 0., 0., 0.]),
+            (-7.75, [-8., -7., -7., -8., -8., -8., -8.]),
+        ];
+
+        for (op, expected) in cases {
+            for (mode, e) in Round::ALL.into_iter().zip(expected) {
+                let a = round_f32(op, mode);
+                assert!(identical_f32(a, e), "a = {}, mode = {:?}, expected = {}, actual = {}", op, mode, e, a);
+            }
+        }
+
+        // 1 + 2^-24 lies halfway between 1 and its successor, and MAX + ulp/2 halfway between MAX
+        // and overflow
+        let max_exp = Format::BINARY32.max_exp();
+        assert_eq!(round_to_format(Format::BINARY32, false, (1 << 24) + 1, -24, false, Round::TiesAway), 0x3f80_0001);
+        assert_eq!(round_to_format(Format::BINARY32, false, (1 << 24) + 1, -24, false, Round::TiesToOdd), 0x3f80_0001);
+        assert_eq!(round_to_format(Format::BINARY32, false, (1 << 24) + 3, -24, false, Round::TiesToOdd), 0x3f80_0001);
+        assert_eq!(round_to_format(Format::BINARY32, true, (1 << 25) - 1, max_exp - 24, false, Round::TiesAway), 0xff80_0000);
+        assert_eq!(round_to_format(Format::BINARY32, true, (1 << 25) - 1, max_exp - 24, false, Round::TiesToOdd), 0xff7f_ffff);
+        assert_eq!(round_to_format(Format::BINARY32, false, 1, -150, false, Round::TiesAway), 0x0000_0001);
+        assert_eq!(round_to_format(Format::BINARY32, false, 1, -150, false, Round::TiesToOdd), 0x0000_0001);
+        assert_eq!(round_to_format(Format::BINARY32, false, 1, -150, false, Round::TiesToEven), 0x0000_0000);
+    }
+
     #[test]
     fn test_multiply_round_down() {
-        compare_binary_f64_impl(&native::mul_down, &multiply_round_down);
+        compare_binary_f64_impl(&native::mul_down, &multiply_round_down, (1, 1));
+
+        // The exact oracle is slower, so it gets a sample of the pairs
+        compare_binary_f64_impl(&|a, b| oracle::mul_f64(a, b, Round::TowardNInf), &multiply_round_down, (17, 19));
+    }
+
+    #[test]
+    fn test_multiply_round_down_hard_cases() {
+        for (op1, op2) in hard_f64_pairs(SEED, HARD_CASES) {
+            let e = oracle::mul_f64(op1, op2, Round::TowardNInf);
+            let a = multiply_round_down(op1, op2);
+
+            assert!(identical_f64(e, a) || (e.is_nan() && a.is_nan()), "a = {:.18e}, b = {:.18e}, expected = {:.18e}, actual = {:.18e}", op1, op2, e, a);
+        }
     }
 }
@@ -0,0 +1,219 @@
+// Signed fixed-point numbers on big integers, the working format of the slow paths of the
+// elementary functions. Each operation is either exact or truncates toward zero by less than one
+// unit in the last place, so callers can add up a bound on the error of a result as they compute
+// it, in units of its last place, and round the whole range once they are done.
+
+use crate::bigint::BigUint;
+use crate::fallback::{round_to_format, Format};
+use crate::modes::*;
+use crate::successor::FloatParts;
+
+/// The value `(-1)^negative * mag * 2^-prec`. Operands of binary operations share `prec`.
+#[derive(Clone, Debug)]
+pub(crate) struct Fixed {
+    pub negative: bool,
+    pub mag: BigUint,
+    pub prec: u32,
+}
+
+impl Fixed {
+    pub fn from_int(n: i64, prec: u32) -> Fixed {
+        Fixed { negative: n < 0, mag: BigUint::from_u128(n.unsigned_abs() as u128).shl(prec), prec }
+    }
+
+    /// `n` units in the last place
+    pub fn ulps(n: u64, prec: u32) -> Fixed {
+        Fixed { negative: false, mag: BigUint::from_u128(n as u128), prec }
+    }
+
+    /// `f * 2^scale` for a finite `f`, truncated.
+    pub fn from_f64(f: f64, scale: i32, prec: u32) -> Fixed {
+        let parts = FloatParts::of_f64(f);
+        let mant = BigUint::from_u128(parts.mant as u128);
+        let shift = parts.exp + scale + prec as i32;
+
+        let mag = if shift >= 0 { mant.shl(shift as u32) } else { mant.shr((-shift) as u32) };
+        Fixed { negative: parts.negative, mag, prec }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mag.is_zero()
+    }
+
+    pub fn neg(&self) -> Fixed {
+        Fixed { negative: !self.negative, ..self.clone() }
+    }
+
+    pub fn add(&self, other: &Fixed) -> Fixed {
+        debug_assert_eq!(self.prec, other.prec);
+
+        let (negative, mag) = if self.negative == other.negative {
+            (self.negative, self.mag.add(&other.mag))
+        } else if self.mag >= other.mag {
+            (self.negative, self.mag.sub(&other.mag))
+        } else {
+            (other.negative, other.mag.sub(&self.mag))
+        };
+
+        Fixed { negative, mag, prec: self.prec }
+    }
+
+    pub fn sub(&self, other: &Fixed) -> Fixed {
+        self.add(&other.neg())
+    }
+
+    /// Product, truncated
+    pub fn mul(&self, other: &Fixed) -> Fixed {
+        debug_assert_eq!(self.prec, other.prec);
+
+        let mag = self.mag.mul(&other.mag).shr(self.prec);
+        Fixed { negative: self.negative != other.negative, mag, prec: self.prec }
+    }
+
+    /// Quotient, truncated; `other` must be nonzero
+    pub fn div(&self, other: &Fixed) -> Fixed {
+        debug_assert_eq!(self.prec, other.prec);
+
+        let (mag, _) = self.mag.shl(self.prec).div_rem(&other.mag);
+        Fixed { negative: self.negative != other.negative, mag, prec: self.prec }
+    }
+
+    /// Square root of the product of two nonnegative values, truncated, so exact operands give a
+    /// result within one unit
+    pub fn sqrt_product(a: &Fixed, b: &Fixed) -> Fixed {
+        debug_assert!(a.prec == b.prec && (!a.negative || a.is_zero()) && (!b.negative || b.is_zero()));
+
+        Fixed { negative: false, mag: a.mag.mul(&b.mag).isqrt(), prec: a.prec }
+    }
+
+    /// Exact product with an integer
+    pub fn mul_int(&self, n: i32) -> Fixed {
+        let mag = self.mag.mul(&BigUint::from_u128(n.unsigned_abs() as u128));
+        Fixed { negative: self.negative != (n < 0), mag, prec: self.prec }
+    }
+
+    /// Quotient by a positive integer, truncated
+    pub fn div_int(&self, n: u32) -> Fixed {
+        Fixed { mag: self.mag.div_u32(n), ..self.clone() }
+    }
+
+    /// `self * 2^n`, truncated if `n` is negative
+    pub fn scale(&self, n: i32) -> Fixed {
+        let mag = if n >= 0 { self.mag.shl(n as u32) } else { self.mag.shr(n.unsigned_abs()) };
+        Fixed { mag, ..self.clone() }
+    }
+
+    /// Rounds `self * 2^scale` to an f64 under the given rounding mode.
+    pub fn to_f64(&self, scale: i32, mode: Round) -> f64 {
+        // Keep the leading 126 bits, enough for round_to_format to place a sticky bit below
+        let bits = self.mag.bits();
+        let exp = scale - self.prec as i32;
+
+        let (mant, exp, sticky) = if bits <= 126 {
+            (self.mag.low_u128(), exp, false)
+        } else {
+            let shift = bits - 126;
+            let kept = self.mag.shr(shift);
+            (kept.low_u128(), exp + shift as i32, kept.shl(shift) != self.mag)
+        };
+
+        f64::from_bits(round_to_format(Format::BINARY64, self.negative, mant, exp, sticky, mode) as u64)
+    }
+
+    /// Rounds every value within `err` units in the last place of `self`, scaled by `2^scale`,
+    /// returning the result if they all round to the same f64, or None if the range is too wide
+    /// to tell.
+    pub fn round_ball(&self, err: u64, scale: i32, mode: Round) -> Option<f64> {
+        let radius = Fixed::ulps(err, self.prec);
+        let lower = self.sub(&radius).to_f64(scale, mode);
+        let upper = self.add(&radius).to_f64(scale, mode);
+
+        if lower.to_bits() == upper.to_bits() { Some(lower) } else { None }
+    }
+
+    /// Rounds the ball of `round_ball` when it can't be decided, to a bound that still holds for
+    /// every value in it in the directed modes: its lower end rounded down, its upper end rounded
+    /// up, or the end nearer zero rounded toward zero. The other modes round the center, which
+    /// comes out at least faithfully rounded for a ball narrower than an ulp.
+    pub fn bound_ball(&self, err: u64, scale: i32, mode: Round) -> f64 {
+        let radius = Fixed::ulps(err, self.prec);
+        let lower = self.sub(&radius).to_f64(scale, Round::TowardNInf);
+        let upper = self.add(&radius).to_f64(scale, Round::TowardPInf);
+
+        match mode {
+            Round::TowardNInf => lower,
+            Round::TowardPInf => upper,
+            Round::TowardZero if lower >= 0.0 => lower,
+            Round::TowardZero if upper <= 0.0 => upper,
+            Round::TowardZero => 0.0,
+            _ => self.to_f64(scale, mode),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracle::Rational;
+    use crate::test_cases::*;
+
+    fn exact(f: &Fixed, scale: i32) -> Rational {
+        Rational { negative: f.negative, num: f.mag.clone(), den: BigUint::one(), exp: scale - f.prec as i32 }
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let third = Fixed::from_int(1, 64).div_int(3);
+        assert_eq!(third.mag.low_u128(), u64::MAX as u128 / 3);
+        assert_eq!(third.mul_int(-3).add(&Fixed::from_int(1, 64)).mag, BigUint::one());
+        assert_eq!(Fixed::from_int(1, 64).div(&Fixed::from_int(3, 64)).mag, third.mag);
+        assert_eq!(third.mul(&Fixed::from_int(-6, 64)).mag.low_u128(), 2 * u64::MAX as u128);
+        assert!(third.sub(&third).is_zero());
+
+        let x = Fixed::from_f64(-0.375, 3, 8);
+        assert_eq!((x.negative, x.mag.low_u128()), (true, 3 << 8));
+        assert_eq!(Fixed::from_f64(1e-300, 0, 64).mag, BigUint::zero());
+        assert_eq!(Fixed::from_f64(1.5, -2, 4).scale(-2).mag.low_u128(), 1);
+
+        // sqrt(2 * 1/2) and sqrt(2 * 3), truncated
+        let (one, two) = (Fixed::from_int(1, 64), Fixed::from_int(2, 64));
+        assert_eq!(Fixed::sqrt_product(&two, &one.scale(-1)).mag, one.mag);
+        let root = Fixed::sqrt_product(&two, &Fixed::from_int(3, 64)).mag;
+        let six = BigUint::from_u128(6).shl(128);
+        assert!(root.mul(&root) <= six && six < root.add(&BigUint::one()).mul(&root.add(&BigUint::one())));
+    }
+
+    #[test]
+    fn test_rounding() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..2000 {
+            let (prec, shift, low_bits) = (rng.range(0, 300) as u32, rng.range(0, 200) as u32, rng.range(1, 64) as u32);
+            let mag = BigUint::from_u128(rng.next_u64() as u128).shl(shift).add(&BigUint::from_u128(rng.mant(low_bits) as u128));
+            let f = Fixed { negative: rng.coin(), mag, prec };
+            let scale = rng.range(-1200, 1000);
+
+            for mode in Round::ALL {
+                let expected = exact(&f, scale).round(Format::BINARY64, mode);
+                assert_eq!(f.to_f64(scale, mode).to_bits() as u128, expected, "{:?} * 2^{}, {:?}", f, scale, mode);
+            }
+        }
+
+        // A ball around 1 + 2^-60 rounds alike until it reaches 1 or the midpoint above
+        let x = Fixed::from_int(1, 64).add(&Fixed::ulps(16, 64));
+        assert_eq!(x.round_ball(15, 0, Round::TowardPInf), Some(1.0 + f64::EPSILON));
+        assert_eq!(x.round_ball(16, 0, Round::TowardPInf), None);
+        assert_eq!(x.round_ball(1 << 10, 0, Round::TiesToEven), Some(1.0));
+        assert_eq!(x.round_ball(1 << 11, 0, Round::TiesAway), None);
+
+        // Undecided, the directed modes take the end of the ball on their side
+        let below = 1.0 - f64::EPSILON / 2.0;
+        assert_eq!(x.bound_ball(16, 0, Round::TowardPInf), 1.0 + f64::EPSILON);
+        assert_eq!(x.bound_ball(16, 0, Round::TowardNInf), 1.0);
+        assert_eq!(x.bound_ball(32, 0, Round::TowardNInf), below);
+        assert_eq!(x.bound_ball(32, 0, Round::TowardZero), below);
+        assert_eq!(x.neg().bound_ball(32, 0, Round::TowardZero), -below);
+        assert_eq!(x.bound_ball(32, 0, Round::TiesToEven), 1.0);
+        assert_eq!(Fixed::ulps(1, 64).bound_ball(2, 0, Round::TowardZero), 0.0);
+    }
+}
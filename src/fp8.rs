@@ -0,0 +1,444 @@
+// 8-bit float formats from the OCP 8-bit floating point specification: E4M3, which trades
+// infinities for range, and E5M2, a truncated binary16 that keeps IEEE 754 special values. They
+// are storage formats, so only conversions are provided. Every value of either is exact in f32,
+// f16 and bf16, and conversions into them round once under any rounding mode, with overflow
+// either saturating to the largest finite value or producing the format's overflow value.
+
+use std::cmp::Ordering;
+use std::fmt;
+use crate::fallback::{round_to_format, Format};
+use crate::half::{BF16, F16};
+use crate::modes::*;
+use crate::successor::FloatParts;
+
+/// What conversions do with values beyond the largest finite value of an 8-bit format, including
+/// infinities
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// Results are clamped to the largest finite value of the right sign, whatever the rounding
+    /// mode. NaNs remain NaNs.
+    Saturate,
+    /// Results overflow as in IEEE 754: to infinity unless the rounding mode points toward zero,
+    /// in which case they round to the largest finite value. E4M3 has no infinities and produces
+    /// NaN instead.
+    Infinity,
+}
+
+/// Defines an 8-bit float type. Values are rounded in `$format`, an IEEE 754 layout with the
+/// same precision and subnormal range as the type once the exponent is offset by the difference
+/// in biases, but with enough exponent range that the type's own overflow threshold can be
+/// applied afterwards.
+macro_rules! fp8_type {
+    ($(#[$doc:meta])* $name:ident, $format:expr, bias: $bias:expr, max: $max:expr, nan: $nan:expr, inf: $inf:expr) => {
+        $(#[$doc])*
+        #[derive(Copy, Clone, Default)]
+        pub struct $name(u8);
+
+        impl $name {
+            const FORMAT: Format = $format;
+            const SIGN_MASK: u8 = 0x80;
+            const FRAC_BITS: u32 = $format.frac_bits;
+            const BIAS: i32 = $bias;
+            const MAX_BITS: u8 = $max;
+            const INF_BITS: Option<u8> = $inf;
+            /// Magnitude of what non-saturating conversions overflow to
+            const OVERFLOW_BITS: u8 = match $inf { Some(inf) => inf, None => $nan };
+
+            pub const ZERO: $name = $name(0);
+            pub const ONE: $name = $name(($bias as u8) << $format.frac_bits);
+            pub const NAN: $name = $name($nan);
+            /// Largest finite value
+            pub const MAX: $name = $name($max);
+            /// Smallest finite value, -MAX
+            pub const MIN: $name = $name($max | Self::SIGN_MASK);
+            /// Smallest positive normal value
+            pub const MIN_POSITIVE: $name = $name(1 << $format.frac_bits);
+            /// Difference between 1 and the next larger value
+            pub const EPSILON: $name = $name((($bias - $format.frac_bits as i32) as u8) << $format.frac_bits);
+
+            pub const fn from_bits(bits: u8) -> $name {
+                $name(bits)
+            }
+
+            pub const fn to_bits(self) -> u8 {
+                self.0
+            }
+
+            pub fn is_nan(self) -> bool {
+                let abs = self.0 & !Self::SIGN_MASK;
+                abs > Self::MAX_BITS && Some(abs) != Self::INF_BITS
+            }
+
+            pub fn is_infinite(self) -> bool {
+                Some(self.0 & !Self::SIGN_MASK) == Self::INF_BITS
+            }
+
+            pub fn is_finite(self) -> bool {
+                self.0 & !Self::SIGN_MASK <= Self::MAX_BITS
+            }
+
+            pub fn is_sign_negative(self) -> bool {
+                self.0 & Self::SIGN_MASK != 0
+            }
+
+            pub fn abs(self) -> $name {
+                $name(self.0 & !Self::SIGN_MASK)
+            }
+
+            /// Rounds a finite value given by its parts.
+            fn from_parts(parts: FloatParts, mode: Round, overflow: Overflow) -> $name {
+                let sign = if parts.negative { Self::SIGN_MASK } else { 0 };
+                let exp = parts.exp + Self::BIAS - Self::FORMAT.bias();
+                let bits = round_to_format(Self::FORMAT, parts.negative, parts.mant as u128, exp, false, mode);
+
+                let abs = bits & (Self::FORMAT.sign_bit() - 1);
+                if abs <= Self::MAX_BITS as u128 {
+                    return $name(sign | abs as u8);
+                }
+
+                // Directed modes never round away from zero past the largest finite value
+                let truncates = match mode {
+                    Round::TowardZero => true,
+                    Round::TowardPInf => parts.negative,
+                    Round::TowardNInf => !parts.negative,
+                    _ => false,
+                };
+
+                $name(sign | if overflow == Overflow::Saturate || truncates { Self::MAX_BITS } else { Self::OVERFLOW_BITS })
+            }
+
+            /// Converts an f32 under the given rounding mode and overflow behavior. NaNs keep
+            /// their sign but not their payload.
+            pub fn from_f32(f: f32, mode: Round, overflow: Overflow) -> $name {
+                let sign = if f.is_sign_negative() { Self::SIGN_MASK } else { 0 };
+
+                if f.is_nan() {
+                    return $name(sign | Self::NAN.0);
+                } else if f.is_infinite() {
+                    return $name(sign | if overflow == Overflow::Saturate { Self::MAX_BITS } else { Self::OVERFLOW_BITS });
+                }
+
+                $name::from_parts(FloatParts::of_f32(f), mode, overflow)
+            }
+
+            /// Converts an f64 under the given rounding mode and overflow behavior, with a single
+            /// rounding.
+            pub fn from_f64(f: f64, mode: Round, overflow: Overflow) -> $name {
+                if !f.is_finite() {
+                    return $name::from_f32(f as f32, mode, overflow);
+                }
+
+                $name::from_parts(FloatParts::of_f64(f), mode, overflow)
+            }
+
+            /// Converts a binary16 value under the given rounding mode and overflow behavior.
+            pub fn from_f16(h: F16, mode: Round, overflow: Overflow) -> $name {
+                $name::from_f32(h.to_f32(), mode, overflow)
+            }
+
+            /// Converts a bfloat16 value under the given rounding mode and overflow behavior.
+            pub fn from_bf16(b: BF16, mode: Round, overflow: Overflow) -> $name {
+                $name::from_f32(b.to_f32(), mode, overflow)
+            }
+
+            /// Converts to an f32, which is always exact.
+            pub fn to_f32(self) -> f32 {
+                let sign = if self.is_sign_negative() { -1. } else { 1. };
+                if self.is_nan() {
+                    return sign * f32::NAN;
+                } else if self.is_infinite() {
+                    return sign * f32::INFINITY;
+                }
+
+                let abs = self.0 & !Self::SIGN_MASK;
+                let frac = (abs & ((1 << Self::FRAC_BITS) - 1)) as i32;
+                let biased = (abs >> Self::FRAC_BITS) as i32;
+
+                let (mant, exp) = if biased == 0 {
+                    (frac, 1 - Self::BIAS - Self::FRAC_BITS as i32)
+                } else {
+                    (frac | (1 << Self::FRAC_BITS), biased - Self::BIAS - Self::FRAC_BITS as i32)
+                };
+
+                sign * mant as f32 * 2f32.powi(exp)
+            }
+
+            /// Converts to an f64, which is always exact.
+            pub fn to_f64(self) -> f64 {
+                self.to_f32() as f64
+            }
+
+            /// Converts to a binary16 value, which is always exact.
+            pub fn to_f16(self) -> F16 {
+                F16::from_f32(self.to_f32(), Round::TiesToEven)
+            }
+
+            /// Converts to a bfloat16 value, which is always exact.
+            pub fn to_bf16(self) -> BF16 {
+                BF16::from_f32(self.to_f32(), Round::TiesToEven)
+            }
+        }
+
+        impl std::ops::Neg for $name {
+            type Output = $name;
+
+            fn neg(self) -> $name {
+                $name(self.0 ^ Self::SIGN_MASK)
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &$name) -> bool {
+                self.to_f32() == other.to_f32()
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &$name) -> Option<Ordering> {
+                self.to_f32().partial_cmp(&other.to_f32())
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Display::fmt(&self.to_f32(), f)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}({:?})", stringify!($name), self.to_f32())
+            }
+        }
+    };
+}
+
+fp8_type!(
+    /// OCP E4M3 float, with 4 exponent bits and 4 bits of precision, stored as its bit pattern.
+    /// It has no infinities, and its only NaNs are 0x7f and 0xff, which makes its largest
+    /// exponent available for finite values up to 448.
+    E4M3, Format { exp_bits: 5, frac_bits: 3 }, bias: 7, max: 0x7e, nan: 0x7f, inf: None
+);
+
+fp8_type!(
+    /// OCP E5M2 float, with 5 exponent bits and 3 bits of precision, stored as its bit pattern.
+    /// It follows IEEE 754, with infinities and NaNs, and is binary16 without its low byte.
+    E5M2, Format { exp_bits: 5, frac_bits: 2 }, bias: 15, max: 0x7b, nan: 0x7e, inf: Some(0x7c)
+);
+
+impl E5M2 {
+    pub const INFINITY: E5M2 = E5M2(0x7c);
+    pub const NEG_INFINITY: E5M2 = E5M2(0xfc);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_cases::*;
+
+    const OVERFLOWS: [Overflow; 2] = [Overflow::Saturate, Overflow::Infinity];
+
+    /// Positive values of an 8-bit type in increasing order, for the reference conversion
+    struct Layout {
+        /// Values indexed by bit pattern, followed by the value one ULP above MAX
+        values: Vec<f64>,
+        overflow: u8,
+    }
+
+    impl Layout {
+        fn new(to_f64: impl Fn(u8) -> f64, max: u8, overflow: u8) -> Layout {
+            let mut values: Vec<f64> = (0..=max).map(to_f64).collect();
+            values.push(2. * values[max as usize] - values[max as usize - 1]);
+            Layout { values, overflow }
+        }
+
+        fn e4m3() -> Layout {
+            Layout::new(|b| E4M3::from_bits(b).to_f64(), 0x7e, 0x7f)
+        }
+
+        fn e5m2() -> Layout {
+            Layout::new(|b| E5M2::from_bits(b).to_f64(), 0x7b, 0x7c)
+        }
+
+        /// Rounds a non-NaN value by searching the type's values for its neighbors, returning
+        /// the acceptable bit patterns: both neighbors for faithful rounding, one otherwise.
+        fn round(&self, x: f64, mode: Round, overflow: Overflow) -> Vec<u8> {
+            let max = (self.values.len() - 2) as u8;
+            let (negative, abs) = (x.is_sign_negative(), x.abs());
+            let sign = if negative { 0x80 } else { 0 };
+            let clamp = |bits: u8| match (bits > max, overflow) {
+                (false, _) => sign | bits,
+                (true, Overflow::Saturate) => sign | max,
+                (true, Overflow::Infinity) => sign | self.overflow,
+            };
+
+            if abs.is_infinite() {
+                return vec![clamp(max + 1)];
+            }
+
+            let value = |bits: u8| self.values[bits as usize];
+            let down = (self.values[..=max as usize].partition_point(|&v| v <= abs) - 1) as u8;
+            if value(down) == abs {
+                return vec![sign | down];
+            }
+
+            let up = down + 1;
+            let mid = (value(down) + value(up)) / 2.;
+            let nearest = |tie: u8| if abs < mid { down } else if abs > mid { up } else { tie };
+            let (even, odd) = if down & 1 == 0 { (down, up) } else { (up, down) };
+
+            let bits = match mode {
+                Round::TiesToEven => nearest(even),
+                Round::TiesAway => nearest(up),
+                Round::TiesToOdd => nearest(odd),
+                Round::TowardZero => down,
+                Round::TowardPInf => if negative { down } else { up },
+                Round::TowardNInf => if negative { up } else { down },
+                Round::Faithful => return vec![clamp(down), clamp(up)],
+            };
+
+            vec![clamp(bits)]
+        }
+    }
+
+    /// Checks conversions of `x` to both types under every mode and overflow behavior.
+    fn check(layouts: &[Layout; 2], x: f64, convert: impl Fn(Round, Overflow) -> (E4M3, E5M2)) {
+        for mode in Round::ALL {
+            for overflow in OVERFLOWS {
+                let (a, b) = convert(mode, overflow);
+
+                if x.is_nan() {
+                    assert!(a.is_nan() && b.is_nan(), "{} {:?} {:?}", x, mode, overflow);
+                    continue;
+                }
+
+                let expected = layouts[0].round(x, mode, overflow);
+                assert!(expected.contains(&a.to_bits()), "E4M3 {:e} {:?} {:?}: {:#04x} not in {:x?}", x, mode, overflow, a.to_bits(), expected);
+                let expected = layouts[1].round(x, mode, overflow);
+                assert!(expected.contains(&b.to_bits()), "E5M2 {:e} {:?} {:?}: {:#04x} not in {:x?}", x, mode, overflow, b.to_bits(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(E4M3::ONE.to_f32(), 1.);
+        assert_eq!(E4M3::MAX.to_f32(), 448.);
+        assert_eq!(E4M3::MIN.to_f32(), -448.);
+        assert_eq!(E4M3::MIN_POSITIVE.to_f32(), 2f32.powi(-6));
+        assert_eq!(E4M3::EPSILON.to_f32(), 2f32.powi(-3));
+        assert_eq!(E4M3::from_bits(1).to_f32(), 2f32.powi(-9));
+        assert_eq!(E5M2::ONE.to_f32(), 1.);
+        assert_eq!(E5M2::MAX.to_f32(), 57344.);
+        assert_eq!(E5M2::MIN_POSITIVE.to_f32(), 2f32.powi(-14));
+        assert_eq!(E5M2::EPSILON.to_f32(), 2f32.powi(-2));
+        assert_eq!(E5M2::from_bits(1).to_f32(), 2f32.powi(-16));
+        assert_eq!(E5M2::NEG_INFINITY.to_f32(), f32::NEG_INFINITY);
+        assert!(E4M3::NAN.is_nan() && E5M2::NAN.is_nan() && E4M3::NAN != E4M3::NAN);
+        assert!(E4M3::ZERO == -E4M3::ZERO && E5M2::NEG_INFINITY < E5M2::MIN);
+    }
+
+    #[test]
+    fn test_encodings_exhaustive() {
+        let (mut e4m3_nans, mut e5m2_nans) = (0, 0);
+
+        for bits in 0..=u8::MAX {
+            let (a, b) = (E4M3::from_bits(bits), E5M2::from_bits(bits));
+            e4m3_nans += a.is_nan() as u32;
+            e5m2_nans += b.is_nan() as u32;
+
+            assert!(!a.is_infinite());
+            assert_eq!(a.is_finite(), bits & 0x7f != 0x7f);
+            assert_eq!(b.is_finite(), bits & 0x7c != 0x7c);
+
+            // E5M2 is the high byte of binary16
+            if b.is_nan() {
+                assert!(b.to_f16().is_nan());
+            } else {
+                assert_eq!(b.to_f16().to_bits(), (bits as u16) << 8, "{:#04x}", bits);
+            }
+
+            // Conversions back from every wider format are exact under any mode
+            for mode in Round::ALL {
+                for overflow in OVERFLOWS {
+                    if !a.is_nan() {
+                        assert_eq!(E4M3::from_f32(a.to_f32(), mode, overflow).to_bits(), bits);
+                        assert_eq!(E4M3::from_f16(a.to_f16(), mode, overflow).to_bits(), bits);
+                        assert_eq!(E4M3::from_bf16(a.to_bf16(), mode, overflow).to_bits(), bits);
+                    }
+                    if b.is_finite() || (b.is_infinite() && overflow == Overflow::Infinity) {
+                        assert_eq!(E5M2::from_f64(b.to_f64(), mode, overflow).to_bits(), bits);
+                        assert_eq!(E5M2::from_bf16(b.to_bf16(), mode, overflow).to_bits(), bits);
+                    }
+                }
+            }
+
+            assert_eq!(a.to_bf16().to_f32().to_bits(), a.to_f32().to_bits());
+            assert_eq!(b.to_bf16().to_f32().to_bits(), b.to_f32().to_bits());
+        }
+
+        assert_eq!((e4m3_nans, e5m2_nans), (2, 6));
+    }
+
+    #[test]
+    fn test_overflow() {
+        for mode in Round::ALL {
+            assert_eq!(E4M3::from_f32(f32::INFINITY, mode, Overflow::Saturate), E4M3::MAX);
+            assert_eq!(E5M2::from_f32(f32::NEG_INFINITY, mode, Overflow::Saturate), E5M2::MIN);
+            assert_eq!(E4M3::from_f32(1e6, mode, Overflow::Saturate), E4M3::MAX);
+            assert_eq!(E5M2::from_f32(-1e6, mode, Overflow::Saturate), E5M2::MIN);
+            assert_eq!(E4M3::from_f32(f32::NEG_INFINITY, mode, Overflow::Infinity).to_bits(), 0xff);
+            assert_eq!(E5M2::from_f32(f32::INFINITY, mode, Overflow::Infinity), E5M2::INFINITY);
+        }
+
+        // 464 is halfway between E4M3's MAX and the next power of two, whose encoding is NaN.
+        // MAX is even, so ties to even stay finite.
+        // TiesToEven, TowardZero, TowardPInf, TowardNInf, Faithful, TiesAway, TiesToOdd
+        let expected = [0x7e, 0x7e, 0x7f, 0x7e, 0x7e, 0x7f, 0x7f];
+        for (mode, e) in Round::ALL.into_iter().zip(expected) {
+            assert_eq!(E4M3::from_f32(464., mode, Overflow::Infinity).to_bits(), e, "{:?}", mode);
+            assert!(E4M3::from_f32(464., mode, Overflow::Saturate).is_finite());
+        }
+
+        // -61440 is halfway between E5M2's MIN and negative infinity. MAX is odd here.
+        let expected = [0xfc, 0xfb, 0xfb, 0xfc, 0xfc, 0xfc, 0xfb];
+        for (mode, e) in Round::ALL.into_iter().zip(expected) {
+            assert_eq!(E5M2::from_f32(-61440., mode, Overflow::Infinity).to_bits(), e, "{:?}", mode);
+            assert!(E5M2::from_f32(-61440., mode, Overflow::Saturate).is_finite());
+        }
+    }
+
+    #[test]
+    fn test_from_f16_exhaustive() {
+        let layouts = [Layout::e4m3(), Layout::e5m2()];
+        for bits in 0..=u16::MAX {
+            let h = F16::from_bits(bits);
+            check(&layouts, h.to_f64(), |mode, overflow| (E4M3::from_f16(h, mode, overflow), E5M2::from_f16(h, mode, overflow)));
+        }
+    }
+
+    #[test]
+    fn test_from_bf16_exhaustive() {
+        let layouts = [Layout::e4m3(), Layout::e5m2()];
+        for bits in 0..=u16::MAX {
+            let b = BF16::from_bits(bits);
+            check(&layouts, b.to_f64(), |mode, overflow| (E4M3::from_bf16(b, mode, overflow), E5M2::from_bf16(b, mode, overflow)));
+        }
+    }
+
+    #[test]
+    fn test_from_f32_and_f64() {
+        let layouts = [Layout::e4m3(), Layout::e5m2()];
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..20000 {
+            // Values within or near the range of the 8-bit formats
+            let mant = rng.mant(53) as f64 * 2f64.powi(-52);
+            let x = mant * 2f64.powi(rng.range(-20, 18)) * if rng.coin() { -1. } else { 1. };
+
+            check(&layouts, x, |mode, overflow| (E4M3::from_f64(x, mode, overflow), E5M2::from_f64(x, mode, overflow)));
+            let f = x as f32;
+            check(&layouts, f as f64, |mode, overflow| (E4M3::from_f32(f, mode, overflow), E5M2::from_f32(f, mode, overflow)));
+        }
+    }
+}
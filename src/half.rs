@@ -0,0 +1,442 @@
+// 16-bit storage formats: IEEE 754 binary16 (F16) and bfloat16 (BF16). Every value of either is
+// exactly representable as an f32, so arithmetic widens its operands to f32 and rounds the exact
+// result once, straight to the 16-bit format, under the requested rounding mode.
+
+use std::cmp::Ordering;
+use std::fmt;
+use crate::fallback::{add_parts, div_parts, mul_parts, round_to_format, sqrt_parts, Format};
+use crate::modes::*;
+use crate::successor::FloatParts;
+
+/// Defines a 16-bit float type with the given layout, along with its successor and predecessor
+/// functions.
+macro_rules! half_type {
+    ($(#[$doc:meta])* $name:ident, $format:expr, $successor:ident, $predecessor:ident) => {
+        $(#[$doc])*
+        #[derive(Copy, Clone, Default)]
+        pub struct $name(u16);
+
+        impl $name {
+            const FORMAT: Format = $format;
+            const SIGN_MASK: u16 = 0x8000;
+            const INF_BITS: u16 = $format.inf_bits() as u16;
+            const FRAC_BITS: u32 = $format.frac_bits;
+
+            pub const ZERO: $name = $name(0);
+            pub const ONE: $name = $name(($format.bias() as u16) << $format.frac_bits);
+            pub const INFINITY: $name = $name(Self::INF_BITS);
+            pub const NEG_INFINITY: $name = $name(Self::INF_BITS | Self::SIGN_MASK);
+            pub const NAN: $name = $name(Self::INF_BITS | (1 << ($format.frac_bits - 1)));
+            /// Largest finite value
+            pub const MAX: $name = $name(Self::INF_BITS - 1);
+            /// Smallest finite value, -MAX
+            pub const MIN: $name = $name((Self::INF_BITS - 1) | Self::SIGN_MASK);
+            /// Smallest positive normal value
+            pub const MIN_POSITIVE: $name = $name(1 << $format.frac_bits);
+            /// Difference between 1 and the next larger value
+            pub const EPSILON: $name = $name((($format.bias() - $format.frac_bits as i32) as u16) << $format.frac_bits);
+
+            pub const fn from_bits(bits: u16) -> $name {
+                $name(bits)
+            }
+
+            pub const fn to_bits(self) -> u16 {
+                self.0
+            }
+
+            pub fn is_nan(self) -> bool {
+                self.0 & !Self::SIGN_MASK > Self::INF_BITS
+            }
+
+            pub fn is_infinite(self) -> bool {
+                self.0 & !Self::SIGN_MASK == Self::INF_BITS
+            }
+
+            pub fn is_finite(self) -> bool {
+                self.0 & !Self::SIGN_MASK < Self::INF_BITS
+            }
+
+            pub fn is_sign_negative(self) -> bool {
+                self.0 & Self::SIGN_MASK != 0
+            }
+
+            pub fn abs(self) -> $name {
+                $name(self.0 & !Self::SIGN_MASK)
+            }
+
+            /// Converts an f32 under the given rounding mode. NaNs keep their sign and as much of
+            /// their payload as fits, and are quieted.
+            pub fn from_f32(f: f32, mode: Round) -> $name {
+                let bits = f.to_bits();
+                let sign = ((bits >> 16) as u16) & Self::SIGN_MASK;
+
+                if f.is_nan() {
+                    let payload = ((bits & 0x007f_ffff) >> (23 - Self::FRAC_BITS)) as u16;
+                    return $name(sign | Self::NAN.0 | payload);
+                } else if f.is_infinite() {
+                    return $name(sign | Self::INF_BITS);
+                }
+
+                let parts = FloatParts::of_f32(f);
+                $name(round_to_format(Self::FORMAT, parts.negative, parts.mant as u128, parts.exp, false, mode) as u16)
+            }
+
+            /// Converts an f64 under the given rounding mode, with a single rounding.
+            pub fn from_f64(f: f64, mode: Round) -> $name {
+                if !f.is_finite() {
+                    return $name::from_f32(f as f32, mode);
+                }
+
+                let parts = FloatParts::of_f64(f);
+                $name(round_to_format(Self::FORMAT, parts.negative, parts.mant as u128, parts.exp, false, mode) as u16)
+            }
+
+            /// Converts to an f32, which is always exact.
+            pub fn to_f32(self) -> f32 {
+                let sign = ((self.0 & Self::SIGN_MASK) as u32) << 16;
+                let frac = (self.0 & ((1 << Self::FRAC_BITS) - 1)) as u128;
+                let biased = ((self.0 & !Self::SIGN_MASK) >> Self::FRAC_BITS) as i32;
+
+                if biased == (Self::INF_BITS >> Self::FRAC_BITS) as i32 {
+                    return f32::from_bits(sign | 0x7f80_0000 | ((frac as u32) << (23 - Self::FRAC_BITS)));
+                }
+
+                let (mant, exp) = if biased == 0 {
+                    (frac, Self::FORMAT.min_exp() - Self::FRAC_BITS as i32)
+                } else {
+                    (frac | (1 << Self::FRAC_BITS), biased - Self::FORMAT.bias() - Self::FRAC_BITS as i32)
+                };
+
+                let bits = round_to_format(Format::BINARY32, false, mant, exp, false, Round::TiesToEven) as u32;
+                f32::from_bits(sign | bits)
+            }
+
+            /// Converts to an f64, which is always exact.
+            pub fn to_f64(self) -> f64 {
+                self.to_f32() as f64
+            }
+
+            /// Computes the sum of two values under the given rounding mode.
+            pub fn add(self, other: $name, mode: Round) -> $name {
+                let (a, b) = (self.to_f32(), other.to_f32());
+                if !a.is_finite() || !b.is_finite() {
+                    return $name::from_f32(a + b, mode);
+                }
+
+                $name(add_parts(Self::FORMAT, FloatParts::of_f32(a), FloatParts::of_f32(b), mode) as u16)
+            }
+
+            /// Computes the difference of two values under the given rounding mode.
+            pub fn sub(self, other: $name, mode: Round) -> $name {
+                let (a, b) = (self.to_f32(), other.to_f32());
+                if !a.is_finite() || !b.is_finite() {
+                    return $name::from_f32(a - b, mode);
+                }
+
+                $name(add_parts(Self::FORMAT, FloatParts::of_f32(a), FloatParts::of_f32(-b), mode) as u16)
+            }
+
+            /// Computes the product of two values under the given rounding mode.
+            pub fn mul(self, other: $name, mode: Round) -> $name {
+                let (a, b) = (self.to_f32(), other.to_f32());
+                if !a.is_finite() || !b.is_finite() || a == 0. || b == 0. {
+                    return $name::from_f32(a * b, mode);
+                }
+
+                $name(mul_parts(Self::FORMAT, FloatParts::of_f32(a), FloatParts::of_f32(b), mode) as u16)
+            }
+
+            /// Computes the quotient of two values under the given rounding mode.
+            pub fn div(self, other: $name, mode: Round) -> $name {
+                let (a, b) = (self.to_f32(), other.to_f32());
+                if !a.is_finite() || !b.is_finite() || a == 0. || b == 0. {
+                    return $name::from_f32(a / b, mode);
+                }
+
+                $name(div_parts(Self::FORMAT, FloatParts::of_f32(a), FloatParts::of_f32(b), mode) as u16)
+            }
+
+            /// Computes the square root under the given rounding mode.
+            pub fn sqrt(self, mode: Round) -> $name {
+                let a = self.to_f32();
+                if a.is_nan() || a <= 0. || a == f32::INFINITY {
+                    return $name::from_f32(a.sqrt(), mode);
+                }
+
+                let parts = FloatParts::of_f32(a);
+                $name(sqrt_parts(Self::FORMAT, parts.mant, parts.exp, mode) as u16)
+            }
+        }
+
+        impl std::ops::Neg for $name {
+            type Output = $name;
+
+            fn neg(self) -> $name {
+                $name(self.0 ^ Self::SIGN_MASK)
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &$name) -> bool {
+                self.to_f32() == other.to_f32()
+            }
+        }
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &$name) -> Option<Ordering> {
+                self.to_f32().partial_cmp(&other.to_f32())
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Display::fmt(&self.to_f32(), f)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}({:?})", stringify!($name), self.to_f32())
+            }
+        }
+
+        /// Returns the least number greater than `f`, with the same conventions as
+        /// `successor_f32`.
+        pub fn $successor(f: $name) -> $name {
+            if f.is_nan() || f.0 == $name::INF_BITS {
+                return f;
+            }
+
+            let abs = f.0 & !$name::SIGN_MASK;
+            $name(if abs == 0 {
+                1
+            } else if f.0 == abs {
+                f.0 + 1
+            } else {
+                f.0 - 1
+            })
+        }
+
+        /// Returns the greatest number less than `f`, with the same conventions as
+        /// `predecessor_f32`.
+        pub fn $predecessor(f: $name) -> $name {
+            -$successor(-f)
+        }
+    };
+}
+
+half_type!(
+    /// IEEE 754 binary16 float, with 5 exponent bits and 11 bits of precision, stored as its bit
+    /// pattern
+    F16, Format::BINARY16, successor_f16, predecessor_f16
+);
+
+half_type!(
+    /// bfloat16 float, with f32's 8 exponent bits and 8 bits of precision, stored as its bit
+    /// pattern
+    BF16, Format::BFLOAT16, successor_bf16, predecessor_bf16
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracle::{self, Value};
+    use crate::successor::*;
+    use crate::test_cases::*;
+
+    /// Checks a 16-bit result against the oracle, accepting either directed result for faithful
+    /// rounding.
+    fn accepts(format: Format, expected: impl Fn(Round) -> Value, actual: u16, mode: Round) -> bool {
+        let ok = |e: Value, mode: Round| {
+            let bits = e.encode(format, mode) as u16;
+            bits == actual || (bits & 0x7fff > format.inf_bits() as u16 && actual & 0x7fff > format.inf_bits() as u16)
+        };
+
+        match mode {
+            Round::Faithful => ok(expected(Round::TowardNInf), Round::TowardNInf) || ok(expected(Round::TowardPInf), Round::TowardPInf),
+            _ => ok(expected(mode), mode)
+        }
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(F16::ONE.to_f32(), 1.);
+        assert_eq!(F16::MAX.to_f32(), 65504.);
+        assert_eq!(F16::MIN_POSITIVE.to_f32(), 2f32.powi(-14));
+        assert_eq!(F16::EPSILON.to_f32(), 2f32.powi(-10));
+        assert_eq!(F16::from_bits(1).to_f32(), 2f32.powi(-24));
+        assert_eq!(BF16::ONE.to_f32(), 1.);
+        assert_eq!(BF16::MAX.to_f32(), f32::from_bits(0x7f7f_0000));
+        assert_eq!(BF16::MIN_POSITIVE.to_f32(), f32::MIN_POSITIVE);
+        assert_eq!(BF16::EPSILON.to_f32(), 2f32.powi(-7));
+        assert_eq!(BF16::MIN, -BF16::MAX);
+        assert!(F16::NAN.is_nan() && BF16::NAN.is_nan() && F16::NAN != F16::NAN);
+        assert!(F16::NEG_INFINITY < F16::MIN && F16::ZERO == -F16::ZERO);
+    }
+
+    #[test]
+    fn test_to_f32_exhaustive() {
+        for bits in 0..=u16::MAX {
+            let (h, b) = (F16::from_bits(bits), BF16::from_bits(bits));
+
+            assert_eq!(b.to_f32().to_bits(), (bits as u32) << 16);
+            if !h.is_nan() {
+                assert_eq!(F16::from_f32(h.to_f32(), Round::TowardZero).to_bits(), bits, "{:#06x}", bits);
+            }
+            assert_eq!(h.to_f64().to_bits(), (h.to_f32() as f64).to_bits());
+
+            match Value::decode(Format::BINARY16, bits as u128) {
+                Value::NaN => assert!(h.is_nan()),
+                v => assert_eq!(h.to_f32().to_bits(), v.encode(Format::BINARY32, Round::TiesToEven) as u32, "{:#06x}", bits),
+            }
+        }
+    }
+
+    #[test]
+    fn test_quantize() {
+        // 1 + 2^-11 lies halfway between 1 and the next F16, and 1 + 3 * 2^-11 halfway between that
+        // and the one after
+        let cases: [(f32, [u16; 7]); 4] = [
+            // TiesToEven, TowardZero, TowardPInf, TowardNInf, Faithful, TiesAway, TiesToOdd
+            (1. + 2f32.powi(-11), [0x3c00, 0x3c00, 0x3c01, 0x3c00, 0x3c00, 0x3c01, 0x3c01]),
+            (-(1. + 3. * 2f32.powi(-11)), [0xbc02, 0xbc01, 0xbc01, 0xbc02, 0xbc02, 0xbc02, 0xbc01]),
+            (65520., [0x7c00, 0x7bff, 0x7c00, 0x7bff, 0x7c00, 0x7c00, 0x7bff]),
+            (2f32.powi(-25), [0x0000, 0x0000, 0x0001, 0x0000, 0x0000, 0x0001, 0x0001]),
+        ];
+
+        for (f, expected) in cases {
+            for (mode, e) in Round::ALL.into_iter().zip(expected) {
+                assert_eq!(F16::from_f32(f, mode).to_bits(), e, "{} {:?}", f, mode);
+            }
+        }
+
+        assert_eq!(BF16::from_f32(f32::from_bits(0x3f80_8000), Round::TiesToEven).to_bits(), 0x3f80);
+        assert_eq!(BF16::from_f32(f32::from_bits(0x3f80_8000), Round::TiesAway).to_bits(), 0x3f81);
+        assert_eq!(BF16::from_f32(f32::from_bits(0x3f81_8000), Round::TiesToOdd).to_bits(), 0x3f81);
+        assert_eq!(BF16::from_f32(f32::MAX, Round::TiesToEven), BF16::INFINITY);
+        assert_eq!(BF16::from_f32(f32::MAX, Round::TowardZero), BF16::MAX);
+        assert_eq!(BF16::from_f32(f32::from_bits(0xffc1_2345), Round::TiesToEven).to_bits(), 0xffc1);
+        assert!(F16::from_f64(f64::NAN, Round::TiesToEven).is_nan());
+        assert!(F16::from_f32(-0., Round::TiesToEven).is_sign_negative());
+    }
+
+    #[test]
+    fn test_conversions_against_oracle() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..20000 {
+            // Mostly values within or near the range of the 16-bit formats
+            let f = if rng.coin() {
+                f64::from_bits(rng.next_u64())
+            } else {
+                let mant = rng.mant(53) as f64 * 2f64.powi(-52);
+                mant * 2f64.powi(rng.range(-30, 18)) * if rng.coin() { -1. } else { 1. }
+            };
+
+            for mode in Round::ALL {
+                let v64 = Value::decode(Format::BINARY64, f.to_bits() as u128);
+                let v32 = Value::decode(Format::BINARY32, (f as f32).to_bits() as u128);
+
+                assert!(accepts(Format::BINARY16, |_| v64.clone(), F16::from_f64(f, mode).to_bits(), mode), "{:e} {:?}", f, mode);
+                assert!(accepts(Format::BFLOAT16, |_| v64.clone(), BF16::from_f64(f, mode).to_bits(), mode), "{:e} {:?}", f, mode);
+                assert!(accepts(Format::BINARY16, |_| v32.clone(), F16::from_f32(f as f32, mode).to_bits(), mode), "{:e} {:?}", f, mode);
+                assert!(accepts(Format::BFLOAT16, |_| v32.clone(), BF16::from_f32(f as f32, mode).to_bits(), mode), "{:e} {:?}", f, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_arithmetic_against_oracle() {
+        type Op<T> = (&'static str, fn(T, T, Round) -> T, fn(&Value, &Value, Round) -> Value);
+
+        fn ops<T>(add: fn(T, T, Round) -> T, sub: fn(T, T, Round) -> T, mul: fn(T, T, Round) -> T, div: fn(T, T, Round) -> T) -> [Op<T>; 4] {
+            [
+                ("add", add, oracle::add),
+                ("sub", sub, |a, b, mode| oracle::add(a, &oracle::neg(b), mode)),
+                ("mul", mul, |a, b, _| oracle::mul(a, b)),
+                ("div", div, |a, b, _| oracle::div(a, b)),
+            ]
+        }
+
+        let f16_ops = ops(F16::add, F16::sub, F16::mul, F16::div);
+        let bf16_ops = ops(BF16::add, BF16::sub, BF16::mul, BF16::div);
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..10000 {
+            let (a, b) = (rng.next_u64() as u16, rng.next_u64() as u16);
+            // Every other pair shares an exponent, so that differences cancel
+            let b = if rng.coin() { (a & 0xfc00) | (b & 0x83ff) } else { b };
+
+            for mode in Round::ALL {
+                for (name, f, exact) in f16_ops {
+                    let (x, y) = (Value::decode(Format::BINARY16, a as u128), Value::decode(Format::BINARY16, b as u128));
+                    let actual = f(F16::from_bits(a), F16::from_bits(b), mode).to_bits();
+                    assert!(accepts(Format::BINARY16, |mode| exact(&x, &y, mode), actual, mode), "F16 {}({:#06x}, {:#06x}) {:?}", name, a, b, mode);
+                }
+
+                for (name, f, exact) in bf16_ops {
+                    let (x, y) = (Value::decode(Format::BFLOAT16, a as u128), Value::decode(Format::BFLOAT16, b as u128));
+                    let actual = f(BF16::from_bits(a), BF16::from_bits(b), mode).to_bits();
+                    assert!(accepts(Format::BFLOAT16, |mode| exact(&x, &y, mode), actual, mode), "BF16 {}({:#06x}, {:#06x}) {:?}", name, a, b, mode);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqrt_exhaustive() {
+        for bits in 0..=u16::MAX {
+            let (h, b) = (F16::from_bits(bits), BF16::from_bits(bits));
+
+            // Brackets from the directed modes: down^2 <= x <= up^2, with up the next value after
+            // down unless the root is exact
+            for (x, down, up, next) in [
+                (h.to_f64(), h.sqrt(Round::TowardNInf).to_f64(), h.sqrt(Round::TowardPInf).to_f64(), successor_f16(h.sqrt(Round::TowardNInf)).to_f64()),
+                (b.to_f64(), b.sqrt(Round::TowardNInf).to_f64(), b.sqrt(Round::TowardPInf).to_f64(), successor_bf16(b.sqrt(Round::TowardNInf)).to_f64()),
+            ] {
+                if x.is_nan() || x < 0. {
+                    assert!(down.is_nan() && up.is_nan(), "{:#06x}", bits);
+                } else if x.is_finite() && x > 0. {
+                    // Squares of 16-bit values are exact in f64
+                    assert!(down * down <= x && (up == down || up == next) && next * next > x, "{:#06x}", bits);
+                    assert_eq!(up == down, down * down == x, "{:#06x}", bits);
+                }
+            }
+
+            assert_eq!(h.sqrt(Round::TiesToEven).to_bits(), F16::from_f64(h.to_f64().sqrt(), Round::TiesToEven).to_bits(), "{:#06x}", bits);
+        }
+    }
+
+    #[test]
+    fn test_successor_exhaustive() {
+        // The neighbors of a 16-bit value are the f32 neighbors rounded outward
+        for bits in 0..=u16::MAX {
+            let (h, b) = (F16::from_bits(bits), BF16::from_bits(bits));
+
+            if h.is_nan() {
+                assert!(successor_f16(h).is_nan() && predecessor_f16(h).is_nan());
+            } else {
+                let f = h.to_f32();
+                assert_eq!(successor_f16(h).to_bits(), F16::from_f32(successor_f32(f), Round::TowardPInf).to_bits(), "{:#06x}", bits);
+                assert_eq!(predecessor_f16(h).to_bits(), F16::from_f32(predecessor_f32(f), Round::TowardNInf).to_bits(), "{:#06x}", bits);
+            }
+
+            if b.is_nan() {
+                assert!(successor_bf16(b).is_nan() && predecessor_bf16(b).is_nan());
+            } else {
+                let f = b.to_f32();
+                assert_eq!(successor_bf16(b).to_bits(), BF16::from_f32(successor_f32(f), Round::TowardPInf).to_bits(), "{:#06x}", bits);
+                assert_eq!(predecessor_bf16(b).to_bits(), BF16::from_f32(predecessor_f32(f), Round::TowardNInf).to_bits(), "{:#06x}", bits);
+            }
+
+            if h.is_finite() {
+                assert_eq!(predecessor_f16(successor_f16(h)), h);
+            }
+        }
+
+        assert_eq!(successor_f16(-F16::ZERO).to_bits(), 1);
+        assert_eq!(predecessor_f16(F16::ZERO).to_bits(), 0x8001);
+        assert_eq!(successor_bf16(BF16::MAX), BF16::INFINITY);
+        assert_eq!(predecessor_bf16(BF16::NEG_INFINITY), BF16::NEG_INFINITY);
+    }
+}
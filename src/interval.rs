@@ -0,0 +1,506 @@
+// Interval arithmetic on f64, in the set-based flavor of IEEE 1788-2015: an interval is a closed,
+// connected set of real numbers, possibly empty or unbounded, and every operation returns the
+// tightest interval of f64 bounds containing the image of its operands, with the lower bound
+// rounded toward -inf and the upper toward +inf by the operations of `ops`. Infinities are never
+// members, only bounds, so [0, 0] * [1, +inf] is [0, 0] and [1, 2] / [0, 1] is [1, +inf].
+//
+// Decorated intervals additionally record what is known about how a result came about, from
+// `com`, every operation was defined and continuous on bounded operands with a bounded result,
+// down to `trv`, nothing is known, e.g., after `sqrt([-1, 4])` silently dropped the negative part
+// of its argument. `ill` marks NaI, not an interval, from an invalid construction.
+//
+// The elementary functions of both kinds of interval are in the `functions` submodule.
+
+use std::cmp::min;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+use crate::modes::*;
+use crate::ops::{add_f64, div_f64, mul_f64, sub_f64};
+
+mod functions;
+
+/// A closed interval of real numbers with f64 bounds, or the empty set. Bounds may be infinite,
+/// making the interval unbounded on that side.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Interval {
+    // The empty set is (+inf, -inf), which the bound arithmetic below carries through on its own
+    lo: f64,
+    hi: f64,
+}
+
+impl Interval {
+    pub const EMPTY: Interval = Interval { lo: f64::INFINITY, hi: f64::NEG_INFINITY };
+    pub const ENTIRE: Interval = Interval { lo: f64::NEG_INFINITY, hi: f64::INFINITY };
+
+    /// The interval `[lo, hi]`, or `None` if that isn't one: a bound is NaN, `lo > hi`, or the
+    /// interval would hold nothing but an infinity.
+    pub fn new(lo: f64, hi: f64) -> Option<Interval> {
+        if lo <= hi && lo < f64::INFINITY && hi > f64::NEG_INFINITY {
+            Some(Interval { lo, hi })
+        } else {
+            None
+        }
+    }
+
+    /// Lower bound, +inf for the empty set. A zero bound is -0, as IEEE 1788 asks, so that
+    /// dividing by it keeps to the right side.
+    pub fn lo(self) -> f64 {
+        if self.lo == 0.0 { -0.0 } else { self.lo }
+    }
+
+    /// Upper bound, -inf for the empty set. A zero bound is +0.
+    pub fn hi(self) -> f64 {
+        if self.hi == 0.0 { 0.0 } else { self.hi }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.lo > self.hi
+    }
+
+    pub fn is_entire(self) -> bool {
+        self.lo == f64::NEG_INFINITY && self.hi == f64::INFINITY
+    }
+
+    /// Whether the interval is nonempty with both bounds finite. The empty set counts as bounded,
+    /// as in IEEE 1788.
+    pub fn is_bounded(self) -> bool {
+        self.is_empty() || (self.lo.is_finite() && self.hi.is_finite())
+    }
+
+    /// Whether `x` is a member. Infinities never are.
+    pub fn contains(self, x: f64) -> bool {
+        x.is_finite() && self.lo <= x && x <= self.hi
+    }
+
+    /// Whether every member of `self` is a member of `other`
+    pub fn is_subset(self, other: Interval) -> bool {
+        self.is_empty() || (other.lo <= self.lo && self.hi <= other.hi)
+    }
+
+    /// Smallest interval containing both
+    pub fn hull(self, other: Interval) -> Interval {
+        Interval { lo: self.lo.min(other.lo), hi: self.hi.max(other.hi) }
+    }
+
+    pub fn intersection(self, other: Interval) -> Interval {
+        let (lo, hi) = (self.lo.max(other.lo), self.hi.min(other.hi));
+        if lo > hi { Interval::EMPTY } else { Interval { lo, hi } }
+    }
+
+    /// Hull of the four endpoint combinations `op(x, y)`, rounded outward. Those that are NaN,
+    /// inf / inf, are skipped by `min` and `max`; the others already bound the range.
+    fn extremes(self, other: Interval, op: impl Fn(f64, f64, Round) -> f64) -> Interval {
+        let mut result = Interval::EMPTY;
+        for (x, y) in [(self.lo, other.lo), (self.lo, other.hi), (self.hi, other.lo), (self.hi, other.hi)] {
+            result.lo = result.lo.min(op(x, y, Round::TowardNInf));
+            result.hi = result.hi.max(op(x, y, Round::TowardPInf));
+        }
+
+        result
+    }
+}
+
+impl Neg for Interval {
+    type Output = Interval;
+
+    fn neg(self) -> Interval {
+        Interval { lo: -self.hi, hi: -self.lo }
+    }
+}
+
+impl Add for Interval {
+    type Output = Interval;
+
+    fn add(self, other: Interval) -> Interval {
+        if self.is_empty() || other.is_empty() {
+            return Interval::EMPTY;
+        }
+
+        // Lower bounds are never +inf and upper bounds never -inf, so neither sum is inf - inf
+        Interval { lo: add_f64(self.lo, other.lo, Round::TowardNInf), hi: add_f64(self.hi, other.hi, Round::TowardPInf) }
+    }
+}
+
+impl Sub for Interval {
+    type Output = Interval;
+
+    fn sub(self, other: Interval) -> Interval {
+        if self.is_empty() || other.is_empty() {
+            return Interval::EMPTY;
+        }
+
+        Interval { lo: sub_f64(self.lo, other.hi, Round::TowardNInf), hi: sub_f64(self.hi, other.lo, Round::TowardPInf) }
+    }
+}
+
+impl Mul for Interval {
+    type Output = Interval;
+
+    fn mul(self, other: Interval) -> Interval {
+        if self.is_empty() || other.is_empty() {
+            return Interval::EMPTY;
+        }
+
+        // A zero bound is a member, and zero times any member is zero, however large
+        self.extremes(other, |x, y, mode| if x == 0.0 || y == 0.0 { 0.0 } else { mul_f64(x, y, mode) })
+    }
+}
+
+impl Div for Interval {
+    type Output = Interval;
+
+    fn div(self, other: Interval) -> Interval {
+        if self.is_empty() || other.is_empty() || (other.lo == 0.0 && other.hi == 0.0) {
+            return Interval::EMPTY;
+        }
+        if other.lo > 0.0 || other.hi < 0.0 {
+            return self.extremes(other, div_f64);
+        }
+
+        // The divisor contains zero. Dividing zero alone still gives zero, but anything else
+        // grows without bound: toward both sides when either operand straddles zero, as the
+        // hull of two half-lines, and otherwise toward one, from the quotient by the divisor's
+        // bound away from zero, which is zero itself for a dividend touching zero
+        if self.lo == 0.0 && self.hi == 0.0 {
+            Interval { lo: 0.0, hi: 0.0 }
+        } else if (other.lo < 0.0 && other.hi > 0.0) || (self.lo < 0.0 && self.hi > 0.0) {
+            Interval::ENTIRE
+        } else if other.lo == 0.0 && self.hi <= 0.0 {
+            Interval { lo: f64::NEG_INFINITY, hi: div_f64(self.hi, other.hi, Round::TowardPInf) }
+        } else if other.lo == 0.0 {
+            Interval { lo: div_f64(self.lo, other.hi, Round::TowardNInf), hi: f64::INFINITY }
+        } else if self.hi <= 0.0 {
+            Interval { lo: div_f64(self.hi, other.lo, Round::TowardNInf), hi: f64::INFINITY }
+        } else {
+            Interval { lo: f64::NEG_INFINITY, hi: div_f64(self.lo, other.lo, Round::TowardPInf) }
+        }
+    }
+}
+
+/// What is known about the evaluation that produced a decorated interval, per IEEE 1788. Ordered
+/// from least to most informative, so the decoration of a result is the minimum of those of its
+/// operands and of the operation itself.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Decoration {
+    /// Ill-formed: not an interval at all
+    Ill,
+    /// Trivial: nothing is known
+    Trv,
+    /// Defined: every operation was defined on its operands
+    Def,
+    /// Defined and continuous: every operation was also continuous on its operands
+    Dac,
+    /// Common: as `Dac`, with every operand and result bounded as well
+    Com,
+}
+
+/// An interval with a decoration. Combinations IEEE 1788 rules out are never formed: the empty
+/// set is always `Trv`, unbounded intervals are at most `Dac`, and `Ill` goes only with NaI.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DecoratedInterval {
+    interval: Interval,
+    decoration: Decoration,
+}
+
+impl DecoratedInterval {
+    /// Not an interval, the result of invalid constructions, which propagates through everything
+    pub const NAI: DecoratedInterval = DecoratedInterval { interval: Interval::EMPTY, decoration: Decoration::Ill };
+    pub const EMPTY: DecoratedInterval = DecoratedInterval { interval: Interval::EMPTY, decoration: Decoration::Trv };
+
+    /// The interval `[lo, hi]`, `Com` if bounded and `Dac` otherwise, or NaI if it isn't one.
+    pub fn new(lo: f64, hi: f64) -> DecoratedInterval {
+        Interval::new(lo, hi).map_or(DecoratedInterval::NAI, DecoratedInterval::from)
+    }
+
+    /// `interval` with `decoration`, adjusted to the closest allowed combination: `Ill` gives
+    /// NaI, the empty set is `Trv` whatever is asked, and `Com` becomes `Dac` when unbounded.
+    pub fn set_dec(interval: Interval, decoration: Decoration) -> DecoratedInterval {
+        let decoration = match decoration {
+            Decoration::Ill => return DecoratedInterval::NAI,
+            _ if interval.is_empty() => Decoration::Trv,
+            Decoration::Com if !interval.is_bounded() => Decoration::Dac,
+            _ => decoration,
+        };
+
+        DecoratedInterval { interval, decoration }
+    }
+
+    /// The bare interval, empty for NaI
+    pub fn interval(self) -> Interval {
+        self.interval
+    }
+
+    pub fn decoration(self) -> Decoration {
+        self.decoration
+    }
+
+    pub fn is_nai(self) -> bool {
+        self.decoration == Decoration::Ill
+    }
+
+    /// Result of an operation on `operands` whose value on their intervals is `value`, with the
+    /// operation's own decoration on them, `Com` if it is defined and continuous there. The
+    /// operands' decorations carry over, and `set_dec` takes `Com` down to `Dac` if the value
+    /// overflowed.
+    pub(crate) fn decorate(operands: &[DecoratedInterval], value: Interval, local: Decoration) -> DecoratedInterval {
+        if operands.iter().any(|x| x.is_nai()) {
+            return DecoratedInterval::NAI;
+        }
+
+        DecoratedInterval::set_dec(value, operands.iter().map(|x| x.decoration).fold(local, min))
+    }
+}
+
+impl From<Interval> for DecoratedInterval {
+    /// The most informative decoration an interval can have on its own, as IEEE 1788's `newDec`
+    fn from(interval: Interval) -> DecoratedInterval {
+        DecoratedInterval::set_dec(interval, Decoration::Com)
+    }
+}
+
+impl Neg for DecoratedInterval {
+    type Output = DecoratedInterval;
+
+    fn neg(self) -> DecoratedInterval {
+        DecoratedInterval::decorate(&[self], -self.interval, Decoration::Com)
+    }
+}
+
+/// Defines a binary operator on decorated intervals through the bare one, with the operation's
+/// own decoration given as a function of the bare operands.
+macro_rules! decorated_op {
+    ($($trait:ident, $method:ident, |$a:ident, $b:ident| $local:expr;)*) => {$(
+        impl $trait for DecoratedInterval {
+            type Output = DecoratedInterval;
+
+            fn $method(self, other: DecoratedInterval) -> DecoratedInterval {
+                let ($a, $b) = (self.interval, other.interval);
+                DecoratedInterval::decorate(&[self, other], $a.$method($b), $local)
+            }
+        }
+    )*};
+}
+
+decorated_op! {
+    Add, add, |_a, _b| Decoration::Com;
+    Sub, sub, |_a, _b| Decoration::Com;
+    Mul, mul, |_a, _b| Decoration::Com;
+    // Undefined wherever the divisor is zero
+    Div, div, |_a, b| if b.contains(0.0) { Decoration::Trv } else { Decoration::Com };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oracle;
+    use crate::successor::*;
+    use crate::test_cases::*;
+    use Decoration::*;
+
+    const INF: f64 = f64::INFINITY;
+
+    fn iv(lo: f64, hi: f64) -> Interval {
+        Interval::new(lo, hi).unwrap()
+    }
+
+    fn dec(lo: f64, hi: f64, decoration: Decoration) -> DecoratedInterval {
+        DecoratedInterval::set_dec(iv(lo, hi), decoration)
+    }
+
+    #[test]
+    fn test_construction() {
+        assert_eq!(Interval::new(2.0, 1.0), None);
+        assert_eq!(Interval::new(f64::NAN, 1.0), None);
+        assert_eq!(Interval::new(INF, INF), None);
+        assert_eq!(Interval::new(-INF, -INF), None);
+        assert_eq!(Interval::new(-INF, INF), Some(Interval::ENTIRE));
+        assert_eq!(Interval::new(1.0, 1.0), Some(Interval { lo: 1.0, hi: 1.0 }));
+
+        assert!(identical_f64(iv(0.0, 0.0).lo(), -0.0));
+        assert!(identical_f64(iv(-0.0, -0.0).hi(), 0.0));
+        assert_eq!(Interval::EMPTY.lo(), INF);
+        assert_eq!(Interval::EMPTY.hi(), -INF);
+
+        assert!(Interval::EMPTY.is_empty() && Interval::EMPTY.is_bounded());
+        assert!(Interval::ENTIRE.is_entire() && !Interval::ENTIRE.is_bounded());
+        assert!(!iv(1.0, INF).contains(INF));
+        assert!(iv(1.0, INF).contains(f64::MAX));
+        assert!(Interval::EMPTY.is_subset(iv(1.0, 2.0)));
+        assert!(iv(1.0, 2.0).is_subset(iv(0.0, INF)));
+        assert!(!iv(-1.0, 2.0).is_subset(iv(0.0, INF)));
+
+        assert_eq!(iv(1.0, 2.0).hull(iv(4.0, 5.0)), iv(1.0, 5.0));
+        assert_eq!(iv(1.0, 2.0).hull(Interval::EMPTY), iv(1.0, 2.0));
+        assert_eq!(iv(1.0, 3.0).intersection(iv(2.0, 5.0)), iv(2.0, 3.0));
+        assert!(iv(1.0, 2.0).intersection(iv(4.0, 5.0)).is_empty());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(iv(1.0, 2.0) + iv(3.0, 4.0), iv(4.0, 6.0));
+        assert_eq!(iv(1.0, 2.0) - iv(3.0, 4.0), iv(-3.0, -1.0));
+        assert_eq!(-iv(1.0, INF), iv(-INF, -1.0));
+        assert_eq!(iv(f64::MAX, f64::MAX) + iv(f64::MAX, f64::MAX), iv(f64::MAX, INF));
+        assert!((Interval::EMPTY + Interval::ENTIRE).is_empty());
+
+        let sum = iv(0.1, 0.1) + iv(0.2, 0.2);
+        assert_eq!(sum.hi, successor_f64(sum.lo));
+        assert!(sum.contains(0.1 + 0.2));
+
+        assert_eq!(iv(-1.0, 2.0) * iv(-3.0, 4.0), iv(-6.0, 8.0));
+        assert_eq!(iv(0.0, 0.0) * Interval::ENTIRE, iv(0.0, 0.0));
+        assert_eq!(iv(0.0, 1.0) * iv(1.0, INF), iv(0.0, INF));
+        assert_eq!(iv(-1.0, 1.0) * iv(1.0, INF), Interval::ENTIRE);
+        assert!((Interval::EMPTY * iv(0.0, 0.0)).is_empty());
+
+        assert_eq!(iv(1.0, 2.0) / iv(4.0, 8.0), iv(0.125, 0.5));
+        assert_eq!(iv(1.0, INF) / iv(1.0, INF), iv(0.0, INF));
+        assert_eq!(Interval::ENTIRE / iv(1.0, INF), Interval::ENTIRE);
+        assert!((iv(1.0, 2.0) / iv(0.0, 0.0)).is_empty());
+        assert_eq!(iv(1.0, 2.0) / iv(0.0, 4.0), iv(0.25, INF));
+        assert_eq!(iv(1.0, 2.0) / iv(-4.0, 0.0), iv(-INF, -0.25));
+        assert_eq!(iv(-2.0, -1.0) / iv(0.0, 4.0), iv(-INF, -0.25));
+        assert_eq!(iv(-2.0, -1.0) / iv(-4.0, 0.0), iv(0.25, INF));
+        assert_eq!(iv(1.0, 2.0) / iv(-1.0, 1.0), Interval::ENTIRE);
+        assert_eq!(iv(0.0, 2.0) / iv(1.0, 1.0), iv(0.0, 2.0));
+        assert_eq!(iv(0.0, 2.0) / iv(0.0, 1.0), iv(0.0, INF));
+        assert_eq!(iv(-2.0, 0.0) / iv(0.0, 1.0), iv(-INF, 0.0));
+        assert_eq!(iv(0.0, 2.0) / iv(-1.0, 0.0), iv(-INF, 0.0));
+        assert_eq!(iv(-2.0, 0.0) / iv(-1.0, 0.0), iv(0.0, INF));
+        assert_eq!(iv(0.0, 2.0) / iv(-1.0, 1.0), Interval::ENTIRE);
+        assert_eq!(iv(-1.0, 2.0) / iv(0.0, 1.0), Interval::ENTIRE);
+        assert_eq!(iv(0.0, 0.0) / iv(-1.0, 1.0), iv(0.0, 0.0));
+        assert_eq!(iv(0.0, 0.0) / iv(0.0, 1.0), iv(0.0, 0.0));
+        assert!((iv(0.0, 0.0) / iv(0.0, 0.0)).is_empty());
+        assert_eq!(iv(-2.0, -1.0) / iv(0.0, INF), iv(-INF, 0.0));
+        assert_eq!(iv(1.0, 2.0) / iv(-INF, 0.0), iv(-INF, 0.0));
+
+        assert_eq!(iv(-1.0, 4.0).sqrt(), iv(0.0, 2.0));
+        assert_eq!(iv(4.0, INF).sqrt(), iv(2.0, INF));
+        assert!(iv(-2.0, -1.0).sqrt().is_empty());
+        let root = iv(2.0, 2.0).sqrt();
+        assert_eq!(root.hi, successor_f64(root.lo));
+    }
+
+    /// Random bound, either a modest value or, now and then, an extreme one
+    fn random_bound(rng: &mut SplitMix64) -> f64 {
+        let sign = if rng.coin() { -1.0 } else { 1.0 };
+        match rng.range(0, 9) {
+            0 => 0.0,
+            1 => INF * sign,
+            2 => f64::MAX * sign,
+            3 => f64::from_bits(rng.mant(20)) * sign,
+            _ => rng.significand_f64() * 2f64.powi(rng.range(-30, 30)) * sign,
+        }
+    }
+
+    fn random_interval(rng: &mut SplitMix64) -> Interval {
+        loop {
+            let (a, b) = (random_bound(rng), random_bound(rng));
+            if let Some(x) = Interval::new(a.min(b), a.max(b)) {
+                return x;
+            }
+        }
+    }
+
+    /// A member of a nonempty interval: a bound, or a point in between
+    fn random_member(rng: &mut SplitMix64, x: Interval) -> f64 {
+        let lo = if x.lo.is_finite() { x.lo } else { -f64::MAX };
+        let hi = if x.hi.is_finite() { x.hi } else { f64::MAX };
+        match rng.range(0, 2) {
+            0 => lo,
+            1 => hi,
+            _ => (lo + (hi - lo) * (rng.next_u64() >> 11) as f64 * 2f64.powi(-53)).clamp(lo, hi),
+        }
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = SplitMix64::new(0x1788);
+        type BareOp = fn(Interval, Interval) -> Interval;
+        type PointOp = fn(f64, f64, Round) -> f64;
+        let cases: [(BareOp, PointOp); 4] = [
+            (|a, b| a + b, oracle::add_f64),
+            (|a, b| a - b, oracle::sub_f64),
+            (|a, b| a * b, oracle::mul_f64),
+            (|a, b| a / b, oracle::div_f64),
+        ];
+
+        for _ in 0..20000 {
+            let (a, b) = (random_interval(&mut rng), random_interval(&mut rng));
+            for (op, exact) in cases {
+                let result = op(a, b);
+                assert!(result.is_empty() || Interval::new(result.lo, result.hi).is_some(), "{:?} {:?}", a, b);
+
+                // Every exact result for members lies within, after rounding outward
+                let (x, y) = (random_member(&mut rng, a), random_member(&mut rng, b));
+                let (down, up) = (exact(x, y, Round::TowardNInf), exact(x, y, Round::TowardPInf));
+                if !down.is_nan() && (down.is_finite() || up.is_finite()) {
+                    assert!(result.lo <= up && down <= result.hi, "{:?} {:?} {} {} {:?}", a, b, x, y, result);
+                }
+
+                // The bounds themselves are attained, to within rounding, when the operands are
+                // bounded away from the troublesome zeros
+                if a.is_bounded() && b.is_bounded() && !b.contains(0.0) {
+                    let corners = [(a.lo, b.lo), (a.lo, b.hi), (a.hi, b.lo), (a.hi, b.hi)];
+                    assert!(corners.iter().any(|&(x, y)| exact(x, y, Round::TowardNInf) == result.lo));
+                    assert!(corners.iter().any(|&(x, y)| exact(x, y, Round::TowardPInf) == result.hi));
+                }
+            }
+
+            let root = a.sqrt();
+            if a.hi >= 0.0 {
+                let x = random_member(&mut rng, a).max(0.0);
+                assert!(root.lo <= crate::fallback::sqrt_f64(x, Round::TowardPInf) && crate::fallback::sqrt_f64(x, Round::TowardNInf) <= root.hi);
+            } else {
+                assert!(root.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_decorations() {
+        assert!(Ill < Trv && Trv < Def && Def < Dac && Dac < Com);
+
+        assert_eq!(DecoratedInterval::new(1.0, 2.0).decoration(), Com);
+        assert_eq!(DecoratedInterval::new(1.0, INF).decoration(), Dac);
+        assert!(DecoratedInterval::new(2.0, 1.0).is_nai());
+        assert_eq!(DecoratedInterval::from(Interval::EMPTY), DecoratedInterval::EMPTY);
+        assert_eq!(DecoratedInterval::set_dec(Interval::EMPTY, Com).decoration(), Trv);
+        assert_eq!(DecoratedInterval::set_dec(Interval::ENTIRE, Com).decoration(), Dac);
+        assert_eq!(DecoratedInterval::set_dec(iv(1.0, 2.0), Def).decoration(), Def);
+        assert!(DecoratedInterval::set_dec(iv(1.0, 2.0), Ill).is_nai());
+        assert_eq!(DecoratedInterval::NAI.interval(), Interval::EMPTY);
+
+        // Defined and continuous everywhere, common as long as everything stays bounded
+        let (a, b) = (DecoratedInterval::new(1.0, 2.0), DecoratedInterval::new(3.0, 4.0));
+        assert_eq!(a + b, dec(4.0, 6.0, Com));
+        assert_eq!(a - b, dec(-3.0, -1.0, Com));
+        assert_eq!(a * b, dec(3.0, 8.0, Com));
+        assert_eq!(-a, dec(-2.0, -1.0, Com));
+        assert_eq!(a + DecoratedInterval::new(1.0, INF), dec(2.0, INF, Dac));
+        let max = DecoratedInterval::new(f64::MAX, f64::MAX);
+        assert_eq!(max + max, dec(f64::MAX, INF, Dac));
+        assert_eq!(max * max * DecoratedInterval::new(0.0, 0.0), dec(0.0, 0.0, Dac));
+
+        // Domain violations
+        assert_eq!(DecoratedInterval::new(-1.0, 4.0).sqrt(), dec(0.0, 2.0, Trv));
+        assert_eq!(DecoratedInterval::new(0.0, 4.0).sqrt(), dec(0.0, 2.0, Com));
+        assert_eq!(DecoratedInterval::new(-2.0, -1.0).sqrt(), DecoratedInterval::EMPTY);
+        assert_eq!(a / b, dec(0.25, div_f64(2.0, 3.0, Round::TowardPInf), Com));
+        assert_eq!(a / DecoratedInterval::new(0.0, 1.0), dec(1.0, INF, Trv));
+        assert_eq!(a / DecoratedInterval::new(0.0, 0.0), DecoratedInterval::EMPTY);
+
+        // Decorations only ever decrease along a computation
+        let trv = DecoratedInterval::new(-1.0, 4.0).sqrt();
+        assert_eq!((trv + b).decoration(), Trv);
+        assert_eq!(DecoratedInterval::set_dec(iv(1.0, 2.0), Def) * b, dec(3.0, 8.0, Def));
+
+        // NaI swallows everything, even the empty set
+        let nai = DecoratedInterval::NAI;
+        for x in [a, DecoratedInterval::EMPTY, nai] {
+            assert!((nai + x).is_nai() && (x - nai).is_nai() && (nai * x).is_nai() && (x / nai).is_nai());
+        }
+        assert!((-nai).is_nai() && nai.sqrt().is_nai());
+        assert_eq!(DecoratedInterval::EMPTY + a, DecoratedInterval::EMPTY);
+    }
+}
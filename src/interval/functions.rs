@@ -0,0 +1,516 @@
+// Elementary functions of intervals, each bound computed by the correctly rounded functions of
+// `elementary` in the direction it needs, so that every result is the tightest enclosure of the
+// image. Monotonic functions only need their bounds evaluated; sin, cos and tan also find which
+// multiples of π/2 the interval contains, exactly, from the quadrants of its bounds, to place
+// their extrema and poles. Functions with a restricted domain, log, sqrt, pow and negative powi,
+// take the part of their argument inside it, and their decorated versions drop to `Trv` when
+// there was any other part.
+
+use std::f64::consts::FRAC_PI_2;
+use crate::elementary::{atan_round, cos_round, exp_round, floor_quadrant, log_round, pow_round, sin_round, tan_round};
+use crate::modes::*;
+use crate::ops::{mul_f64, sqrt_f64};
+use super::{Decoration, DecoratedInterval, Interval};
+
+/// [0, +inf], the domain of sqrt and log, closed for convenience
+const NONNEGATIVE: Interval = Interval { lo: 0.0, hi: f64::INFINITY };
+
+impl Interval {
+    pub fn exp(self) -> Interval {
+        self.increasing(exp_round)
+    }
+
+    /// Natural logarithm of the positive part, empty if there is none. A lower bound of zero
+    /// gives -inf.
+    pub fn log(self) -> Interval {
+        let x = self.nonnegative();
+        if x.is_empty() || x.hi == 0.0 {
+            return Interval::EMPTY;
+        }
+
+        x.increasing(log_round)
+    }
+
+    /// Square root of the nonnegative part, empty if there is none.
+    pub fn sqrt(self) -> Interval {
+        self.nonnegative().increasing(sqrt_f64)
+    }
+
+    /// `self^y` on the nonnegative part of `self`, over the domain of IEEE 1788's pow: x > 0, or
+    /// x = 0 with y > 0.
+    pub fn pow(self, y: Interval) -> Interval {
+        let x = self.nonnegative();
+        if x.is_empty() || y.is_empty() {
+            return Interval::EMPTY;
+        } else if x.hi == 0.0 {
+            // Only 0^y for y > 0
+            return if y.hi > 0.0 { Interval { lo: 0.0, hi: 0.0 } } else { Interval::EMPTY };
+        }
+
+        // x^y = e^(y log x), and y log x is bilinear in y and log x, so the extremes lie at the
+        // corners. pow's own values there are the limits within the domain: 0^y is 0 for y > 0,
+        // +inf for y < 0, and 1 for y = 0, the value of x^0 at every x > 0.
+        x.extremes(y, pow_round)
+    }
+
+    /// Integer power, as IEEE 1788's pown: x^0 is 1 even for x = 0, and negative powers take the
+    /// part of `self` other than zero.
+    pub fn powi(self, n: i32) -> Interval {
+        let pown = |x: f64, mode: Round| pow_round(x, n as f64, mode);
+        if self.is_empty() {
+            return Interval::EMPTY;
+        } else if n == 0 {
+            return Interval { lo: 1.0, hi: 1.0 };
+        } else if n > 0 && n % 2 != 0 {
+            return self.increasing(pown);
+        } else if n > 0 {
+            return Interval { lo: pown(self.mig(), Round::TowardNInf), hi: pown(self.mag(), Round::TowardPInf) };
+        }
+
+        // Negative powers, falling away from zero on either side, with a pole there
+        let (lo, hi) = (self.lo, self.hi);
+        if lo == 0.0 && hi == 0.0 {
+            Interval::EMPTY
+        } else if n % 2 == 0 && self.contains(0.0) {
+            Interval { lo: pown(self.mag(), Round::TowardNInf), hi: f64::INFINITY }
+        } else if n % 2 == 0 {
+            Interval { lo: pown(self.mag(), Round::TowardNInf), hi: pown(self.mig(), Round::TowardPInf) }
+        } else if lo > 0.0 || hi < 0.0 {
+            Interval { lo: pown(hi, Round::TowardNInf), hi: pown(lo, Round::TowardPInf) }
+        } else if lo == 0.0 {
+            Interval { lo: pown(hi, Round::TowardNInf), hi: f64::INFINITY }
+        } else if hi == 0.0 {
+            Interval { lo: f64::NEG_INFINITY, hi: pown(lo, Round::TowardPInf) }
+        } else {
+            Interval::ENTIRE
+        }
+    }
+
+    /// Square, tighter than `self * self`, which doesn't know both factors are the same member.
+    pub fn sqr(self) -> Interval {
+        if self.is_empty() {
+            return Interval::EMPTY;
+        }
+
+        let (mig, mag) = (self.mig(), self.mag());
+        Interval { lo: mul_f64(mig, mig, Round::TowardNInf), hi: mul_f64(mag, mag, Round::TowardPInf) }
+    }
+
+    pub fn abs(self) -> Interval {
+        if self.is_empty() {
+            return Interval::EMPTY;
+        }
+
+        Interval { lo: self.mig(), hi: self.mag() }
+    }
+
+    /// Pointwise minimum, `min(x, y)` for every x in `self` and y in `other`
+    pub fn min(self, other: Interval) -> Interval {
+        if self.is_empty() || other.is_empty() {
+            return Interval::EMPTY;
+        }
+
+        Interval { lo: self.lo.min(other.lo), hi: self.hi.min(other.hi) }
+    }
+
+    /// Pointwise maximum, `max(x, y)` for every x in `self` and y in `other`
+    pub fn max(self, other: Interval) -> Interval {
+        if self.is_empty() || other.is_empty() {
+            return Interval::EMPTY;
+        }
+
+        Interval { lo: self.lo.max(other.lo), hi: self.hi.max(other.hi) }
+    }
+
+    /// Sine, which peaks where cos crosses zero going down, at the start of quadrant 1.
+    pub fn sin(self) -> Interval {
+        self.periodic(sin_round, 1)
+    }
+
+    /// Cosine, which peaks at the start of quadrant 0.
+    pub fn cos(self) -> Interval {
+        self.periodic(cos_round, 0)
+    }
+
+    /// Tangent, everything if the interval contains a pole, at the start of an odd quadrant.
+    pub fn tan(self) -> Interval {
+        if self.is_empty() {
+            return Interval::EMPTY;
+        } else if !self.is_bounded() {
+            return Interval::ENTIRE;
+        }
+
+        match self.quadrants() {
+            Some((_, 0)) => self.increasing(tan_round),
+            Some((quadrant, 1)) if quadrant % 2 != 0 => self.increasing(tan_round),
+            _ => Interval::ENTIRE,
+        }
+    }
+
+    pub fn atan(self) -> Interval {
+        self.increasing(atan_round)
+    }
+
+    /// Smallest magnitude of a member
+    fn mig(self) -> f64 {
+        if self.contains(0.0) { 0.0 } else { self.lo.abs().min(self.hi.abs()) }
+    }
+
+    /// Largest magnitude of a member
+    fn mag(self) -> f64 {
+        self.lo.abs().max(self.hi.abs())
+    }
+
+    /// The part in [0, +inf], with a zero lower bound made +0 so that functions of it take their
+    /// limit from above
+    fn nonnegative(self) -> Interval {
+        let x = self.intersection(NONNEGATIVE);
+        if x.lo == 0.0 { Interval { lo: 0.0, ..x } } else { x }
+    }
+
+    /// Image under a nondecreasing function, given correctly rounded, including at infinities
+    fn increasing(self, f: impl Fn(f64, Round) -> f64) -> Interval {
+        if self.is_empty() {
+            return Interval::EMPTY;
+        }
+
+        Interval { lo: f(self.lo, Round::TowardNInf), hi: f(self.hi, Round::TowardPInf) }
+    }
+
+    /// Quadrant of the lower bound of a bounded, nonempty interval and the number of multiples of
+    /// π/2 the interval contains, or `None` if it could contain a whole period of four of them.
+    fn quadrants(self) -> Option<(u32, u32)> {
+        let width = self.hi - self.lo;
+        if width >= 7.0 {
+            return None;
+        }
+
+        // The count mod 4 is exact, and n multiples need a width between (n - 1) π/2 and
+        // (n + 1) π/2, so the width, even rounded, tells n from n + 4
+        let quadrant = floor_quadrant(self.lo);
+        let mut count = (floor_quadrant(self.hi) + 4 - quadrant) % 4;
+        if width > (count + 2) as f64 * FRAC_PI_2 {
+            count += 4;
+        }
+
+        if count >= 4 { None } else { Some((quadrant, count)) }
+    }
+
+    /// Image under sin or cos, whose maximum is at the start of quadrant `peak` and minimum two
+    /// quadrants on, and which is monotonic in between
+    fn periodic(self, f: impl Fn(f64, Round) -> f64, peak: u32) -> Interval {
+        const UNIT: Interval = Interval { lo: -1.0, hi: 1.0 };
+        if self.is_empty() {
+            return Interval::EMPTY;
+        } else if !self.is_bounded() {
+            return UNIT;
+        }
+
+        let Some((quadrant, count)) = self.quadrants() else {
+            return UNIT;
+        };
+
+        let mut result = Interval {
+            lo: f(self.lo, Round::TowardNInf).min(f(self.hi, Round::TowardNInf)),
+            hi: f(self.lo, Round::TowardPInf).max(f(self.hi, Round::TowardPInf)),
+        };
+        for k in 1..=count {
+            match (quadrant + k) % 4 {
+                q if q == peak => result.hi = 1.0,
+                q if q == (peak + 2) % 4 => result.lo = -1.0,
+                _ => {}
+            }
+        }
+
+        result
+    }
+}
+
+/// Defines decorated versions of unary functions of intervals, with the function's own decoration
+/// given in terms of its bare argument and value.
+macro_rules! decorated_unary {
+    ($($name:ident, |$x:ident, $value:ident| $local:expr;)*) => {$(
+        pub fn $name(self) -> DecoratedInterval {
+            let $x = self.interval;
+            let $value = $x.$name();
+            DecoratedInterval::decorate(&[self], $value, $local)
+        }
+    )*};
+}
+
+/// The elementary functions on decorated intervals, `Com` where defined and continuous on the
+/// whole argument and `Trv` where the bare function dropped part of it.
+impl DecoratedInterval {
+    decorated_unary! {
+        exp, |_x, _value| Decoration::Com;
+        log, |x, _value| if x.lo > 0.0 { Decoration::Com } else { Decoration::Trv };
+        sqrt, |x, _value| if x.is_subset(NONNEGATIVE) { Decoration::Com } else { Decoration::Trv };
+        sqr, |_x, _value| Decoration::Com;
+        abs, |_x, _value| Decoration::Com;
+        sin, |_x, _value| Decoration::Com;
+        cos, |_x, _value| Decoration::Com;
+        // Only unbounded in the presence of a pole, since the bounds are finite otherwise
+        tan, |_x, value| if value.is_entire() { Decoration::Trv } else { Decoration::Com };
+        atan, |_x, _value| Decoration::Com;
+    }
+
+    /// Decorated `pow`, defined and continuous where x > 0, or x >= 0 with y > 0.
+    pub fn pow(self, y: DecoratedInterval) -> DecoratedInterval {
+        let (a, b) = (self.interval, y.interval);
+        let defined = a.lo > 0.0 || (a.lo >= 0.0 && b.lo > 0.0);
+        DecoratedInterval::decorate(&[self, y], a.pow(b), if defined { Decoration::Com } else { Decoration::Trv })
+    }
+
+    /// Decorated `powi`, undefined at zero for negative powers.
+    pub fn powi(self, n: i32) -> DecoratedInterval {
+        let defined = n >= 0 || !self.interval.contains(0.0);
+        DecoratedInterval::decorate(&[self], self.interval.powi(n), if defined { Decoration::Com } else { Decoration::Trv })
+    }
+
+    pub fn min(self, other: DecoratedInterval) -> DecoratedInterval {
+        DecoratedInterval::decorate(&[self, other], self.interval.min(other.interval), Decoration::Com)
+    }
+
+    pub fn max(self, other: DecoratedInterval) -> DecoratedInterval {
+        DecoratedInterval::decorate(&[self, other], self.interval.max(other.interval), Decoration::Com)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{E, PI};
+    use crate::successor::*;
+    use crate::test_cases::*;
+    use Decoration::*;
+
+    const INF: f64 = f64::INFINITY;
+
+    fn iv(lo: f64, hi: f64) -> Interval {
+        Interval::new(lo, hi).unwrap()
+    }
+
+    fn dec(lo: f64, hi: f64, decoration: Decoration) -> DecoratedInterval {
+        DecoratedInterval::set_dec(iv(lo, hi), decoration)
+    }
+
+    /// The interval from f(lo) rounded down to f(hi) rounded up
+    fn outward(f: fn(f64, Round) -> f64, lo: f64, hi: f64) -> Interval {
+        iv(f(lo, Round::TowardNInf), f(hi, Round::TowardPInf))
+    }
+
+    #[test]
+    fn test_monotonic() {
+        assert_eq!(iv(0.0, 1.0).exp(), iv(1.0, successor_f64(E)));
+        assert_eq!(Interval::ENTIRE.exp(), iv(0.0, INF));
+        assert_eq!(iv(700.0, 710.0).exp(), outward(exp_round, 700.0, 710.0));
+        assert_eq!(iv(700.0, 710.0).exp().hi(), INF);
+
+        assert_eq!(iv(1.0, E).log(), iv(0.0, 1.0));
+        assert_eq!(iv(-1.0, 1.0).log(), iv(-INF, 0.0));
+        assert_eq!(iv(0.0, INF).log(), Interval::ENTIRE);
+        assert!(iv(-1.0, 0.0).log().is_empty());
+        assert!(Interval::EMPTY.log().is_empty());
+
+        assert_eq!(iv(-1.0, 4.0).sqrt(), iv(0.0, 2.0));
+        assert_eq!(iv(4.0, INF).sqrt(), iv(2.0, INF));
+        assert!(iv(-2.0, -1.0).sqrt().is_empty());
+        assert_eq!(iv(2.0, 2.0).sqrt(), outward(sqrt_f64, 2.0, 2.0));
+
+        assert_eq!(Interval::ENTIRE.atan(), outward(atan_round, -INF, INF));
+        assert_eq!(iv(-1.0, 1.0).atan(), outward(atan_round, -1.0, 1.0));
+
+        assert_eq!(iv(-3.0, 2.0).abs(), iv(0.0, 3.0));
+        assert_eq!(iv(-3.0, -2.0).abs(), iv(2.0, 3.0));
+        assert_eq!(iv(-INF, 1.0).abs(), iv(0.0, INF));
+        assert_eq!(iv(-3.0, 2.0).sqr(), iv(0.0, 9.0));
+        assert_eq!(iv(-3.0, -2.0).sqr(), iv(4.0, 9.0));
+        assert_eq!(iv(-3.0, 2.0) * iv(-3.0, 2.0), iv(-6.0, 9.0));
+        assert_eq!(iv(0.1, 0.1).sqr(), iv(mul_f64(0.1, 0.1, Round::TowardNInf), mul_f64(0.1, 0.1, Round::TowardPInf)));
+
+        assert_eq!(iv(1.0, 4.0).min(iv(2.0, 3.0)), iv(1.0, 3.0));
+        assert_eq!(iv(1.0, 4.0).max(iv(2.0, 3.0)), iv(2.0, 4.0));
+        assert_eq!(iv(1.0, 4.0).min(iv(-INF, 0.0)), iv(-INF, 0.0));
+        assert!(iv(1.0, 4.0).max(Interval::EMPTY).is_empty());
+    }
+
+    #[test]
+    fn test_powers() {
+        assert_eq!(iv(-2.0, 3.0).powi(0), iv(1.0, 1.0));
+        assert_eq!(iv(0.0, 0.0).powi(0), iv(1.0, 1.0));
+        assert_eq!(iv(-2.0, 3.0).powi(2), iv(0.0, 9.0));
+        assert_eq!(iv(-2.0, 3.0).powi(3), iv(-8.0, 27.0));
+        assert_eq!(iv(-3.0, -2.0).powi(4), iv(16.0, 81.0));
+        assert_eq!(iv(-INF, -2.0).powi(3), iv(-INF, -8.0));
+        assert_eq!(iv(1.1, 1.1).powi(5), outward(|x, mode| pow_round(x, 5.0, mode), 1.1, 1.1));
+
+        assert_eq!(iv(2.0, 4.0).powi(-1), iv(0.25, 0.5));
+        assert_eq!(iv(-2.0, -1.0).powi(-1), iv(-1.0, -0.5));
+        assert_eq!(iv(0.0, 2.0).powi(-1), iv(0.5, INF));
+        assert_eq!(iv(-2.0, 0.0).powi(-1), iv(-INF, -0.5));
+        assert_eq!(iv(-1.0, 1.0).powi(-1), Interval::ENTIRE);
+        assert_eq!(iv(-2.0, 4.0).powi(-2), iv(0.0625, INF));
+        assert_eq!(iv(-4.0, -2.0).powi(-2), iv(0.0625, 0.25));
+        assert_eq!(iv(-INF, -2.0).powi(-2), iv(0.0, 0.25));
+        assert!(iv(0.0, 0.0).powi(-2).is_empty());
+        assert!(iv(-0.0, 0.0).powi(-3).is_empty());
+
+        assert_eq!(iv(2.0, 4.0).pow(iv(-1.0, 2.0)), iv(0.25, 16.0));
+        assert_eq!(iv(0.25, 4.0).pow(iv(-0.5, 0.5)), iv(0.5, 2.0));
+        assert_eq!(iv(-1.0, 4.0).pow(iv(0.5, 0.5)), iv(0.0, 2.0));
+        assert_eq!(iv(-0.0, 2.0).pow(iv(-1.0, 1.0)), iv(0.0, INF));
+        assert_eq!(iv(0.0, 2.0).pow(iv(0.0, 0.0)), iv(1.0, 1.0));
+        assert_eq!(iv(0.0, 0.5).pow(iv(-1.0, 0.0)), iv(1.0, INF));
+        assert_eq!(iv(0.0, 0.0).pow(iv(-1.0, 2.0)), iv(0.0, 0.0));
+        assert!(iv(0.0, 0.0).pow(iv(-1.0, 0.0)).is_empty());
+        assert!(iv(-2.0, -1.0).pow(iv(1.0, 2.0)).is_empty());
+        assert_eq!(iv(0.5, 2.0).pow(Interval::ENTIRE), iv(0.0, INF));
+        assert_eq!(iv(1.0, 1.0).pow(Interval::ENTIRE), iv(1.0, 1.0));
+        assert_eq!(iv(2.0, 2.0).pow(iv(0.5, 0.5)), outward(sqrt_f64, 2.0, 2.0));
+    }
+
+    #[test]
+    fn test_trigonometric() {
+        let unit = iv(-1.0, 1.0);
+        assert_eq!(iv(0.1, 0.2).sin(), outward(sin_round, 0.1, 0.2));
+        assert_eq!(iv(1.0, 2.0).sin(), iv(sin_round(1.0, Round::TowardNInf), 1.0));
+        assert_eq!(iv(4.0, 5.0).sin(), iv(-1.0, sin_round(4.0, Round::TowardPInf)));
+        assert_eq!(iv(-2.0, -1.0).sin(), iv(-1.0, sin_round(-1.0, Round::TowardPInf)));
+        assert_eq!(iv(1.0, 5.0).sin(), unit);
+        assert_eq!(iv(0.0, 7.0).sin(), unit);
+        assert_eq!(iv(0.0, INF).sin(), unit);
+        assert_eq!(iv(1e300, 1e300).sin(), outward(sin_round, 1e300, 1e300));
+        assert_eq!(iv(1e300, successor_f64(1e300)).sin(), unit);
+        assert!(Interval::EMPTY.sin().is_empty());
+
+        assert_eq!(iv(-1.0, 1.0).cos(), iv(cos_round(1.0, Round::TowardNInf), 1.0));
+        assert_eq!(iv(3.0, 4.0).cos(), iv(-1.0, cos_round(4.0, Round::TowardPInf)));
+        assert_eq!(iv(PI, PI).cos(), outward(cos_round, PI, PI));
+        assert_eq!(iv(0.5, 1.5).cos(), outward(cos_round, 1.5, 0.5));
+        assert_eq!(iv(-1.0, 5.0).cos(), unit);
+
+        // Three multiples of π/2, taking in both extremes of sin but only the minimum of cos
+        assert_eq!(iv(0.2, 6.2).cos(), iv(-1.0, cos_round(6.2, Round::TowardPInf)));
+        assert_eq!(iv(0.2, 6.2).sin(), unit);
+
+        assert_eq!(iv(-1.0, 1.0).tan(), outward(tan_round, -1.0, 1.0));
+        assert_eq!(iv(2.0, 4.0).tan(), outward(tan_round, 2.0, 4.0));
+        assert_eq!(iv(1.0, 2.0).tan(), Interval::ENTIRE);
+        assert_eq!(iv(3.0, 5.0).tan(), Interval::ENTIRE);
+        assert_eq!(iv(-2.0, -1.0).tan(), Interval::ENTIRE);
+        assert_eq!(iv(1.0, 1.0).tan(), outward(tan_round, 1.0, 1.0));
+        assert_eq!(iv(-INF, 0.0).tan(), Interval::ENTIRE);
+
+        // Either side of π/2, whose f64 lies below it
+        let half_pi = FRAC_PI_2;
+        assert_eq!(iv(1.5, half_pi).tan(), outward(tan_round, 1.5, half_pi));
+        assert_eq!(iv(1.5, successor_f64(half_pi)).tan(), Interval::ENTIRE);
+        assert_eq!(iv(1.5, half_pi).sin(), outward(sin_round, 1.5, half_pi));
+        assert_eq!(iv(1.5, successor_f64(half_pi)).sin().hi, 1.0);
+    }
+
+    /// Random interval on a scale set by `spread`, now and then with an infinite bound, and a
+    /// member of it
+    fn random_case(rng: &mut SplitMix64, spread: i32) -> (Interval, f64) {
+        let mut point = || rng.significand_f64() * 2f64.powi(rng.range(-spread, spread)) * if rng.coin() { -1.0 } else { 1.0 };
+        let (a, b, c) = (point(), point(), point());
+        let mut sorted = [a, b, c];
+        sorted.sort_by(f64::total_cmp);
+
+        let lo = if rng.range(0, 19) == 0 { -INF } else { sorted[0] };
+        let hi = if rng.range(0, 19) == 0 { INF } else { sorted[2] };
+        (iv(lo, hi), sorted[1])
+    }
+
+    #[test]
+    fn test_random() {
+        type Function = (&'static str, fn(Interval) -> Interval, fn(f64, Round) -> f64);
+        let functions: [Function; 9] = [
+            ("exp", Interval::exp, exp_round),
+            ("log", Interval::log, log_round),
+            ("sqrt", Interval::sqrt, sqrt_f64),
+            ("sqr", Interval::sqr, |x, mode| mul_f64(x, x, mode)),
+            ("sin", Interval::sin, sin_round),
+            ("cos", Interval::cos, cos_round),
+            ("tan", Interval::tan, tan_round),
+            ("atan", Interval::atan, atan_round),
+            ("cube", |x| x.powi(-3), |x, mode| pow_round(x, -3.0, mode)),
+        ];
+
+        let mut rng = SplitMix64::new(SEED);
+        for _ in 0..2000 {
+            for (name, f, exact) in functions {
+                let spread = if rng.coin() { 3 } else { 12 };
+                let (x, member) = random_case(&mut rng, spread);
+                let result = f(x);
+
+                // The value at any member in the domain is enclosed
+                let (down, up) = (exact(member, Round::TowardNInf), exact(member, Round::TowardPInf));
+                if !down.is_nan() && down.is_finite() {
+                    assert!(result.lo <= up && down <= result.hi, "{} {:?} {} {:?}", name, x, member, result);
+                }
+
+                // and, for bounded arguments, the bounds of the periodic functions are attained
+                // to within the resolution of a scan, whenever they are finite
+                if x.is_bounded() && x.hi - x.lo < 8.0 && matches!(name, "sin" | "cos" | "tan") && !result.is_entire() {
+                    let (mut lo, mut hi) = (INF, -INF);
+                    for k in 0..=512 {
+                        let t = if k == 512 { x.hi } else { (x.lo + (x.hi - x.lo) * k as f64 / 512.0).clamp(x.lo, x.hi) };
+                        let y = exact(t, Round::TiesToEven);
+                        (lo, hi) = (lo.min(y), hi.max(y));
+                    }
+                    assert!(lo - result.lo < 1e-4 && result.hi - hi < 1e-4, "{} {:?} {:?} {} {}", name, x, result, lo, hi);
+                }
+            }
+
+            // pow, over a member of each argument
+            let ((x, a), (y, b)) = (random_case(&mut rng, 3), random_case(&mut rng, 3));
+            let result = x.pow(y);
+            if a > 0.0 {
+                assert!(result.lo <= pow_round(a, b, Round::TowardPInf) && pow_round(a, b, Round::TowardNInf) <= result.hi,
+                    "{:?} {:?} {} {} {:?}", x, y, a, b, result);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decorations() {
+        let (a, b) = (DecoratedInterval::new(1.0, 2.0), DecoratedInterval::new(-1.0, 4.0));
+        assert_eq!(a.exp().decoration(), Com);
+        assert_eq!(DecoratedInterval::new(0.0, 1000.0).exp(), dec(1.0, INF, Dac));
+        assert_eq!(DecoratedInterval::new(1.0, INF).atan().decoration(), Dac);
+
+        assert_eq!(a.log().decoration(), Com);
+        assert_eq!(b.log(), dec(-INF, log_round(4.0, Round::TowardPInf), Trv));
+        assert_eq!(DecoratedInterval::new(-2.0, -1.0).log(), DecoratedInterval::EMPTY);
+        assert_eq!(b.sqrt(), dec(0.0, 2.0, Trv));
+        assert_eq!(DecoratedInterval::new(0.0, 4.0).sqrt(), dec(0.0, 2.0, Com));
+
+        assert_eq!(a.pow(b).decoration(), Com);
+        assert_eq!(b.pow(a), dec(0.0, 16.0, Trv));
+        assert_eq!(DecoratedInterval::new(0.0, 4.0).pow(a).decoration(), Com);
+        assert_eq!(DecoratedInterval::new(0.0, 4.0).pow(b).decoration(), Trv);
+        assert_eq!(b.powi(2), dec(0.0, 16.0, Com));
+        assert_eq!(b.powi(-2), dec(0.0625, INF, Trv));
+        assert_eq!(a.powi(-2), dec(0.25, 1.0, Com));
+
+        assert_eq!(b.sin().decoration(), Com);
+        assert_eq!(DecoratedInterval::new(1.0, INF).cos(), dec(-1.0, 1.0, Dac));
+        assert_eq!(DecoratedInterval::new(-1.0, 1.0).tan().decoration(), Com);
+        assert_eq!(a.tan(), dec(-INF, INF, Trv));
+
+        assert_eq!(b.abs(), dec(0.0, 4.0, Com));
+        assert_eq!(b.sqr(), dec(0.0, 16.0, Com));
+        assert_eq!(a.min(b), dec(-1.0, 2.0, Com));
+        assert_eq!(a.max(DecoratedInterval::new(0.0, INF)), dec(1.0, INF, Dac));
+
+        // Through a whole expression, sqrt(x) + exp(-x^2) on a partly negative x
+        let y = b.sqrt() + (-b.sqr()).exp();
+        assert_eq!(y.decoration(), Trv);
+        assert!(y.interval().contains(1.0));
+
+        let nai = DecoratedInterval::NAI;
+        assert!(nai.exp().is_nai() && nai.log().is_nai() && nai.sin().is_nai() && nai.powi(0).is_nai());
+        assert!(a.pow(nai).is_nai() && nai.min(a).is_nai() && a.max(nai).is_nai());
+    }
+}
@@ -1,7 +1,31 @@
+#![allow(clippy::needless_return, clippy::bool_assert_comparison)]
+
 pub mod fallback;
 pub mod native;
+pub mod ops;
+pub mod eft;
+pub mod dd;
+pub mod elementary;
+pub mod interval;
 pub mod modes;
 pub mod successor;
+pub mod test_vectors;
+pub mod f128;
+pub mod half;
+pub mod stochastic;
+pub mod fp8;
+pub mod soft_float;
+#[cfg(target_arch = "x86_64")]
+pub mod x87;
+
+pub use rounding_mode_macros::{enclose, round_down, round_up, rounding_mode};
+
+mod bigint;
+mod fixed;
 
 #[cfg(test)]
 pub mod test_cases;
+#[cfg(test)]
+mod exhaustive;
+#[cfg(test)]
+mod oracle;
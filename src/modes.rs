@@ -2,7 +2,7 @@
 
 /// Enum for various rounding modes, some which are supported by floating-point units. For the
 /// supported ones, we use an enum convention aligning with C's fesetround.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(i32)]
 pub enum Round {
     /// Ties to even, the default rounding mode in any program. Values are rounded to the nearest
@@ -20,8 +20,8 @@ pub enum Round {
     /// Imposes no restrictions on the rounding; the value is within one ULP of the exact result.
     Faithful = -0x1,
     /// Ties away from 0, i.e., values midway between consecutive values are rounded up for
-    /// positive numbers and below for negative numbers. This mode is not supported by any
-    /// floating-point unit.
+    /// positive numbers and below for negative numbers. Of the supported architectures, only
+    /// RISC-V implements this mode in hardware.
     TiesAway = -0x2,
     /// Ties to odd, analogous to ties to even, except that numbers midway between two
     /// representable values are rounded to the neighboring value with a one in the last place.
@@ -30,17 +30,28 @@ pub enum Round {
 }
 
 impl Round {
+    /// Every rounding mode, in declaration order.
+    pub const ALL: [Round; 7] = [
+        Round::TiesToEven,
+        Round::TowardZero,
+        Round::TowardPInf,
+        Round::TowardNInf,
+        Round::Faithful,
+        Round::TiesAway,
+        Round::TiesToOdd
+    ];
+
     /// Whether a rounding mode is natively supported. Additionally returns true for faithful
     /// rounding, which isn't a typical rounding mode per se, but imposes no restrictions on
     /// rounding.
     pub fn is_native(&self) -> bool {
-        return (*self as i32) >= -0x1;
+        return (*self as i32) >= -0x1 || self.is_fpu_native();
     }
 
     /// Whether a rounding mode is FPU native, meaning it is an actual rounding mode supported by
-    /// the FPU. Thus, it excludes faithful rounding.
+    /// the FPU. Thus, it excludes faithful rounding. Ties away from zero is FPU native on RISC-V.
     pub fn is_fpu_native(&self) -> bool {
-        return (*self as i32) >= 0x0;
+        return (*self as i32) >= 0x0 || (cfg!(target_arch = "riscv64") && *self == Round::TiesAway);
     }
 }
 
@@ -55,7 +66,7 @@ mod tests {
         assert_eq!(Round::Faithful.is_native(), true);
         assert_eq!(Round::TowardPInf.is_native(), true);
         assert_eq!(Round::TowardNInf.is_native(), true);
-        assert_eq!(Round::TiesAway.is_native(), false);
+        assert_eq!(Round::TiesAway.is_native(), cfg!(target_arch = "riscv64"));
         assert_eq!(Round::TiesToOdd.is_native(), false);
     }
 
@@ -66,7 +77,7 @@ mod tests {
         assert_eq!(Round::Faithful.is_fpu_native(), false);
         assert_eq!(Round::TowardPInf.is_fpu_native(), true);
         assert_eq!(Round::TowardNInf.is_fpu_native(), true);
-        assert_eq!(Round::TiesAway.is_fpu_native(), false);
+        assert_eq!(Round::TiesAway.is_fpu_native(), cfg!(target_arch = "riscv64"));
         assert_eq!(Round::TiesToOdd.is_fpu_native(), false);
     }
 }
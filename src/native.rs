@@ -1,45 +1,28 @@
-use std::arch::asm;
-use crate::modes::*;
-
-pub fn mul_down(mut a: f64, b: f64) -> f64 {
-    unsafe {
-        asm!(
-            "sub rsp, 8",
-            "stmxcsr [rsp]",
-            "mov dword ptr [rsp + 4], 0x3F80", // Round down
-            "ldmxcsr [rsp + 4]",
-            "mulsd {a}, {b}",
-            "ldmxcsr [rsp]",  // restore old state
-            "add rsp, 8",
-            a = inout(xmm_reg) a,
-            b = in(xmm_reg) b
-        );
-    }
-
-    return a;
-}
-
-/// Return the rounding mode that the MXCSR is currently in
-pub fn get_rounding_mode() -> Round {
-    let mut mxcsr: i32;
+// Native operations under a given rounding mode, carried out by the FPU with its rounding mode
+// switched for the duration of each operation, or encoded in the instruction where the
+// architecture allows. Each supported architecture provides the same functions in its own
+// submodule.
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64;
+#[cfg(target_arch = "x86_64")]
+pub use x86_64::*;
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64;
+#[cfg(target_arch = "aarch64")]
+pub use aarch64::*;
+
+#[cfg(target_arch = "riscv64")]
+mod riscv64;
+#[cfg(target_arch = "riscv64")]
+pub use riscv64::*;
 
-    unsafe {
-        asm!(
-            "sub rsp, 4",
-            "ldmxcsr [rsp]",
-            "mov {mxcsr}, [rsp]",
-            "add rsp, 4",
-            mxcsr = out(reg) mxcsr
-        );
-
-        let mode = mxcsr & 0xc00; // Brings into range of Round
-        let mode: Round = ::std::mem::transmute(mode); 
-
-        return mode;
-    }
-}
+#[cfg(test)]
+use crate::modes::*;
 
 /// Panics if we are not in round-to-nearest mode again
+#[cfg(test)]
 fn ensure_state_restored() {
     match get_rounding_mode() {
         Round::TiesToEven => {
@@ -92,4 +75,78 @@ mod tests {
     fn test_mul_rd() {
         test_binary(mul_rd_tests.to_vec(), &mul_down)
     }
+
+    #[test]
+    fn test_mul_toward_n_inf() {
+        test_binary(mul_rd_tests.to_vec(), &|a, b| mul_f64(a, b, Round::TowardNInf))
+    }
+
+    #[test]
+    fn test_directed_modes() {
+        let third = |mode| div_f64(1.0, 3.0, mode);
+
+        assert!(same_float(third(Round::TiesToEven), 1.0 / 3.0));
+        assert!(same_float(third(Round::Faithful), 1.0 / 3.0));
+        assert!(same_float(third(Round::TowardNInf), third(Round::TowardZero)));
+        assert!(third(Round::TowardNInf) < third(Round::TowardPInf));
+        assert!(same_float(add_f64(1.0, -1.0, Round::TowardNInf), -0.0));
+        assert!(same_float(sub_f64(1.0, 1.0, Round::TowardPInf), 0.0));
+        assert_eq!(mul_f32(f32::MAX, 2.0, Round::TowardZero), f32::MAX);
+        assert_eq!(add_f32(f32::MAX, f32::MAX, Round::TowardPInf), f32::INFINITY);
+
+        ensure_state_restored()
+    }
+
+    #[test]
+    fn test_fma() {
+        #[cfg(target_arch = "x86_64")]
+        if !is_x86_feature_detected!("fma") {
+            return;
+        }
+
+        // The fused product keeps the rounding error of 1/3, and (1 + ulp)^2 - 1 lies exactly
+        // between two floats
+        assert!(same_float(fma_f64(1.0 / 3.0, 3.0, -1.0, Round::TiesToEven), -2f64.powi(-54)));
+
+        let x = 1.0 + f64::EPSILON;
+        let low = 2f64.powi(-51);
+        assert!(same_float(fma_f64(x, x, -1.0, Round::TiesToEven), low));
+        assert!(same_float(fma_f64(x, x, -1.0, Round::TowardNInf), low));
+        assert!(same_float(fma_f64(x, x, -1.0, Round::TowardPInf), low + 2f64.powi(-103)));
+        assert!(same_float(fma_f64(-x, x, 1.0, Round::TowardZero), -low));
+
+        let y = 1.0 + f32::EPSILON;
+        assert_eq!(fma_f32(y, y, -1.0, Round::TowardPInf), 2f32.powi(-22) + 2f32.powi(-45));
+        assert_eq!(fma_f32(y, y, -1.0, Round::TowardZero), 2f32.powi(-22));
+
+        ensure_state_restored()
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(not(target_arch = "riscv64"))]
+    fn test_non_native_mode() {
+        mul_f64(1.0, 3.0, Round::TiesAway);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ties_to_odd_not_native() {
+        mul_f64(1.0, 3.0, Round::TiesToOdd);
+    }
+
+    #[test]
+    #[cfg(target_arch = "riscv64")]
+    fn test_ties_away() {
+        let tie = 2f64.powi(-53);
+
+        assert!(same_float(add_f64(1.0, tie, Round::TiesAway), 1.0 + 2.0 * tie));
+        assert!(same_float(sub_f64(-1.0, tie, Round::TiesAway), -1.0 - 2.0 * tie));
+        assert!(same_float(add_f64(1.0, tie, Round::TiesToEven), 1.0));
+        assert_eq!(add_f32(1.0, 2f32.powi(-24), Round::TiesAway), 1.0 + f32::EPSILON);
+        assert_eq!(round_f32(2.5, Round::TiesAway), 3.0);
+        assert_eq!(f32_to_i32(-0.5, Round::TiesAway), Some(-1));
+
+        ensure_state_restored()
+    }
 }
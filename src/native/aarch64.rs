@@ -0,0 +1,228 @@
+// aarch64 implementation of the native operations, which load the requested rounding mode into
+// the RMode field of the FPCR around each instruction and restore the previous value afterward.
+
+use std::arch::asm;
+use crate::modes::*;
+
+/// RMode field of the FPCR, bits 22 and 23
+const RMODE_MASK: u64 = 0b11 << 22;
+
+fn get_fpcr() -> u64 {
+    let fpcr: u64;
+
+    unsafe {
+        asm!(
+            "mrs {fpcr}, fpcr",
+            fpcr = out(reg) fpcr,
+            options(nomem, nostack)
+        );
+    }
+
+    return fpcr;
+}
+
+/// FPCR value with the RMode field set for the given rounding mode and everything else as in
+/// `fpcr`. Faithful rounding is served by rounding to nearest.
+fn fpcr_for(fpcr: u64, mode: Round) -> u64 {
+    assert!(mode.is_native(), "{:?} is not supported natively", mode);
+
+    let rmode = match mode {
+        Round::TowardPInf => 0b01,
+        Round::TowardNInf => 0b10,
+        Round::TowardZero => 0b11,
+        _ => 0b00,
+    };
+
+    (fpcr & !RMODE_MASK) | (rmode << 22)
+}
+
+pub fn mul_down(mut a: f64, b: f64) -> f64 {
+    let old = get_fpcr();
+    let new = fpcr_for(old, Round::TowardNInf);
+
+    unsafe {
+        asm!(
+            "msr fpcr, {new}",
+            "fmul {a:d}, {a:d}, {b:d}",
+            "msr fpcr, {old}", // restore old state
+            new = in(reg) new,
+            old = in(reg) old,
+            a = inout(vreg) a,
+            b = in(vreg) b,
+            options(nomem, nostack)
+        );
+    }
+
+    return a;
+}
+
+/// Defines a function applying a unary instruction under the given rounding mode, restoring the
+/// FPCR afterward. `$size` is the register modifier for the operand type.
+macro_rules! unary_op {
+    ($(#[$attr:meta])* $name:ident, $ty:ty, $insn:literal, $size:literal) => {
+        $(#[$attr])*
+        pub fn $name(mut a: $ty, mode: Round) -> $ty {
+            let old = get_fpcr();
+            let new = fpcr_for(old, mode);
+
+            unsafe {
+                asm!(
+                    "msr fpcr, {new}",
+                    concat!($insn, " {a:", $size, "}, {a:", $size, "}"),
+                    "msr fpcr, {old}",
+                    new = in(reg) new,
+                    old = in(reg) old,
+                    a = inout(vreg) a,
+                    options(nomem, nostack)
+                );
+            }
+
+            return a;
+        }
+    };
+}
+
+/// Defines a function applying a binary instruction under the given rounding mode, restoring the
+/// FPCR afterward. `$size` is the register modifier for the operand type.
+macro_rules! binary_op {
+    ($(#[$attr:meta])* $name:ident, $ty:ty, $insn:literal, $size:literal) => {
+        $(#[$attr])*
+        pub fn $name(mut a: $ty, b: $ty, mode: Round) -> $ty {
+            let old = get_fpcr();
+            let new = fpcr_for(old, mode);
+
+            unsafe {
+                asm!(
+                    "msr fpcr, {new}",
+                    concat!($insn, " {a:", $size, "}, {a:", $size, "}, {b:", $size, "}"),
+                    "msr fpcr, {old}",
+                    new = in(reg) new,
+                    old = in(reg) old,
+                    a = inout(vreg) a,
+                    b = in(vreg) b,
+                    options(nomem, nostack)
+                );
+            }
+
+            return a;
+        }
+    };
+}
+
+/// Defines a function applying a fused multiply-add instruction, `a * b + c`, under the given
+/// rounding mode, restoring the FPCR afterward. `$size` is the register modifier for the operand
+/// type.
+macro_rules! fma_op {
+    ($(#[$attr:meta])* $name:ident, $ty:ty, $size:literal) => {
+        $(#[$attr])*
+        pub fn $name(mut a: $ty, b: $ty, c: $ty, mode: Round) -> $ty {
+            let old = get_fpcr();
+            let new = fpcr_for(old, mode);
+
+            unsafe {
+                asm!(
+                    "msr fpcr, {new}",
+                    concat!("fmadd {a:", $size, "}, {a:", $size, "}, {b:", $size, "}, {c:", $size, "}"),
+                    "msr fpcr, {old}",
+                    new = in(reg) new,
+                    old = in(reg) old,
+                    a = inout(vreg) a,
+                    b = in(vreg) b,
+                    c = in(vreg) c,
+                    options(nomem, nostack)
+                );
+            }
+
+            return a;
+        }
+    };
+}
+
+binary_op!(
+    /// Sum of two f64s under the given rounding mode.
+    add_f64, f64, "fadd", "d"
+);
+
+binary_op!(
+    /// Difference of two f64s under the given rounding mode.
+    sub_f64, f64, "fsub", "d"
+);
+
+binary_op!(
+    /// Product of two f64s under the given rounding mode.
+    mul_f64, f64, "fmul", "d"
+);
+
+binary_op!(
+    /// Quotient of two f64s under the given rounding mode.
+    div_f64, f64, "fdiv", "d"
+);
+
+binary_op!(
+    /// Sum of two f32s under the given rounding mode.
+    add_f32, f32, "fadd", "s"
+);
+
+binary_op!(
+    /// Difference of two f32s under the given rounding mode.
+    sub_f32, f32, "fsub", "s"
+);
+
+binary_op!(
+    /// Product of two f32s under the given rounding mode.
+    mul_f32, f32, "fmul", "s"
+);
+
+binary_op!(
+    /// Quotient of two f32s under the given rounding mode.
+    div_f32, f32, "fdiv", "s"
+);
+
+unary_op!(
+    /// Square root of an f64 under the given rounding mode.
+    sqrt_f64, f64, "fsqrt", "d"
+);
+
+unary_op!(
+    /// Square root of an f32 under the given rounding mode.
+    sqrt_f32, f32, "fsqrt", "s"
+);
+
+fma_op!(
+    /// `a * b + c` for f64s with a single rounding under the given rounding mode.
+    fma_f64, f64, "d"
+);
+
+fma_op!(
+    /// `a * b + c` for f32s with a single rounding under the given rounding mode.
+    fma_f32, f32, "s"
+);
+
+unary_op!(
+    /// Rounds an f32 to an integral value under the given rounding mode, preserving the sign of
+    /// zero.
+    round_f32, f32, "frintx", "s"
+);
+
+/// Converts an f32 to an i32 under the given rounding mode, returning None for NaN and for
+/// results out of range.
+pub fn f32_to_i32(a: f32, mode: Round) -> Option<i32> {
+    // The conversion instructions saturate rather than flag invalid conversions, so round to an
+    // integral value first, after which the range check is exact and the cast can't round again
+    let r = round_f32(a, mode);
+    if r.is_nan() || !(-2147483648.0..2147483648.0).contains(&r) {
+        return None;
+    }
+
+    return Some(r as i32);
+}
+
+/// Return the rounding mode that the FPCR is currently in
+pub fn get_rounding_mode() -> Round {
+    match (get_fpcr() & RMODE_MASK) >> 22 {
+        0b00 => Round::TiesToEven,
+        0b01 => Round::TowardPInf,
+        0b10 => Round::TowardNInf,
+        _ => Round::TowardZero,
+    }
+}
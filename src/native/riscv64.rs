@@ -0,0 +1,209 @@
+// riscv64 implementation of the native operations. RISC-V encodes the rounding mode in each
+// floating-point instruction, so rather than swapping the frm CSR around an operation, each mode
+// gets its own instruction with a static rounding field. Among those modes is RMM, rounding to
+// nearest with ties away from zero, which makes `Round::TiesAway` native on this architecture.
+
+use std::arch::asm;
+use crate::modes::*;
+
+/// Issues `$template` with the static rounding field for `$mode` appended as its last operand.
+/// Faithful rounding is served by rounding to nearest.
+macro_rules! static_rounding {
+    ($mode:expr, $template:expr, $($operands:tt)*) => {
+        match $mode {
+            Round::TiesToEven | Round::Faithful => asm!(concat!($template, ", rne"), $($operands)*),
+            Round::TowardZero => asm!(concat!($template, ", rtz"), $($operands)*),
+            Round::TowardNInf => asm!(concat!($template, ", rdn"), $($operands)*),
+            Round::TowardPInf => asm!(concat!($template, ", rup"), $($operands)*),
+            Round::TiesAway => asm!(concat!($template, ", rmm"), $($operands)*),
+            Round::TiesToOdd => panic!("{:?} is not supported natively", $mode),
+        }
+    };
+}
+
+pub fn mul_down(mut a: f64, b: f64) -> f64 {
+    unsafe {
+        asm!(
+            "fmul.d {a}, {a}, {b}, rdn",
+            a = inout(freg) a,
+            b = in(freg) b,
+            options(pure, nomem, nostack)
+        );
+    }
+
+    return a;
+}
+
+/// Defines a function applying a unary instruction under the given rounding mode.
+macro_rules! unary_op {
+    ($(#[$attr:meta])* $name:ident, $ty:ty, $insn:literal) => {
+        $(#[$attr])*
+        pub fn $name(mut a: $ty, mode: Round) -> $ty {
+            unsafe {
+                static_rounding!(
+                    mode,
+                    concat!($insn, " {a}, {a}"),
+                    a = inout(freg) a,
+                    options(pure, nomem, nostack)
+                );
+            }
+
+            return a;
+        }
+    };
+}
+
+/// Defines a function applying a binary instruction under the given rounding mode.
+macro_rules! binary_op {
+    ($(#[$attr:meta])* $name:ident, $ty:ty, $insn:literal) => {
+        $(#[$attr])*
+        pub fn $name(mut a: $ty, b: $ty, mode: Round) -> $ty {
+            unsafe {
+                static_rounding!(
+                    mode,
+                    concat!($insn, " {a}, {a}, {b}"),
+                    a = inout(freg) a,
+                    b = in(freg) b,
+                    options(pure, nomem, nostack)
+                );
+            }
+
+            return a;
+        }
+    };
+}
+
+/// Defines a function applying a fused multiply-add instruction, `a * b + c`, under the given
+/// rounding mode.
+macro_rules! fma_op {
+    ($(#[$attr:meta])* $name:ident, $ty:ty, $insn:literal) => {
+        $(#[$attr])*
+        pub fn $name(mut a: $ty, b: $ty, c: $ty, mode: Round) -> $ty {
+            unsafe {
+                static_rounding!(
+                    mode,
+                    concat!($insn, " {a}, {a}, {b}, {c}"),
+                    a = inout(freg) a,
+                    b = in(freg) b,
+                    c = in(freg) c,
+                    options(pure, nomem, nostack)
+                );
+            }
+
+            return a;
+        }
+    };
+}
+
+binary_op!(
+    /// Sum of two f64s under the given rounding mode.
+    add_f64, f64, "fadd.d"
+);
+
+binary_op!(
+    /// Difference of two f64s under the given rounding mode.
+    sub_f64, f64, "fsub.d"
+);
+
+binary_op!(
+    /// Product of two f64s under the given rounding mode.
+    mul_f64, f64, "fmul.d"
+);
+
+binary_op!(
+    /// Quotient of two f64s under the given rounding mode.
+    div_f64, f64, "fdiv.d"
+);
+
+binary_op!(
+    /// Sum of two f32s under the given rounding mode.
+    add_f32, f32, "fadd.s"
+);
+
+binary_op!(
+    /// Difference of two f32s under the given rounding mode.
+    sub_f32, f32, "fsub.s"
+);
+
+binary_op!(
+    /// Product of two f32s under the given rounding mode.
+    mul_f32, f32, "fmul.s"
+);
+
+binary_op!(
+    /// Quotient of two f32s under the given rounding mode.
+    div_f32, f32, "fdiv.s"
+);
+
+unary_op!(
+    /// Square root of an f64 under the given rounding mode.
+    sqrt_f64, f64, "fsqrt.d"
+);
+
+unary_op!(
+    /// Square root of an f32 under the given rounding mode.
+    sqrt_f32, f32, "fsqrt.s"
+);
+
+fma_op!(
+    /// `a * b + c` for f64s with a single rounding under the given rounding mode.
+    fma_f64, f64, "fmadd.d"
+);
+
+fma_op!(
+    /// `a * b + c` for f32s with a single rounding under the given rounding mode.
+    fma_f32, f32, "fmadd.s"
+);
+
+/// Rounds an f32 to an integral value under the given rounding mode, preserving the sign of zero.
+pub fn round_f32(a: f32, mode: Round) -> f32 {
+    const TWO_POW_23: f32 = 8388608.0;
+
+    if a.is_nan() {
+        return a + a;
+    } else if a.abs() >= TWO_POW_23 {
+        // Already integral
+        return a;
+    }
+
+    // Adding 2^23 with the same sign pushes every fractional bit out of the mantissa, rounding
+    // in the given mode; subtracting it again is exact.
+    let magic = TWO_POW_23.copysign(a);
+    let sum = add_f32(a, magic, mode);
+
+    return (sum - magic).copysign(magic);
+}
+
+/// Converts an f32 to an i32 under the given rounding mode, returning None for NaN and for
+/// results out of range.
+pub fn f32_to_i32(a: f32, mode: Round) -> Option<i32> {
+    // fcvt.w.s saturates rather than flagging invalid conversions in its result, so round to an
+    // integral value first, after which the range check is exact and the cast can't round again
+    let r = round_f32(a, mode);
+    if r.is_nan() || !(-2147483648.0..2147483648.0).contains(&r) {
+        return None;
+    }
+
+    return Some(r as i32);
+}
+
+/// Return the dynamic rounding mode in the frm CSR, which the operations above don't use
+pub fn get_rounding_mode() -> Round {
+    let frm: u64;
+
+    unsafe {
+        asm!(
+            "frrm {frm}",
+            frm = out(reg) frm,
+            options(nomem, nostack)
+        );
+    }
+
+    match frm {
+        0b001 => Round::TowardZero,
+        0b010 => Round::TowardNInf,
+        0b011 => Round::TowardPInf,
+        0b100 => Round::TiesAway,
+        _ => Round::TiesToEven,
+    }
+}
@@ -0,0 +1,291 @@
+// x86_64 implementation of the native operations, which load the requested rounding mode into
+// the MXCSR around each SSE instruction and restore the previous value afterward.
+
+use std::arch::asm;
+use std::arch::x86_64::*;
+use crate::modes::*;
+
+pub fn mul_down(mut a: f64, b: f64) -> f64 {
+    unsafe {
+        asm!(
+            "sub rsp, 8",
+            "stmxcsr [rsp]",
+            "mov dword ptr [rsp + 4], 0x3F80", // Round down
+            "ldmxcsr [rsp + 4]",
+            "mulsd {a}, {b}",
+            "ldmxcsr [rsp]",  // restore old state
+            "add rsp, 8",
+            a = inout(xmm_reg) a,
+            b = in(xmm_reg) b
+        );
+    }
+
+    return a;
+}
+
+/// MXCSR value with all exceptions masked and the given rounding mode. Faithful rounding is
+/// served by rounding to nearest.
+fn mxcsr_for(mode: Round) -> u32 {
+    assert!(mode.is_native(), "{:?} is not supported natively", mode);
+
+    0x1f80 | (((mode as i32).max(0) as u32) << 3)
+}
+
+/// Defines a function applying a unary SSE instruction under the given rounding mode, restoring
+/// the MXCSR afterward.
+macro_rules! unary_op {
+    ($(#[$attr:meta])* $name:ident, $ty:ty, $insn:literal) => {
+        $(#[$attr])*
+        pub fn $name(mut a: $ty, mode: Round) -> $ty {
+            let csr = mxcsr_for(mode);
+
+            unsafe {
+                asm!(
+                    "sub rsp, 8",
+                    "stmxcsr [rsp]",
+                    "mov dword ptr [rsp + 4], {csr:e}",
+                    "ldmxcsr [rsp + 4]",
+                    concat!($insn, " {a}, {a}"),
+                    "ldmxcsr [rsp]",
+                    "add rsp, 8",
+                    csr = in(reg) csr,
+                    a = inout(xmm_reg) a
+                );
+            }
+
+            return a;
+        }
+    };
+}
+
+/// Defines a function applying a binary SSE instruction under the given rounding mode, restoring
+/// the MXCSR afterward.
+macro_rules! binary_op {
+    ($(#[$attr:meta])* $name:ident, $ty:ty, $insn:literal) => {
+        $(#[$attr])*
+        pub fn $name(mut a: $ty, b: $ty, mode: Round) -> $ty {
+            let csr = mxcsr_for(mode);
+
+            unsafe {
+                asm!(
+                    "sub rsp, 8",
+                    "stmxcsr [rsp]",
+                    "mov dword ptr [rsp + 4], {csr:e}",
+                    "ldmxcsr [rsp + 4]",
+                    concat!($insn, " {a}, {b}"),
+                    "ldmxcsr [rsp]",
+                    "add rsp, 8",
+                    csr = in(reg) csr,
+                    a = inout(xmm_reg) a,
+                    b = in(xmm_reg) b
+                );
+            }
+
+            return a;
+        }
+    };
+}
+
+/// Defines a function applying a fused multiply-add instruction, `a * b + c`, under the given
+/// rounding mode, restoring the MXCSR afterward. These need the FMA extension, which unlike SSE2
+/// isn't part of the x86_64 baseline.
+macro_rules! fma_op {
+    ($(#[$attr:meta])* $name:ident, $ty:ty, $insn:literal) => {
+        $(#[$attr])*
+        pub fn $name(mut a: $ty, b: $ty, c: $ty, mode: Round) -> $ty {
+            assert!(is_x86_feature_detected!("fma"), "FMA is not supported by this CPU");
+            let csr = mxcsr_for(mode);
+
+            unsafe {
+                asm!(
+                    "sub rsp, 8",
+                    "stmxcsr [rsp]",
+                    "mov dword ptr [rsp + 4], {csr:e}",
+                    "ldmxcsr [rsp + 4]",
+                    concat!($insn, " {a}, {b}, {c}"),
+                    "ldmxcsr [rsp]",
+                    "add rsp, 8",
+                    csr = in(reg) csr,
+                    a = inout(xmm_reg) a,
+                    b = in(xmm_reg) b,
+                    c = in(xmm_reg) c
+                );
+            }
+
+            return a;
+        }
+    };
+}
+
+binary_op!(
+    /// Sum of two f64s under the given rounding mode.
+    add_f64, f64, "addsd"
+);
+
+binary_op!(
+    /// Difference of two f64s under the given rounding mode.
+    sub_f64, f64, "subsd"
+);
+
+binary_op!(
+    /// Product of two f64s under the given rounding mode.
+    mul_f64, f64, "mulsd"
+);
+
+binary_op!(
+    /// Quotient of two f64s under the given rounding mode.
+    div_f64, f64, "divsd"
+);
+
+binary_op!(
+    /// Sum of two f32s under the given rounding mode.
+    add_f32, f32, "addss"
+);
+
+binary_op!(
+    /// Difference of two f32s under the given rounding mode.
+    sub_f32, f32, "subss"
+);
+
+binary_op!(
+    /// Product of two f32s under the given rounding mode.
+    mul_f32, f32, "mulss"
+);
+
+binary_op!(
+    /// Quotient of two f32s under the given rounding mode.
+    div_f32, f32, "divss"
+);
+
+unary_op!(
+    /// Square root of an f64 under the given rounding mode.
+    sqrt_f64, f64, "sqrtsd"
+);
+
+unary_op!(
+    /// Square root of an f32 under the given rounding mode.
+    sqrt_f32, f32, "sqrtss"
+);
+
+fma_op!(
+    /// `a * b + c` for f64s with a single rounding under the given rounding mode. Panics if the
+    /// CPU lacks the FMA extension.
+    fma_f64, f64, "vfmadd213sd"
+);
+
+fma_op!(
+    /// `a * b + c` for f32s with a single rounding under the given rounding mode. Panics if the
+    /// CPU lacks the FMA extension.
+    fma_f32, f32, "vfmadd213ss"
+);
+
+/// Rounds an f32 to an integral value under the given rounding mode, preserving the sign of zero.
+/// Uses SSE4.1's `roundss` where the CPU has it, and otherwise emulates it by adding and
+/// subtracting 2^23 in the given mode.
+pub fn round_f32(a: f32, mode: Round) -> f32 {
+    if is_x86_feature_detected!("sse4.1") {
+        assert!(mode.is_native(), "{:?} is not supported natively", mode);
+        return unsafe { round_ss(a, mode) };
+    }
+
+    return round_magic(a, mode);
+}
+
+/// `roundss`, which takes the rounding mode from its immediate rather than from the MXCSR.
+#[target_feature(enable = "sse4.1")]
+unsafe fn round_ss(a: f32, mode: Round) -> f32 {
+    let a = _mm_set_ss(a);
+    let rounded = match mode {
+        Round::TowardZero => _mm_round_ss::<{ _MM_FROUND_TO_ZERO | _MM_FROUND_NO_EXC }>(a, a),
+        Round::TowardPInf => _mm_round_ss::<{ _MM_FROUND_TO_POS_INF | _MM_FROUND_NO_EXC }>(a, a),
+        Round::TowardNInf => _mm_round_ss::<{ _MM_FROUND_TO_NEG_INF | _MM_FROUND_NO_EXC }>(a, a),
+        _ => _mm_round_ss::<{ _MM_FROUND_TO_NEAREST_INT | _MM_FROUND_NO_EXC }>(a, a),
+    };
+
+    return _mm_cvtss_f32(rounded);
+}
+
+/// Rounds an f32 to an integral value without SSE4.1.
+pub(crate) fn round_magic(mut a: f32, mode: Round) -> f32 {
+    const TWO_POW_23: f32 = 8388608.0;
+
+    if a.is_nan() {
+        return a + a;
+    } else if a.abs() >= TWO_POW_23 {
+        // Already integral
+        return a;
+    }
+
+    // Adding 2^23 with the same sign pushes every fractional bit out of the mantissa, rounding
+    // in the current mode; subtracting it again is exact.
+    let magic = TWO_POW_23.copysign(a);
+    let csr = mxcsr_for(mode);
+
+    unsafe {
+        asm!(
+            "sub rsp, 8",
+            "stmxcsr [rsp]",
+            "mov dword ptr [rsp + 4], {csr:e}",
+            "ldmxcsr [rsp + 4]",
+            "addss {a}, {magic}",
+            "subss {a}, {magic}",
+            "ldmxcsr [rsp]",
+            "add rsp, 8",
+            csr = in(reg) csr,
+            a = inout(xmm_reg) a,
+            magic = in(xmm_reg) magic
+        );
+    }
+
+    return a.copysign(magic);
+}
+
+/// Converts an f32 to an i32 under the given rounding mode, returning None for NaN and for
+/// results out of range.
+pub fn f32_to_i32(a: f32, mode: Round) -> Option<i32> {
+    let csr = mxcsr_for(mode);
+    let res: i32;
+
+    unsafe {
+        asm!(
+            "sub rsp, 8",
+            "stmxcsr [rsp]",
+            "mov dword ptr [rsp + 4], {csr:e}",
+            "ldmxcsr [rsp + 4]",
+            "cvtss2si {res:e}, {a}",
+            "ldmxcsr [rsp]",
+            "add rsp, 8",
+            csr = in(reg) csr,
+            a = in(xmm_reg) a,
+            res = lateout(reg) res
+        );
+    }
+
+    // Invalid conversions return the "integer indefinite" value, which is also the legitimate
+    // result for exactly -2^31
+    if res == i32::MIN && a != -2147483648.0 {
+        return None;
+    }
+
+    return Some(res);
+}
+
+/// Return the rounding mode that the MXCSR is currently in
+pub fn get_rounding_mode() -> Round {
+    let mut mxcsr: i32;
+
+    unsafe {
+        asm!(
+            "sub rsp, 4",
+            "stmxcsr [rsp]",
+            "mov {mxcsr:e}, [rsp]",
+            "add rsp, 4",
+            mxcsr = out(reg) mxcsr
+        );
+
+        let mode = (mxcsr >> 3) & 0xc00; // Brings into range of Round
+        let mode: Round = ::std::mem::transmute(mode); 
+
+        return mode;
+    }
+}
@@ -0,0 +1,642 @@
+// Rounded operations that pick the fastest correct implementation on their own. Which one that is
+// depends on the operation, the rounding mode, the target and the features of the CPU we run on,
+// so the choice is made once per (operation, mode) pair, when first needed, and cached.
+//
+// Round-to-nearest modes need no mode switch at all: Rust's own operators already round that way.
+// Directed modes go through AVX-512's embedded rounding where available, which encodes the mode
+// in the instruction, and otherwise through the native module, which switches the FPU's mode
+// around each operation. Whatever the hardware can't do falls back to software.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use lazy_static::lazy_static;
+use crate::modes::*;
+use crate::{fallback, native};
+
+/// Operations dispatched by this module
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Sqrt,
+    Fma,
+}
+
+impl Op {
+    pub const ALL: [Op; 6] = [Op::Add, Op::Sub, Op::Mul, Op::Div, Op::Sqrt, Op::Fma];
+}
+
+/// Ways of carrying out an operation under a rounding mode
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Implementation {
+    /// Rust's own operators, which round to nearest, ties to even
+    Default,
+    /// The `native` module, which switches the FPU's rounding mode around each operation
+    Native,
+    /// AVX-512 instructions with the rounding mode embedded, leaving the MXCSR alone
+    Avx512,
+    /// The `fallback` module, in software
+    Software,
+}
+
+/// CPU features relevant to the choice of implementation
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub struct Features {
+    /// SSE2, part of the x86_64 baseline
+    pub sse2: bool,
+    /// Fused multiply-add instructions. Part of the baseline on aarch64 and riscv64gc, an
+    /// extension on x86_64.
+    pub fma: bool,
+    /// AVX. The VEX-encoded FMA instructions need the OS to save AVX state, which detecting `fma`
+    /// already checks.
+    pub avx: bool,
+    /// AVX-512 Foundation, which brings embedded rounding to scalar operations
+    pub avx512f: bool,
+}
+
+impl Features {
+    /// Features of the CPU we are running on
+    pub fn detect() -> Features {
+        #[cfg(target_arch = "x86_64")]
+        return Features {
+            sse2: is_x86_feature_detected!("sse2"),
+            fma: is_x86_feature_detected!("fma"),
+            avx: is_x86_feature_detected!("avx"),
+            avx512f: is_x86_feature_detected!("avx512f"),
+        };
+
+        #[cfg(not(target_arch = "x86_64"))]
+        return Features { fma: true, ..Features::default() };
+    }
+}
+
+/// Whether Rust's own fused multiply-add compiles to a single instruction, rather than a call to
+/// the C library's `fma`
+const FMA_INLINE: bool = cfg!(any(not(target_arch = "x86_64"), target_feature = "fma"));
+
+/// Fastest correct implementation of `op` under `mode` on a CPU with the given features.
+pub fn select(features: Features, op: Op, mode: Round) -> Implementation {
+    let nearest = matches!(mode, Round::TiesToEven | Round::Faithful);
+
+    if !mode.is_native() {
+        return Implementation::Software;
+    } else if op == Op::Fma && !features.fma {
+        // Rust's fused multiply-add is still correct here, through the C library
+        return if nearest { Implementation::Default } else { Implementation::Software };
+    } else if nearest && (op != Op::Fma || FMA_INLINE) {
+        return Implementation::Default;
+    } else if features.avx512f {
+        return Implementation::Avx512;
+    }
+
+    Implementation::Native
+}
+
+/// Position of a rounding mode in `Round::ALL`
+fn mode_index(mode: Round) -> usize {
+    match mode {
+        Round::TiesToEven => 0,
+        Round::TowardZero => 1,
+        Round::TowardPInf => 2,
+        Round::TowardNInf => 3,
+        Round::Faithful => 4,
+        Round::TiesAway => 5,
+        Round::TiesToOdd => 6,
+    }
+}
+
+lazy_static! {
+    static ref FEATURES: Features = Features::detect();
+
+    /// Implementation chosen for each operation, indexed as `Op::ALL`, and rounding mode, indexed
+    /// as `Round::ALL`
+    static ref CHOICES: [[Implementation; 7]; 6] =
+        Op::ALL.map(|op| Round::ALL.map(|mode| select(*FEATURES, op, mode)));
+}
+
+static FORCE_SOFTWARE: AtomicBool = AtomicBool::new(false);
+
+/// Features of the CPU we are running on, detected once
+pub fn features() -> Features {
+    *FEATURES
+}
+
+/// Makes every operation in this module use the software implementation until called again with
+/// `false`, e.g., to test the fallbacks on hardware that wouldn't otherwise use them.
+pub fn force_software(force: bool) {
+    FORCE_SOFTWARE.store(force, Ordering::Relaxed);
+}
+
+/// Implementation currently used for `op` under `mode`
+pub fn implementation(op: Op, mode: Round) -> Implementation {
+    if FORCE_SOFTWARE.load(Ordering::Relaxed) {
+        return Implementation::Software;
+    }
+
+    CHOICES[op as usize][mode_index(mode)]
+}
+
+/// Defines a function dispatching an operation to the implementation chosen for it. `$default` is
+/// the same operation with Rust's own operators.
+macro_rules! dispatch {
+    ($(#[$attr:meta])* $name:ident, $op:expr, $ty:ty, ($($arg:ident),*), $default:expr) => {
+        $(#[$attr])*
+        pub fn $name($($arg: $ty,)* mode: Round) -> $ty {
+            match implementation($op, mode) {
+                Implementation::Default => $default,
+                Implementation::Native => native::$name($($arg,)* mode),
+                #[cfg(target_arch = "x86_64")]
+                Implementation::Avx512 => avx512::$name($($arg,)* mode),
+                #[cfg(not(target_arch = "x86_64"))]
+                Implementation::Avx512 => unreachable!(),
+                Implementation::Software => fallback::$name($($arg,)* mode),
+            }
+        }
+    };
+}
+
+dispatch!(
+    /// Sum of two f64s under the given rounding mode.
+    add_f64, Op::Add, f64, (a, b), a + b
+);
+
+dispatch!(
+    /// Difference of two f64s under the given rounding mode.
+    sub_f64, Op::Sub, f64, (a, b), a - b
+);
+
+dispatch!(
+    /// Product of two f64s under the given rounding mode.
+    mul_f64, Op::Mul, f64, (a, b), a * b
+);
+
+dispatch!(
+    /// Quotient of two f64s under the given rounding mode.
+    div_f64, Op::Div, f64, (a, b), a / b
+);
+
+dispatch!(
+    /// Square root of an f64 under the given rounding mode.
+    sqrt_f64, Op::Sqrt, f64, (a), a.sqrt()
+);
+
+dispatch!(
+    /// `a * b + c` for f64s with a single rounding under the given rounding mode.
+    fma_f64, Op::Fma, f64, (a, b, c), a.mul_add(b, c)
+);
+
+dispatch!(
+    /// Sum of two f32s under the given rounding mode.
+    add_f32, Op::Add, f32, (a, b), a + b
+);
+
+dispatch!(
+    /// Difference of two f32s under the given rounding mode.
+    sub_f32, Op::Sub, f32, (a, b), a - b
+);
+
+dispatch!(
+    /// Product of two f32s under the given rounding mode.
+    mul_f32, Op::Mul, f32, (a, b), a * b
+);
+
+dispatch!(
+    /// Quotient of two f32s under the given rounding mode.
+    div_f32, Op::Div, f32, (a, b), a / b
+);
+
+dispatch!(
+    /// Square root of an f32 under the given rounding mode.
+    sqrt_f32, Op::Sqrt, f32, (a), a.sqrt()
+);
+
+dispatch!(
+    /// `a * b + c` for f32s with a single rounding under the given rounding mode.
+    fma_f32, Op::Fma, f32, (a, b, c), a.mul_add(b, c)
+);
+
+/// Product of two f64s rounded down, like `native::mul_down` and `fallback::multiply_round_down`.
+pub fn mul_down(a: f64, b: f64) -> f64 {
+    mul_f64(a, b, Round::TowardNInf)
+}
+
+/// Arithmetic under a rounding mode, which the `rounding_mode` attribute rewrites operators into.
+/// Integers ignore the rounding mode.
+pub trait RoundedArith<Rhs = Self> {
+    type Output;
+
+    fn add_rounded(self, rhs: Rhs, mode: Round) -> Self::Output;
+    fn sub_rounded(self, rhs: Rhs, mode: Round) -> Self::Output;
+    fn mul_rounded(self, rhs: Rhs, mode: Round) -> Self::Output;
+    fn div_rounded(self, rhs: Rhs, mode: Round) -> Self::Output;
+}
+
+/// Implements `RoundedArith` for a float type through the dispatched operations above.
+macro_rules! rounded_float {
+    ($ty:ty, $add:ident, $sub:ident, $mul:ident, $div:ident) => {
+        impl RoundedArith for $ty {
+            type Output = $ty;
+
+            #[inline]
+            fn add_rounded(self, rhs: $ty, mode: Round) -> $ty { $add(self, rhs, mode) }
+            #[inline]
+            fn sub_rounded(self, rhs: $ty, mode: Round) -> $ty { $sub(self, rhs, mode) }
+            #[inline]
+            fn mul_rounded(self, rhs: $ty, mode: Round) -> $ty { $mul(self, rhs, mode) }
+            #[inline]
+            fn div_rounded(self, rhs: $ty, mode: Round) -> $ty { $div(self, rhs, mode) }
+        }
+    };
+}
+
+rounded_float!(f64, add_f64, sub_f64, mul_f64, div_f64);
+rounded_float!(f32, add_f32, sub_f32, mul_f32, div_f32);
+
+/// Implements `RoundedArith` for integer types with the plain operators.
+macro_rules! rounded_int {
+    ($($ty:ty),*) => {$(
+        impl RoundedArith for $ty {
+            type Output = $ty;
+
+            #[inline]
+            fn add_rounded(self, rhs: $ty, _: Round) -> $ty { self + rhs }
+            #[inline]
+            fn sub_rounded(self, rhs: $ty, _: Round) -> $ty { self - rhs }
+            #[inline]
+            fn mul_rounded(self, rhs: $ty, _: Round) -> $ty { self * rhs }
+            #[inline]
+            fn div_rounded(self, rhs: $ty, _: Round) -> $ty { self / rhs }
+        }
+    )*};
+}
+
+rounded_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Defines `RoundedArith` on references in terms of the values they point to, as the operators
+/// are.
+macro_rules! rounded_ref {
+    ($(<$($lt:lifetime),*> $lhs:ty, $rhs:ty, |$a:ident, $b:ident| ($load_a:expr, $load_b:expr);)*) => {$(
+        impl<$($lt,)* T: RoundedArith + Copy> RoundedArith<$rhs> for $lhs {
+            type Output = T::Output;
+
+            #[inline]
+            fn add_rounded(self, rhs: $rhs, mode: Round) -> T::Output { let ($a, $b) = (self, rhs); $load_a.add_rounded($load_b, mode) }
+            #[inline]
+            fn sub_rounded(self, rhs: $rhs, mode: Round) -> T::Output { let ($a, $b) = (self, rhs); $load_a.sub_rounded($load_b, mode) }
+            #[inline]
+            fn mul_rounded(self, rhs: $rhs, mode: Round) -> T::Output { let ($a, $b) = (self, rhs); $load_a.mul_rounded($load_b, mode) }
+            #[inline]
+            fn div_rounded(self, rhs: $rhs, mode: Round) -> T::Output { let ($a, $b) = (self, rhs); $load_a.div_rounded($load_b, mode) }
+        }
+    )*};
+}
+
+rounded_ref! {
+    <'a> &'a T, T, |a, b| (*a, b);
+    <'b> T, &'b T, |a, b| (a, *b);
+    <'a, 'b> &'a T, &'b T, |a, b| (*a, *b);
+}
+
+/// Interval arithmetic on `(lo, hi)` enclosures, which `enclose!` rewrites operators into. Each
+/// result contains every exact result for operands within the given bounds.
+pub trait Enclose: Sized {
+    /// Enclosure of a value known exactly
+    fn point(self) -> (Self, Self);
+    /// Enclosure of a literal, given the number of significant bits in its exact value, or `None`
+    /// if that value is not a dyadic rational at all. Literals the type can't hold exactly widen
+    /// to their neighbors.
+    fn literal(self, bits: Option<u32>) -> (Self, Self);
+
+    fn neg_enclosure(a: (Self, Self)) -> (Self, Self);
+    fn add_enclosure(a: (Self, Self), b: (Self, Self)) -> (Self, Self);
+    fn sub_enclosure(a: (Self, Self), b: (Self, Self)) -> (Self, Self);
+    fn mul_enclosure(a: (Self, Self), b: (Self, Self)) -> (Self, Self);
+    fn div_enclosure(a: (Self, Self), b: (Self, Self)) -> (Self, Self);
+}
+
+/// Implements `Enclose` for a float type.
+macro_rules! enclose_float {
+    ($ty:ident, $add:ident, $sub:ident, $mul:ident, $div:ident, $successor:path, $predecessor:path) => {
+        impl Enclose for $ty {
+            fn point(self) -> ($ty, $ty) {
+                (self, self)
+            }
+
+            fn literal(self, bits: Option<u32>) -> ($ty, $ty) {
+                match bits {
+                    Some(bits) if bits <= $ty::MANTISSA_DIGITS => (self, self),
+                    _ => ($predecessor(self), $successor(self)),
+                }
+            }
+
+            fn neg_enclosure(a: ($ty, $ty)) -> ($ty, $ty) {
+                (-a.1, -a.0)
+            }
+
+            fn add_enclosure(a: ($ty, $ty), b: ($ty, $ty)) -> ($ty, $ty) {
+                ($add(a.0, b.0, Round::TowardNInf), $add(a.1, b.1, Round::TowardPInf))
+            }
+
+            fn sub_enclosure(a: ($ty, $ty), b: ($ty, $ty)) -> ($ty, $ty) {
+                ($sub(a.0, b.1, Round::TowardNInf), $sub(a.1, b.0, Round::TowardPInf))
+            }
+
+            fn mul_enclosure(a: ($ty, $ty), b: ($ty, $ty)) -> ($ty, $ty) {
+                extreme_products(a, b, $mul)
+            }
+
+            fn div_enclosure(a: ($ty, $ty), b: ($ty, $ty)) -> ($ty, $ty) {
+                if b.0 <= 0.0 && b.1 >= 0.0 && !a.0.is_nan() && !a.1.is_nan() {
+                    // The divisor may be zero
+                    return ($ty::NEG_INFINITY, $ty::INFINITY);
+                }
+
+                extreme_products(a, b, $div)
+            }
+        }
+    };
+}
+
+/// Smallest of the endpoint combinations `op(x, y)` rounded down and largest rounded up. The
+/// combinations that are NaN, 0 * inf and inf / inf, lie at the edge of a range whose other
+/// combinations already bound it, so they are skipped, unless an endpoint is NaN itself.
+fn extreme_products<T: Copy + PartialOrd>(a: (T, T), b: (T, T), op: fn(T, T, Round) -> T) -> (T, T) {
+    let is_nan = |x: &T| x.partial_cmp(x).is_none();
+    if let Some(nan) = [a.0, a.1, b.0, b.1].into_iter().find(is_nan) {
+        return (nan, nan);
+    }
+
+    let (mut lo, mut hi) = (None, None);
+    for (x, y) in [(a.0, b.0), (a.0, b.1), (a.1, b.0), (a.1, b.1)] {
+        let (down, up) = (op(x, y, Round::TowardNInf), op(x, y, Round::TowardPInf));
+
+        if !is_nan(&down) && lo.is_none_or(|lo| down < lo) {
+            lo = Some(down);
+        }
+        if !is_nan(&up) && hi.is_none_or(|hi| up > hi) {
+            hi = Some(up);
+        }
+    }
+
+    // Only inf * 0 and the like leave every combination NaN
+    let nan = op(a.0, b.0, Round::TiesToEven);
+    (lo.unwrap_or(nan), hi.unwrap_or(nan))
+}
+
+enclose_float!(f64, add_f64, sub_f64, mul_f64, div_f64, crate::successor::successor_f64, crate::successor::predecessor_f64);
+enclose_float!(f32, add_f32, sub_f32, mul_f32, div_f32, crate::successor::successor_f32, crate::successor::predecessor_f32);
+
+/// Scalar AVX-512 instructions with embedded rounding. Only called once `avx512f` is detected.
+#[cfg(target_arch = "x86_64")]
+mod avx512 {
+    use std::arch::asm;
+    use crate::modes::*;
+
+    /// Issues `$template` with the embedded rounding operand for `$mode` appended. Faithful
+    /// rounding is served by rounding to nearest.
+    macro_rules! embedded_rounding {
+        ($mode:expr, $template:expr, $($operands:tt)*) => {
+            match $mode {
+                Round::TiesToEven | Round::Faithful => asm!(concat!($template, ", {{rn-sae}}"), $($operands)*),
+                Round::TowardZero => asm!(concat!($template, ", {{rz-sae}}"), $($operands)*),
+                Round::TowardNInf => asm!(concat!($template, ", {{rd-sae}}"), $($operands)*),
+                Round::TowardPInf => asm!(concat!($template, ", {{ru-sae}}"), $($operands)*),
+                _ => panic!("{:?} is not supported natively", $mode),
+            }
+        };
+    }
+
+    /// Defines a function applying an instruction to `a` and further operands, with the result
+    /// replacing `a`.
+    macro_rules! embedded_op {
+        ($name:ident, $ty:ty, $template:literal, ($($arg:ident),*)) => {
+            pub fn $name(mut a: $ty, $($arg: $ty,)* mode: Round) -> $ty {
+                unsafe {
+                    embedded_rounding!(
+                        mode,
+                        $template,
+                        a = inout(xmm_reg) a,
+                        $($arg = in(xmm_reg) $arg,)*
+                        options(pure, nomem, nostack)
+                    );
+                }
+
+                return a;
+            }
+        };
+    }
+
+    embedded_op!(add_f64, f64, "vaddsd {a}, {a}, {b}", (b));
+    embedded_op!(sub_f64, f64, "vsubsd {a}, {a}, {b}", (b));
+    embedded_op!(mul_f64, f64, "vmulsd {a}, {a}, {b}", (b));
+    embedded_op!(div_f64, f64, "vdivsd {a}, {a}, {b}", (b));
+    embedded_op!(sqrt_f64, f64, "vsqrtsd {a}, {a}, {a}", ());
+    embedded_op!(fma_f64, f64, "vfmadd213sd {a}, {b}, {c}", (b, c));
+    embedded_op!(add_f32, f32, "vaddss {a}, {a}, {b}", (b));
+    embedded_op!(sub_f32, f32, "vsubss {a}, {a}, {b}", (b));
+    embedded_op!(mul_f32, f32, "vmulss {a}, {a}, {b}", (b));
+    embedded_op!(div_f32, f32, "vdivss {a}, {a}, {b}", (b));
+    embedded_op!(sqrt_f32, f32, "vsqrtss {a}, {a}, {a}", ());
+    embedded_op!(fma_f32, f32, "vfmadd213ss {a}, {b}, {c}", (b, c));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_cases::*;
+    use crate::oracle;
+    use crate::successor::identical_f64;
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let none = Features::default();
+        let sse2 = Features { sse2: true, ..none };
+        let fma = Features { fma: true, avx: true, ..sse2 };
+        let avx512 = Features { avx512f: true, ..fma };
+
+        for features in [none, sse2, fma, avx512] {
+            assert_eq!(select(features, Op::Add, Round::TiesToEven), Implementation::Default);
+            assert_eq!(select(features, Op::Sqrt, Round::Faithful), Implementation::Default);
+            assert_eq!(select(features, Op::Mul, Round::TiesToOdd), Implementation::Software);
+        }
+
+        assert_eq!(select(sse2, Op::Mul, Round::TowardNInf), Implementation::Native);
+        assert_eq!(select(avx512, Op::Mul, Round::TowardNInf), Implementation::Avx512);
+        assert_eq!(select(avx512, Op::Fma, Round::TowardZero), Implementation::Avx512);
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            assert_eq!(select(sse2, Op::Div, Round::TiesAway), Implementation::Software);
+            assert_eq!(select(sse2, Op::Fma, Round::TowardPInf), Implementation::Software);
+            assert_eq!(select(sse2, Op::Fma, Round::TiesToEven), Implementation::Default);
+            assert_eq!(select(fma, Op::Fma, Round::TowardPInf), Implementation::Native);
+            assert_eq!(select(fma, Op::Fma, Round::TiesToEven), if FMA_INLINE { Implementation::Default } else { Implementation::Native });
+        }
+
+        #[cfg(target_arch = "riscv64")]
+        assert_eq!(select(none, Op::Div, Round::TiesAway), Implementation::Native);
+    }
+
+    /// Every implementation of every operation, called directly, so that each is tested
+    /// regardless of which the dispatch picks on this machine
+    type Kernels<T> = [(Op, &'static dyn Fn(T, T, T, Round) -> T); 6];
+
+    fn kernels_f64(implementation: Implementation) -> Kernels<f64> {
+        macro_rules! kernels {
+            ($module:ident) => {
+                [
+                    (Op::Add, &|a, b, _, mode| $module::add_f64(a, b, mode)),
+                    (Op::Sub, &|a, b, _, mode| $module::sub_f64(a, b, mode)),
+                    (Op::Mul, &|a, b, _, mode| $module::mul_f64(a, b, mode)),
+                    (Op::Div, &|a, b, _, mode| $module::div_f64(a, b, mode)),
+                    (Op::Sqrt, &|a, _, _, mode| $module::sqrt_f64(a, mode)),
+                    (Op::Fma, &|a, b, c, mode| $module::fma_f64(a, b, c, mode)),
+                ]
+            };
+        }
+
+        match implementation {
+            Implementation::Native => kernels!(native),
+            #[cfg(target_arch = "x86_64")]
+            Implementation::Avx512 => kernels!(avx512),
+            _ => kernels!(fallback),
+        }
+    }
+
+    fn kernels_f32(implementation: Implementation) -> Kernels<f32> {
+        macro_rules! kernels {
+            ($module:ident) => {
+                [
+                    (Op::Add, &|a, b, _, mode| $module::add_f32(a, b, mode)),
+                    (Op::Sub, &|a, b, _, mode| $module::sub_f32(a, b, mode)),
+                    (Op::Mul, &|a, b, _, mode| $module::mul_f32(a, b, mode)),
+                    (Op::Div, &|a, b, _, mode| $module::div_f32(a, b, mode)),
+                    (Op::Sqrt, &|a, _, _, mode| $module::sqrt_f32(a, mode)),
+                    (Op::Fma, &|a, b, c, mode| $module::fma_f32(a, b, c, mode)),
+                ]
+            };
+        }
+
+        match implementation {
+            Implementation::Native => kernels!(native),
+            #[cfg(target_arch = "x86_64")]
+            Implementation::Avx512 => kernels!(avx512),
+            _ => kernels!(fallback),
+        }
+    }
+
+    fn oracle_f64(op: Op, a: f64, b: f64, c: f64, mode: Round) -> f64 {
+        match op {
+            Op::Add => oracle::add_f64(a, b, mode),
+            Op::Sub => oracle::sub_f64(a, b, mode),
+            Op::Mul => oracle::mul_f64(a, b, mode),
+            Op::Div => oracle::div_f64(a, b, mode),
+            Op::Sqrt => oracle::sqrt_f64(a, mode),
+            Op::Fma => oracle::fma_f64(a, b, c, mode),
+        }
+    }
+
+    fn oracle_f32(op: Op, a: f32, b: f32, c: f32, mode: Round) -> f32 {
+        match op {
+            Op::Add => oracle::add_f32(a, b, mode),
+            Op::Sub => oracle::sub_f32(a, b, mode),
+            Op::Mul => oracle::mul_f32(a, b, mode),
+            Op::Div => oracle::div_f32(a, b, mode),
+            Op::Sqrt => oracle::sqrt_f32(a, mode),
+            Op::Fma => oracle::fma_f32(a, b, c, mode),
+        }
+    }
+
+    /// Number of hard cases thrown at each implementation
+    const HARD_CASES: usize = 2000;
+
+    /// Implementations this machine can run, with the modes each supports
+    fn available() -> Vec<(Implementation, Vec<Round>)> {
+        let native_modes: Vec<Round> = Round::ALL.into_iter().filter(Round::is_native).collect();
+
+        let mut available = vec![
+            (Implementation::Software, Round::ALL.to_vec()),
+            (Implementation::Native, native_modes.clone()),
+        ];
+
+        if features().avx512f {
+            available.push((Implementation::Avx512, native_modes));
+        }
+
+        available
+    }
+
+    #[test]
+    fn test_implementations() {
+        let fma = features().fma;
+
+        for (implementation, modes) in available() {
+            for (op, kernel) in kernels_f64(implementation) {
+                if op == Op::Fma && implementation == Implementation::Native && !fma {
+                    continue;
+                }
+
+                for ((a, b), c) in hard_f64_pairs(SEED, HARD_CASES).into_iter().zip(RANDOM_F64.iter().cycle()) {
+                    for &mode in &modes {
+                        let actual = kernel(a, b, *c, mode);
+                        assert!(oracle::accepts_f64(|mode| oracle_f64(op, a, b, *c, mode), actual, mode), "{:?} {:?}: a = {:.18e}, b = {:.18e}, c = {:.18e}, mode = {:?}, actual = {:.18e}", implementation, op, a, b, c, mode, actual);
+                    }
+                }
+            }
+
+            for (op, kernel) in kernels_f32(implementation) {
+                if op == Op::Fma && implementation == Implementation::Native && !fma {
+                    continue;
+                }
+
+                for (a, b) in hard_f32_pairs(SEED, HARD_CASES) {
+                    let c = a * 0.75 - b;
+                    for &mode in &modes {
+                        let actual = kernel(a, b, c, mode);
+                        assert!(oracle::accepts_f32(|mode| oracle_f32(op, a, b, c, mode), actual, mode), "{:?} {:?}: a = {:.9e}, b = {:.9e}, c = {:.9e}, mode = {:?}, actual = {:.9e}", implementation, op, a, b, c, mode, actual);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dispatch() {
+        for ((a, b), c) in hard_f64_pairs(SEED, HARD_CASES).into_iter().zip(RANDOM_F64.iter().cycle()) {
+            for mode in Round::ALL {
+                for (op, kernel) in kernels_f64(Implementation::Software) {
+                    let actual = match op {
+                        Op::Add => add_f64(a, b, mode),
+                        Op::Sub => sub_f64(a, b, mode),
+                        Op::Mul => mul_f64(a, b, mode),
+                        Op::Div => div_f64(a, b, mode),
+                        Op::Sqrt => sqrt_f64(a, mode),
+                        Op::Fma => fma_f64(a, b, *c, mode),
+                    };
+
+                    assert!(oracle::accepts_f64(|mode| kernel(a, b, *c, mode), actual, mode), "{:?}: a = {:.18e}, b = {:.18e}, c = {:.18e}, mode = {:?}, actual = {:.18e}", op, a, b, c, mode, actual);
+                }
+            }
+
+            assert!(identical_f64(mul_down(a, b), fallback::multiply_round_down(a, b)));
+        }
+    }
+
+    #[test]
+    fn test_enclosures() {
+        assert_eq!(f64::mul_enclosure((-2.0, 3.0), (-5.0, 4.0)), (-15.0, 12.0));
+        assert_eq!(f64::mul_enclosure((0.0, 1.0), (1.0, f64::INFINITY)), (0.0, f64::INFINITY));
+        assert_eq!(f64::div_enclosure((1.0, 2.0), (-4.0, -2.0)), (-1.0, -0.25));
+        assert_eq!(f64::div_enclosure((1.0, 2.0), (-4.0, 0.0)), (f64::NEG_INFINITY, f64::INFINITY));
+        assert_eq!(f64::sub_enclosure((1.0, 2.0), (0.5, 3.0)), (-2.0, 1.5));
+        assert_eq!(f32::neg_enclosure((1.0, 2.0)), (-2.0, -1.0));
+
+        let third = f64::div_enclosure((1.0, 1.0), (3.0, 3.0));
+        assert_eq!(third, (div_f64(1.0, 3.0, Round::TowardNInf), div_f64(1.0, 3.0, Round::TowardPInf)));
+        assert!(third.0 < third.1);
+
+        let (lo, hi) = f64::mul_enclosure((f64::NAN, 1.0), (0.0, 1.0));
+        assert!(lo.is_nan() && hi.is_nan());
+    }
+}
@@ -0,0 +1,536 @@
+// Exact rational arithmetic, used by the tests as a reference for correctly rounded operations.
+// It shares nothing with the fallback or native implementations beyond the description of the
+// formats and the big integers, so a rounding bug in either can't hide behind the same bug in the
+// reference. Speed is not a goal.
+
+use std::cmp::Ordering;
+use crate::bigint::BigUint;
+use crate::fallback::Format;
+use crate::modes::*;
+
+/// Exact value `(-1)^negative * num / den * 2^exp`. Zeros keep their sign so that the operations
+/// below can follow IEEE 754's rules for the sign of a zero result.
+#[derive(Clone, Debug)]
+pub struct Rational {
+    pub negative: bool,
+    pub num: BigUint,
+    pub den: BigUint,
+    pub exp: i32,
+}
+
+impl Rational {
+    pub fn zero(negative: bool) -> Rational {
+        Rational { negative, num: BigUint::zero(), den: BigUint::one(), exp: 0 }
+    }
+
+    /// The value `(-1)^negative * mant * 2^exp`
+    pub fn from_parts(negative: bool, mant: u128, exp: i32) -> Rational {
+        Rational { negative, num: BigUint::from_u128(mant), den: BigUint::one(), exp }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num.is_zero()
+    }
+
+    pub fn neg(&self) -> Rational {
+        Rational { negative: !self.negative, ..self.clone() }
+    }
+
+    /// Exact sum. An exact zero sum is positive; callers apply the rounding-dependent sign rules.
+    pub fn add(&self, other: &Rational) -> Rational {
+        let exp = self.exp.min(other.exp);
+        let a = self.num.shl((self.exp - exp) as u32).mul(&other.den);
+        let b = other.num.shl((other.exp - exp) as u32).mul(&self.den);
+        let den = self.den.mul(&other.den);
+
+        let (negative, num) = if self.negative == other.negative {
+            (self.negative, a.add(&b))
+        } else if a >= b {
+            (self.negative, a.sub(&b))
+        } else {
+            (other.negative, b.sub(&a))
+        };
+
+        if num.is_zero() {
+            return Rational::zero(false);
+        }
+
+        Rational { negative, num, den, exp }
+    }
+
+    pub fn mul(&self, other: &Rational) -> Rational {
+        Rational {
+            negative: self.negative != other.negative,
+            num: self.num.mul(&other.num),
+            den: self.den.mul(&other.den),
+            exp: self.exp + other.exp,
+        }
+    }
+
+    /// Exact quotient; `other` must be nonzero
+    pub fn div(&self, other: &Rational) -> Rational {
+        assert!(!other.is_zero(), "division by zero");
+
+        Rational {
+            negative: self.negative != other.negative,
+            num: self.num.mul(&other.den),
+            den: self.den.mul(&other.num),
+            exp: self.exp - other.exp,
+        }
+    }
+
+    /// A value that rounds to `bits` significant bits or fewer exactly as sqrt(|self|) does:
+    /// the root itself when it is rational, and otherwise its floor at `bits + 2` bits or more
+    /// plus half a unit, which lies strictly between the same two candidates and midpoints.
+    pub fn sqrt(&self, bits: u32) -> Rational {
+        if self.is_zero() {
+            return self.clone();
+        }
+
+        // With floor(log2(|self|)) >= e - 1, scaling by 2^2t leaves a root of at least 2^(bits + 2)
+        let e = self.num.bits() as i32 - self.den.bits() as i32 + self.exp;
+        let t = bits as i32 + 3 - (e - 1).div_euclid(2);
+
+        // floor(sqrt(y)) = isqrt(floor(y)), and the root is exact only if both steps are
+        let shift = self.exp + 2 * t;
+        let (n, d) = if shift >= 0 {
+            (self.num.shl(shift as u32), self.den.clone())
+        } else {
+            (self.num.clone(), self.den.shl((-shift) as u32))
+        };
+        let (y, rem) = n.div_rem(&d);
+        let root = y.isqrt();
+
+        if rem.is_zero() && root.mul(&root) == y {
+            Rational { negative: false, num: root, den: BigUint::one(), exp: -t }
+        } else {
+            Rational { negative: false, num: root.shl(1).add(&BigUint::one()), den: BigUint::one(), exp: -t - 1 }
+        }
+    }
+
+    /// Compares |self| with 2^k.
+    pub fn cmp_magnitude_pow2(&self, k: i32) -> Ordering {
+        // |self| vs 2^k  <=>  num * 2^exp vs den * 2^k
+        let shift = k - self.exp;
+        if shift >= 0 {
+            self.num.cmp(&self.den.shl(shift as u32))
+        } else {
+            self.num.shl((-shift) as u32).cmp(&self.den)
+        }
+    }
+
+    /// Rounds to `format` under `mode`, returning the bit pattern. Faithful rounding is carried
+    /// out as ties to even, as everywhere else in the crate.
+    pub fn round(&self, format: Format, mode: Round) -> u128 {
+        let sign = if self.negative { format.sign_bit() } else { 0 };
+        if self.is_zero() {
+            return sign;
+        }
+
+        let precision = format.frac_bits + 1;
+
+        // floor(log2(|self|)), from an estimate that is off by at most one
+        let mut e = self.num.bits() as i32 - self.den.bits() as i32 + self.exp;
+        if self.cmp_magnitude_pow2(e) == Ordering::Less {
+            e -= 1;
+        }
+
+        // Exponent of the last place of the result, as if the exponent range had no upper bound
+        let mut quantum = e.max(format.min_exp()) - format.frac_bits as i32;
+
+        // |self| / 2^quantum = n / d, whose integer part m fits in precision bits
+        let shift = self.exp - quantum;
+        let (mut n, d) = if shift >= 0 {
+            (self.num.shl(shift as u32), self.den.clone())
+        } else {
+            (self.num.clone(), self.den.shl((-shift) as u32))
+        };
+
+        let mut m = 0u128;
+        if self.den == BigUint::one() {
+            // d is a power of two, so m can be read off directly
+            let big_m = n.shr(d.bits() - 1);
+            n = n.sub(&big_m.shl(d.bits() - 1));
+            m = big_m.low_u128();
+        } else {
+            for i in (0..=precision).rev() {
+                let t = d.shl(i);
+                if n >= t {
+                    n = n.sub(&t);
+                    m |= 1 << i;
+                }
+            }
+        }
+        debug_assert!(m < 1 << precision);
+
+        // n is now the remainder, so the discarded fraction is n / d
+        let inexact = !n.is_zero();
+        let vs_half = n.shl(1).cmp(&d);
+
+        let increment = match mode {
+            Round::TowardZero => false,
+            Round::TowardPInf => inexact && !self.negative,
+            Round::TowardNInf => inexact && self.negative,
+            Round::TiesToEven | Round::Faithful => vs_half == Ordering::Greater || (vs_half == Ordering::Equal && m & 1 == 1),
+            Round::TiesAway => vs_half != Ordering::Less,
+            Round::TiesToOdd => vs_half == Ordering::Greater || (vs_half == Ordering::Equal && m & 1 == 0),
+        };
+
+        if increment {
+            m += 1;
+            if m == 1 << precision {
+                m >>= 1;
+                quantum += 1;
+            }
+        }
+
+        if m == 0 {
+            return sign;
+        }
+
+        // Exponent of the leading bit of the rounded value
+        let top = quantum + 127 - m.leading_zeros() as i32;
+        if top > format.max_exp() {
+            let to_inf = match mode {
+                Round::TowardZero => false,
+                Round::TowardPInf => !self.negative,
+                Round::TowardNInf => self.negative,
+                _ => true
+            };
+
+            return sign | if to_inf { format.inf_bits() } else { format.max_bits() };
+        }
+
+        let implicit = 1u128 << format.frac_bits;
+        if m < implicit {
+            // Subnormal, so the exponent field is zero
+            return sign | m;
+        }
+
+        sign | (((top + format.bias()) as u128) << format.frac_bits) | (m - implicit)
+    }
+}
+
+/// A floating-point datum, decoded into an exact value where possible
+#[derive(Clone, Debug)]
+pub enum Value {
+    NaN,
+    Inf(bool),
+    Finite(Rational),
+}
+
+impl Value {
+    pub fn decode(format: Format, bits: u128) -> Value {
+        let negative = bits & format.sign_bit() != 0;
+        let magnitude = bits & (format.sign_bit() - 1);
+        let frac = magnitude & ((1 << format.frac_bits) - 1);
+        let biased = (magnitude >> format.frac_bits) as i32;
+
+        if magnitude > format.inf_bits() {
+            Value::NaN
+        } else if magnitude == format.inf_bits() {
+            Value::Inf(negative)
+        } else if biased == 0 {
+            Value::Finite(Rational::from_parts(negative, frac, format.min_exp() - format.frac_bits as i32))
+        } else {
+            Value::Finite(Rational::from_parts(negative, frac | (1 << format.frac_bits), biased - format.bias() - format.frac_bits as i32))
+        }
+    }
+
+    pub fn encode(&self, format: Format, mode: Round) -> u128 {
+        match self {
+            Value::NaN => format.inf_bits() | (1 << (format.frac_bits - 1)),
+            Value::Inf(negative) => format.inf_bits() | if *negative { format.sign_bit() } else { 0 },
+            Value::Finite(r) => r.round(format, mode),
+        }
+    }
+}
+
+/// Exact sum, with the sign of an exact zero sum chosen as IEEE 754 prescribes
+fn sum(a: &Rational, b: &Rational, mode: Round) -> Rational {
+    let s = a.add(b);
+    if !s.is_zero() {
+        return s;
+    }
+
+    if a.is_zero() && b.is_zero() && a.negative == b.negative {
+        Rational::zero(a.negative)
+    } else {
+        Rational::zero(mode == Round::TowardNInf)
+    }
+}
+
+pub fn add(a: &Value, b: &Value, mode: Round) -> Value {
+    match (a, b) {
+        (Value::NaN, _) | (_, Value::NaN) => Value::NaN,
+        (Value::Inf(s), Value::Inf(t)) => if s == t { Value::Inf(*s) } else { Value::NaN },
+        (Value::Inf(s), _) | (_, Value::Inf(s)) => Value::Inf(*s),
+        (Value::Finite(x), Value::Finite(y)) => Value::Finite(sum(x, y, mode)),
+    }
+}
+
+pub fn neg(a: &Value) -> Value {
+    match a {
+        Value::NaN => Value::NaN,
+        Value::Inf(s) => Value::Inf(!s),
+        Value::Finite(x) => Value::Finite(x.neg()),
+    }
+}
+
+pub fn mul(a: &Value, b: &Value) -> Value {
+    match (a, b) {
+        (Value::NaN, _) | (_, Value::NaN) => Value::NaN,
+        (Value::Inf(_), Value::Finite(x)) | (Value::Finite(x), Value::Inf(_)) if x.is_zero() => Value::NaN,
+        (Value::Inf(s), Value::Inf(t)) => Value::Inf(s != t),
+        (Value::Inf(s), Value::Finite(x)) | (Value::Finite(x), Value::Inf(s)) => Value::Inf(*s != x.negative),
+        (Value::Finite(x), Value::Finite(y)) => Value::Finite(x.mul(y)),
+    }
+}
+
+pub fn div(a: &Value, b: &Value) -> Value {
+    match (a, b) {
+        (Value::NaN, _) | (_, Value::NaN) | (Value::Inf(_), Value::Inf(_)) => Value::NaN,
+        (Value::Inf(s), Value::Finite(x)) => Value::Inf(*s != x.negative),
+        (Value::Finite(x), Value::Inf(s)) => Value::Finite(Rational::zero(*s != x.negative)),
+        (Value::Finite(x), Value::Finite(y)) if y.is_zero() => {
+            if x.is_zero() { Value::NaN } else { Value::Inf(x.negative != y.negative) }
+        }
+        (Value::Finite(x), Value::Finite(y)) => Value::Finite(x.div(y)),
+    }
+}
+
+/// Square root, rounded to `format`, whose precision decides how much of the root to compute
+pub fn sqrt(a: &Value, format: Format) -> Value {
+    match a {
+        Value::NaN | Value::Inf(true) => Value::NaN,
+        Value::Inf(false) => Value::Inf(false),
+        // sqrt(-0) = -0
+        Value::Finite(x) if x.is_zero() => Value::Finite(x.clone()),
+        Value::Finite(x) if x.negative => Value::NaN,
+        Value::Finite(x) => Value::Finite(x.sqrt(format.frac_bits + 1)),
+    }
+}
+
+/// a * b + c with a single rounding
+pub fn fma(a: &Value, b: &Value, c: &Value, mode: Round) -> Value {
+    return add(&mul(a, b), c, mode);
+}
+
+/// Defines f64 and f32 wrappers of a binary operation in terms of its exact counterpart.
+macro_rules! binary_op {
+    ($name_f64:ident, $name_f32:ident, |$a:ident, $b:ident, $mode:ident| $exact:expr) => {
+        pub fn $name_f64(a: f64, b: f64, mode: Round) -> f64 {
+            let $a = Value::decode(Format::BINARY64, a.to_bits() as u128);
+            let $b = Value::decode(Format::BINARY64, b.to_bits() as u128);
+            let $mode = mode;
+
+            f64::from_bits($exact.encode(Format::BINARY64, mode) as u64)
+        }
+
+        pub fn $name_f32(a: f32, b: f32, mode: Round) -> f32 {
+            let $a = Value::decode(Format::BINARY32, a.to_bits() as u128);
+            let $b = Value::decode(Format::BINARY32, b.to_bits() as u128);
+            let $mode = mode;
+
+            f32::from_bits($exact.encode(Format::BINARY32, mode) as u32)
+        }
+    };
+}
+
+binary_op!(add_f64, add_f32, |a, b, mode| add(&a, &b, mode));
+binary_op!(sub_f64, sub_f32, |a, b, mode| add(&a, &neg(&b), mode));
+binary_op!(mul_f64, mul_f32, |a, b, _mode| mul(&a, &b));
+binary_op!(div_f64, div_f32, |a, b, _mode| div(&a, &b));
+
+pub fn sqrt_f64(a: f64, mode: Round) -> f64 {
+    let a = Value::decode(Format::BINARY64, a.to_bits() as u128);
+    f64::from_bits(sqrt(&a, Format::BINARY64).encode(Format::BINARY64, mode) as u64)
+}
+
+pub fn sqrt_f32(a: f32, mode: Round) -> f32 {
+    let a = Value::decode(Format::BINARY32, a.to_bits() as u128);
+    f32::from_bits(sqrt(&a, Format::BINARY32).encode(Format::BINARY32, mode) as u32)
+}
+
+pub fn fma_f64(a: f64, b: f64, c: f64, mode: Round) -> f64 {
+    let [a, b, c] = [a, b, c].map(|f| Value::decode(Format::BINARY64, f.to_bits() as u128));
+    f64::from_bits(fma(&a, &b, &c, mode).encode(Format::BINARY64, mode) as u64)
+}
+
+pub fn fma_f32(a: f32, b: f32, c: f32, mode: Round) -> f32 {
+    let [a, b, c] = [a, b, c].map(|f| Value::decode(Format::BINARY32, f.to_bits() as u128));
+    f32::from_bits(fma(&a, &b, &c, mode).encode(Format::BINARY32, mode) as u32)
+}
+
+/// Whether `actual` is an acceptable result given the oracle's bracketing results: identical to
+/// `expected` (any NaN standing for any other), or, for faithful rounding, either neighbor.
+pub fn accepts_f64(expected: impl Fn(Round) -> f64, actual: f64, mode: Round) -> bool {
+    let ok = |e: f64| e.to_bits() == actual.to_bits() || (e.is_nan() && actual.is_nan());
+
+    match mode {
+        Round::Faithful => ok(expected(Round::TowardNInf)) || ok(expected(Round::TowardPInf)),
+        _ => ok(expected(mode))
+    }
+}
+
+pub fn accepts_f32(expected: impl Fn(Round) -> f32, actual: f32, mode: Round) -> bool {
+    let ok = |e: f32| e.to_bits() == actual.to_bits() || (e.is_nan() && actual.is_nan());
+
+    match mode {
+        Round::Faithful => ok(expected(Round::TowardNInf)) || ok(expected(Round::TowardPInf)),
+        _ => ok(expected(mode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native;
+    use crate::successor::*;
+    use crate::test_cases::*;
+
+    #[test]
+    fn test_exact_operations() {
+        let third = Rational::from_parts(false, 1, 0).div(&Rational::from_parts(false, 3, 0));
+        let one = third.add(&third).add(&third);
+        assert_eq!(one.round(Format::BINARY64, Round::TiesToEven), 1f64.to_bits() as u128);
+
+        // 1/3 in every rounding mode
+        let down = 0x3fd5_5555_5555_5555u128;
+        let expected = [down, down, down + 1, down, down, down, down];
+        for (mode, e) in Round::ALL.into_iter().zip(expected) {
+            assert_eq!(third.round(Format::BINARY64, mode), e, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn test_sqrt() {
+        // Exact roots, including subnormal ones, and their signs
+        assert_eq!(sqrt_f64(2.25, Round::TowardZero), 1.5);
+        assert_eq!(sqrt_f64(f64::from_bits(1), Round::TiesToEven), 2f64.powi(-537));
+        assert_eq!(sqrt_f32(f32::from_bits(2), Round::TowardNInf), 2f32.powi(-74));
+        assert!(identical_f64(sqrt_f64(-0., Round::TowardNInf), -0.));
+        assert!(sqrt_f64(-1., Round::TiesToEven).is_nan());
+        assert!(sqrt_f64(f64::NEG_INFINITY, Round::TiesToEven).is_nan());
+        assert_eq!(sqrt_f64(f64::INFINITY, Round::TowardZero), f64::INFINITY);
+
+        // sqrt(2) = 0x1.6a09e667f3bcc908...p0, closer to the float above
+        let (down, up) = (f64::from_bits(0x3ff6_a09e_667f_3bcc), f64::from_bits(0x3ff6_a09e_667f_3bcd));
+        for (mode, e) in Round::ALL.into_iter().zip([up, down, up, down, up, up, up]) {
+            assert!(identical_f64(sqrt_f64(2., mode), e), "{:?}", mode);
+        }
+
+        // (m + 1/2)^2 has an exact root at the midpoint between m and m + 1, which each tie rule
+        // breaks its own way
+        let m = (1u64 << 52) + (1 << 51);
+        let square = Rational::from_parts(false, (2 * m as u128 + 1).pow(2), -2);
+        let root = square.sqrt(53);
+        assert_eq!(root.round(Format::BINARY64, Round::TiesToEven), (m as f64).to_bits() as u128);
+        assert_eq!(root.round(Format::BINARY64, Round::TiesAway), (m as f64 + 1.).to_bits() as u128);
+        assert_eq!(root.round(Format::BINARY64, Round::TiesToOdd), (m as f64 + 1.).to_bits() as u128);
+    }
+
+    #[test]
+    fn test_non_native_modes() {
+        // 1 + 2^-53 is halfway between 1 and its successor, and 1 + 3 * 2^-53 is halfway between
+        // the successor and the one after
+        let one = 1f64;
+        let tie = 2f64.powi(-53);
+
+        assert_eq!(add_f64(one, tie, Round::TiesAway), successor_f64(one));
+        assert_eq!(add_f64(one, tie, Round::TiesToOdd), successor_f64(one));
+        assert_eq!(add_f64(one, 3. * tie, Round::TiesToOdd), successor_f64(one));
+        assert_eq!(add_f64(one, 3. * tie, Round::TiesAway), successor_f64(successor_f64(one)));
+        assert_eq!(add_f64(-one, -tie, Round::TiesAway), -successor_f64(one));
+
+        // Halfway between MAX and the next power of two
+        let half_ulp = 2f64.powi(970);
+        assert_eq!(add_f64(f64::MAX, half_ulp, Round::TiesAway), f64::INFINITY);
+        assert_eq!(add_f64(f64::MAX, half_ulp, Round::TiesToOdd), f64::MAX);
+
+        // Half of the smallest subnormal
+        let min = f32::from_bits(1);
+        assert_eq!(mul_f32(min, 0.5, Round::TiesAway), min);
+        assert_eq!(mul_f32(min, 0.5, Round::TiesToOdd), min);
+        assert_eq!(mul_f32(min, 0.5, Round::TiesToEven).to_bits(), 0);
+        assert_eq!(mul_f32(-min, 0.5, Round::TiesToEven).to_bits(), 0x8000_0000);
+    }
+
+    #[test]
+    fn test_special_values() {
+        let inf = f64::INFINITY;
+
+        assert!(add_f64(inf, -inf, Round::TiesToEven).is_nan());
+        assert!(mul_f64(inf, 0., Round::TiesToEven).is_nan());
+        assert!(div_f64(0., -0., Round::TiesToEven).is_nan());
+        assert!(fma_f64(inf, 0., 1., Round::TiesToEven).is_nan());
+        assert!(fma_f64(inf, 1., -inf, Round::TiesToEven).is_nan());
+        assert_eq!(div_f64(-1., 0., Round::TiesToEven), -inf);
+        assert_eq!(fma_f64(2., 3., inf, Round::TiesToEven), inf);
+
+        // Signs of exact zero results
+        assert_eq!(add_f64(1., -1., Round::TiesToEven).to_bits(), 0);
+        assert_eq!(add_f64(1., -1., Round::TowardNInf).to_bits(), (-0f64).to_bits());
+        assert_eq!(add_f64(-0., -0., Round::TowardPInf).to_bits(), (-0f64).to_bits());
+        assert_eq!(fma_f64(-0., 1., 0., Round::TiesToEven).to_bits(), 0);
+        assert_eq!(fma_f64(-0., 1., -0., Round::TiesToEven).to_bits(), (-0f64).to_bits());
+        assert_eq!(div_f64(-1., inf, Round::TiesToEven).to_bits(), (-0f64).to_bits());
+    }
+
+    #[test]
+    fn test_fma() {
+        // (1 + 2^-30)^2 = 1 + 2^-29 + 2^-60, whose last term is lost with a separate rounding
+        let a = 1. + 2f64.powi(-30);
+        let c = -(1. + 2f64.powi(-29));
+
+        assert_eq!(fma_f64(a, a, c, Round::TiesToEven), 2f64.powi(-60));
+        assert_eq!(fma_f64(a, a, c, Round::TiesToEven), a.mul_add(a, c));
+        assert_eq!(fma_f32(1.5, 1.5, -2.25, Round::TiesToEven), 0.);
+    }
+
+    /// The oracle against the hardware on hard cases, under the modes the hardware supports
+    #[test]
+    fn test_against_native() {
+        type Ops64 = [(fn(f64, f64, Round) -> f64, fn(f64, f64, Round) -> f64); 4];
+        type Ops32 = [(fn(f32, f32, Round) -> f32, fn(f32, f32, Round) -> f32); 4];
+
+        let ops_f64: Ops64 = [(add_f64, native::add_f64), (sub_f64, native::sub_f64), (mul_f64, native::mul_f64), (div_f64, native::div_f64)];
+        let ops_f32: Ops32 = [(add_f32, native::add_f32), (sub_f32, native::sub_f32), (mul_f32, native::mul_f32), (div_f32, native::div_f32)];
+
+        for (a, b) in hard_f64_pairs(SEED, 2000) {
+            for mode in Round::ALL.into_iter().filter(Round::is_native) {
+                for (i, (oracle, native)) in ops_f64.iter().enumerate() {
+                    let e = native(a, b, mode);
+                    assert!(accepts_f64(|mode| oracle(a, b, mode), e, mode), "op {}, a = {:e}, b = {:e}, mode = {:?}", i, a, b, mode);
+                }
+            }
+        }
+
+        for (a, b) in hard_f32_pairs(SEED, 2000) {
+            for mode in Round::ALL.into_iter().filter(Round::is_native) {
+                for (i, (oracle, native)) in ops_f32.iter().enumerate() {
+                    let e = native(a, b, mode);
+                    assert!(accepts_f32(|mode| oracle(a, b, mode), e, mode), "op {}, a = {:e}, b = {:e}, mode = {:?}", i, a, b, mode);
+                }
+            }
+        }
+
+        // sqrt, on every kind of input and on random significands with either exponent parity
+        let mut rng = SplitMix64::new(SEED);
+        let inputs = RANDOM_F64.iter().copied().chain((0..2000).map(|_| f64::from_bits(rng.next_u64() >> 1)));
+        for a in inputs {
+            for mode in Round::ALL.into_iter().filter(Round::is_native) {
+                assert!(accepts_f64(|mode| sqrt_f64(a, mode), native::sqrt_f64(a, mode), mode), "sqrt({:e}), mode = {:?}", a, mode);
+                let a = a as f32;
+                assert!(accepts_f32(|mode| sqrt_f32(a, mode), native::sqrt_f32(a, mode), mode), "sqrt({:e}), mode = {:?}", a, mode);
+            }
+        }
+
+        for &a in RANDOM_F64.iter().step_by(97) {
+            for &b in RANDOM_F64.iter().step_by(101) {
+                let c = a * 0.75;
+                assert!(accepts_f64(|mode| fma_f64(a, b, c, mode), a.mul_add(b, c), Round::TiesToEven), "fma({:e}, {:e}, {:e})", a, b, c);
+            }
+        }
+    }
+}
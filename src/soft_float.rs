@@ -0,0 +1,468 @@
+// Software floats in custom binary formats, for simulating the numerics of hypothetical hardware.
+// A format is given by the widths of its exponent and trailing mantissa fields, laid out like an
+// IEEE 754 interchange format, and optionally without subnormals. Every value of a format no
+// wider than f64 in either field is exact in f64, so operations decompose their operands as f64s
+// and round the exact result once, straight to the format.
+
+use std::cmp::Ordering;
+use std::fmt;
+use crate::f128::F128;
+use crate::fallback::*;
+use crate::modes::*;
+use crate::successor::FloatParts;
+
+/// Float with `EXP_BITS` exponent bits and `MANT_BITS` trailing mantissa bits, stored as its bit
+/// pattern in the low `EXP_BITS + MANT_BITS + 1` bits of a u64. Formats may have 2 to 11
+/// exponent bits and 1 to 52 mantissa bits; others fail to compile once used.
+///
+/// Without `SUBNORMALS`, the format's values jump from zero to `MIN_POSITIVE`, and results
+/// between them round to one or the other under the rounding mode like any other neighbors.
+/// Bit patterns that would encode subnormals read as zeros of the same sign.
+#[derive(Copy, Clone, Default)]
+pub struct SoftFloat<const EXP_BITS: u32, const MANT_BITS: u32, const SUBNORMALS: bool = true>(u64);
+
+/// NVIDIA's TensorFloat-32, with f32's range and binary16's precision
+pub type TF32 = SoftFloat<8, 10>;
+
+impl<const EXP_BITS: u32, const MANT_BITS: u32, const SUBNORMALS: bool> SoftFloat<EXP_BITS, MANT_BITS, SUBNORMALS> {
+    const FORMAT: Format = {
+        assert!(EXP_BITS >= 2 && EXP_BITS <= 11, "SoftFloat needs 2 to 11 exponent bits");
+        assert!(MANT_BITS >= 1 && MANT_BITS <= 52, "SoftFloat needs 1 to 52 mantissa bits");
+        Format { exp_bits: EXP_BITS, frac_bits: MANT_BITS }
+    };
+    const SIGN_MASK: u64 = Self::FORMAT.sign_bit() as u64;
+    const INF_BITS: u64 = Self::FORMAT.inf_bits() as u64;
+
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self((Self::FORMAT.bias() as u64) << MANT_BITS);
+    pub const INFINITY: Self = Self(Self::INF_BITS);
+    pub const NEG_INFINITY: Self = Self(Self::INF_BITS | Self::SIGN_MASK);
+    pub const NAN: Self = Self(Self::INF_BITS | (1 << (MANT_BITS - 1)));
+    /// Largest finite value
+    pub const MAX: Self = Self(Self::INF_BITS - 1);
+    /// Smallest finite value, -MAX
+    pub const MIN: Self = Self((Self::INF_BITS - 1) | Self::SIGN_MASK);
+    /// Smallest positive normal value
+    pub const MIN_POSITIVE: Self = Self(1 << MANT_BITS);
+    /// Difference between 1 and the next larger value
+    pub const EPSILON: Self = Self(((Self::FORMAT.bias() - MANT_BITS as i32) as u64) << MANT_BITS);
+
+    /// Makes a value from its bit pattern, which must fit in the format's width.
+    pub const fn from_bits(bits: u64) -> Self {
+        debug_assert!(bits & !(Self::SIGN_MASK | (Self::SIGN_MASK - 1)) == 0);
+        Self(bits)
+    }
+
+    pub const fn to_bits(self) -> u64 {
+        self.0
+    }
+
+    pub fn is_nan(self) -> bool {
+        self.0 & !Self::SIGN_MASK > Self::INF_BITS
+    }
+
+    pub fn is_infinite(self) -> bool {
+        self.0 & !Self::SIGN_MASK == Self::INF_BITS
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.0 & !Self::SIGN_MASK < Self::INF_BITS
+    }
+
+    /// Whether the value is a zero, which includes subnormal patterns without `SUBNORMALS`.
+    pub fn is_zero(self) -> bool {
+        let abs = self.0 & !Self::SIGN_MASK;
+        abs == 0 || (!SUBNORMALS && abs < Self::MIN_POSITIVE.0)
+    }
+
+    pub fn is_sign_negative(self) -> bool {
+        self.0 & Self::SIGN_MASK != 0
+    }
+
+    pub fn abs(self) -> Self {
+        Self(self.0 & !Self::SIGN_MASK)
+    }
+
+    /// Rounds an exact value to the format.
+    fn round(exact: Exact, mode: Round) -> Self {
+        if !SUBNORMALS && exact.mant != 0 {
+            let top = exact.exp + 127 - exact.mant.leading_zeros() as i32;
+
+            // Below MIN_POSITIVE, count in units of it, which leaves 0 or 1
+            if top < Self::FORMAT.min_exp() {
+                let shift = (Self::FORMAT.min_exp() - exact.exp) as u32;
+                let units = round_shift(exact.negative, exact.mant, shift, exact.sticky, mode) as u64;
+                let sign = if exact.negative { Self::SIGN_MASK } else { 0 };
+                return Self(sign | units << MANT_BITS);
+            }
+        }
+
+        Self(exact.round(Self::FORMAT, mode) as u64)
+    }
+
+    /// Converts an f64 under the given rounding mode. NaNs keep their sign but not their payload.
+    pub fn from_f64(f: f64, mode: Round) -> Self {
+        let sign = if f.is_sign_negative() { Self::SIGN_MASK } else { 0 };
+
+        if f.is_nan() {
+            return Self(sign | Self::NAN.0);
+        } else if f.is_infinite() {
+            return Self(sign | Self::INF_BITS);
+        }
+
+        let parts = FloatParts::of_f64(f);
+        Self::round(Exact { negative: parts.negative, mant: parts.mant as u128, exp: parts.exp, sticky: false }, mode)
+    }
+
+    /// Converts an f32 under the given rounding mode.
+    pub fn from_f32(f: f32, mode: Round) -> Self {
+        Self::from_f64(f as f64, mode)
+    }
+
+    /// Converts a binary128 value under the given rounding mode, with a single rounding, so that
+    /// values computed exactly in binary128 can be rounded to the format directly.
+    pub fn from_f128(f: F128, mode: Round) -> Self {
+        if !f.is_finite() {
+            return Self::from_f64(f.to_f64(Round::TiesToEven), mode);
+        }
+
+        let bits = f.to_bits();
+        let biased = ((bits >> 112) & 0x7fff) as i32;
+        let frac = bits & ((1 << 112) - 1);
+        let mant = if biased == 0 { frac } else { frac | (1 << 112) };
+
+        Self::round(Exact { negative: f.is_sign_negative(), mant, exp: biased.max(1) - 16383 - 112, sticky: false }, mode)
+    }
+
+    /// Converts to an f64, which is always exact.
+    pub fn to_f64(self) -> f64 {
+        let sign = if self.is_sign_negative() { -1. } else { 1. };
+        if self.is_nan() {
+            return sign * f64::NAN;
+        } else if self.is_infinite() {
+            return sign * f64::INFINITY;
+        } else if self.is_zero() {
+            return sign * 0.;
+        }
+
+        let abs = self.0 & !Self::SIGN_MASK;
+        let frac = abs & ((1 << MANT_BITS) - 1);
+        let biased = (abs >> MANT_BITS) as i32;
+        let bias = Self::FORMAT.bias();
+
+        let (mant, exp) = if biased == 0 {
+            (frac, 1 - bias - MANT_BITS as i32)
+        } else {
+            (frac | (1 << MANT_BITS), biased - bias - MANT_BITS as i32)
+        };
+
+        let bits = round_to_format(Format::BINARY64, false, mant as u128, exp, false, Round::TiesToEven) as u64;
+        sign * f64::from_bits(bits)
+    }
+
+    /// Converts to an f32 under the given rounding mode.
+    pub fn to_f32(self, mode: Round) -> f32 {
+        let f = self.to_f64();
+        if !f.is_finite() {
+            return f as f32;
+        }
+
+        let parts = FloatParts::of_f64(f);
+        f32::from_bits(round_to_format(Format::BINARY32, parts.negative, parts.mant as u128, parts.exp, false, mode) as u32)
+    }
+
+    /// Computes the sum of two values under the given rounding mode.
+    pub fn add(self, other: Self, mode: Round) -> Self {
+        let (a, b) = (self.to_f64(), other.to_f64());
+        if !a.is_finite() || !b.is_finite() {
+            return Self::from_f64(a + b, mode);
+        }
+
+        Self::round(add_exact(FloatParts::of_f64(a), FloatParts::of_f64(b), mode), mode)
+    }
+
+    /// Computes the difference of two values under the given rounding mode.
+    pub fn sub(self, other: Self, mode: Round) -> Self {
+        self.add(-other, mode)
+    }
+
+    /// Computes the product of two values under the given rounding mode.
+    pub fn mul(self, other: Self, mode: Round) -> Self {
+        let (a, b) = (self.to_f64(), other.to_f64());
+        if !a.is_finite() || !b.is_finite() || a == 0. || b == 0. {
+            return Self::from_f64(a * b, mode);
+        }
+
+        Self::round(mul_exact(FloatParts::of_f64(a), FloatParts::of_f64(b)), mode)
+    }
+
+    /// Computes the quotient of two values under the given rounding mode.
+    pub fn div(self, other: Self, mode: Round) -> Self {
+        let (a, b) = (self.to_f64(), other.to_f64());
+        if !a.is_finite() || !b.is_finite() || a == 0. || b == 0. {
+            return Self::from_f64(a / b, mode);
+        }
+
+        Self::round(div_exact(FloatParts::of_f64(a), FloatParts::of_f64(b)), mode)
+    }
+
+    /// Computes the square root under the given rounding mode.
+    pub fn sqrt(self, mode: Round) -> Self {
+        let a = self.to_f64();
+        if a.is_nan() || a <= 0. || a == f64::INFINITY {
+            return Self::from_f64(a.sqrt(), mode);
+        }
+
+        let parts = FloatParts::of_f64(a);
+        Self::round(sqrt_exact(parts.mant, parts.exp), mode)
+    }
+
+    /// Returns the least value greater than this one, with the same conventions as
+    /// `successor_f64`.
+    pub fn successor(self) -> Self {
+        if self.is_nan() || self.0 == Self::INF_BITS {
+            return self;
+        }
+
+        let smallest = if SUBNORMALS { 1 } else { Self::MIN_POSITIVE.0 };
+        if self.is_zero() {
+            Self(smallest)
+        } else if !self.is_sign_negative() {
+            Self(self.0 + 1)
+        } else if self.0 & !Self::SIGN_MASK == smallest {
+            Self(Self::SIGN_MASK)
+        } else {
+            Self(self.0 - 1)
+        }
+    }
+
+    /// Returns the greatest value less than this one, with the same conventions as
+    /// `predecessor_f64`.
+    pub fn predecessor(self) -> Self {
+        -(-self).successor()
+    }
+}
+
+impl<const EXP_BITS: u32, const MANT_BITS: u32, const SUBNORMALS: bool> std::ops::Neg for SoftFloat<EXP_BITS, MANT_BITS, SUBNORMALS> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0 ^ Self::SIGN_MASK)
+    }
+}
+
+impl<const EXP_BITS: u32, const MANT_BITS: u32, const SUBNORMALS: bool> PartialEq for SoftFloat<EXP_BITS, MANT_BITS, SUBNORMALS> {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_f64() == other.to_f64()
+    }
+}
+
+impl<const EXP_BITS: u32, const MANT_BITS: u32, const SUBNORMALS: bool> PartialOrd for SoftFloat<EXP_BITS, MANT_BITS, SUBNORMALS> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.to_f64().partial_cmp(&other.to_f64())
+    }
+}
+
+impl<const EXP_BITS: u32, const MANT_BITS: u32, const SUBNORMALS: bool> fmt::Display for SoftFloat<EXP_BITS, MANT_BITS, SUBNORMALS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
+
+impl<const EXP_BITS: u32, const MANT_BITS: u32, const SUBNORMALS: bool> fmt::Debug for SoftFloat<EXP_BITS, MANT_BITS, SUBNORMALS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SoftFloat<{}, {}>({:?})", EXP_BITS, MANT_BITS, self.to_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::half::*;
+    use crate::oracle::{self, Value};
+    use crate::test_cases::*;
+
+    type Binary16 = SoftFloat<5, 10>;
+    type Bfloat16 = SoftFloat<8, 7>;
+    type Binary32 = SoftFloat<8, 23>;
+    type Binary64 = SoftFloat<11, 52>;
+
+    /// Operations under test, with their exact counterparts
+    type Op<T> = (&'static str, fn(T, T, Round) -> T, fn(&Value, &Value, Round) -> Value);
+
+    fn ops<const E: u32, const M: u32, const S: bool>() -> [Op<SoftFloat<E, M, S>>; 4] {
+        [
+            ("add", SoftFloat::add, oracle::add),
+            ("sub", SoftFloat::sub, |a, b, mode| oracle::add(a, &oracle::neg(b), mode)),
+            ("mul", SoftFloat::mul, |a, b, _| oracle::mul(a, b)),
+            ("div", SoftFloat::div, |a, b, _| oracle::div(a, b)),
+        ]
+    }
+
+    /// Rounds an oracle value to a format without subnormals, which differs from the oracle's own
+    /// rounding only between zero and the smallest normal value.
+    fn encode_without_subnormals(format: Format, v: &Value, mode: Round) -> u128 {
+        let Value::Finite(r) = v else {
+            return v.encode(format, mode);
+        };
+
+        let min_exp = format.min_exp();
+        if r.is_zero() || r.cmp_magnitude_pow2(min_exp) != Ordering::Less {
+            return v.encode(format, mode);
+        }
+
+        let sign = if r.negative { format.sign_bit() } else { 0 };
+        let up = match mode {
+            Round::TiesToEven | Round::Faithful => r.cmp_magnitude_pow2(min_exp - 1) == Ordering::Greater,
+            Round::TiesAway | Round::TiesToOdd => r.cmp_magnitude_pow2(min_exp - 1) != Ordering::Less,
+            Round::TowardZero => false,
+            Round::TowardPInf => !r.negative,
+            Round::TowardNInf => r.negative,
+        };
+
+        sign | if up { 1 << format.frac_bits } else { 0 }
+    }
+
+    /// Checks every operation on every pair of values of a small format against the oracle.
+    fn check_exhaustive<const E: u32, const M: u32, const S: bool>() {
+        let format = Format { exp_bits: E, frac_bits: M };
+        let count = 1u64 << (E + M + 1);
+        let encode = |v: &Value, mode| if S { v.encode(format, mode) } else { encode_without_subnormals(format, v, mode) };
+        let decode = |bits: u64| match Value::decode(format, bits as u128) {
+            Value::Finite(r) if !S && r.cmp_magnitude_pow2(format.min_exp()) == Ordering::Less => Value::Finite(oracle::Rational::zero(r.negative)),
+            v => v,
+        };
+
+        for a in 0..count {
+            for b in 0..count {
+                let (x, y) = (SoftFloat::<E, M, S>::from_bits(a), SoftFloat::<E, M, S>::from_bits(b));
+                let (u, v) = (decode(a), decode(b));
+
+                for mode in Round::ALL {
+                    for (name, f, exact) in ops::<E, M, S>() {
+                        let actual = f(x, y, mode);
+                        let faithful = |mode| encode(&exact(&u, &v, mode), mode) as u64;
+                        let ok = match mode {
+                            Round::Faithful => actual.0 == faithful(Round::TowardNInf) || actual.0 == faithful(Round::TowardPInf),
+                            _ => actual.0 == faithful(mode),
+                        } || (actual.is_nan() && SoftFloat::<E, M, S>::from_bits(faithful(mode)).is_nan());
+
+                        assert!(ok, "{:?} {}({:#x}, {:#x}) {:?} = {:#x}", actual, name, a, b, mode, actual.0);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(Binary16::MAX.to_f64(), 65504.);
+        assert_eq!(Binary16::EPSILON.to_f64(), 2f64.powi(-10));
+        assert_eq!(Binary64::MIN_POSITIVE.to_f64(), f64::MIN_POSITIVE);
+        assert_eq!(Binary64::from_bits(1).to_f64(), f64::from_bits(1));
+        assert_eq!(TF32::MAX.to_f64(), (2. - 2f64.powi(-10)) * 2f64.powi(127));
+        assert_eq!(SoftFloat::<3, 2>::MAX.to_f64(), 14.);
+        assert_eq!(SoftFloat::<3, 2>::from_bits(1).to_f64(), 0.0625);
+        assert_eq!(SoftFloat::<3, 2, false>::from_bits(1).to_f64(), 0.);
+        assert!(SoftFloat::<3, 2, false>::from_bits(0x23).is_sign_negative() && SoftFloat::<3, 2, false>::from_bits(0x23).is_zero());
+        assert!(Binary16::NAN.is_nan() && Binary16::NAN != Binary16::NAN && Binary16::NEG_INFINITY < Binary16::MIN);
+        assert_eq!(format!("{:?}", TF32::ONE), "SoftFloat<8, 10>(1.0)");
+    }
+
+    #[test]
+    fn test_known_formats() {
+        // Decoding agrees with the dedicated types on every 16-bit pattern
+        for bits in 0..=u16::MAX {
+            let (h, b) = (Binary16::from_bits(bits as u64), Bfloat16::from_bits(bits as u64));
+            assert!(h.to_f64().to_bits() == F16::from_bits(bits).to_f64().to_bits() || h.is_nan(), "{:#06x}", bits);
+            assert!(b.to_f64().to_bits() == BF16::from_bits(bits).to_f64().to_bits() || b.is_nan(), "{:#06x}", bits);
+            assert_eq!((h.is_nan(), b.is_nan()), (F16::from_bits(bits).is_nan(), BF16::from_bits(bits).is_nan()));
+
+            if !h.is_nan() {
+                assert_eq!(h.successor().to_bits(), successor_f16(F16::from_bits(bits)).to_bits() as u64);
+                assert_eq!(b.predecessor().to_bits(), predecessor_bf16(BF16::from_bits(bits)).to_bits() as u64);
+            }
+        }
+
+        // Arithmetic agrees with the fallbacks in the formats they implement
+        let mut rng = SplitMix64::new(SEED);
+        for (a, b) in hard_f64_pairs(SEED, 2000) {
+            for mode in Round::ALL {
+                let (x, y) = (Binary64::from_f64(a, mode), Binary64::from_f64(b, mode));
+                let same = |x: Binary64, f: f64| x.to_f64().to_bits() == f.to_bits() || (x.is_nan() && f.is_nan());
+                assert!(same(x.add(y, mode), add_f64(a, b, mode)), "{:e} + {:e} {:?}", a, b, mode);
+                assert!(same(x.mul(y, mode), mul_f64(a, b, mode)), "{:e} * {:e} {:?}", a, b, mode);
+                assert!(same(x.div(y, mode), div_f64(a, b, mode)), "{:e} / {:e} {:?}", a, b, mode);
+                assert!(same(x.sqrt(mode), sqrt_f64(a, mode)), "sqrt({:e}) {:?}", a, mode);
+
+                let (a, b) = (f32::from_bits(rng.next_u64() as u32), f32::from_bits(rng.next_u64() as u32));
+                let (x, y) = (Binary32::from_f32(a, mode), Binary32::from_f32(b, mode));
+                assert!(x.sub(y, mode).to_f32(mode).to_bits() == sub_f32(a, b, mode).to_bits() || (a - b).is_nan());
+                assert!(x.div(y, mode).to_f32(mode).to_bits() == div_f32(a, b, mode).to_bits() || (a / b).is_nan());
+
+                let (h, g) = (F16::from_bits(a.to_bits() as u16), F16::from_bits(b.to_bits() as u16));
+                let (x, y) = (Binary16::from_bits(h.to_bits() as u64), Binary16::from_bits(g.to_bits() as u64));
+                assert!(x.mul(y, mode).to_bits() == h.mul(g, mode).to_bits() as u64 || h.mul(g, mode).is_nan());
+                assert!(x.sqrt(mode).to_bits() == h.sqrt(mode).to_bits() as u64 || h.sqrt(mode).is_nan());
+            }
+        }
+    }
+
+    #[test]
+    fn test_conversions() {
+        let mut rng = SplitMix64::new(SEED);
+        let tf32 = Format { exp_bits: 8, frac_bits: 10 };
+
+        for _ in 0..10000 {
+            // Values of every magnitude, and binary128 values with more precision than f64
+            let f = f64::from_bits(rng.next_u64());
+            let q = F128::from_f64(f).mul(F128::from_f64(1. + rng.mant(53) as f64 * 2f64.powi(-60)), Round::TiesToEven);
+            let (v, w) = (Value::decode(Format::BINARY64, f.to_bits() as u128), Value::decode(Format::BINARY128, q.to_bits()));
+
+            for mode in Round::ALL {
+                let (x, y) = (TF32::from_f64(f, mode), Bfloat16::from_f128(q, mode));
+                if f.is_nan() {
+                    assert!(x.is_nan() && y.is_nan());
+                    continue;
+                }
+
+                assert!(oracle::accepts_f64(|mode| TF32::from_bits(v.encode(tf32, mode) as u64).to_f64(), x.to_f64(), mode), "{:e} {:?}", f, mode);
+                assert!(oracle::accepts_f64(|mode| BF16::from_bits(w.encode(Format::BFLOAT16, mode) as u16).to_f64(), y.to_f64(), mode), "{:?} {:?}", q, mode);
+                assert!(oracle::accepts_f32(|mode| f32::from_bits(v.encode(Format::BINARY32, mode) as u32), Binary64::from_f64(f, mode).to_f32(mode), mode));
+            }
+        }
+    }
+
+    #[test]
+    fn test_small_formats_exhaustive() {
+        check_exhaustive::<3, 2, true>();
+        check_exhaustive::<3, 2, false>();
+        check_exhaustive::<2, 3, false>();
+        check_exhaustive::<4, 1, true>();
+    }
+
+    #[test]
+    fn test_without_subnormals() {
+        type F = SoftFloat<5, 10, false>;
+        let min = F::MIN_POSITIVE.to_f64();
+
+        // TiesToEven, TowardZero, TowardPInf, TowardNInf, Faithful, TiesAway, TiesToOdd
+        let cases: [(f64, [f64; 7]); 3] = [
+            (min / 2., [0., 0., min, 0., 0., min, min]),
+            (-min * 0.75, [-min, -0., -0., -min, -min, -min, -min]),
+            (min * 2f64.powi(-30), [0., 0., min, 0., 0., 0., 0.]),
+        ];
+
+        for (f, expected) in cases {
+            for (mode, e) in Round::ALL.into_iter().zip(expected) {
+                assert_eq!(F::from_f64(f, mode).to_f64().to_bits(), e.to_bits(), "{} {:?}", f, mode);
+            }
+        }
+
+        assert_eq!(F::ZERO.successor(), F::MIN_POSITIVE);
+        assert_eq!(F::MIN_POSITIVE.predecessor().to_bits(), 0);
+        assert_eq!((-F::MIN_POSITIVE).successor().to_bits(), F::SIGN_MASK);
+        assert_eq!(F::MIN_POSITIVE.mul(F::from_f64(0.5, Round::TiesToEven), Round::TowardPInf), F::MIN_POSITIVE);
+        assert_eq!(F::MIN_POSITIVE.sub(F::MIN_POSITIVE.successor(), Round::TowardNInf).to_f64(), -min);
+    }
+}
@@ -0,0 +1,307 @@
+// Stochastic rounding, which rounds an inexact value up with probability equal to its distance
+// from the float below, divided by the gap between the two floats, so that rounding is unbiased
+// on average. It can't be expressed as a `Round` variant since each rounding draws from a random
+// source, so every function here takes one explicitly.
+
+use crate::fallback::*;
+use crate::half::{BF16, F16};
+use crate::modes::*;
+use crate::successor::FloatParts;
+
+/// Source of uniformly distributed random bits for stochastic rounding
+pub trait RandomSource {
+    fn next_u64(&mut self) -> u64;
+}
+
+/// SplitMix64, a small, fast and deterministic generator, seedable for reproducible results. It
+/// is plenty random for rounding, though not cryptographically secure.
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+}
+
+impl RandomSource for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Rounds an exact value to `format` stochastically, returning its bit pattern. The discarded
+/// fraction is compared against 64 random bits, so the probability of rounding up is exact for
+/// fractions of up to 64 bits and off by at most 2^-64 otherwise. Values too large for the
+/// format round up to infinity with a probability as if it were the float after `MAX`, and
+/// always once they reach `MAX` plus a unit in its last place.
+pub(crate) fn round_stochastic<R: RandomSource + ?Sized>(format: Format, exact: Exact, rng: &mut R) -> u128 {
+    let away = if exact.negative { Round::TowardNInf } else { Round::TowardPInf };
+    if exact.mant == 0 {
+        return exact.round(format, Round::TowardZero);
+    }
+
+    // Locate the rounding position as round_to_format does
+    let top = exact.exp + 127 - exact.mant.leading_zeros() as i32;
+    if top > format.max_exp() {
+        return exact.round(format, away);
+    }
+
+    let lsb = top.max(format.min_exp()) - format.frac_bits as i32;
+    if lsb <= exact.exp {
+        return exact.round(format, Round::TowardZero);
+    }
+
+    // Top 64 bits of the discarded fraction, and whether anything lies below them
+    let shift = (lsb - exact.exp) as u32;
+    let rem = if shift >= 128 { exact.mant } else { exact.mant & ((1 << shift) - 1) };
+    let (fraction, below) = if shift <= 64 {
+        ((rem << (64 - shift)) as u64, exact.sticky)
+    } else if shift - 64 >= 128 {
+        (0, rem != 0 || exact.sticky)
+    } else {
+        let low = shift - 64;
+        ((rem >> low) as u64, rem & ((1 << low) - 1) != 0 || exact.sticky)
+    };
+
+    let r = rng.next_u64();
+    let up = r < fraction || (r == fraction && below);
+
+    exact.round(format, if up { away } else { Round::TowardZero })
+}
+
+/// Rounds an f64 to an f32 stochastically.
+pub fn f64_to_f32<R: RandomSource + ?Sized>(f: f64, rng: &mut R) -> f32 {
+    if !f.is_finite() {
+        return f as f32;
+    }
+
+    let parts = FloatParts::of_f64(f);
+    let exact = Exact { negative: parts.negative, mant: parts.mant as u128, exp: parts.exp, sticky: false };
+    f32::from_bits(round_stochastic(Format::BINARY32, exact, rng) as u32)
+}
+
+/// Rounds an f32 to a bfloat16 stochastically.
+pub fn f32_to_bf16<R: RandomSource + ?Sized>(f: f32, rng: &mut R) -> BF16 {
+    if !f.is_finite() {
+        return BF16::from_f32(f, Round::TiesToEven);
+    }
+
+    let parts = FloatParts::of_f32(f);
+    let exact = Exact { negative: parts.negative, mant: parts.mant as u128, exp: parts.exp, sticky: false };
+    BF16::from_bits(round_stochastic(Format::BFLOAT16, exact, rng) as u16)
+}
+
+/// Rounds an f32 to a binary16 float stochastically.
+pub fn f32_to_f16<R: RandomSource + ?Sized>(f: f32, rng: &mut R) -> F16 {
+    if !f.is_finite() {
+        return F16::from_f32(f, Round::TiesToEven);
+    }
+
+    let parts = FloatParts::of_f32(f);
+    let exact = Exact { negative: parts.negative, mant: parts.mant as u128, exp: parts.exp, sticky: false };
+    F16::from_bits(round_stochastic(Format::BINARY16, exact, rng) as u16)
+}
+
+/// Defines stochastically rounded f64 and f32 versions of a binary operation, given its exact
+/// counterpart, deferring to the hardware when `special` holds since those results are exact.
+macro_rules! stochastic_op {
+    ($(#[$doc:meta])* $name_f64:ident, $name_f32:ident, $op:tt, |$a:ident, $b:ident| $special:expr, $exact:expr) => {
+        $(#[$doc])*
+        pub fn $name_f64<R: RandomSource + ?Sized>($a: f64, $b: f64, rng: &mut R) -> f64 {
+            if $special {
+                return $a $op $b;
+            }
+
+            let ($a, $b) = (FloatParts::of_f64($a), FloatParts::of_f64($b));
+            f64::from_bits(round_stochastic(Format::BINARY64, $exact, rng) as u64)
+        }
+
+        $(#[$doc])*
+        pub fn $name_f32<R: RandomSource + ?Sized>($a: f32, $b: f32, rng: &mut R) -> f32 {
+            if $special {
+                return $a $op $b;
+            }
+
+            let ($a, $b) = (FloatParts::of_f32($a), FloatParts::of_f32($b));
+            f32::from_bits(round_stochastic(Format::BINARY32, $exact, rng) as u32)
+        }
+    };
+}
+
+stochastic_op!(
+    /// Computes the sum of two floats, rounded stochastically. Exact zero sums are +0.
+    add_f64, add_f32, +, |a, b| !a.is_finite() || !b.is_finite(), add_exact(a, b, Round::TiesToEven)
+);
+
+stochastic_op!(
+    /// Computes the difference of two floats, rounded stochastically.
+    sub_f64, sub_f32, -, |a, b| !a.is_finite() || !b.is_finite(), add_exact(a, FloatParts { negative: !b.negative, ..b }, Round::TiesToEven)
+);
+
+stochastic_op!(
+    /// Computes the product of two floats, rounded stochastically.
+    mul_f64, mul_f32, *, |a, b| !a.is_finite() || !b.is_finite() || a == 0. || b == 0., mul_exact(a, b)
+);
+
+stochastic_op!(
+    /// Computes the quotient of two floats, rounded stochastically.
+    div_f64, div_f32, /, |a, b| !a.is_finite() || !b.is_finite() || a == 0. || b == 0., div_exact(a, b)
+);
+
+/// Computes the square root of an f64, rounded stochastically.
+pub fn sqrt_f64<R: RandomSource + ?Sized>(a: f64, rng: &mut R) -> f64 {
+    if a.is_nan() || a <= 0. || a == f64::INFINITY {
+        return a.sqrt();
+    }
+
+    let parts = FloatParts::of_f64(a);
+    f64::from_bits(round_stochastic(Format::BINARY64, sqrt_exact(parts.mant, parts.exp), rng) as u64)
+}
+
+/// Computes the square root of an f32, rounded stochastically.
+pub fn sqrt_f32<R: RandomSource + ?Sized>(a: f32, rng: &mut R) -> f32 {
+    if a.is_nan() || a <= 0. || a == f32::INFINITY {
+        return a.sqrt();
+    }
+
+    let parts = FloatParts::of_f32(a);
+    f32::from_bits(round_stochastic(Format::BINARY32, sqrt_exact(parts.mant, parts.exp), rng) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::f128::F128;
+    use crate::successor::*;
+    use crate::test_cases::SEED;
+
+    /// Number of draws in each statistical test
+    const TRIALS: usize = 100_000;
+
+    /// Rounds `trials` times, asserting that every result is one of the two neighbors and that
+    /// the frequency of rounding up is within six standard deviations of `p`.
+    fn assert_rounds_up_with(p: f64, down: f64, up: f64, mut round: impl FnMut() -> f64) {
+        let mut ups = 0;
+        for _ in 0..TRIALS {
+            let r = round();
+            assert!(r == down || r == up, "{} is neither {} nor {}", r, down, up);
+            ups += (r == up && up != down) as usize;
+        }
+
+        let sigma = (p * (1. - p) / TRIALS as f64).sqrt();
+        let freq = ups as f64 / TRIALS as f64;
+        assert!((freq - p).abs() <= 6. * sigma + 1e-12, "rounded up {} of the time, expected {}", freq, p);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let draw = |seed| {
+            let mut rng = SplitMix64::new(seed);
+            (0..100).map(|i| f64_to_f32(1. + i as f64 * 1e-9, &mut rng)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(draw(SEED), draw(SEED));
+        assert_ne!(draw(SEED), draw(SEED + 1));
+    }
+
+    #[test]
+    fn test_exact_values() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..1000 {
+            assert_eq!(f64_to_f32(1.5, &mut rng), 1.5);
+            assert_eq!(add_f64(0.5, 0.25, &mut rng), 0.75);
+            assert_eq!(sqrt_f32(9., &mut rng), 3.);
+            assert_eq!(f32_to_bf16(-2.5, &mut rng).to_f32(), -2.5);
+            assert_eq!(f32_to_f16(65504., &mut rng).to_f32(), 65504.);
+        }
+
+        assert!(add_f64(1., -1., &mut rng).to_bits() == 0);
+        assert!(mul_f32(-0., 3., &mut rng).is_sign_negative());
+        assert!(div_f64(0., 0., &mut rng).is_nan());
+        assert_eq!(f64_to_f32(f64::NEG_INFINITY, &mut rng), f32::NEG_INFINITY);
+        assert!(f32_to_f16(f32::NAN, &mut rng).is_nan());
+    }
+
+    #[test]
+    fn test_probabilities() {
+        let mut rng = SplitMix64::new(SEED);
+
+        // 1 + 2^-26 lies an eighth of the way from 1 to its f32 successor
+        let x = 1. + 2f64.powi(-26);
+        assert_rounds_up_with(0.125, 1., successor_f32(1.) as f64, || f64_to_f32(x, &mut rng) as f64);
+        // -x is an eighth of an ULP below -1, so it rounds up to -1 seven times out of eight
+        assert_rounds_up_with(0.875, -(successor_f32(1.) as f64), -1., || f64_to_f32(-x, &mut rng) as f64);
+
+        // Quotients whose fractions we get from binary128 approximations
+        let third = F128::ONE.div(F128::from_i64(3), Round::TiesToEven);
+        let (down, up) = (third.to_f64(Round::TowardNInf), third.to_f64(Round::TowardPInf));
+        let p = third.sub(F128::from_f64(down), Round::TiesToEven).to_f64(Round::TiesToEven) / (up - down);
+        assert_rounds_up_with(p, down, up, || div_f64(1., 3., &mut rng));
+
+        let (down, up) = (third.to_f32(Round::TowardNInf) as f64, third.to_f32(Round::TowardPInf) as f64);
+        let p = third.sub(F128::from_f64(down), Round::TiesToEven).to_f64(Round::TiesToEven) / (up - down);
+        assert_rounds_up_with(p, down, up, || div_f32(1., 3., &mut rng) as f64);
+
+        // The f32 nearest 1/3, rounded to the 16-bit formats
+        let (down, up) = (BF16::from_f32(1. / 3., Round::TowardNInf).to_f64(), BF16::from_f32(1. / 3., Round::TowardPInf).to_f64());
+        let p = ((1f32 / 3.) as f64 - down) / (up - down);
+        assert_rounds_up_with(p, down, up, || f32_to_bf16(1. / 3., &mut rng).to_f64());
+
+        let (down, up) = (F16::from_f32(1. / 3., Round::TowardNInf).to_f64(), F16::from_f32(1. / 3., Round::TowardPInf).to_f64());
+        let p = ((1f32 / 3.) as f64 - down) / (up - down);
+        assert_rounds_up_with(p, down, up, || f32_to_f16(1. / 3., &mut rng).to_f64());
+
+        // Halfway between subnormals, and between MAX and the next power of two
+        let tiny = f32::from_bits(1) as f64;
+        assert_rounds_up_with(0.5, 0., tiny, || mul_f32(f32::from_bits(1), 0.5, &mut rng) as f64);
+        let max = f32::MAX;
+        assert_rounds_up_with(0.5, max as f64, f64::INFINITY, || add_f32(max, 2f32.powi(103), &mut rng) as f64);
+        assert_eq!(mul_f32(max, 2., &mut rng), f32::INFINITY);
+
+        // sqrt(2) in f64, whose fraction we get from its binary128 approximation
+        let root = F128::from_i64(2).sqrt(Round::TiesToEven);
+        let (down, up) = (root.to_f64(Round::TowardNInf), root.to_f64(Round::TowardPInf));
+        let p = root.sub(F128::from_f64(down), Round::TiesToEven).to_f64(Round::TiesToEven) / (up - down);
+        assert_rounds_up_with(p, down, up, || sqrt_f64(2., &mut rng));
+    }
+
+    #[test]
+    fn test_unbiased_sum() {
+        // Adding 2^-26 to 1 repeatedly stagnates under round to nearest, since each increment is
+        // below half an ULP, while stochastic rounding tracks the exact sum on average
+        let mut rng = SplitMix64::new(SEED);
+        let increment = 2f32.powi(-26);
+        let n = 1 << 16;
+
+        let (mut nearest, mut stochastic) = (1f32, 1f32);
+        for _ in 0..n {
+            nearest += increment;
+            stochastic = add_f32(stochastic, increment, &mut rng);
+        }
+
+        let exact = 1. + n as f64 * increment as f64;
+        assert_eq!(nearest, 1.);
+        assert!((stochastic as f64 - exact).abs() < 0.02 * (exact - 1.), "{} vs {}", stochastic, exact);
+
+        // The mean error of many independent roundings of random values vanishes
+        let mut error = 0.;
+        let mut values = SplitMix64::new(SEED + 1);
+        for _ in 0..TRIALS {
+            let x = 1. + (values.next_u64() >> 11) as f64 * 2f64.powi(-53);
+            error += (f64_to_f32(x, &mut rng) as f64 - x) / f32::EPSILON as f64;
+        }
+
+        // Each error has variance f(1 - f) for a fraction f, which averages to 1/6
+        let mean = error / TRIALS as f64;
+        assert!(mean.abs() < 6. * (1. / 6. / TRIALS as f64).sqrt(), "mean error {}", mean);
+    }
+}
@@ -1,5 +1,7 @@
 // Getting the predecessor and successor of floats. Source: https://rust-lang.github.io/rfcs/3173-float-next-up-down.html
 
+use std::cmp::Ordering;
+
 /// Returns the least number greater than `f`.
 ///
 /// Let `TINY` be the smallest representable positive `f32`. Then,
@@ -138,6 +140,402 @@ pub fn identical_f32(a: f32, b: f32) -> bool {
     a.to_bits() == b.to_bits()
 }
 
+/// Returns the unit in the last place of `f`, i.e., the gap between `|f|` and the next float of
+/// larger magnitude. For `MAX` this is the gap the next float would have had if the exponent range
+/// were unbounded. Returns `TINY` for zeros, `INFINITY` for infinities and `f` for NaN.
+pub fn ulp_f64(f: f64) -> f64 {
+    const CLEAR_SIGN_MASK: u64 = 0x7fff_ffff_ffff_ffff;
+
+    if f.is_nan() {
+        return f;
+    } else if f.is_infinite() {
+        return f64::INFINITY;
+    }
+
+    let biased = (f.to_bits() & CLEAR_SIGN_MASK) >> 52;
+    if biased > 52 {
+        f64::from_bits((biased - 52) << 52)
+    } else {
+        // The gap is subnormal
+        f64::from_bits(1 << (biased.max(1) - 1))
+    }
+}
+
+/// Returns the unit in the last place of `f`, i.e., the gap between `|f|` and the next float of
+/// larger magnitude. For `MAX` this is the gap the next float would have had if the exponent range
+/// were unbounded. Returns `TINY` for zeros, `INFINITY` for infinities and `f` for NaN.
+pub fn ulp_f32(f: f32) -> f32 {
+    const CLEAR_SIGN_MASK: u32 = 0x7fff_ffff;
+
+    if f.is_nan() {
+        return f;
+    } else if f.is_infinite() {
+        return f32::INFINITY;
+    }
+
+    let biased = (f.to_bits() & CLEAR_SIGN_MASK) >> 23;
+    if biased > 23 {
+        f32::from_bits((biased - 23) << 23)
+    } else {
+        f32::from_bits(1 << (biased.max(1) - 1))
+    }
+}
+
+/// Maps non-NaN floats to integers such that consecutive floats map to consecutive integers. Both
+/// zeros map to 0, and the infinities map one past `MAX` and `-MAX`.
+fn ordinal_f64(f: f64) -> i64 {
+    const CLEAR_SIGN_MASK: u64 = 0x7fff_ffff_ffff_ffff;
+
+    let bits = f.to_bits();
+    let abs = (bits & CLEAR_SIGN_MASK) as i64;
+
+    if bits == abs as u64 { abs } else { -abs }
+}
+
+/// Inverse of `ordinal_f64`. Zero is returned as +0.0.
+fn from_ordinal_f64(ord: i64) -> f64 {
+    let abs = f64::from_bits(ord.unsigned_abs());
+
+    if ord < 0 { -abs } else { abs }
+}
+
+fn ordinal_f32(f: f32) -> i64 {
+    const CLEAR_SIGN_MASK: u32 = 0x7fff_ffff;
+
+    let bits = f.to_bits();
+    let abs = (bits & CLEAR_SIGN_MASK) as i64;
+
+    if bits == abs as u32 { abs } else { -abs }
+}
+
+fn from_ordinal_f32(ord: i64) -> f32 {
+    let abs = f32::from_bits(ord.unsigned_abs() as u32);
+
+    if ord < 0 { -abs } else { abs }
+}
+
+/// Returns the signed number of representable values one must step from `a` to reach `b`, so
+/// `ulp_distance_f64(a, successor_f64(a)) == 1` for all non-NaN `a` other than `INFINITY`. The two
+/// zeros are counted as a single value, so the distance between `-TINY` and `TINY` is 2. The
+/// distance between the two infinities does not fit and saturates to `i64::MAX` or `i64::MIN`.
+///
+/// # Panics
+///
+/// Panics if either argument is NaN.
+pub fn ulp_distance_f64(a: f64, b: f64) -> i64 {
+    assert!(!a.is_nan() && !b.is_nan(), "ULP distance is undefined for NaN");
+
+    ordinal_f64(b).saturating_sub(ordinal_f64(a))
+}
+
+/// Returns the signed number of representable values one must step from `a` to reach `b`, so
+/// `ulp_distance_f32(a, successor_f32(a)) == 1` for all non-NaN `a` other than `INFINITY`. The two
+/// zeros are counted as a single value, so the distance between `-TINY` and `TINY` is 2.
+///
+/// # Panics
+///
+/// Panics if either argument is NaN.
+pub fn ulp_distance_f32(a: f32, b: f32) -> i64 {
+    assert!(!a.is_nan() && !b.is_nan(), "ULP distance is undefined for NaN");
+
+    ordinal_f32(b) - ordinal_f32(a)
+}
+
+/// Returns the next float after `f` in the direction of `toward`, like C's `nextafter`. If either
+/// argument is NaN, the result is NaN; if `f == toward`, `toward` is returned.
+pub fn next_after_f64(f: f64, toward: f64) -> f64 {
+    if f.is_nan() || toward.is_nan() {
+        return f + toward;
+    }
+
+    if f < toward {
+        successor_f64(f)
+    } else if f > toward {
+        predecessor_f64(f)
+    } else {
+        toward
+    }
+}
+
+/// Returns the next float after `f` in the direction of `toward`, like C's `nextafter`. If either
+/// argument is NaN, the result is NaN; if `f == toward`, `toward` is returned.
+pub fn next_after_f32(f: f32, toward: f32) -> f32 {
+    if f.is_nan() || toward.is_nan() {
+        return f + toward;
+    }
+
+    if f < toward {
+        successor_f32(f)
+    } else if f > toward {
+        predecessor_f32(f)
+    } else {
+        toward
+    }
+}
+
+/// Steps `n` representable values from `f`, upward for positive `n` and downward for negative
+/// `n`, so `step_ulps_f64(f, 1) == successor_f64(f)`. The two zeros count as a single value; a step
+/// landing on zero returns the zero with the sign of `f`, matching `successor_f64` and
+/// `predecessor_f64`. Steps past `MAX` or `-MAX` saturate to the corresponding infinity, and NaN is
+/// returned unchanged.
+pub fn step_ulps_f64(f: f64, n: i64) -> f64 {
+    const INF_ORDINAL: i64 = 0x7ff0_0000_0000_0000;
+
+    if f.is_nan() || n == 0 {
+        return f;
+    }
+
+    let ord = ordinal_f64(f).saturating_add(n).clamp(-INF_ORDINAL, INF_ORDINAL);
+
+    if ord == 0 {
+        0f64.copysign(f)
+    } else {
+        from_ordinal_f64(ord)
+    }
+}
+
+/// Steps `n` representable values from `f`, upward for positive `n` and downward for negative
+/// `n`, so `step_ulps_f32(f, 1) == successor_f32(f)`. The two zeros count as a single value; a step
+/// landing on zero returns the zero with the sign of `f`, matching `successor_f32` and
+/// `predecessor_f32`. Steps past `MAX` or `-MAX` saturate to the corresponding infinity, and NaN is
+/// returned unchanged.
+pub fn step_ulps_f32(f: f32, n: i64) -> f32 {
+    const INF_ORDINAL: i64 = 0x7f80_0000;
+
+    if f.is_nan() || n == 0 {
+        return f;
+    }
+
+    let ord = ordinal_f32(f).saturating_add(n).clamp(-INF_ORDINAL, INF_ORDINAL);
+
+    if ord == 0 {
+        0f32.copysign(f)
+    } else {
+        from_ordinal_f32(ord)
+    }
+}
+
+/// Maps a float's bit pattern to an integer such that comparing the integers agrees with
+/// `total_order_f64`. This is a bijection, with inverse `from_ordered_bits_f64`: `-NaN` maps to
+/// the smallest keys, followed by `NEG_INFINITY`, the negative numbers, -0.0, +0.0, the positive
+/// numbers, `INFINITY` and finally `NaN`.
+pub fn to_ordered_bits_f64(f: f64) -> u64 {
+    const SIGN_MASK: u64 = 0x8000_0000_0000_0000;
+
+    let bits = f.to_bits();
+    if bits & SIGN_MASK != 0 { !bits } else { bits | SIGN_MASK }
+}
+
+/// Inverse of `to_ordered_bits_f64`.
+pub fn from_ordered_bits_f64(key: u64) -> f64 {
+    const SIGN_MASK: u64 = 0x8000_0000_0000_0000;
+
+    f64::from_bits(if key & SIGN_MASK != 0 { key & !SIGN_MASK } else { !key })
+}
+
+/// Maps a float's bit pattern to an integer such that comparing the integers agrees with
+/// `total_order_f32`. This is a bijection, with inverse `from_ordered_bits_f32`.
+pub fn to_ordered_bits_f32(f: f32) -> u32 {
+    const SIGN_MASK: u32 = 0x8000_0000;
+
+    let bits = f.to_bits();
+    if bits & SIGN_MASK != 0 { !bits } else { bits | SIGN_MASK }
+}
+
+/// Inverse of `to_ordered_bits_f32`.
+pub fn from_ordered_bits_f32(key: u32) -> f32 {
+    const SIGN_MASK: u32 = 0x8000_0000;
+
+    f32::from_bits(if key & SIGN_MASK != 0 { key & !SIGN_MASK } else { !key })
+}
+
+/// Compares two floats according to the IEEE 754 totalOrder predicate, which orders
+/// -NaN < `NEG_INFINITY` < negative numbers < -0.0 < +0.0 < positive numbers < `INFINITY` < NaN,
+/// with NaNs ordered by payload. Two floats compare equal exactly when they are `identical_f64`.
+pub fn total_order_f64(a: f64, b: f64) -> Ordering {
+    to_ordered_bits_f64(a).cmp(&to_ordered_bits_f64(b))
+}
+
+/// Compares two floats according to the IEEE 754 totalOrder predicate. Two floats compare equal
+/// exactly when they are `identical_f32`.
+pub fn total_order_f32(a: f32, b: f32) -> Ordering {
+    to_ordered_bits_f32(a).cmp(&to_ordered_bits_f32(b))
+}
+
+/// Iterator over every float between two endpoints in total order, created by `float_range_f64`.
+#[derive(Clone, Debug)]
+pub struct FloatRangeF64 {
+    /// Ordered key of the next value to yield from the front
+    next: u64,
+    /// Ordered key of the last value to yield from the back
+    last: u64,
+    done: bool,
+}
+
+/// Returns an iterator over every float `f` with `a <= f <= b` in total order, i.e., every
+/// representable value between the two endpoints, inclusive. Both zeros are yielded if the range
+/// spans zero, and NaNs only if an endpoint is NaN. The range is empty if `a` comes after `b`.
+pub fn float_range_f64(a: f64, b: f64) -> FloatRangeF64 {
+    let next = to_ordered_bits_f64(a);
+    let last = to_ordered_bits_f64(b);
+
+    FloatRangeF64 { next, last, done: next > last }
+}
+
+impl Iterator for FloatRangeF64 {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        if self.done {
+            return None;
+        }
+
+        let f = from_ordered_bits_f64(self.next);
+        if self.next == self.last {
+            self.done = true;
+        } else {
+            self.next += 1;
+        }
+
+        Some(f)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+
+        match usize::try_from(self.last - self.next).ok().and_then(|n| n.checked_add(1)) {
+            Some(n) => (n, Some(n)),
+            None => (usize::MAX, None),
+        }
+    }
+}
+
+impl DoubleEndedIterator for FloatRangeF64 {
+    fn next_back(&mut self) -> Option<f64> {
+        if self.done {
+            return None;
+        }
+
+        let f = from_ordered_bits_f64(self.last);
+        if self.next == self.last {
+            self.done = true;
+        } else {
+            self.last -= 1;
+        }
+
+        Some(f)
+    }
+}
+
+/// Iterator over every float between two endpoints in total order, created by `float_range_f32`.
+#[derive(Clone, Debug)]
+pub struct FloatRangeF32 {
+    next: u32,
+    last: u32,
+    done: bool,
+}
+
+/// Returns an iterator over every float `f` with `a <= f <= b` in total order, i.e., every
+/// representable value between the two endpoints, inclusive. The range is empty if `a` comes after
+/// `b`. `float_range_f32(f32::NEG_INFINITY, f32::INFINITY)` enumerates every non-NaN `f32`.
+pub fn float_range_f32(a: f32, b: f32) -> FloatRangeF32 {
+    let next = to_ordered_bits_f32(a);
+    let last = to_ordered_bits_f32(b);
+
+    FloatRangeF32 { next, last, done: next > last }
+}
+
+impl Iterator for FloatRangeF32 {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.done {
+            return None;
+        }
+
+        let f = from_ordered_bits_f32(self.next);
+        if self.next == self.last {
+            self.done = true;
+        } else {
+            self.next += 1;
+        }
+
+        Some(f)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            return (0, Some(0));
+        }
+
+        match usize::try_from(self.last - self.next).ok().and_then(|n| n.checked_add(1)) {
+            Some(n) => (n, Some(n)),
+            None => (usize::MAX, None),
+        }
+    }
+}
+
+impl DoubleEndedIterator for FloatRangeF32 {
+    fn next_back(&mut self) -> Option<f32> {
+        if self.done {
+            return None;
+        }
+
+        let f = from_ordered_bits_f32(self.last);
+        if self.next == self.last {
+            self.done = true;
+        } else {
+            self.last -= 1;
+        }
+
+        Some(f)
+    }
+}
+
+/// A finite float decomposed as `(-1)^negative * mant * 2^exp`, where `mant` is the integer
+/// significand with the implicit bit placed if appropriate, and `exp` is the exponent of its last
+/// place. Subnormals simply have fewer significant bits in `mant`, and zeros have a zero `mant`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FloatParts {
+    pub negative: bool,
+    pub exp: i32,
+    pub mant: u64,
+}
+
+impl FloatParts {
+    /// Decomposes a finite f64.
+    pub fn of_f64(f: f64) -> FloatParts {
+        debug_assert!(f.is_finite());
+
+        let bits = f.to_bits();
+        let biased = ((bits >> 52) & 0x7ff) as i32;
+        let mut mant = bits & 0x000f_ffff_ffff_ffff;
+
+        if biased != 0 {
+            mant += 1 << 52; // implicit upper bit
+        }
+
+        FloatParts { negative: bits >> 63 != 0, exp: biased.max(1) - 1075, mant }
+    }
+
+    /// Decomposes a finite f32.
+    pub fn of_f32(f: f32) -> FloatParts {
+        debug_assert!(f.is_finite());
+
+        let bits = f.to_bits();
+        let biased = ((bits >> 23) & 0xff) as i32;
+        let mut mant = (bits & 0x007f_ffff) as u64;
+
+        if biased != 0 {
+            mant += 1 << 23;
+        }
+
+        FloatParts { negative: bits >> 31 != 0, exp: biased.max(1) - 150, mant }
+    }
+}
+
 /// Mask out a float's various parts, for non-zero finite numbers only. Exponent is
 /// unbiased. Sign is stored as a 64 bit integer. Mantissa has a leading one
 /// placed if appropriate, and is guaranteed to be in the range [2^53, 2^54 - 1].
@@ -169,7 +567,7 @@ pub fn sign_exp_mant_f64(a: f64) -> (u64, i32, u64) {
 
 /// This function assumes the mantissa has exactly 53 bits of precision (i.e., it is between 2^53
 /// and 2^54 - 1), and that it is nonzero
-pub fn from_sign_exp_mant_f64(sign: u64, mut exp: i32, mut mant: u64) -> f64 {
+pub fn from_sign_exp_mant_f64(sign: u64, mut exp: i32, mant: u64) -> f64 {
     if exp <= -1023 {
         // denormal enjoyer
         exp = -1023;
@@ -181,4 +579,157 @@ pub fn from_sign_exp_mant_f64(sign: u64, mut exp: i32, mut mant: u64) -> f64 {
     f64::from_bits(bits)
 }
 
-pub const MIN_SUBNORMAL_F64: f64 = 4.940656458412465442e-324;
+pub const MIN_SUBNORMAL_F64: f64 = 5e-324;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ulp() {
+        assert!(identical_f64(ulp_f64(1.0), f64::EPSILON));
+        assert!(identical_f64(ulp_f64(-1.0), f64::EPSILON));
+        assert!(identical_f64(ulp_f64(predecessor_f64(1.0)), f64::EPSILON / 2.0));
+        assert!(identical_f64(ulp_f64(0.0), MIN_SUBNORMAL_F64));
+        assert!(identical_f64(ulp_f64(f64::MIN_POSITIVE), MIN_SUBNORMAL_F64));
+        assert!(identical_f64(ulp_f64(f64::MIN_POSITIVE * 4.0), MIN_SUBNORMAL_F64 * 4.0));
+        assert!(identical_f64(ulp_f64(f64::MAX), f64::MAX - predecessor_f64(f64::MAX)));
+        assert!(identical_f64(ulp_f64(f64::NEG_INFINITY), f64::INFINITY));
+        assert!(ulp_f64(f64::NAN).is_nan());
+
+        assert!(identical_f32(ulp_f32(1.0), f32::EPSILON));
+        assert!(identical_f32(ulp_f32(-0.0), f32::from_bits(1)));
+        assert!(identical_f32(ulp_f32(f32::MAX), f32::MAX - predecessor_f32(f32::MAX)));
+
+        let mut f = MIN_SUBNORMAL_F64;
+        while f.is_finite() {
+            assert!(identical_f64(ulp_f64(f), successor_f64(f) - f), "f = {:e}", f);
+            f = successor_f64(f) * 1.75;
+        }
+    }
+
+    #[test]
+    fn test_ulp_distance() {
+        assert_eq!(ulp_distance_f64(1.0, successor_f64(1.0)), 1);
+        assert_eq!(ulp_distance_f64(successor_f64(1.0), 1.0), -1);
+        assert_eq!(ulp_distance_f64(1.0, 2.0), 1 << 52);
+        assert_eq!(ulp_distance_f64(-0.0, 0.0), 0);
+        assert_eq!(ulp_distance_f64(-MIN_SUBNORMAL_F64, MIN_SUBNORMAL_F64), 2);
+        assert_eq!(ulp_distance_f64(f64::MAX, f64::INFINITY), 1);
+        assert_eq!(ulp_distance_f64(f64::NEG_INFINITY, f64::INFINITY), i64::MAX);
+        assert_eq!(ulp_distance_f64(f64::INFINITY, f64::NEG_INFINITY), i64::MIN);
+
+        assert_eq!(ulp_distance_f32(-1.0, 1.0), 2 * 0x3f80_0000);
+        assert_eq!(ulp_distance_f32(f32::NEG_INFINITY, f32::INFINITY), 2 * 0x7f80_0000);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ulp_distance_nan() {
+        ulp_distance_f64(f64::NAN, 1.0);
+    }
+
+    #[test]
+    fn test_next_after() {
+        assert!(identical_f64(next_after_f64(1.0, 2.0), successor_f64(1.0)));
+        assert!(identical_f64(next_after_f64(1.0, -2.0), predecessor_f64(1.0)));
+        assert!(identical_f64(next_after_f64(0.0, -0.0), -0.0));
+        assert!(identical_f64(next_after_f64(f64::MAX, f64::INFINITY), f64::INFINITY));
+        assert!(next_after_f64(f64::NAN, 1.0).is_nan());
+        assert!(next_after_f64(1.0, f64::NAN).is_nan());
+
+        assert!(identical_f32(next_after_f32(-0.0, 1.0), f32::from_bits(1)));
+        assert!(identical_f32(next_after_f32(f32::INFINITY, 0.0), f32::MAX));
+    }
+
+    #[test]
+    fn test_step_ulps() {
+        let tiny = MIN_SUBNORMAL_F64;
+
+        assert!(identical_f64(step_ulps_f64(-tiny, 1), -0.0));
+        assert!(identical_f64(step_ulps_f64(tiny, -1), 0.0));
+        assert!(identical_f64(step_ulps_f64(-tiny, 2), tiny));
+        assert!(identical_f64(step_ulps_f64(-0.0, 1), tiny));
+        assert!(identical_f64(step_ulps_f64(-0.0, 0), -0.0));
+        assert!(identical_f64(step_ulps_f64(1.0, 1 << 52), 2.0));
+        assert!(identical_f64(step_ulps_f64(f64::MAX, 1), f64::INFINITY));
+        assert!(identical_f64(step_ulps_f64(f64::MAX, i64::MAX), f64::INFINITY));
+        assert!(identical_f64(step_ulps_f64(f64::INFINITY, -1), f64::MAX));
+        assert!(identical_f64(step_ulps_f64(0.0, i64::MIN), f64::NEG_INFINITY));
+        assert!(step_ulps_f64(f64::NAN, 1).is_nan());
+
+        for &f in &[-2.5, -tiny, -0.0, 0.0, tiny, 1.0, f64::MAX, f64::NEG_INFINITY] {
+            assert!(identical_f64(step_ulps_f64(f, 1), successor_f64(f)), "f = {:e}", f);
+            assert!(identical_f64(step_ulps_f64(f, -1), predecessor_f64(f)) || f == f64::NEG_INFINITY, "f = {:e}", f);
+            assert_eq!(ulp_distance_f64(f, step_ulps_f64(f, 12345)), 12345.min(ulp_distance_f64(f, f64::INFINITY)));
+        }
+
+        assert!(identical_f32(step_ulps_f32(-f32::from_bits(1), 1), -0.0));
+        assert!(identical_f32(step_ulps_f32(1.0, -(1 << 23)), 0.5));
+        assert!(identical_f32(step_ulps_f32(-f32::MAX, -3), f32::NEG_INFINITY));
+    }
+
+    #[test]
+    fn test_ordered_bits() {
+        let values = [
+            -f64::NAN, f64::NEG_INFINITY, -f64::MAX, -1.0, -MIN_SUBNORMAL_F64, -0.0, 0.0,
+            MIN_SUBNORMAL_F64, f64::MIN_POSITIVE, 1.0, f64::MAX, f64::INFINITY, f64::NAN
+        ];
+
+        for i in 0..values.len() {
+            let key = to_ordered_bits_f64(values[i]);
+            assert!(identical_f64(from_ordered_bits_f64(key), values[i]));
+
+            for j in 0..values.len() {
+                assert_eq!(total_order_f64(values[i], values[j]), i.cmp(&j));
+                assert_eq!(total_order_f64(values[i], values[j]), key.cmp(&to_ordered_bits_f64(values[j])));
+            }
+        }
+
+        assert_eq!(to_ordered_bits_f64(-0.0) + 1, to_ordered_bits_f64(0.0));
+        assert_eq!(to_ordered_bits_f32(-0.0) + 1, to_ordered_bits_f32(0.0));
+        assert_eq!(total_order_f32(-f32::NAN, f32::NEG_INFINITY), Ordering::Less);
+        assert_eq!(total_order_f32(f32::NAN, f32::INFINITY), Ordering::Greater);
+
+        for key in (0..=u32::MAX).step_by(65521) {
+            assert_eq!(to_ordered_bits_f32(from_ordered_bits_f32(key)), key);
+        }
+    }
+
+    #[test]
+    fn test_float_range() {
+        let tiny = MIN_SUBNORMAL_F64;
+
+        let v: Vec<f64> = float_range_f64(-2.0 * tiny, tiny).collect();
+        assert_eq!(v.len(), 5);
+        assert!(v.iter().zip([-2.0 * tiny, -tiny, -0.0, 0.0, tiny]).all(|(&a, b)| identical_f64(a, b)));
+
+        let v: Vec<f64> = float_range_f64(1.0, successor_f64(successor_f64(1.0))).rev().collect();
+        assert_eq!(v, vec![successor_f64(successor_f64(1.0)), successor_f64(1.0), 1.0]);
+
+        assert_eq!(float_range_f64(1.0, 1.0).count(), 1);
+        assert_eq!(float_range_f64(2.0, 1.0).count(), 0);
+        assert_eq!(float_range_f64(f64::MAX, f64::INFINITY).size_hint(), (2, Some(2)));
+        assert_eq!(float_range_f64(f64::INFINITY, f64::NAN).size_hint().0 as u64, f64::NAN.to_bits() - f64::INFINITY.to_bits() + 1);
+
+        assert_eq!(float_range_f32(1.0, 2.0).count(), (1 << 23) + 1);
+        assert_eq!(float_range_f32(1.0, 2.0).size_hint(), ((1 << 23) + 1, Some((1 << 23) + 1)));
+        assert!(float_range_f32(-1e-40, 1e-40).zip(float_range_f32(-1e-40, 1e-40).skip(1)).all(|(a, b)| total_order_f32(a, b) == Ordering::Less));
+        assert_eq!(float_range_f32(f32::NEG_INFINITY, f32::INFINITY).size_hint().0 as u64, (1u64 << 32) - 2 * ((1 << 23) - 1));
+    }
+
+    #[test]
+    fn test_float_parts() {
+        assert_eq!(FloatParts::of_f64(1.0), FloatParts { negative: false, exp: -52, mant: 1 << 52 });
+        assert_eq!(FloatParts::of_f64(-0.0), FloatParts { negative: true, exp: -1074, mant: 0 });
+        assert_eq!(FloatParts::of_f64(-MIN_SUBNORMAL_F64), FloatParts { negative: true, exp: -1074, mant: 1 });
+        assert_eq!(FloatParts::of_f32(-3.0), FloatParts { negative: true, exp: -22, mant: 3 << 22 });
+        assert_eq!(FloatParts::of_f32(f32::from_bits(0x0040_0000)), FloatParts { negative: false, exp: -149, mant: 1 << 22 });
+
+        for &f in &[0.1, -2.5e-310, f64::MAX, f64::MIN_POSITIVE, 12345.678] {
+            let parts = FloatParts::of_f64(f);
+            let value = parts.mant as f64 * 2f64.powi(parts.exp / 2) * 2f64.powi(parts.exp - parts.exp / 2);
+            assert!(identical_f64(if parts.negative { -value } else { value }, f), "f = {:e}", f);
+        }
+    }
+}
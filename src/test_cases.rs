@@ -1,4 +1,6 @@
 use lazy_static::lazy_static;
+use crate::fallback::{round_to_format, Format};
+use crate::modes::*;
 use crate::successor::*;
 
 lazy_static! {
@@ -46,3 +48,369 @@ lazy_static! {
         v
     };
 }
+
+/// Seed used by the tests, so that any failure is reproducible
+pub const SEED: u64 = 0x5eed_f10a_7000_0001;
+
+pub use crate::stochastic::{RandomSource, SplitMix64};
+
+/// Helpers for picking test cases
+impl SplitMix64 {
+    /// Uniform integer in `lo..=hi`
+    pub fn range(&mut self, lo: i32, hi: i32) -> i32 {
+        lo + (self.next_u64() % (hi - lo + 1) as u64) as i32
+    }
+
+    pub fn coin(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+
+    /// Random mantissa with exactly `bits` significant bits
+    pub fn mant(&mut self, bits: u32) -> u64 {
+        let top = 1u64 << (bits - 1);
+        top | (self.next_u64() & (top - 1))
+    }
+
+    /// Uniformly random f64 significand in [1, 2), every fraction bit random
+    pub fn significand_f64(&mut self) -> f64 {
+        f64::from_bits(0x3ff0_0000_0000_0000 | self.next_u64() >> 12)
+    }
+
+    /// Uniformly random f32 significand in [1, 2)
+    pub fn significand_f32(&mut self) -> f32 {
+        f32::from_bits(0x3f80_0000 | (self.next_u64() >> 41) as u32)
+    }
+}
+
+/// Kinds of hard cases produced by `HardCases`, cycled through in order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CaseKind {
+    /// The exact product has exactly one bit more than the precision, ending in a one, so it lies
+    /// precisely between two floats
+    MulTie,
+    /// The product lies within a couple of ULPs of the overflow threshold
+    NearOverflow,
+    /// The operands are within a few ULPs of each other, with either sign, so that their sum or
+    /// difference cancels catastrophically
+    Cancellation,
+    /// The product, quotient or sum lands in or near the subnormal range
+    Subnormal,
+    /// Arbitrary bit patterns, including NaNs and infinities
+    RandomBits,
+}
+
+impl CaseKind {
+    pub const ALL: [CaseKind; 5] = [
+        CaseKind::MulTie,
+        CaseKind::NearOverflow,
+        CaseKind::Cancellation,
+        CaseKind::Subnormal,
+        CaseKind::RandomBits
+    ];
+}
+
+/// Deterministic, seedable generator of operand pairs targeting cases that are hard to round
+/// correctly, for any binary format. Operands are produced as bit patterns; use `f64_pairs` or
+/// `f32_pairs` for floats.
+pub struct HardCases {
+    rng: SplitMix64,
+    format: Format,
+    count: usize,
+}
+
+impl HardCases {
+    fn new(seed: u64, format: Format) -> HardCases {
+        HardCases { rng: SplitMix64::new(seed), format, count: 0 }
+    }
+
+    fn precision(&self) -> u32 {
+        self.format.frac_bits + 1
+    }
+
+    /// Bit pattern of `(-1)^negative * mant * 2^exp`, which must be representable
+    fn make(&self, negative: bool, mant: u64, exp: i32) -> u128 {
+        round_to_format(self.format, negative, mant as u128, exp, false, Round::TiesToEven)
+    }
+
+    /// Random float with full precision whose leading bit has exponent `top`
+    fn with_top(&mut self, top: i32) -> u128 {
+        let p = self.precision();
+        let negative = self.rng.coin();
+        let mant = self.rng.mant(p);
+
+        self.make(negative, mant, top - (p as i32 - 1))
+    }
+
+    /// Splits a product's leading exponent `top` between two operands, keeping both normal
+    fn split_top(&mut self, top: i32) -> (i32, i32) {
+        let (min, max) = (self.format.min_exp(), self.format.max_exp());
+        let ta = self.rng.range((top - max).max(min), (top - min).min(max));
+
+        (ta, top - ta)
+    }
+
+    fn mul_tie(&mut self) -> (u128, u128) {
+        let p = self.precision();
+
+        // Odd mantissas whose product has exactly p + 1 bits
+        let (ma, mb) = loop {
+            let lb = self.rng.range(2, p as i32) as u32;
+            let la = p + 1 - lb + self.rng.range(0, 1) as u32;
+            if la > p {
+                continue;
+            }
+
+            let ma = self.rng.mant(la) | 1;
+            let mb = self.rng.mant(lb) | 1;
+            if (ma as u128 * mb as u128).leading_zeros() == 128 - (p + 1) {
+                break (ma, mb);
+            }
+        };
+
+        // The product's leading bit is bit p of ma * mb, so placing it at exponent `top` fixes the
+        // sum of the operands' exponents
+        let top = self.rng.range(self.format.min_exp(), self.format.max_exp());
+        let (ta, _) = self.split_top(top);
+        let ea = ta - (63 - ma.leading_zeros() as i32);
+        let eb = top - p as i32 - ea;
+
+        let (sa, sb) = (self.rng.coin(), self.rng.coin());
+        let a = self.make(sa, ma, ea);
+        let b = self.make(sb, mb, eb);
+
+        (a, b)
+    }
+
+    fn near_overflow(&mut self) -> (u128, u128) {
+        let p = self.precision() as i32;
+        let ma = self.rng.mant(p as u32) as u128;
+
+        // ma * mb lands within a couple of units of 2^(2p - 1), which we place at the overflow
+        // threshold 2^(max_exp + 1)
+        let target = 1u128 << (2 * p - 1);
+        let mb = target.div_ceil(ma);
+        let mb = (mb as i128 + self.rng.range(-2, 2) as i128) as u64;
+        let (mb, shift) = if mb >> p != 0 { (mb >> 1, 1) } else { (mb, 0) };
+
+        let exp_sum = self.format.max_exp() + 1 - (2 * p - 1);
+        let (ta, _) = self.split_top(self.format.max_exp() + 1);
+        let ea = ta - (p - 1);
+        let (sa, sb) = (self.rng.coin(), self.rng.coin());
+
+        (self.make(sa, ma as u64, ea), self.make(sb, mb, exp_sum - ea + shift))
+    }
+
+    fn cancellation(&mut self) -> (u128, u128) {
+        let top = self.rng.range(self.format.min_exp(), self.format.max_exp());
+        let a = self.with_top(top);
+
+        // Step a few ULPs away, staying on the same side of zero, then pick either sign
+        let steps = self.rng.range(-3, 3) as i128;
+        let b = (a as i128 + steps) as u128 & !self.format.sign_bit() | (a & self.format.sign_bit());
+        let b = if self.rng.coin() { b ^ self.format.sign_bit() } else { b };
+
+        (a, b)
+    }
+
+    fn subnormal(&mut self) -> (u128, u128) {
+        let p = self.precision() as i32;
+        let min = self.format.min_exp();
+
+        match self.rng.range(0, 2) {
+            // Product of normals landing below the normal range
+            0 => {
+                let top = self.rng.range(min - p - 1, min);
+                let (ta, tb) = self.split_top(top);
+                (self.with_top(ta), self.with_top(tb))
+            }
+            // Operands straddling the smallest normal
+            1 => {
+                let negative = self.rng.coin();
+                let a = self.make(negative, 1, min) as i128 + self.rng.range(-4, 4) as i128;
+                let b = self.rng.next_u64() as u128 & ((1 << self.format.frac_bits) - 1);
+                (a as u128, b | if self.rng.coin() { self.format.sign_bit() } else { 0 })
+            }
+            // Quotient of a small number by a large one
+            _ => {
+                let ta = self.rng.range(min, min + p / 2);
+                let tb = self.rng.range(p / 2, p);
+                (self.with_top(ta), self.with_top(tb))
+            }
+        }
+    }
+
+    fn random_bits(&mut self) -> (u128, u128) {
+        let mask = (self.format.sign_bit() << 1) - 1;
+        (self.rng.next_u64() as u128 & mask, self.rng.next_u64() as u128 & mask)
+    }
+
+    /// Produces the next pair of operands as bit patterns, along with the kind of case.
+    pub fn next_bits(&mut self) -> (CaseKind, u128, u128) {
+        let kind = CaseKind::ALL[self.count % CaseKind::ALL.len()];
+        self.count += 1;
+
+        let (a, b) = match kind {
+            CaseKind::MulTie => self.mul_tie(),
+            CaseKind::NearOverflow => self.near_overflow(),
+            CaseKind::Cancellation => self.cancellation(),
+            CaseKind::Subnormal => self.subnormal(),
+            CaseKind::RandomBits => self.random_bits(),
+        };
+
+        (kind, a, b)
+    }
+}
+
+/// Generates `count` hard f64 operand pairs from the given seed.
+pub fn hard_f64_pairs(seed: u64, count: usize) -> Vec<(f64, f64)> {
+    let mut gen = HardCases::new(seed, Format::BINARY64);
+
+    (0..count).map(|_| {
+        let (_, a, b) = gen.next_bits();
+        (f64::from_bits(a as u64), f64::from_bits(b as u64))
+    }).collect()
+}
+
+/// Generates `count` hard f32 operand pairs from the given seed.
+pub fn hard_f32_pairs(seed: u64, count: usize) -> Vec<(f32, f32)> {
+    let mut gen = HardCases::new(seed, Format::BINARY32);
+
+    (0..count).map(|_| {
+        let (_, a, b) = gen.next_bits();
+        (f32::from_bits(a as u32), f32::from_bits(b as u32))
+    }).collect()
+}
+
+/// A line of tests/data/elementary.txt: a function, its arguments, and its exact result rounded
+/// down, up and to nearest, even, as computed by a multiple-precision library.
+pub struct Reference {
+    pub function: &'static str,
+    pub args: Vec<f64>,
+    pub down: f64,
+    pub up: f64,
+    pub nearest: f64,
+}
+
+impl Reference {
+    fn parse(line: &'static str) -> Reference {
+        let mut fields = line.split_whitespace();
+        let function = fields.next().unwrap();
+        let mut values: Vec<f64> = fields.map(|f| f64::from_bits(u64::from_str_radix(f, 16).unwrap())).collect();
+        let results = values.split_off(values.len() - 3);
+
+        Reference { function, args: values, down: results[0], up: results[1], nearest: results[2] }
+    }
+
+    /// Whether `actual` is the exact result rounded under `mode`. None of the results in the table
+    /// is exact or a tie, so the other modes follow from the three given.
+    pub fn accepts(&self, actual: f64, mode: Round) -> bool {
+        let expected = match mode {
+            Round::TowardNInf => self.down,
+            Round::TowardPInf => self.up,
+            Round::TowardZero if self.down.is_sign_negative() => self.up,
+            Round::TowardZero => self.down,
+            Round::Faithful => return identical_f64(actual, self.down) || identical_f64(actual, self.up),
+            Round::TiesToEven | Round::TiesAway | Round::TiesToOdd => self.nearest,
+        };
+
+        identical_f64(actual, expected)
+    }
+}
+
+lazy_static! {
+    /// The table of elementary function results, from tests/data/generate_elementary.py
+    pub static ref ELEMENTARY: Vec<Reference> = include_str!("../tests/data/elementary.txt")
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(Reference::parse)
+        .collect();
+}
+
+/// Checks `f` against every line of the elementary function table for the named function, in
+/// every rounding mode.
+pub fn check_reference(function: &str, f: impl Fn(&[f64], Round) -> f64) {
+    let lines: Vec<_> = ELEMENTARY.iter().filter(|r| r.function == function).collect();
+    assert!(!lines.is_empty(), "no reference results for {}", function);
+
+    for r in lines {
+        for mode in Round::ALL {
+            let actual = f(&r.args, mode);
+            assert!(
+                r.accepts(actual, mode),
+                "{}{:?}, {:?}: expected {:e} <= {:e} <= {:e}, nearest {:e}",
+                function, r.args, mode, r.down, actual, r.up, r.nearest
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native;
+
+    fn f64_cases(kind: CaseKind, count: usize) -> Vec<(f64, f64)> {
+        let mut gen = HardCases::new(SEED, Format::BINARY64);
+
+        (0..count * CaseKind::ALL.len())
+            .map(|_| gen.next_bits())
+            .filter(|&(k, _, _)| k == kind)
+            .map(|(_, a, b)| (f64::from_bits(a as u64), f64::from_bits(b as u64)))
+            .collect()
+    }
+
+    #[test]
+    fn deterministic() {
+        let pairs = hard_f64_pairs(SEED, 100);
+
+        assert!(pairs.iter().zip(hard_f64_pairs(SEED, 100)).all(|(p, q)| identical_f64(p.0, q.0) && identical_f64(p.1, q.1)));
+        assert!(pairs.iter().zip(hard_f64_pairs(SEED + 1, 100)).any(|(p, q)| !identical_f64(p.0, q.0)));
+        assert_eq!(hard_f32_pairs(SEED, 100).len(), 100);
+    }
+
+    #[test]
+    fn mul_ties() {
+        let cases = f64_cases(CaseKind::MulTie, 1000);
+
+        // The product is a tie if it exceeds its truncation by exactly half a unit in the last place.
+        // The error of the truncated product is exact as long as it is normal.
+        let ties = cases.iter().filter(|&&(a, b)| {
+            let down = native::mul_f64(a.abs(), b.abs(), Round::TowardZero);
+            down >= f64::MIN_POSITIVE && a.abs().mul_add(b.abs(), -down) == ulp_f64(down) / 2.0
+        }).count();
+        assert!(ties * 10 >= cases.len() * 9, "only {} of {} products are ties", ties, cases.len());
+
+        for (a, b) in hard_f32_pairs(SEED, 1000).into_iter().step_by(CaseKind::ALL.len()) {
+            let exact = (a as f64 * b as f64).abs();
+            if exact >= f32::MIN_POSITIVE as f64 && exact < f32::MAX as f64 {
+                let down = native::mul_f32(a.abs(), b.abs(), Round::TowardZero);
+                assert_eq!(exact - down as f64, ulp_f32(down) as f64 / 2.0, "a = {:e}, b = {:e}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn near_overflow() {
+        for (a, b) in f64_cases(CaseKind::NearOverflow, 1000) {
+            let down = native::mul_f64(a.abs(), b.abs(), Round::TowardZero);
+            assert!(ulp_distance_f64(down, f64::MAX) <= 4, "a = {:e}, b = {:e}", a, b);
+        }
+    }
+
+    #[test]
+    fn cancellation() {
+        for (a, b) in f64_cases(CaseKind::Cancellation, 1000) {
+            let diff = (a + b).abs().min((a - b).abs());
+            assert!(diff <= 3.0 * ulp_f64(a), "a = {:e}, b = {:e}", a, b);
+        }
+    }
+
+    #[test]
+    fn subnormal() {
+        let cases = f64_cases(CaseKind::Subnormal, 1000);
+        let tiny = |f: f64| f.abs() < 2.0 * f64::MIN_POSITIVE;
+
+        let hits = cases.iter().filter(|&&(a, b)| tiny(a * b) || tiny(a / b) || tiny(a + b)).count();
+        assert!(hits * 10 >= cases.len() * 9, "only {} of {} cases are near the subnormal range", hits, cases.len());
+    }
+}
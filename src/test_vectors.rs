@@ -0,0 +1,408 @@
+// Readers for industry floating-point test vector formats, so that suites like Berkeley TestFloat
+// and IBM FPgen can be run against the native and fallback operations.
+//
+// TestFloat's testfloat_gen prints one vector per line as hex operands, the hex result and the
+// hex exception flags. The function and rounding mode are given on its command line rather than in
+// the output, so they must be supplied by the caller, e.g., from the file name.
+//
+// FPgen .fptest files carry everything on each line:
+//
+//     b64* > -1.0000000000001P-1 +1.8000000000000P0 -> -1.8000000000001P-1 x
+//
+// i.e., format and operation, rounding mode, optional trapped exceptions, operands, "->", the
+// result and the exceptions raised.
+
+use std::error::Error;
+use std::fmt;
+use crate::modes::*;
+
+/// Operation exercised by a test vector
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Sqrt,
+    /// Fused multiply-add of the three operands, a * b + c
+    Fma,
+    /// Rounding to an integral value in the same format
+    RoundToIntegral,
+    /// Conversion to a 32-bit signed integer
+    ToI32,
+}
+
+impl Operation {
+    /// Number of operands the operation takes
+    pub fn arity(&self) -> usize {
+        match self {
+            Operation::Sqrt | Operation::RoundToIntegral | Operation::ToI32 => 1,
+            Operation::Fma => 3,
+            _ => 2
+        }
+    }
+}
+
+/// Binary format of a test vector's floating-point operands
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Precision {
+    F32,
+    F64,
+}
+
+/// Set of IEEE 754 exception flags, using TestFloat's encoding.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Flags(pub u8);
+
+impl Flags {
+    pub const INEXACT: Flags = Flags(0x1);
+    pub const UNDERFLOW: Flags = Flags(0x2);
+    pub const OVERFLOW: Flags = Flags(0x4);
+    pub const DIV_BY_ZERO: Flags = Flags(0x8);
+    pub const INVALID: Flags = Flags(0x10);
+
+    pub fn contains(&self, other: Flags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+/// A single test vector. Operands and the result are bit patterns of the vector's precision,
+/// except that conversions to integers store the integer's two's complement bits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestVector {
+    pub op: Operation,
+    pub precision: Precision,
+    pub mode: Round,
+    pub operands: Vec<u64>,
+    /// Expected result, or None if any result is acceptable
+    pub result: Option<u64>,
+    pub flags: Flags,
+    /// Line of the source text the vector came from, starting at 1
+    pub line: usize,
+}
+
+/// Error encountered while parsing test vectors
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+fn error(line: usize, message: String) -> ParseError {
+    ParseError { line, message }
+}
+
+/// Maps a TestFloat function name, such as `f64_mul` or `f32_roundToInt`, to its operation.
+pub fn testfloat_function(name: &str) -> Option<(Operation, Precision)> {
+    let (precision, op) = name.split_once('_')?;
+    let precision = match precision {
+        "f32" => Precision::F32,
+        "f64" => Precision::F64,
+        _ => return None
+    };
+
+    let op = match op {
+        "add" => Operation::Add,
+        "sub" => Operation::Sub,
+        "mul" => Operation::Mul,
+        "div" => Operation::Div,
+        "sqrt" => Operation::Sqrt,
+        "mulAdd" => Operation::Fma,
+        "roundToInt" => Operation::RoundToIntegral,
+        "to_i32" => Operation::ToI32,
+        _ => return None
+    };
+
+    Some((op, precision))
+}
+
+/// Maps a TestFloat rounding option, such as `-rmin`, to a rounding mode. TestFloat's `-rodd` is
+/// round-to-odd in the "sticky" sense (truncate, then set the last bit if inexact), which differs
+/// from `Round::TiesToOdd`, so it maps to None.
+pub fn testfloat_rounding(option: &str) -> Option<Round> {
+    match option.trim_start_matches('-') {
+        "rnear_even" => Some(Round::TiesToEven),
+        "rminMag" => Some(Round::TowardZero),
+        "rmin" => Some(Round::TowardNInf),
+        "rmax" => Some(Round::TowardPInf),
+        "rnear_maxMag" => Some(Round::TiesAway),
+        _ => None
+    }
+}
+
+fn parse_hex(token: &str, line: usize) -> Result<u64, ParseError> {
+    u64::from_str_radix(token, 16).map_err(|_| error(line, format!("invalid hex number {:?}", token)))
+}
+
+/// Parses testfloat_gen output for the given function and rounding mode. Blank lines are skipped.
+pub fn parse_testfloat(text: &str, op: Operation, precision: Precision, mode: Round) -> Result<Vec<TestVector>, ParseError> {
+    let mut vectors = vec![];
+
+    for (i, line) in text.lines().enumerate() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.is_empty() {
+            continue;
+        }
+
+        if tokens.len() != op.arity() + 2 {
+            return Err(error(i + 1, format!("expected {} fields, found {}", op.arity() + 2, tokens.len())));
+        }
+
+        let fields = tokens.iter().map(|t| parse_hex(t, i + 1)).collect::<Result<Vec<u64>, _>>()?;
+
+        vectors.push(TestVector {
+            op,
+            precision,
+            mode,
+            operands: fields[..op.arity()].to_vec(),
+            result: Some(fields[op.arity()]),
+            flags: Flags(fields[op.arity() + 1] as u8),
+            line: i + 1,
+        });
+    }
+
+    Ok(vectors)
+}
+
+/// Maps an FPgen rounding field, such as `=0` or `>`, to a rounding mode.
+pub fn fpgen_rounding(field: &str) -> Option<Round> {
+    match field {
+        "=0" => Some(Round::TiesToEven),
+        "=^" => Some(Round::TiesAway),
+        "0" => Some(Round::TowardZero),
+        ">" => Some(Round::TowardPInf),
+        "<" => Some(Round::TowardNInf),
+        _ => None
+    }
+}
+
+fn fpgen_flags(field: &str, line: usize) -> Result<Flags, ParseError> {
+    let mut flags = 0;
+
+    for c in field.chars() {
+        flags |= match c {
+            'x' => Flags::INEXACT.0,
+            'u' => Flags::UNDERFLOW.0,
+            'o' => Flags::OVERFLOW.0,
+            'z' => Flags::DIV_BY_ZERO.0,
+            'i' => Flags::INVALID.0,
+            _ => return Err(error(line, format!("invalid exception flags {:?}", field)))
+        };
+    }
+
+    Ok(Flags(flags))
+}
+
+/// Parses an FPgen operand such as `-1.7FFFFFP127`, `+0.000001P-126`, `+Zero`, `-Inf`, `Q` or `S`
+/// into a bit pattern. Returns None for `#`, which stands for any value.
+fn fpgen_operand(token: &str, precision: Precision, line: usize) -> Result<Option<u64>, ParseError> {
+    let (exp_bits, frac_bits) = match precision {
+        Precision::F32 => (8, 23),
+        Precision::F64 => (11, 52),
+    };
+    let bias = (1i64 << (exp_bits - 1)) - 1;
+    let inf = ((1u64 << exp_bits) - 1) << frac_bits;
+    let invalid = || error(line, format!("invalid operand {:?}", token));
+
+    // NaNs are given without sign; quiet ones have the top fraction bit set
+    match token {
+        "#" => return Ok(None),
+        "Q" => return Ok(Some(inf | 1 << (frac_bits - 1))),
+        "S" => return Ok(Some(inf | 1)),
+        _ => {}
+    }
+
+    let negative = match token.as_bytes().first() {
+        Some(b'+') => false,
+        Some(b'-') => true,
+        _ => return Err(invalid())
+    };
+    let sign = (negative as u64) << (exp_bits + frac_bits);
+
+    let magnitude = match &token[1..] {
+        "Zero" => 0,
+        "Inf" => inf,
+        rest => {
+            let (mant, exp) = rest.split_once('P').ok_or_else(invalid)?;
+            let (lead, frac) = mant.split_once('.').ok_or_else(invalid)?;
+            let frac = u64::from_str_radix(frac, 16).map_err(|_| invalid())?;
+            let exp: i64 = exp.parse().map_err(|_| invalid())?;
+
+            if frac >> frac_bits != 0 {
+                return Err(invalid());
+            }
+
+            match lead {
+                "1" if exp > -bias && exp <= bias => (((exp + bias) as u64) << frac_bits) | frac,
+                "0" if exp == 1 - bias => frac,
+                _ => return Err(invalid())
+            }
+        }
+    };
+
+    Ok(Some(sign | magnitude))
+}
+
+/// Parses the contents of an FPgen .fptest file. Blank lines and lines starting with `//` or `--`
+/// are skipped, as are vectors for operations or formats this crate doesn't implement.
+pub fn parse_fpgen(text: &str) -> Result<Vec<TestVector>, ParseError> {
+    let mut vectors = vec![];
+
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") || line.starts_with("--") {
+            continue;
+        }
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let head = tokens[0];
+
+        let (precision, op) = if let Some(op) = head.strip_prefix("b32") {
+            (Precision::F32, op)
+        } else if let Some(op) = head.strip_prefix("b64") {
+            (Precision::F64, op)
+        } else {
+            continue;
+        };
+
+        let op = match op {
+            "+" => Operation::Add,
+            "-" => Operation::Sub,
+            "*" => Operation::Mul,
+            "/" => Operation::Div,
+            "V" => Operation::Sqrt,
+            "*+" => Operation::Fma,
+            "rfi" => Operation::RoundToIntegral,
+            _ => continue
+        };
+
+        let mode = tokens.get(1)
+            .and_then(|&field| fpgen_rounding(field))
+            .ok_or_else(|| error(line_no, "missing or invalid rounding mode".to_string()))?;
+
+        let arrow = tokens.iter().position(|&t| t == "->")
+            .ok_or_else(|| error(line_no, "missing \"->\"".to_string()))?;
+
+        // Between the rounding mode and the operands there may be a field of trapped exceptions
+        let first = arrow.checked_sub(op.arity()).filter(|&first| first == 2 || first == 3)
+            .ok_or_else(|| error(line_no, format!("expected {} operands", op.arity())))?;
+
+        let operands = tokens[first..arrow].iter()
+            .map(|t| fpgen_operand(t, precision, line_no)?.ok_or_else(|| error(line_no, "operands must be specified".to_string())))
+            .collect::<Result<Vec<u64>, _>>()?;
+
+        let result = tokens.get(arrow + 1).ok_or_else(|| error(line_no, "missing result".to_string()))?;
+        let result = fpgen_operand(result, precision, line_no)?;
+
+        let flags = match tokens.get(arrow + 2) {
+            Some(field) => fpgen_flags(field, line_no)?,
+            None => Flags::default()
+        };
+
+        vectors.push(TestVector { op, precision, mode, operands, result, flags, line: line_no });
+    }
+
+    Ok(vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_testfloat() {
+        let text = "3FF0000000000000 4000000000000000 4000000000000000 00\n\n7FEFFFFFFFFFFFFF 4000000000000000 7FEFFFFFFFFFFFFF 05\n";
+        let (op, precision) = testfloat_function("f64_mul").unwrap();
+        let vectors = parse_testfloat(text, op, precision, testfloat_rounding("-rminMag").unwrap()).unwrap();
+
+        assert_eq!(vectors.len(), 2);
+        assert_eq!(vectors[1], TestVector {
+            op: Operation::Mul,
+            precision: Precision::F64,
+            mode: Round::TowardZero,
+            operands: vec![0x7fef_ffff_ffff_ffff, 0x4000_0000_0000_0000],
+            result: Some(0x7fef_ffff_ffff_ffff),
+            flags: Flags(Flags::INEXACT.0 | Flags::OVERFLOW.0),
+            line: 3,
+        });
+        assert!(vectors[1].flags.contains(Flags::OVERFLOW));
+
+        let sqrt = parse_testfloat("40800000 40000000 00", Operation::Sqrt, Precision::F32, Round::TiesToEven).unwrap();
+        assert_eq!(sqrt[0].operands, vec![0x4080_0000]);
+        assert_eq!(sqrt[0].result, Some(0x4000_0000));
+
+        assert_eq!(parse_testfloat("3F800000 00", Operation::Sqrt, Precision::F32, Round::TiesToEven).unwrap_err().line, 1);
+        assert_eq!(parse_testfloat("3F800000 3F80000G 00", Operation::Sqrt, Precision::F32, Round::TiesToEven).unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn test_testfloat_names() {
+        assert_eq!(testfloat_function("f32_roundToInt"), Some((Operation::RoundToIntegral, Precision::F32)));
+        assert_eq!(testfloat_function("f32_to_i32"), Some((Operation::ToI32, Precision::F32)));
+        assert_eq!(testfloat_function("f64_mulAdd"), Some((Operation::Fma, Precision::F64)));
+        assert_eq!(testfloat_function("f16_add"), None);
+        assert_eq!(testfloat_function("f64_rem"), None);
+
+        assert_eq!(testfloat_rounding("rnear_even"), Some(Round::TiesToEven));
+        assert_eq!(testfloat_rounding("-rnear_maxMag"), Some(Round::TiesAway));
+        assert_eq!(testfloat_rounding("-rmax"), Some(Round::TowardPInf));
+        assert_eq!(testfloat_rounding("-rodd"), None);
+    }
+
+    #[test]
+    fn test_parse_fpgen() {
+        let text = "\
+            -- a comment\n\
+            b32+ =0 +1.000000P0 +1.000000P0 -> +1.000000P1\n\
+            b64* > i -1.0000000000001P-1 +1.8000000000000P0 -> -1.8000000000001P-1 x\n\
+            b32V =^ -1.000000P0 -> Q i\n\
+            b32/ 0 +0.000001P-126 -Inf -> -Zero\n\
+            b32*+ < +1.7FFFFFP127 +1.000000P1 -1.000000P0 -> # xo\n\
+            b32% =0 +1.000000P0 +1.000000P0 -> +Zero\n\
+            d64+ =0 +1E0 +1E0 -> +2E0\n";
+
+        let vectors = parse_fpgen(text).unwrap();
+        assert_eq!(vectors.len(), 5);
+
+        assert_eq!(vectors[0].operands, vec![0x3f80_0000, 0x3f80_0000]);
+        assert_eq!(vectors[0].result, Some(0x4000_0000));
+        assert_eq!(vectors[0].line, 2);
+
+        assert_eq!(vectors[1].mode, Round::TowardPInf);
+        assert_eq!(vectors[1].operands, vec![0xbfe0_0000_0000_0001, 0x3ff8_0000_0000_0000]);
+        assert_eq!(vectors[1].flags, Flags::INEXACT);
+
+        assert_eq!(vectors[2].op, Operation::Sqrt);
+        assert_eq!(vectors[2].mode, Round::TiesAway);
+        assert!(f32::from_bits(vectors[2].result.unwrap() as u32).is_nan());
+        assert_eq!(vectors[2].flags, Flags::INVALID);
+
+        assert_eq!(vectors[3].operands, vec![0x0000_0001, 0xff80_0000]);
+        assert_eq!(vectors[3].result, Some(0x8000_0000));
+
+        assert_eq!(vectors[4].op, Operation::Fma);
+        assert_eq!(vectors[4].operands, vec![0x7f7f_ffff, 0x4000_0000, 0xbf80_0000]);
+        assert_eq!(vectors[4].result, None);
+        assert_eq!(vectors[4].flags, Flags(Flags::INEXACT.0 | Flags::OVERFLOW.0));
+    }
+
+    #[test]
+    fn test_parse_fpgen_errors() {
+        assert_eq!(parse_fpgen("b32+ =0 +1.000000P0 -> +1.000000P0").unwrap_err().line, 1);
+        assert_eq!(parse_fpgen("\nb32+ ? +1.000000P0 +1.000000P0 -> +1.000000P1").unwrap_err().line, 2);
+        assert!(parse_fpgen("b32+ =0 +1.000000P0 +1.000000P0 +1.000000P1").is_err());
+        assert!(parse_fpgen("b32+ =0 +1.FFFFFFP0 +1.000000P0 -> +1.000000P1").is_err());
+        assert!(parse_fpgen("b32+ =0 +1.000000P200 +1.000000P0 -> +1.000000P1").is_err());
+        assert!(parse_fpgen("b32+ =0 +1.000000P0 +1.000000P0 -> +1.000000P1 q").is_err());
+        assert!(parse_fpgen("b32+ =0 # +1.000000P0 -> +1.000000P1").is_err());
+    }
+}
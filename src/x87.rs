@@ -0,0 +1,519 @@
+// The x87 FPU, which x86_64 still carries alongside SSE. It has its own control word, separate
+// from the MXCSR that the native module drives, whose rounding control field takes the same
+// values as `Round` and whose precision control field sets the precision that arithmetic rounds
+// to. Its 80-bit extended format, with a 64-bit mantissa, is exposed as `F80`; each operation
+// loads its own control word and restores the caller's afterward, like the native module does.
+
+use std::arch::asm;
+use std::cmp::Ordering;
+use std::fmt;
+use crate::f128::F128;
+use crate::modes::*;
+
+/// Precision control field of the x87 control word, giving the precision that arithmetic
+/// results are rounded to. The exponent range stays extended in every setting.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PrecisionControl {
+    /// 24 bits, as f32
+    Single = 0x000,
+    /// 53 bits, as f64
+    Double = 0x200,
+    /// 64 bits, the default
+    Extended = 0x300,
+}
+
+/// Value of the x87 control word
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ControlWord(pub u16);
+
+impl ControlWord {
+    /// Control word set by `finit`: all exceptions masked, extended precision, ties to even
+    pub const DEFAULT: ControlWord = ControlWord(0x037f);
+
+    const ROUNDING_MASK: u16 = 0x0c00;
+    const PRECISION_MASK: u16 = 0x0300;
+
+    pub fn rounding(self) -> Round {
+        match self.0 & Self::ROUNDING_MASK {
+            0x000 => Round::TiesToEven,
+            0x400 => Round::TowardNInf,
+            0x800 => Round::TowardPInf,
+            _ => Round::TowardZero,
+        }
+    }
+
+    /// Precision control field, or None for the reserved setting.
+    pub fn precision(self) -> Option<PrecisionControl> {
+        match self.0 & Self::PRECISION_MASK {
+            0x000 => Some(PrecisionControl::Single),
+            0x200 => Some(PrecisionControl::Double),
+            0x300 => Some(PrecisionControl::Extended),
+            _ => None,
+        }
+    }
+
+    /// Replaces the rounding control field. Faithful rounding is served by rounding to nearest.
+    pub fn with_rounding(self, mode: Round) -> ControlWord {
+        assert!(mode.is_native(), "{:?} is not supported natively", mode);
+
+        ControlWord((self.0 & !Self::ROUNDING_MASK) | (mode as i32).max(0) as u16)
+    }
+
+    pub fn with_precision(self, precision: PrecisionControl) -> ControlWord {
+        ControlWord((self.0 & !Self::PRECISION_MASK) | precision as u16)
+    }
+}
+
+/// Returns the current x87 control word.
+pub fn get_control_word() -> ControlWord {
+    let mut cw: u16 = 0;
+
+    unsafe {
+        asm!(
+            "fnstcw [{cw}]",
+            cw = in(reg) &mut cw
+        );
+    }
+
+    ControlWord(cw)
+}
+
+/// Loads the x87 control word, which affects all later x87 arithmetic on this thread. The
+/// operations on `F80` don't depend on it.
+pub fn set_control_word(cw: ControlWord) {
+    unsafe {
+        asm!(
+            "fldcw [{cw}]",
+            cw = in(reg) &cw.0
+        );
+    }
+}
+
+/// Control word for the operations below: all exceptions masked, extended precision and the
+/// given rounding mode.
+fn control_word_for(mode: Round) -> u16 {
+    ControlWord::DEFAULT.with_rounding(mode).0
+}
+
+/// x87 80-bit extended precision float, with a 15-bit exponent and a 64-bit mantissa whose
+/// leading bit is explicit. Only the first ten bytes are significant, as in memory.
+#[derive(Copy, Clone, Default)]
+#[repr(C)]
+pub struct F80 {
+    mant: u64,
+    sign_exp: u16,
+}
+
+/// Defines a function loading an operand with `$load`, storing it again with `$store` under the
+/// given rounding mode, which rounds it to the destination format.
+macro_rules! convert {
+    ($(#[$attr:meta])* $name:ident, $from:ty, $to:ty, $load:literal, $store:literal) => {
+        $(#[$attr])*
+        fn $name(a: $from, mode: Round) -> $to {
+            let cw = control_word_for(mode);
+            let mut res = <$to>::default();
+
+            unsafe {
+                asm!(
+                    "sub rsp, 8",
+                    "fnstcw [rsp]",
+                    "mov word ptr [rsp + 2], {cw:x}",
+                    "fldcw [rsp + 2]",
+                    concat!($load, " [{a}]"),
+                    concat!($store, " [{res}]"),
+                    "fldcw [rsp]",
+                    "add rsp, 8",
+                    cw = in(reg) cw,
+                    a = in(reg) &a,
+                    res = in(reg) &mut res,
+                    out("st(0)") _, out("st(1)") _, out("st(2)") _, out("st(3)") _,
+                    out("st(4)") _, out("st(5)") _, out("st(6)") _, out("st(7)") _,
+                );
+            }
+
+            return res;
+        }
+    };
+}
+
+convert!(f64_to_f80, f64, F80, "fld qword ptr", "fstp tbyte ptr");
+convert!(f32_to_f80, f32, F80, "fld dword ptr", "fstp tbyte ptr");
+convert!(i64_to_f80, i64, F80, "fild qword ptr", "fstp tbyte ptr");
+convert!(f80_to_f64, F80, f64, "fld tbyte ptr", "fstp qword ptr");
+convert!(f80_to_f32, F80, f32, "fld tbyte ptr", "fstp dword ptr");
+convert!(f80_to_i64, F80, i64, "fld tbyte ptr", "fistp qword ptr");
+
+/// Defines an arithmetic method on F80 applying a popping x87 instruction to `self` in st(1) and
+/// `other` in st(0) under the given rounding mode.
+macro_rules! binary_op {
+    ($(#[$attr:meta])* $name:ident, $insn:literal) => {
+        $(#[$attr])*
+        pub fn $name(self, other: F80, mode: Round) -> F80 {
+            let cw = control_word_for(mode);
+            let mut res = F80::ZERO;
+
+            unsafe {
+                asm!(
+                    "sub rsp, 8",
+                    "fnstcw [rsp]",
+                    "mov word ptr [rsp + 2], {cw:x}",
+                    "fldcw [rsp + 2]",
+                    "fld tbyte ptr [{a}]",
+                    "fld tbyte ptr [{b}]",
+                    concat!($insn, " st(1), st"),
+                    "fstp tbyte ptr [{res}]",
+                    "fldcw [rsp]",
+                    "add rsp, 8",
+                    cw = in(reg) cw,
+                    a = in(reg) &self,
+                    b = in(reg) &other,
+                    res = in(reg) &mut res,
+                    out("st(0)") _, out("st(1)") _, out("st(2)") _, out("st(3)") _,
+                    out("st(4)") _, out("st(5)") _, out("st(6)") _, out("st(7)") _,
+                );
+            }
+
+            return res;
+        }
+    };
+}
+
+impl F80 {
+    pub const ZERO: F80 = F80 { mant: 0, sign_exp: 0 };
+    pub const ONE: F80 = F80 { mant: 1 << 63, sign_exp: 0x3fff };
+    pub const INFINITY: F80 = F80 { mant: 1 << 63, sign_exp: 0x7fff };
+    pub const NEG_INFINITY: F80 = F80 { mant: 1 << 63, sign_exp: 0xffff };
+    pub const NAN: F80 = F80 { mant: 0xc000_0000_0000_0000, sign_exp: 0x7fff };
+    /// Largest finite value
+    pub const MAX: F80 = F80 { mant: u64::MAX, sign_exp: 0x7ffe };
+    /// Smallest finite value, -MAX
+    pub const MIN: F80 = F80 { mant: u64::MAX, sign_exp: 0xfffe };
+    /// Smallest positive normal value
+    pub const MIN_POSITIVE: F80 = F80 { mant: 1 << 63, sign_exp: 1 };
+    /// Difference between 1 and the next larger value
+    pub const EPSILON: F80 = F80 { mant: 1 << 63, sign_exp: 0x3fff - 63 };
+
+    /// Makes a value from its 80-bit pattern: the sign and exponent above the 64-bit mantissa.
+    pub const fn from_bits(bits: u128) -> F80 {
+        F80 { mant: bits as u64, sign_exp: (bits >> 64) as u16 }
+    }
+
+    pub const fn to_bits(self) -> u128 {
+        ((self.sign_exp as u128) << 64) | self.mant as u128
+    }
+
+    pub fn is_nan(self) -> bool {
+        self.sign_exp & 0x7fff == 0x7fff && self.mant << 1 != 0
+    }
+
+    pub fn is_infinite(self) -> bool {
+        self.sign_exp & 0x7fff == 0x7fff && self.mant << 1 == 0
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.sign_exp & 0x7fff != 0x7fff
+    }
+
+    pub fn is_sign_negative(self) -> bool {
+        self.sign_exp & 0x8000 != 0
+    }
+
+    pub fn abs(self) -> F80 {
+        F80 { sign_exp: self.sign_exp & 0x7fff, ..self }
+    }
+
+    binary_op!(
+        /// Computes the sum of two values under the given rounding mode.
+        add, "faddp"
+    );
+
+    binary_op!(
+        /// Computes the difference of two values under the given rounding mode.
+        sub, "fsubp"
+    );
+
+    binary_op!(
+        /// Computes the product of two values under the given rounding mode.
+        mul, "fmulp"
+    );
+
+    binary_op!(
+        /// Computes the quotient of two values under the given rounding mode.
+        div, "fdivp"
+    );
+
+    /// Computes the square root under the given rounding mode.
+    pub fn sqrt(self, mode: Round) -> F80 {
+        let cw = control_word_for(mode);
+        let mut res = F80::ZERO;
+
+        unsafe {
+            asm!(
+                "sub rsp, 8",
+                "fnstcw [rsp]",
+                "mov word ptr [rsp + 2], {cw:x}",
+                "fldcw [rsp + 2]",
+                "fld tbyte ptr [{a}]",
+                "fsqrt",
+                "fstp tbyte ptr [{res}]",
+                "fldcw [rsp]",
+                "add rsp, 8",
+                cw = in(reg) cw,
+                a = in(reg) &self,
+                res = in(reg) &mut res,
+                out("st(0)") _, out("st(1)") _, out("st(2)") _, out("st(3)") _,
+                out("st(4)") _, out("st(5)") _, out("st(6)") _, out("st(7)") _,
+            );
+        }
+
+        return res;
+    }
+
+    /// Converts an f64, which is always exact. Signaling NaNs are quieted.
+    pub fn from_f64(f: f64) -> F80 {
+        f64_to_f80(f, Round::TiesToEven)
+    }
+
+    /// Converts an f32, which is always exact. Signaling NaNs are quieted.
+    pub fn from_f32(f: f32) -> F80 {
+        f32_to_f80(f, Round::TiesToEven)
+    }
+
+    /// Converts an i64, which is always exact.
+    pub fn from_i64(n: i64) -> F80 {
+        i64_to_f80(n, Round::TiesToEven)
+    }
+
+    /// Converts to an f64 under the given rounding mode, with a single rounding.
+    pub fn to_f64(self, mode: Round) -> f64 {
+        f80_to_f64(self, mode)
+    }
+
+    /// Converts to an f32 under the given rounding mode, with a single rounding.
+    pub fn to_f32(self, mode: Round) -> f32 {
+        f80_to_f32(self, mode)
+    }
+
+    /// Rounds to an integer under the given rounding mode, returning None for NaN and for results
+    /// out of range.
+    pub fn to_i64(self, mode: Round) -> Option<i64> {
+        let res = f80_to_i64(self, mode);
+
+        // Invalid conversions return the "integer indefinite" value, which is also the
+        // legitimate result for values rounding to -2^63
+        if res == i64::MIN && self.to_f128().to_i64(mode) != Some(i64::MIN) {
+            return None;
+        }
+
+        return Some(res);
+    }
+
+    /// Converts to a binary128 value, which is exact for every value with a canonical encoding.
+    pub fn to_f128(self) -> F128 {
+        let sign = ((self.sign_exp & 0x8000) as u128) << 112;
+        let exp = (self.sign_exp & 0x7fff) as u128;
+        let frac = ((self.mant << 1) as u128) << 48;
+
+        if exp == 0x7fff && frac != 0 {
+            return F128::from_bits(sign | F128::NAN.to_bits() | frac);
+        }
+
+        F128::from_bits(sign | (exp << 112) | frac)
+    }
+}
+
+impl std::ops::Neg for F80 {
+    type Output = F80;
+
+    fn neg(self) -> F80 {
+        F80 { sign_exp: self.sign_exp ^ 0x8000, ..self }
+    }
+}
+
+impl PartialEq for F80 {
+    fn eq(&self, other: &F80) -> bool {
+        self.to_f128() == other.to_f128()
+    }
+}
+
+impl PartialOrd for F80 {
+    fn partial_cmp(&self, other: &F80) -> Option<Ordering> {
+        self.to_f128().partial_cmp(&other.to_f128())
+    }
+}
+
+impl fmt::Display for F80 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f128(), f)
+    }
+}
+
+impl fmt::Debug for F80 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "F80({})", self.to_f128())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fallback::Format;
+    use crate::oracle::{self, Value};
+    use crate::test_cases::*;
+
+    /// F80 as an IEEE 754 layout, which it matches once its explicit leading bit is dropped
+    const EXTENDED: Format = Format { exp_bits: 15, frac_bits: 63 };
+
+    const FPU_MODES: [Round; 4] = [Round::TiesToEven, Round::TowardZero, Round::TowardPInf, Round::TowardNInf];
+
+    fn decode(f: F80) -> Value {
+        Value::decode(EXTENDED, ((f.sign_exp as u128) << 63) | (f.mant & !(1 << 63)) as u128)
+    }
+
+    fn encode(v: &Value, mode: Round) -> F80 {
+        let bits = v.encode(EXTENDED, mode);
+        let sign_exp = (bits >> 63) as u16;
+        let explicit = if sign_exp & 0x7fff != 0 { 1 << 63 } else { 0 };
+        F80 { mant: bits as u64 | explicit, sign_exp }
+    }
+
+    /// Random F80 with a canonical encoding, mostly of moderate magnitude
+    fn random(rng: &mut SplitMix64) -> F80 {
+        let exp = if rng.coin() { rng.range(0x3fff - 40, 0x3fff + 40) } else { rng.range(0, 0x7ffe) } as u16;
+        let mant = rng.next_u64() | if exp != 0 { 1 << 63 } else { 0 };
+        let sign = if rng.coin() { 0x8000 } else { 0 };
+
+        F80 { mant, sign_exp: sign | exp }
+    }
+
+    fn same(a: F80, b: F80) -> bool {
+        a.to_bits() == b.to_bits() || (a.is_nan() && b.is_nan())
+    }
+
+    #[test]
+    fn test_control_word() {
+        let saved = get_control_word();
+        assert_eq!(saved.rounding(), Round::TiesToEven);
+        assert_eq!(saved.precision(), Some(PrecisionControl::Extended));
+
+        for mode in FPU_MODES {
+            for precision in [PrecisionControl::Single, PrecisionControl::Double, PrecisionControl::Extended] {
+                set_control_word(saved.with_rounding(mode).with_precision(precision));
+                let cw = get_control_word();
+                set_control_word(saved);
+
+                assert_eq!((cw.rounding(), cw.precision()), (mode, Some(precision)));
+                assert_eq!(cw.0 & !0x0f00, saved.0 & !0x0f00);
+            }
+        }
+
+        // Operations restore the caller's control word, whatever it is
+        let custom = saved.with_rounding(Round::TowardPInf).with_precision(PrecisionControl::Single);
+        set_control_word(custom);
+        let third = F80::ONE.div(F80::from_i64(3), Round::TowardZero);
+        let cw = get_control_word();
+        set_control_word(saved);
+
+        assert_eq!(cw, custom);
+        assert_eq!(third.mant, 0xaaaa_aaaa_aaaa_aaaa);
+        assert_eq!(get_control_word(), saved);
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(F80::ONE.to_f64(Round::TiesToEven), 1.);
+        assert_eq!(F80::EPSILON.to_f128(), F128::from_f64(2f64.powi(-63)));
+        assert_eq!(F80::MIN_POSITIVE.to_f128(), F128::MIN_POSITIVE);
+        assert_eq!(F80::MAX.to_f64(Round::TiesToEven), f64::INFINITY);
+        assert_eq!(F80::MAX.to_f64(Round::TowardZero), f64::MAX);
+        assert_eq!(F80::MIN, -F80::MAX);
+        assert!(F80::NAN.is_nan() && F80::NAN != F80::NAN && F80::INFINITY.is_infinite());
+        assert!(F80::NEG_INFINITY < F80::MIN && F80::ZERO == -F80::ZERO);
+        assert_eq!(F80::from_f64(0.1).to_f128(), F128::from_f64(0.1));
+        assert_eq!(F80::from_i64(i64::MIN).to_i64(Round::TiesToEven), Some(i64::MIN));
+        assert_eq!(F80::from_f64(1e19).to_i64(Round::TiesToEven), None);
+        assert_eq!(F80::from_f64(-2.5).to_i64(Round::TowardNInf), Some(-3));
+        assert_eq!(F80::from_i64(i64::MIN).sub(F80::from_f64(0.5), Round::TiesToEven).to_i64(Round::TowardZero), Some(i64::MIN));
+        assert_eq!(format!("{:?}", F80::from_f64(1.5)), "F80(1.5e0)");
+    }
+
+    #[test]
+    fn test_arithmetic_against_oracle() {
+        type Op = (&'static str, fn(F80, F80, Round) -> F80, fn(&Value, &Value, Round) -> Value);
+        let ops: [Op; 4] = [
+            ("add", F80::add, oracle::add),
+            ("sub", F80::sub, |a, b, mode| oracle::add(a, &oracle::neg(b), mode)),
+            ("mul", F80::mul, |a, b, _| oracle::mul(a, b)),
+            ("div", F80::div, |a, b, _| oracle::div(a, b)),
+        ];
+
+        let mut rng = SplitMix64::new(SEED);
+        for _ in 0..2000 {
+            let (a, b) = (random(&mut rng), random(&mut rng));
+            // Every other pair shares an exponent, so that differences cancel
+            let b = if rng.coin() { F80 { sign_exp: (a.sign_exp & 0x7fff) | (b.sign_exp & 0x8000), ..b } } else { b };
+
+            for mode in FPU_MODES {
+                for (name, f, exact) in ops {
+                    let expected = encode(&exact(&decode(a), &decode(b), mode), mode);
+                    let actual = f(a, b, mode);
+                    assert!(same(actual, expected), "{}({:?}, {:?}) {:?}: expected {:?}, actual {:?}", name, a, b, mode, expected, actual);
+                }
+            }
+
+            // Faithful rounding is served by rounding to nearest
+            assert_eq!(a.mul(b, Round::Faithful).to_bits(), a.mul(b, Round::TiesToEven).to_bits());
+        }
+    }
+
+    #[test]
+    fn test_sqrt_brackets() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..2000 {
+            let a = random(&mut rng).abs();
+            let (down, up) = (a.sqrt(Round::TowardNInf), a.sqrt(Round::TowardPInf));
+            let Value::Finite(x) = decode(a) else { unreachable!() };
+
+            // down^2 <= a <= up^2, with up the next value after down unless the root is exact
+            let square = |r: F80| match oracle::mul(&decode(r), &decode(r)) {
+                Value::Finite(r) => r,
+                _ => unreachable!(),
+            };
+            let (below, above) = (x.add(&square(down).neg()), square(up).add(&x.neg()));
+            assert!((below.is_zero() || !below.negative) && (above.is_zero() || !above.negative), "{:?}", a);
+            assert_eq!(below.is_zero(), up.to_bits() == down.to_bits(), "{:?}", a);
+            assert!(up.to_bits() == down.to_bits() || up.to_bits() == down.to_bits() + 1 || (down.mant == u64::MAX && up.mant == 1 << 63), "{:?}", a);
+            assert_eq!(a.sqrt(Round::TowardZero).to_bits(), down.to_bits());
+        }
+
+        assert!(F80::from_f64(-1.).sqrt(Round::TiesToEven).is_nan());
+        assert_eq!(F80::from_f64(-0.).sqrt(Round::TowardNInf).to_bits(), F80::from_f64(-0.).to_bits());
+        assert_eq!(F80::from_i64(2).sqrt(Round::TiesToEven).mant, 0xb504_f333_f9de_6484);
+    }
+
+    #[test]
+    fn test_conversions_against_oracle() {
+        let mut rng = SplitMix64::new(SEED);
+
+        for _ in 0..10000 {
+            let a = random(&mut rng);
+            let f = f64::from_bits(rng.next_u64());
+
+            for mode in FPU_MODES {
+                let v = decode(a);
+                assert_eq!(a.to_f64(mode).to_bits(), v.encode(Format::BINARY64, mode) as u64, "{:?} {:?}", a, mode);
+                assert_eq!(a.to_f32(mode).to_bits(), v.encode(Format::BINARY32, mode) as u32, "{:?} {:?}", a, mode);
+            }
+
+            if !f.is_nan() {
+                assert_eq!(F80::from_f64(f).to_f128(), F128::from_f64(f));
+                assert_eq!(F80::from_f64(f).to_f64(Round::TowardZero).to_bits(), f.to_bits());
+                assert_eq!(F80::from_f32(f as f32).to_f128(), F128::from_f32(f as f32));
+            }
+
+            let n = rng.next_u64() as i64;
+            assert_eq!(F80::from_i64(n).to_i64(Round::TowardZero), Some(n));
+        }
+    }
+}
@@ -0,0 +1,457 @@
+#!/usr/bin/env python3
+# Generates the TestFloat (*.tv) and FPgen (basic.fptest) test vectors in this directory, with
+# exact rational arithmetic as the reference. Operands mix random bit patterns, special values and
+# cases built to be hard: exact ties of products, results near the overflow threshold and in the
+# subnormal range, cancelling sums, exact quotients and square roots, and halves for the
+# conversions to integers.
+#
+#     python3 tests/data/generate_vectors.py tests/data
+import random, sys, os
+from fractions import Fraction
+from math import isqrt
+
+FMT = {32: (8, 23), 64: (11, 52)}
+
+INEXACT, UNDERFLOW, OVERFLOW, DIVZERO, INVALID = 1, 2, 4, 8, 16
+
+MODES = ['rne', 'rtz', 'rup', 'rdn', 'rna']
+
+
+class Fmt:
+    def __init__(self, w):
+        self.w = w
+        self.eb, self.fb = FMT[w]
+        self.bias = (1 << (self.eb - 1)) - 1
+        self.emin = 1 - self.bias
+        self.emax = self.bias
+        self.inf = ((1 << self.eb) - 1) << self.fb
+        self.sign = 1 << (self.eb + self.fb)
+        self.qnan = self.inf | (1 << (self.fb - 1))
+
+    def decode(self, bits):
+        """Returns ('nan', snan?) | ('inf', neg) | ('num', Fraction)"""
+        neg = bool(bits & self.sign)
+        mag = bits & (self.sign - 1)
+        if mag > self.inf:
+            return ('nan', not (mag >> (self.fb - 1)) & 1)
+        if mag == self.inf:
+            return ('inf', neg)
+        e = mag >> self.fb
+        f = mag & ((1 << self.fb) - 1)
+        if e == 0:
+            v = Fraction(f) * Fraction(2) ** (self.emin - self.fb)
+        else:
+            v = Fraction(f | (1 << self.fb)) * Fraction(2) ** (e - self.bias - self.fb)
+        return ('num', -v if neg else v, neg)
+
+
+def floor_log2(a):
+    e = a.numerator.bit_length() - a.denominator.bit_length()
+    if Fraction(2) ** e > a:
+        e -= 1
+    if Fraction(2) ** (e + 1) <= a:
+        e += 1
+    return e
+
+
+def round_int(neg, n, rem, mode):
+    """rem in [0, 1) fraction part"""
+    half = Fraction(1, 2)
+    if mode == 'rtz':
+        up = False
+    elif mode == 'rup':
+        up = rem != 0 and not neg
+    elif mode == 'rdn':
+        up = rem != 0 and neg
+    elif mode == 'rne':
+        up = rem > half or (rem == half and n % 2 == 1)
+    elif mode == 'rna':
+        up = rem >= half
+    else:
+        raise ValueError(mode)
+    return n + 1 if up else n
+
+
+def round_value(fmt, x, mode, zero_neg=False):
+    """Round exact Fraction x to fmt. Returns (bits, flags)."""
+    if x == 0:
+        return (fmt.sign if zero_neg else 0), 0
+    neg = x < 0
+    a = -x if neg else x
+    e = floor_log2(a)
+    re = max(e, fmt.emin)
+    q = re - fmt.fb
+    scaled = a / Fraction(2) ** q
+    n = scaled.numerator // scaled.denominator
+    rem = scaled - n
+    n = round_int(neg, n, rem, mode)
+    flags = INEXACT if rem != 0 else 0
+    sign = fmt.sign if neg else 0
+    if e > fmt.emax:
+        return sign | overflow(fmt, neg, mode), INEXACT | OVERFLOW
+    bits = ((re + fmt.bias - 1) << fmt.fb) + n
+    if bits >= fmt.inf:
+        return sign | overflow(fmt, neg, mode), INEXACT | OVERFLOW
+    if rem != 0:
+        # tininess after rounding: round with unbounded exponent
+        q2 = e - fmt.fb
+        s2 = a / Fraction(2) ** q2
+        n2 = s2.numerator // s2.denominator
+        n2 = round_int(neg, n2, s2 - n2, mode)
+        if Fraction(n2) * Fraction(2) ** q2 < Fraction(2) ** fmt.emin:
+            flags |= UNDERFLOW
+    return sign | bits, flags
+
+
+def overflow(fmt, neg, mode):
+    to_inf = {'rtz': False, 'rup': not neg, 'rdn': neg}.get(mode, True)
+    return fmt.inf if to_inf else fmt.inf - 1
+
+
+def nan_result(fmt, ops, invalid=False):
+    flags = INVALID if invalid else 0
+    for d in ops:
+        if d[0] == 'nan' and d[1]:
+            flags |= INVALID
+    return fmt.qnan, flags
+
+
+def sqrt_fraction(a):
+    """Returns a Fraction r with the same rounding as sqrt(a) for any format up to 64 bits."""
+    k = 1200
+    scaled = a * Fraction(2) ** (2 * k)
+    n = scaled.numerator // scaled.denominator
+    s = isqrt(n)
+    exact = (s * s == n) and scaled.denominator == 1
+    r = Fraction(s, 1 << k)
+    if not exact:
+        r += Fraction(1, 1 << (k + 1))
+    return r
+
+
+def op_eval(fmt, op, mode, operands):
+    d = [fmt.decode(b) for b in operands]
+    if any(x[0] == 'nan' for x in d):
+        return nan_result(fmt, d)
+    if op in ('add', 'sub'):
+        a, b = d
+        if op == 'sub':
+            b = ('inf', not b[1]) if b[0] == 'inf' else ('num', -b[1], not b[2])
+        if a[0] == 'inf' and b[0] == 'inf':
+            if a[1] != b[1]:
+                return nan_result(fmt, d, True)
+            return (fmt.sign if a[1] else 0) | fmt.inf, 0
+        if a[0] == 'inf' or b[0] == 'inf':
+            inf = a if a[0] == 'inf' else b
+            return (fmt.sign if inf[1] else 0) | fmt.inf, 0
+        s = a[1] + b[1]
+        if s == 0:
+            if a[1] == 0 and b[1] == 0 and a[2] == b[2]:
+                return round_value(fmt, s, mode, a[2])
+            return round_value(fmt, s, mode, mode == 'rdn')
+        return round_value(fmt, s, mode)
+    if op == 'mul':
+        a, b = d
+        na = a[1] if a[0] == 'inf' else a[2]
+        nb = b[1] if b[0] == 'inf' else b[2]
+        neg = na != nb
+        if a[0] == 'inf' or b[0] == 'inf':
+            if (a[0] == 'num' and a[1] == 0) or (b[0] == 'num' and b[1] == 0):
+                return nan_result(fmt, d, True)
+            return (fmt.sign if neg else 0) | fmt.inf, 0
+        return round_value(fmt, a[1] * b[1], mode, neg)
+    if op == 'div':
+        a, b = d
+        na = a[1] if a[0] == 'inf' else a[2]
+        nb = b[1] if b[0] == 'inf' else b[2]
+        neg = na != nb
+        sg = fmt.sign if neg else 0
+        if a[0] == 'inf':
+            if b[0] == 'inf':
+                return nan_result(fmt, d, True)
+            return sg | fmt.inf, 0
+        if b[0] == 'inf':
+            return sg, 0
+        if b[1] == 0:
+            if a[1] == 0:
+                return nan_result(fmt, d, True)
+            return sg | fmt.inf, DIVZERO
+        return round_value(fmt, a[1] / b[1], mode, neg)
+    if op == 'sqrt':
+        (a,) = d
+        if a[0] == 'inf':
+            if a[1]:
+                return nan_result(fmt, d, True)
+            return fmt.inf, 0
+        if a[1] == 0:
+            return operands[0], 0
+        if a[1] < 0:
+            return nan_result(fmt, d, True)
+        return round_value(fmt, sqrt_fraction(a[1]), mode)
+    if op == 'fma':
+        a, b, c = d
+        na = a[1] if a[0] == 'inf' else a[2]
+        nb = b[1] if b[0] == 'inf' else b[2]
+        nc = c[1] if c[0] == 'inf' else c[2]
+        pneg = na != nb
+        if a[0] == 'inf' or b[0] == 'inf':
+            if (a[0] == 'num' and a[1] == 0) or (b[0] == 'num' and b[1] == 0):
+                return nan_result(fmt, d, True)
+            if c[0] == 'inf' and c[1] != pneg:
+                return nan_result(fmt, d, True)
+            return (fmt.sign if pneg else 0) | fmt.inf, 0
+        if c[0] == 'inf':
+            return (fmt.sign if c[1] else 0) | fmt.inf, 0
+        p = a[1] * b[1]
+        s = p + c[1]
+        if s == 0:
+            if p == 0 and c[1] == 0 and pneg == nc:
+                return round_value(fmt, s, mode, pneg)
+            return round_value(fmt, s, mode, mode == 'rdn')
+        return round_value(fmt, s, mode)
+    if op == 'rfi':
+        (a,) = d
+        if a[0] == 'inf':
+            return operands[0], 0
+        x = a[1]
+        neg = a[2]
+        ax = -x if neg else x
+        n = ax.numerator // ax.denominator
+        rem = ax - n
+        n = round_int(neg, n, rem, mode)
+        if n == 0:
+            return (fmt.sign if neg else 0), (INEXACT if rem else 0)
+        bits, _ = round_value(fmt, Fraction(-n if neg else n), 'rne')
+        return bits, (INEXACT if rem else 0)
+    raise ValueError(op)
+
+
+def to_i32(fmt, mode, operand):
+    d = fmt.decode(operand)
+    if d[0] != 'num':
+        return 0x80000000, INVALID
+    x = d[1]
+    neg = d[2]
+    ax = -x if neg else x
+    n = ax.numerator // ax.denominator
+    rem = ax - n
+    n = round_int(neg, n, rem, mode)
+    v = -n if neg else n
+    if v < -2**31 or v >= 2**31:
+        return 0x80000000, INVALID
+    return v & 0xffffffff, (INEXACT if rem else 0)
+
+
+# ---------------------------------------------------------------- operand generation
+
+def make(fmt, neg, mant, exp):
+    """bits of (-1)^neg mant 2^exp, mant<=p bits, must be representable"""
+    b, fl = round_value(fmt, Fraction(mant) * Fraction(2) ** exp * (-1 if neg else 1), 'rne')
+    return b
+
+
+def rand_full(fmt, rng, top):
+    p = fmt.fb + 1
+    m = (1 << (p - 1)) | rng.getrandbits(p - 1)
+    return make(fmt, rng.random() < 0.5, m, top - (p - 1))
+
+
+def specials(fmt):
+    return [0, fmt.sign, fmt.inf, fmt.inf | fmt.sign, fmt.qnan, fmt.inf | 1, 1, 1 | fmt.sign,
+            fmt.inf - 1, (fmt.inf - 1) | fmt.sign, 1 << fmt.fb, (1 << fmt.fb) | fmt.sign,
+            (1 << fmt.fb) - 1, make(fmt, False, 1, 0), make(fmt, True, 1, 0)]
+
+
+def gen_operands(fmt, rng, op, n):
+    mask = (fmt.sign << 1) - 1
+    out = []
+    sp = specials(fmt)
+    arity = {'sqrt': 1, 'rfi': 1, 'i32': 1, 'fma': 3}.get(op, 2)
+    while len(out) < n:
+        r = rng.random()
+        if r < 0.1:
+            ops = [rng.choice(sp) for _ in range(arity)]
+        elif r < 0.25:
+            ops = [rng.getrandbits(fmt.w) & mask for _ in range(arity)]
+        elif r < 0.5:
+            ops = [rand_full(fmt, rng, rng.randint(-8, 8)) for _ in range(arity)]
+        else:
+            ops = special_case(fmt, rng, op, arity)
+        out.append(ops)
+    return out
+
+
+def special_case(fmt, rng, op, arity):
+    p = fmt.fb + 1
+    if op == 'mul' or (op == 'fma' and rng.random() < 0.5):
+        kind = rng.random()
+        if kind < 0.5:
+            # exact tie
+            while True:
+                lb = rng.randint(2, p)
+                la = p + 1 - lb + rng.randint(0, 1)
+                if la > p:
+                    continue
+                ma = ((1 << (la - 1)) | rng.getrandbits(la - 1)) | 1
+                mb = ((1 << (lb - 1)) | rng.getrandbits(lb - 1)) | 1
+                if (ma * mb).bit_length() == p + 1:
+                    break
+            top = rng.randint(fmt.emin - p, fmt.emax)
+            ta = rng.randint(max(fmt.emin, top - fmt.emax), min(fmt.emax, top - fmt.emin))
+            ea = ta - (ma.bit_length() - 1)
+            eb = top - p - ea
+            ops = [make(fmt, rng.random() < 0.5, ma, ea), make(fmt, rng.random() < 0.5, mb, eb)]
+        elif kind < 0.75:
+            ma = (1 << (p - 1)) | rng.getrandbits(p - 1)
+            target = 1 << (2 * p - 1)
+            mb = -(-target // ma) + rng.randint(-2, 2)
+            sh = 0
+            if mb >> p:
+                mb >>= 1
+                sh = 1
+            ta = rng.randint(1, fmt.emax)
+            ea = ta - (p - 1)
+            es = fmt.emax + 1 - (2 * p - 1)
+            ops = [make(fmt, rng.random() < 0.5, ma, ea), make(fmt, rng.random() < 0.5, mb, es - ea + sh)]
+        else:
+            top = rng.randint(fmt.emin - p - 1, fmt.emin)
+            ta = rng.randint(fmt.emin, top - fmt.emin)
+            ops = [rand_full(fmt, rng, ta), rand_full(fmt, rng, top - ta)]
+        if op == 'fma':
+            # c close to -a*b
+            pa = fmt.decode(ops[0])
+            pb = fmt.decode(ops[1])
+            prod = pa[1] * pb[1]
+            c, _ = round_value(fmt, -prod, rng.choice(MODES))
+            c = (c + rng.randint(-2, 2)) & ((fmt.sign << 1) - 1)
+            ops.append(c)
+        return ops
+    if op in ('add', 'sub', 'fma'):
+        top = rng.randint(fmt.emin, fmt.emax)
+        a = rand_full(fmt, rng, top)
+        kind = rng.random()
+        if kind < 0.5:
+            b = (a & (fmt.sign - 1)) + rng.randint(-3, 3)
+            b |= fmt.sign if rng.random() < 0.5 else 0
+        else:
+            gap = rng.randint(1, 2 * p + 3)
+            b = rand_full(fmt, rng, max(fmt.emin - p + 1, top - gap))
+        ops = [a, b]
+        if op == 'fma':
+            ops = [a, make(fmt, False, 1, 0), b]
+        return ops
+    if op == 'div':
+        kind = rng.random()
+        if kind < 0.4:
+            # exact quotient
+            mq = (1 << (p // 2)) | rng.getrandbits(p // 2 - 1)
+            mb = ((1 << (p // 2)) | rng.getrandbits(p // 2 - 1)) | 1
+            ea = rng.randint(-20, 20)
+            eb = rng.randint(-20, 20)
+            return [make(fmt, rng.random() < 0.5, mq * mb, ea + eb), make(fmt, rng.random() < 0.5, mb, eb)]
+        elif kind < 0.7:
+            ta = rng.randint(fmt.emin, fmt.emin + p // 2)
+            tb = rng.randint(p // 2, p)
+            return [rand_full(fmt, rng, ta), rand_full(fmt, rng, tb)]
+        else:
+            return [rand_full(fmt, rng, rng.randint(fmt.emax - 4, fmt.emax)), rand_full(fmt, rng, rng.randint(-4, 0))]
+    if op == 'sqrt':
+        kind = rng.random()
+        if kind < 0.4:
+            m = (1 << (p // 2)) | rng.getrandbits(p // 2 - 1)
+            e = rng.randint(-40, 40)
+            b = make(fmt, False, m * m, 2 * e)
+            return [(b + rng.randint(-1, 1))]
+        return [rand_full(fmt, rng, rng.randint(fmt.emin - p + 1, fmt.emax)) & (fmt.sign - 1)]
+    if op in ('rfi', 'i32'):
+        kind = rng.random()
+        lim = p + 1 if op == 'rfi' else 33
+        if kind < 0.5:
+            # halves and near halves
+            n = rng.getrandbits(rng.randint(1, min(lim, p - 2)))
+            b = make(fmt, rng.random() < 0.5, 2 * n + 1, -1)
+            return [b + rng.randint(-1, 1)]
+        return [rand_full(fmt, rng, rng.randint(-3, lim))]
+    raise ValueError(op)
+
+
+# ---------------------------------------------------------------- output
+
+TF_MODE = {'rne': 'rnear_even', 'rtz': 'rminMag', 'rup': 'rmax', 'rdn': 'rmin', 'rna': 'rnear_maxMag'}
+FP_MODE = {'rne': '=0', 'rtz': '0', 'rup': '>', 'rdn': '<', 'rna': '=^'}
+FP_OP = {'add': '+', 'sub': '-', 'mul': '*', 'div': '/', 'sqrt': 'V', 'fma': '*+', 'rfi': 'rfi'}
+TF_OP = {'add': 'add', 'sub': 'sub', 'mul': 'mul', 'div': 'div', 'sqrt': 'sqrt', 'fma': 'mulAdd', 'rfi': 'roundToInt', 'i32': 'to_i32'}
+
+
+def fp_num(fmt, bits, is_result=False):
+    neg = bits & fmt.sign
+    mag = bits & (fmt.sign - 1)
+    s = '-' if neg else '+'
+    if mag > fmt.inf:
+        if is_result:
+            return 'Q'
+        return 'S' if not (mag >> (fmt.fb - 1)) & 1 else 'Q'
+    if mag == fmt.inf:
+        return s + 'Inf'
+    if mag == 0:
+        return s + 'Zero'
+    e = mag >> fmt.fb
+    f = mag & ((1 << fmt.fb) - 1)
+    digits = (fmt.fb + 3) // 4
+    if e == 0:
+        return '%s0.%0*XP%d' % (s, digits, f, fmt.emin)
+    return '%s1.%0*XP%d' % (s, digits, f, e - fmt.bias)
+
+
+def fp_flags(flags):
+    out = ''
+    for bit, c in ((INEXACT, 'x'), (UNDERFLOW, 'u'), (OVERFLOW, 'o'), (DIVZERO, 'z'), (INVALID, 'i')):
+        if flags & bit:
+            out += c
+    return out
+
+
+SEED = 20301
+
+
+def main(outdir, seed=SEED):
+    rng = random.Random(seed)
+    os.makedirs(outdir, exist_ok=True)
+
+    tf = [(64, 'add'), (64, 'sub'), (64, 'mul'), (64, 'div'), (64, 'sqrt'),
+          (32, 'add'), (32, 'mul'), (32, 'div'), (32, 'sqrt'), (32, 'rfi'), (32, 'i32')]
+    for w, op in tf:
+        fmt = Fmt(w)
+        for mode in MODES:
+            lines = []
+            for ops in gen_operands(fmt, rng, op, 150):
+                if op == 'i32':
+                    res, flags = to_i32(fmt, mode, ops[0])
+                    rw = 8
+                else:
+                    res, flags = op_eval(fmt, op, mode, ops)
+                    rw = w // 4
+                fields = ['%0*X' % (w // 4, o) for o in ops] + ['%0*X' % (rw, res), '%02X' % flags]
+                lines.append(' '.join(fields))
+            name = 'f%d_%s_%s.tv' % (w, TF_OP[op], TF_MODE[mode])
+            with open(os.path.join(outdir, name), 'w') as f:
+                f.write('\n'.join(lines) + '\n')
+
+    lines = []
+    for w in (32, 64):
+        fmt = Fmt(w)
+        for op in ('add', 'sub', 'mul', 'div', 'sqrt', 'fma', 'rfi'):
+            if op == 'rfi' and w == 64:
+                continue
+            for mode in MODES:
+                for ops in gen_operands(fmt, rng, op, 12):
+                    res, flags = op_eval(fmt, op, mode, ops)
+                    lines.append('b%d%s %s %s -> %s %s' % (
+                        w, FP_OP[op], FP_MODE[mode], ' '.join(fp_num(fmt, o) for o in ops),
+                        fp_num(fmt, res, True), fp_flags(flags)))
+    with open(os.path.join(outdir, 'basic.fptest'), 'w') as f:
+        f.write('\n'.join(l.rstrip() for l in lines) + '\n')
+
+
+if __name__ == '__main__':
+    main(sys.argv[1], *map(int, sys.argv[2:3]))
@@ -0,0 +1,81 @@
+// Expressions evaluated by `round_down!`, `round_up!` and `enclose!`, checked against the same
+// arithmetic through `ops` by hand and against quad precision.
+
+use rounding_mode::f128::F128;
+use rounding_mode::modes::*;
+use rounding_mode::successor::*;
+use rounding_mode::{enclose, ops, round_down, round_up};
+
+const VALUES: [f64; 8] = [0.1, 1.0 / 3.0, 2.7, -1.1e-7, 12345.678, -0.75, 1e300, -3.0];
+
+#[test]
+fn test_directed() {
+    for x in VALUES {
+        for y in VALUES {
+            let down = ops::add_f64(ops::mul_f64(x, y, Round::TowardNInf), ops::div_f64(x, 3.0, Round::TowardNInf), Round::TowardNInf);
+            let up = ops::add_f64(ops::mul_f64(x, y, Round::TowardPInf), ops::div_f64(x, 3.0, Round::TowardPInf), Round::TowardPInf);
+
+            assert_eq!(round_down!{ x * y + x / 3.0 }.to_bits(), down.to_bits());
+            assert_eq!(round_up!{ x * y + x / 3.0 }.to_bits(), up.to_bits());
+        }
+    }
+
+    assert_eq!(round_down!{ 1.0f32 / 3.0 }, 0.3333333);
+    assert_eq!(round_up!{ 1.0f32 / 3.0 }, 0.33333334);
+}
+
+/// Bounds on a quad precision value, rounded down and up
+type Bounds = (F128, F128);
+
+/// Bounds on `a * b` or `a / b` for an exact `b`, whose sign decides which bound of `a` goes where
+fn scaled(a: Bounds, b: F128, op: fn(F128, F128, Round) -> F128) -> Bounds {
+    let (lo, hi) = if b.is_sign_negative() { (a.1, a.0) } else { a };
+
+    (op(lo, b, Round::TowardNInf), op(hi, b, Round::TowardPInf))
+}
+
+#[test]
+fn test_enclose() {
+    let tenth = (F128::parse("0.1", Round::TowardNInf).unwrap(), F128::parse("0.1", Round::TowardPInf).unwrap());
+
+    for x in VALUES {
+        for y in VALUES {
+            // Subtraction, negation and division by negative values all need the bounds swapped
+            let (lo, hi) = enclose!{ -(x - 0.1) / y + x * (y - x) };
+
+            // Bounds on the exact value, with every quad precision operation rounded outward
+            let (x128, y128) = (F128::from_f64(x), F128::from_f64(y));
+            let diff = (x128.sub(tenth.1, Round::TowardNInf), x128.sub(tenth.0, Round::TowardPInf));
+            let quotient = scaled((-diff.1, -diff.0), y128, F128::div);
+            let product = scaled((y128.sub(x128, Round::TowardNInf), y128.sub(x128, Round::TowardPInf)), x128, F128::mul);
+            let exact = (quotient.0.add(product.0, Round::TowardNInf), quotient.1.add(product.1, Round::TowardPInf));
+
+            assert!(lo <= exact.0.to_f64(Round::TowardNInf) && exact.1.to_f64(Round::TowardPInf) <= hi, "x = {:e}, y = {:e}, lo = {:e}, hi = {:e}", x, y, lo, hi);
+
+            // Each rounding and the widened literal cost about an ulp of the term they occur in
+            let scale = (x.abs() + 0.1) / y.abs() + (x * y).abs() + x * x;
+            assert!(!scale.is_finite() || hi - lo <= scale * 4e-15, "x = {:e}, y = {:e}, lo = {:e}, hi = {:e}", x, y, lo, hi);
+        }
+    }
+}
+
+#[test]
+fn test_enclose_points() {
+    let x: f64 = 2.7;
+
+    // Exact operations, exact literals and variables give a point, while 0.1 widens to its neighbors
+    assert_eq!(enclose!{ 0.5 * x - x }, (-1.35, -1.35));
+    assert_eq!(enclose!{ 0.1 }, (predecessor_f64(0.1), successor_f64(0.1)));
+    assert_eq!(enclose!{ 0.1f32 }, (predecessor_f32(0.1), successor_f32(0.1)));
+
+    // Fields, indexing and dereferences read stored values, which are exact
+    let (pair, values, r) = ((x, 0.25), [x, 0.5], &x);
+    assert_eq!(enclose!{ pair.0 * values[1] - *r }, (-1.35, -1.35));
+
+    // Division by a range containing zero is unbounded
+    assert_eq!(enclose!{ 1.0 / (x - x) }, (f64::NEG_INFINITY, f64::INFINITY));
+    assert_eq!(enclose!{ 1.0 / (x - 2.7) }, (f64::NEG_INFINITY, f64::INFINITY));
+
+    let (lo, hi) = enclose!{ f64::NAN * x + 1.0 };
+    assert!(lo.is_nan() && hi.is_nan());
+}
@@ -0,0 +1,24 @@
+// `ops::force_software` flips a flag shared by the whole process, so it is tested here, in a test
+// binary of its own, rather than next to the tests in the crate, which run in parallel and would
+// silently switch implementations halfway through.
+
+use rounding_mode::modes::*;
+use rounding_mode::ops::*;
+use rounding_mode::successor::identical_f64;
+
+#[test]
+fn test_force_software() {
+    force_software(true);
+    for op in Op::ALL {
+        for mode in Round::ALL {
+            assert_eq!(implementation(op, mode), Implementation::Software);
+        }
+    }
+
+    assert_eq!(div_f32(1.0, 3.0, Round::TiesToOdd), 0.33333334);
+    assert!(identical_f64(add_f64(1.0, -1.0, Round::TowardNInf), -0.0));
+    force_software(false);
+
+    assert_eq!(implementation(Op::Add, Round::TiesToEven), Implementation::Default);
+    assert_eq!(implementation(Op::Add, Round::TowardNInf), select(features(), Op::Add, Round::TowardNInf));
+}
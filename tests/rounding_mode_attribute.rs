@@ -0,0 +1,60 @@
+// Functions rewritten by the `rounding_mode` attribute, checked against the same arithmetic
+// through `ops` by hand.
+
+use rounding_mode::modes::*;
+use rounding_mode::{ops, rounding_mode};
+
+#[rounding_mode(TowardNInf)]
+fn lower(x: f64) -> f64 {
+    x*x - 2.0*x
+}
+
+#[rounding_mode(TowardPInf)]
+fn upper(x: f64) -> f64 {
+    x*x - 2.0*x
+}
+
+#[rounding_mode(Round::TowardZero)]
+fn sum(values: &[f32]) -> f32 {
+    let mut total = 0.0;
+    for v in values {
+        total += v / 3.0;
+    }
+
+    total
+}
+
+#[rounding_mode(TowardPInf)]
+fn scaled(values: &[f64], factor: f64) -> Vec<f64> {
+    let count = values.len() * 2 - values.len();
+    values.iter().take(count).map(|v| v * factor + 1.0).collect()
+}
+
+#[test]
+fn test_bounds() {
+    for x in [0.1, 1.0 / 3.0, 2.7, -1.1e-7, 12345.678] {
+        let down = ops::sub_f64(ops::mul_f64(x, x, Round::TowardNInf), ops::mul_f64(2.0, x, Round::TowardNInf), Round::TowardNInf);
+        let up = ops::sub_f64(ops::mul_f64(x, x, Round::TowardPInf), ops::mul_f64(2.0, x, Round::TowardPInf), Round::TowardPInf);
+
+        assert_eq!(lower(x).to_bits(), down.to_bits());
+        assert_eq!(upper(x).to_bits(), up.to_bits());
+        assert!(lower(x) <= x * x - 2.0 * x && x * x - 2.0 * x <= upper(x));
+    }
+
+    assert!(lower(0.1) < upper(0.1));
+}
+
+#[test]
+fn test_statements() {
+    let values = [1.0f32, 2.0, 0.1, 7.0];
+
+    let mut total = 0.0;
+    for v in values {
+        total = ops::add_f32(total, ops::div_f32(v, 3.0, Round::TowardZero), Round::TowardZero);
+    }
+
+    assert_eq!(sum(&values), total);
+
+    let expected: Vec<f64> = [0.1, 0.2].iter().map(|&v| ops::add_f64(ops::mul_f64(v, 0.3, Round::TowardPInf), 1.0, Round::TowardPInf)).collect();
+    assert_eq!(scaled(&[0.1, 0.2], 0.3), expected);
+}
@@ -0,0 +1,162 @@
+// Runs the test vectors in tests/data against both the native and the fallback operations.
+// TestFloat files are named after the testfloat_gen arguments that produced them, e.g.,
+// f64_mul_rmin.tv, and FPgen files end in .fptest.
+
+use std::fs;
+use std::path::Path;
+use rounding_mode::modes::*;
+use rounding_mode::test_vectors::*;
+use rounding_mode::{fallback, native, ops};
+
+/// Implementation under test
+#[derive(Copy, Clone, Debug)]
+enum Backend {
+    Native,
+    Fallback,
+}
+
+fn load(path: &Path) -> Vec<TestVector> {
+    let text = fs::read_to_string(path).unwrap();
+    let name = path.file_name().unwrap().to_str().unwrap();
+
+    let vectors = if let Some(stem) = name.strip_suffix(".tv") {
+        // Both function names and rounding options may contain underscores
+        let ((op, precision), mode) = stem.match_indices('_')
+            .find_map(|(i, _)| Some((testfloat_function(&stem[..i])?, testfloat_rounding(&stem[i + 1..])?)))
+            .unwrap_or_else(|| panic!("{}: unknown function or rounding mode", name));
+
+        parse_testfloat(&text, op, precision, mode)
+    } else if name.ends_with(".fptest") {
+        parse_fpgen(&text)
+    } else {
+        return vec![];
+    };
+
+    vectors.unwrap_or_else(|e| panic!("{}: {}", name, e))
+}
+
+/// Evaluates a vector, returning the result's bits, or None if the backend doesn't implement the
+/// operation or rounding mode.
+fn eval(v: &TestVector, backend: Backend) -> Option<Option<u64>> {
+    if let Backend::Native = backend {
+        if !v.mode.is_native() {
+            return None;
+        }
+    }
+
+    let f64_op = |i: usize| f64::from_bits(v.operands[i]);
+    let f32_op = |i: usize| f32::from_bits(v.operands[i] as u32);
+
+    let f64_result = |f: f64| Some(Some(f.to_bits()));
+    let f32_result = |f: f32| Some(Some(f.to_bits() as u64));
+
+    let binary_f64 = |native: fn(f64, f64, Round) -> f64, fallback: fn(f64, f64, Round) -> f64| {
+        let f = match backend { Backend::Native => native, Backend::Fallback => fallback };
+        f64_result(f(f64_op(0), f64_op(1), v.mode))
+    };
+    let binary_f32 = |native: fn(f32, f32, Round) -> f32, fallback: fn(f32, f32, Round) -> f32| {
+        let f = match backend { Backend::Native => native, Backend::Fallback => fallback };
+        f32_result(f(f32_op(0), f32_op(1), v.mode))
+    };
+    let ternary_f64 = |native: fn(f64, f64, f64, Round) -> f64, fallback: fn(f64, f64, f64, Round) -> f64| {
+        let f = match backend { Backend::Native => native, Backend::Fallback => fallback };
+        f64_result(f(f64_op(0), f64_op(1), f64_op(2), v.mode))
+    };
+    let ternary_f32 = |native: fn(f32, f32, f32, Round) -> f32, fallback: fn(f32, f32, f32, Round) -> f32| {
+        let f = match backend { Backend::Native => native, Backend::Fallback => fallback };
+        f32_result(f(f32_op(0), f32_op(1), f32_op(2), v.mode))
+    };
+    let unary_f64 = |native: fn(f64, Round) -> f64, fallback: fn(f64, Round) -> f64| {
+        let f = match backend { Backend::Native => native, Backend::Fallback => fallback };
+        f64_result(f(f64_op(0), v.mode))
+    };
+    let unary_f32 = |native: fn(f32, Round) -> f32, fallback: fn(f32, Round) -> f32| {
+        let f = match backend { Backend::Native => native, Backend::Fallback => fallback };
+        f32_result(f(f32_op(0), v.mode))
+    };
+
+    // The native fused multiply-add needs an extension on x86_64
+    if let (Backend::Native, Operation::Fma) = (backend, v.op) {
+        if !ops::features().fma {
+            return None;
+        }
+    }
+
+    match (v.op, v.precision) {
+        (Operation::Add, Precision::F64) => binary_f64(native::add_f64, fallback::add_f64),
+        (Operation::Sub, Precision::F64) => binary_f64(native::sub_f64, fallback::sub_f64),
+        (Operation::Mul, Precision::F64) => binary_f64(native::mul_f64, fallback::mul_f64),
+        (Operation::Div, Precision::F64) => binary_f64(native::div_f64, fallback::div_f64),
+        (Operation::Sqrt, Precision::F64) => unary_f64(native::sqrt_f64, fallback::sqrt_f64),
+        (Operation::Fma, Precision::F64) => ternary_f64(native::fma_f64, fallback::fma_f64),
+        (Operation::Add, Precision::F32) => binary_f32(native::add_f32, fallback::add_f32),
+        (Operation::Sub, Precision::F32) => binary_f32(native::sub_f32, fallback::sub_f32),
+        (Operation::Mul, Precision::F32) => binary_f32(native::mul_f32, fallback::mul_f32),
+        (Operation::Div, Precision::F32) => binary_f32(native::div_f32, fallback::div_f32),
+        (Operation::Sqrt, Precision::F32) => unary_f32(native::sqrt_f32, fallback::sqrt_f32),
+        (Operation::Fma, Precision::F32) => ternary_f32(native::fma_f32, fallback::fma_f32),
+        (Operation::RoundToIntegral, Precision::F32) => unary_f32(native::round_f32, fallback::round_f32),
+        (Operation::ToI32, Precision::F32) => {
+            let f = match backend { Backend::Native => native::f32_to_i32, Backend::Fallback => fallback::f32_to_i32 };
+            Some(f(f32_op(0), v.mode).map(|i| i as u32 as u64))
+        }
+        _ => None
+    }
+}
+
+/// Whether an actual result satisfies the vector. Any NaN matches an expected NaN, since the
+/// default NaN differs between platforms. Conversions to integers must fail exactly when the
+/// vector raises the invalid flag.
+fn matches(v: &TestVector, actual: Option<u64>) -> bool {
+    if v.op == Operation::ToI32 {
+        return if v.flags.contains(Flags::INVALID) { actual.is_none() } else { actual == v.result };
+    }
+
+    let (Some(expected), Some(actual)) = (v.result, actual) else {
+        return v.result.is_none();
+    };
+
+    let is_nan = |bits: u64| match v.precision {
+        Precision::F32 => f32::from_bits(bits as u32).is_nan(),
+        Precision::F64 => f64::from_bits(bits).is_nan(),
+    };
+
+    expected == actual || (is_nan(expected) && is_nan(actual))
+}
+
+#[test]
+fn run_test_vectors() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+
+    let mut paths: Vec<_> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).collect();
+    paths.sort();
+
+    let mut failures = vec![];
+    let (mut run, mut skipped) = (0, 0);
+
+    for path in paths {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+
+        for v in load(&path) {
+            for backend in [Backend::Native, Backend::Fallback] {
+                let Some(actual) = eval(&v, backend) else {
+                    skipped += 1;
+                    continue;
+                };
+
+                run += 1;
+                if !matches(&v, actual) {
+                    failures.push(format!(
+                        "{}:{}: {:?} {:?} {:?} on {:x?}: expected = {:x?}, actual = {:x?}",
+                        name, v.line, backend, v.op, v.mode, v.operands, v.result, actual
+                    ));
+                }
+            }
+        }
+    }
+
+    println!("Ran {} vectors, skipped {} unsupported", run, skipped);
+
+    assert!(run > 0, "no test vectors found in {}", dir.display());
+    assert!(failures.is_empty(), "{} failures:\n{}", failures.len(), failures.iter().take(20).cloned().collect::<Vec<_>>().join("\n"));
+}